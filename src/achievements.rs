@@ -1,11 +1,70 @@
 use std::{
-    collections::{HashMap, VecDeque},
-    fs,
+    collections::{BTreeMap, HashMap, VecDeque},
     path::Path,
 };
+use web_time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    condition::Condition,
+    flags::FlagStore,
+    save,
+    stats::{Stat, StatsManager},
+    vfs,
+};
+
+// Rank inside an achievement chain; a higher tier implies every lower one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AchievementTier {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl AchievementTier {
+    pub fn title_key(self) -> &'static str {
+        match self {
+            Self::Bronze => "achievements.tier.bronze",
+            Self::Silver => "achievements.tier.silver",
+            Self::Gold => "achievements.tier.gold",
+        }
+    }
+}
+
+// Display bucket derived from the unlock percentage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RarityClass {
+    Common,
+    Uncommon,
+    Rare,
+    Legendary,
+}
+
+impl RarityClass {
+    pub fn from_percent(percent: f32) -> Self {
+        if percent >= 50.0 {
+            Self::Common
+        } else if percent >= 20.0 {
+            Self::Uncommon
+        } else if percent >= 5.0 {
+            Self::Rare
+        } else {
+            Self::Legendary
+        }
+    }
+
+    pub fn title_key(self) -> &'static str {
+        match self {
+            Self::Common => "achievements.rarity.common",
+            Self::Uncommon => "achievements.rarity.uncommon",
+            Self::Rare => "achievements.rarity.rare",
+            Self::Legendary => "achievements.rarity.legendary",
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AchievementDefinition {
     pub id: String,
@@ -13,28 +72,97 @@ pub struct AchievementDefinition {
     pub description: String,
     #[serde(default)]
     pub trigger: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    // Share of players that unlocked it, in percent; lower is rarer.
+    #[serde(default)]
+    pub rarity: Option<f32>,
+    // Tiers of one chain share a counter named after the chain.
+    #[serde(default)]
+    pub chain: Option<String>,
+    #[serde(default)]
+    pub tier: Option<AchievementTier>,
+    // Counter value that unlocks this tier; None means it is only granted directly.
+    #[serde(default)]
+    pub goal: Option<u32>,
+    // Sum of trigger payloads that unlocks an unchained achievement; None unlocks on the
+    // first trigger.
+    #[serde(default)]
+    pub threshold: Option<u32>,
+    // Unlock expression over stats and flags, e.g.
+    // `stats.choices_made >= 10 && flags.route_a`; see `condition::Condition`.
+    #[serde(default)]
+    pub condition: Option<String>,
+    // Name and description stay secret until unlocked.
+    #[serde(default)]
+    pub hidden: bool,
+    // Score awarded on unlock.
+    #[serde(default)]
+    pub points: Option<u32>,
+    // Image paths; without `locked_icon` a locked entry shows `icon` dimmed.
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub locked_icon: Option<String>,
 }
 
 #[derive(Clone, Debug)]
 pub struct AchievementState {
     pub definition: AchievementDefinition,
+    pub hidden: bool,
     pub unlocked: bool,
+    // Unix timestamp (seconds) of the unlock, when known.
+    pub unlocked_at: Option<u64>,
 }
 
 #[derive(Clone, Debug)]
 pub struct AchievementSnapshotItem {
+    pub id: String,
+    // Empty while a hidden achievement is locked.
     pub name: String,
     pub description: String,
+    pub hidden: bool,
     pub unlocked: bool,
+    pub category: Option<String>,
+    pub rarity: Option<f32>,
+    pub points: Option<u32>,
+    // None while a hidden achievement is locked.
+    pub icon: Option<String>,
+    pub locked_icon: Option<String>,
+    pub unlocked_at: Option<u64>,
+    pub chain: Option<String>,
+    pub tier: Option<AchievementTier>,
+    // Chain goal or trigger threshold.
+    pub goal: Option<u32>,
+    // Current value of the chain or trigger counter.
+    pub progress: u32,
 }
 
+// Sent to subscribers for every unlock, including silent ones.
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub struct AchievementUnlocked {
+    pub id: String,
+    pub name: String,
+    pub points: Option<u32>,
+    // No toast was queued: a lower tier implied by a higher one, or an unlock restored
+    // from elsewhere.
+    pub silent: bool,
+}
+
+pub type SubscriptionId = u64;
+
+type UnlockCallback = Box<dyn FnMut(&AchievementUnlocked)>;
+
 #[derive(Clone, Debug)]
 pub struct AchievementNotification {
     pub name: String,
     pub description: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+// Catalog entry as stored in the achievements file. Files from before the progress split
+// also carry unlock state, which is imported once when no progress file exists yet.
+#[derive(Clone, Debug, Deserialize)]
 struct AchievementRecord {
     pub id: String,
     pub name: String,
@@ -42,23 +170,140 @@ struct AchievementRecord {
     #[serde(default)]
     pub trigger: Option<String>,
     #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub rarity: Option<f32>,
+    #[serde(default)]
+    pub chain: Option<String>,
+    #[serde(default)]
+    pub tier: Option<AchievementTier>,
+    #[serde(default)]
+    pub goal: Option<u32>,
+    #[serde(default)]
+    pub threshold: Option<u32>,
+    #[serde(default)]
+    pub condition: Option<String>,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub points: Option<u32>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub locked_icon: Option<String>,
+    #[serde(default)]
     pub unlocked: bool,
+    #[serde(default)]
+    pub unlocked_at: Option<u64>,
+    // Chain counter at save time, repeated on every tier of the chain.
+    #[serde(default)]
+    pub progress: u32,
 }
 
+// Layout version written to the achievements file. Older files are migrated one
+// version at a time on load, so new fields never cost players their progress.
+// 1: unversioned, either a bare list or `{ "achievements": [...] }`.
+// 2: `{ "version": 2, "achievements": [...] }` with chains, hidden flags and progress.
+// 3: same layout, definitions only; unlocks live in the per-user progress file.
+pub const ACHIEVEMENTS_FILE_VERSION: u32 = 3;
+
 #[derive(Debug, Deserialize)]
-#[serde(untagged)]
-enum AchievementFileFormat {
-    List(Vec<AchievementRecord>),
-    WithRoot {
-        achievements: Vec<AchievementRecord>,
-    },
+struct AchievementFile {
+    achievements: Vec<AchievementRecord>,
+}
+
+fn file_version(value: &serde_json::Value) -> Result<u32, String> {
+    match value.get("version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| format!("invalid achievements file version: {version}")),
+    }
+}
+
+fn migrate_achievements_file(mut value: serde_json::Value) -> Result<AchievementFile, String> {
+    let version = file_version(&value)?;
+    if version > ACHIEVEMENTS_FILE_VERSION {
+        return Err(format!(
+            "achievements file version {version} is newer than supported version {ACHIEVEMENTS_FILE_VERSION}"
+        ));
+    }
+
+    for from in version..ACHIEVEMENTS_FILE_VERSION {
+        value = match from {
+            1 => migrate_v1(value),
+            // Version 2 unlock fields still parse and are imported once.
+            _ => value,
+        };
+    }
+
+    serde_json::from_value(value).map_err(|err| err.to_string())
+}
+
+// Records of version 1 parse as-is (new fields default), only the root changes.
+fn migrate_v1(value: serde_json::Value) -> serde_json::Value {
+    let achievements = match value {
+        serde_json::Value::Object(mut root) => root
+            .remove("achievements")
+            .unwrap_or_else(|| serde_json::Value::Array(Vec::new())),
+        list => list,
+    };
+    serde_json::json!({ "version": 2, "achievements": achievements })
+}
+
+// Conditions may only read stats by their `Stat::key()` and flags.
+fn validate_condition(condition: Condition) -> Result<Condition, String> {
+    for variable in condition.variables() {
+        let known = match variable.split_once('.') {
+            Some(("stats", key)) => Stat::ALL.iter().any(|stat| stat.key() == key),
+            Some(("flags", key)) => !key.is_empty(),
+            _ => false,
+        };
+        if !known {
+            return Err(format!("unknown condition variable {variable}"));
+        }
+    }
+    Ok(condition)
+}
+
+const PROGRESS_FILE_VERSION: u32 = 1;
+
+// Per-user unlock state keyed by id, written apart from the shipped catalog so content
+// patches never touch it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProgressFile {
+    #[serde(default)]
+    version: u32,
+    // Unlocked id -> Unix timestamp (seconds) of the unlock, when known.
+    #[serde(default)]
+    unlocked: BTreeMap<String, Option<u64>>,
+    #[serde(default)]
+    counters: BTreeMap<String, u32>,
+    // Trigger id -> summed payloads, for threshold achievements.
+    #[serde(default)]
+    triggers: BTreeMap<String, u32>,
 }
 
 pub struct AchievementManager {
     achievements: Vec<AchievementState>,
     id_lookup: HashMap<String, usize>,
     trigger_lookup: HashMap<String, Vec<String>>,
+    // Chain id -> indices of its tiers, lowest first.
+    chain_lookup: HashMap<String, Vec<usize>>,
+    counters: HashMap<String, u32>,
+    // Trigger id -> summed payloads; only triggers with threshold listeners count.
+    trigger_counters: HashMap<String, u32>,
+    // Parsed unlock conditions by achievement index.
+    conditions: Vec<(usize, Condition)>,
+    // Stat values and flag revision of the last condition pass.
+    condition_inputs: Option<([u32; Stat::ALL.len()], u64)>,
+    // Progress for ids and chains the current catalog lacks, written back unchanged so
+    // a patch that drops and later restores an entry keeps the unlock.
+    orphaned_progress: ProgressFile,
     notifications: VecDeque<AchievementNotification>,
+    subscribers: Vec<(SubscriptionId, UnlockCallback)>,
+    next_subscription: SubscriptionId,
     dirty: bool,
 }
 
@@ -71,7 +316,20 @@ impl AchievementManager {
                 name: definition.name,
                 description: definition.description,
                 trigger: definition.trigger,
+                category: definition.category,
+                rarity: definition.rarity,
+                chain: definition.chain,
+                tier: definition.tier,
+                goal: definition.goal,
+                threshold: definition.threshold,
+                condition: definition.condition,
+                hidden: definition.hidden,
+                points: definition.points,
+                icon: definition.icon,
+                locked_icon: definition.locked_icon,
                 unlocked: false,
+                unlocked_at: None,
+                progress: 0,
             })
             .collect();
 
@@ -79,9 +337,12 @@ impl AchievementManager {
     }
 
     fn from_records(records: Vec<AchievementRecord>) -> Result<Self, String> {
-        let mut achievements = Vec::with_capacity(records.len());
+        let mut achievements: Vec<AchievementState> = Vec::with_capacity(records.len());
         let mut id_lookup = HashMap::with_capacity(records.len());
         let mut trigger_lookup: HashMap<String, Vec<String>> = HashMap::new();
+        let mut chain_lookup: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut counters: HashMap<String, u32> = HashMap::new();
+        let mut conditions = Vec::new();
 
         for record in records {
             let id = record.id.trim();
@@ -100,8 +361,80 @@ impl AchievementManager {
                     .trigger
                     .map(|value| value.trim().to_owned())
                     .filter(|value| !value.is_empty()),
+                category: record
+                    .category
+                    .map(|value| value.trim().to_owned())
+                    .filter(|value| !value.is_empty()),
+                rarity: record
+                    .rarity
+                    .filter(|value| value.is_finite())
+                    .map(|value| value.clamp(0.0, 100.0)),
+                chain: record
+                    .chain
+                    .map(|value| value.trim().to_owned())
+                    .filter(|value| !value.is_empty()),
+                tier: record.tier,
+                goal: record.goal,
+                threshold: record.threshold,
+                condition: record
+                    .condition
+                    .map(|value| value.trim().to_owned())
+                    .filter(|value| !value.is_empty()),
+                hidden: record.hidden,
+                points: record.points,
+                icon: record
+                    .icon
+                    .map(|value| value.trim().to_owned())
+                    .filter(|value| !value.is_empty()),
+                locked_icon: record
+                    .locked_icon
+                    .map(|value| value.trim().to_owned())
+                    .filter(|value| !value.is_empty()),
             };
 
+            if normalized.threshold.is_some() {
+                if let Some(chain) = normalized.chain.as_deref() {
+                    return Err(format!(
+                        "achievement {} is in chain {chain} and must use goal instead of threshold",
+                        normalized.id
+                    ));
+                }
+                if normalized.trigger.is_none() {
+                    return Err(format!(
+                        "achievement {} has a threshold but no trigger",
+                        normalized.id
+                    ));
+                }
+            }
+
+            if let Some(source) = normalized.condition.as_deref() {
+                let condition = Condition::parse(source)
+                    .and_then(validate_condition)
+                    .map_err(|err| format!("achievement {}: {err}", normalized.id))?;
+                conditions.push((achievements.len(), condition));
+            }
+
+            if let Some(chain) = normalized.chain.as_deref() {
+                let Some(tier) = normalized.tier else {
+                    return Err(format!(
+                        "achievement {} is in chain {chain} but has no tier",
+                        normalized.id
+                    ));
+                };
+                let tiers = chain_lookup.entry(chain.to_owned()).or_default();
+                if tiers
+                    .iter()
+                    .any(|index| achievements[*index].definition.tier == Some(tier))
+                {
+                    return Err(format!(
+                        "duplicate {tier:?} tier in achievement chain {chain}"
+                    ));
+                }
+                tiers.push(achievements.len());
+                let counter = counters.entry(chain.to_owned()).or_default();
+                *counter = (*counter).max(record.progress);
+            }
+
             if let Some(trigger) = normalized.trigger.as_deref() {
                 trigger_lookup
                     .entry(trigger.to_owned())
@@ -112,50 +445,88 @@ impl AchievementManager {
             id_lookup.insert(normalized.id.clone(), achievements.len());
             achievements.push(AchievementState {
                 definition: normalized,
+                hidden: record.hidden,
                 unlocked: record.unlocked,
+                unlocked_at: record.unlocked_at.filter(|_| record.unlocked),
             });
         }
 
+        for tiers in chain_lookup.values_mut() {
+            tiers.sort_by_key(|index| achievements[*index].definition.tier);
+        }
+
         Ok(Self {
             achievements,
             id_lookup,
             trigger_lookup,
+            chain_lookup,
+            counters,
+            trigger_counters: HashMap::new(),
+            conditions,
+            condition_inputs: None,
+            orphaned_progress: ProgressFile::default(),
             notifications: VecDeque::new(),
+            subscribers: Vec::new(),
+            next_subscription: 0,
             dirty: false,
         })
     }
 
     pub fn load_from_json_file(path: impl AsRef<Path>) -> Result<Self, String> {
-        let path = path.as_ref();
-        let raw = fs::read_to_string(path)
-            .map_err(|err| format!("failed to read achievements file {}: {err}", path.display()))?;
-
-        let parsed: AchievementFileFormat = serde_json::from_str(&raw).map_err(|err| {
-            format!(
-                "failed to parse achievements json {}: {err}",
-                path.display()
-            )
+        let file = save::read_with_backup(path.as_ref(), "achievements file", |path, raw| {
+            serde_json::from_str(raw)
+                .map_err(|err| err.to_string())
+                .and_then(migrate_achievements_file)
+                .map_err(|err| {
+                    format!(
+                        "failed to parse achievements json {}: {err}",
+                        path.display()
+                    )
+                })
         })?;
 
-        let records = match parsed {
-            AchievementFileFormat::List(list) => list,
-            AchievementFileFormat::WithRoot { achievements } => achievements,
-        };
-
-        Self::from_records(records)
+        Self::from_records(file.achievements)
     }
 
     pub fn snapshot(&self) -> Vec<AchievementSnapshotItem> {
         self.achievements
             .iter()
-            .map(|entry| AchievementSnapshotItem {
-                name: entry.definition.name.clone(),
-                description: entry.definition.description.clone(),
-                unlocked: entry.unlocked,
+            .map(|entry| {
+                let masked = entry.hidden && !entry.unlocked;
+                let reveal = |text: &String| if masked { String::new() } else { text.clone() };
+                let reveal_icon = |icon: &Option<String>| icon.clone().filter(|_| !masked);
+                AchievementSnapshotItem {
+                    id: entry.definition.id.clone(),
+                    name: reveal(&entry.definition.name),
+                    description: reveal(&entry.definition.description),
+                    hidden: entry.hidden,
+                    unlocked: entry.unlocked,
+                    category: entry.definition.category.clone(),
+                    rarity: entry.definition.rarity,
+                    points: entry.definition.points,
+                    icon: reveal_icon(&entry.definition.icon),
+                    locked_icon: reveal_icon(&entry.definition.locked_icon),
+                    unlocked_at: entry.unlocked_at,
+                    chain: entry.definition.chain.clone(),
+                    tier: entry.definition.tier,
+                    goal: entry.definition.goal.or(entry.definition.threshold),
+                    progress: self.counter_progress(&entry.definition),
+                }
             })
             .collect()
     }
 
+    fn counter_progress(&self, definition: &AchievementDefinition) -> u32 {
+        let counter = match (definition.chain.as_deref(), definition.trigger.as_deref()) {
+            (Some(chain), _) => self.counters.get(chain),
+            (None, Some(trigger)) if definition.threshold.is_some() => {
+                self.trigger_counters.get(trigger)
+            }
+            _ => None,
+        };
+        counter.copied().unwrap_or(0)
+    }
+
     pub fn is_unlocked(&self, achievement_id: &str) -> bool {
         let Some(index) = self.id_lookup.get(achievement_id).copied() else {
             return false;
@@ -168,12 +539,38 @@ impl AchievementManager {
     }
 
     pub fn trigger(&mut self, trigger_id: &str) -> Vec<String> {
+        self.trigger_with(trigger_id, 1)
+    }
+
+    // Fires a trigger carrying `amount`, e.g. lines read since the last call. Counted
+    // chain tiers and threshold achievements add it to their counter; the rest unlock
+    // outright.
+    pub fn trigger_with(&mut self, trigger_id: &str, amount: u32) -> Vec<String> {
         let Some(target_ids) = self.trigger_lookup.get(trigger_id).cloned() else {
             return Vec::new();
         };
 
         let mut unlocked_ids = Vec::new();
+        let mut counted_chains: Vec<String> = Vec::new();
+        let mut trigger_total = None;
         for achievement_id in target_ids {
+            let definition = &self.achievements[self.id_lookup[&achievement_id]].definition;
+            // Counted tiers bump their chain once per trigger, however many tiers listen.
+            if let (Some(chain), Some(_)) = (definition.chain.clone(), definition.goal) {
+                if !counted_chains.contains(&chain) {
+                    unlocked_ids.extend(self.advance_chain(&chain, amount));
+                    counted_chains.push(chain);
+                }
+                continue;
+            }
+            if let Some(threshold) = definition.threshold {
+                let total =
+                    *trigger_total.get_or_insert_with(|| self.advance_trigger(trigger_id, amount));
+                if total >= threshold && self.grant_internal(&achievement_id) {
+                    unlocked_ids.push(achievement_id);
+                }
+                continue;
+            }
             if self.grant_internal(&achievement_id) {
                 unlocked_ids.push(achievement_id);
             }
@@ -182,6 +579,93 @@ impl AchievementManager {
         unlocked_ids
     }
 
+    // Adds `amount` to a chain counter and unlocks every tier whose goal it reaches.
+    #[allow(dead_code)]
+    pub fn add_progress(&mut self, chain: &str, amount: u32) -> Result<Vec<String>, String> {
+        if !self.chain_lookup.contains_key(chain) {
+            return Err(format!("achievement chain not found: {chain}"));
+        }
+
+        Ok(self.advance_chain(chain, amount))
+    }
+
+    // Raises a chain counter to an externally tracked value, e.g. a lifetime statistic.
+    // Unknown chains are ignored so every stat can be offered without a matching chain.
+    pub fn sync_progress(&mut self, chain: &str, value: u32) -> Vec<String> {
+        if !self.chain_lookup.contains_key(chain) {
+            return Vec::new();
+        }
+        let current = self.counters.get(chain).copied().unwrap_or(0);
+        if value <= current {
+            return Vec::new();
+        }
+
+        self.advance_chain(chain, value - current)
+    }
+
+    fn advance_trigger(&mut self, trigger_id: &str, amount: u32) -> u32 {
+        let counter = self
+            .trigger_counters
+            .entry(trigger_id.to_owned())
+            .or_default();
+        if amount > 0 {
+            *counter = counter.saturating_add(amount);
+            self.dirty = true;
+        }
+        *counter
+    }
+
+    fn advance_chain(&mut self, chain: &str, amount: u32) -> Vec<String> {
+        let counter = self.counters.entry(chain.to_owned()).or_default();
+        let progress = counter.saturating_add(amount);
+        if progress == *counter {
+            return Vec::new();
+        }
+        *counter = progress;
+        self.dirty = true;
+
+        let reached: Vec<String> = self.chain_lookup[chain]
+            .iter()
+            .map(|index| &self.achievements[*index].definition)
+            .filter(|definition| definition.goal.is_some_and(|goal| progress >= goal))
+            .map(|definition| definition.id.clone())
+            .collect();
+        reached
+            .into_iter()
+            .filter(|achievement_id| self.grant_internal(achievement_id))
+            .collect()
+    }
+
+    // Unlocks achievements whose condition holds. Cheap to call every frame: the pass
+    // only runs when a stat value or flag changed since the last one.
+    pub fn evaluate_conditions(&mut self, stats: &StatsManager, flags: &FlagStore) -> Vec<String> {
+        let inputs = (Stat::ALL.map(|stat| stats.value(stat)), flags.revision());
+        if self.conditions.is_empty() || self.condition_inputs == Some(inputs) {
+            return Vec::new();
+        }
+        self.condition_inputs = Some(inputs);
+
+        let resolve = |name: &str| match name.split_once('.') {
+            Some(("stats", key)) => Stat::ALL
+                .into_iter()
+                .find(|stat| stat.key() == key)
+                .map(|stat| stats.value(stat).to_string()),
+            Some(("flags", key)) => flags.get(key).map(str::to_owned),
+            _ => None,
+        };
+        let satisfied: Vec<String> = self
+            .conditions
+            .iter()
+            .filter(|(index, _)| !self.achievements[*index].unlocked)
+            .filter(|(_, condition)| condition.evaluate(&resolve))
+            .map(|(index, _)| self.achievements[*index].definition.id.clone())
+            .collect();
+        satisfied
+            .into_iter()
+            .filter(|achievement_id| self.grant_internal(achievement_id))
+            .collect()
+    }
+
     pub fn grant(&mut self, achievement_id: &str) -> Result<bool, String> {
         if !self.id_lookup.contains_key(achievement_id) {
             return Err(format!("achievement not found: {achievement_id}"));
@@ -190,43 +674,234 @@ impl AchievementManager {
         Ok(self.grant_internal(achievement_id))
     }
 
+    // Locks every achievement and zeroes chain counters. Chains fed by statistics
+    // catch up again on their next sync.
+    pub fn reset_all(&mut self) {
+        for entry in &mut self.achievements {
+            entry.unlocked = false;
+            entry.unlocked_at = None;
+        }
+        self.counters.clear();
+        self.trigger_counters.clear();
+        self.condition_inputs = None;
+        self.orphaned_progress = ProgressFile::default();
+        self.notifications.clear();
+        self.dirty = true;
+    }
+
+    // Locks one achievement. Higher tiers of its chain are locked with it and the chain
+    // or trigger counter drops below its goal, so the next event does not re-unlock it.
+    #[allow(dead_code)]
+    pub fn reset(&mut self, achievement_id: &str) -> Result<bool, String> {
+        let Some(index) = self.id_lookup.get(achievement_id).copied() else {
+            return Err(format!("achievement not found: {achievement_id}"));
+        };
+
+        let definition = &self.achievements[index].definition;
+        let mut targets = vec![index];
+        if let (Some(trigger), Some(threshold)) =
+            (definition.trigger.as_deref(), definition.threshold)
+            && let Some(counter) = self.trigger_counters.get_mut(trigger)
+        {
+            *counter = (*counter).min(threshold.saturating_sub(1));
+        }
+        if let (Some(chain), Some(tier)) = (definition.chain.clone(), definition.tier) {
+            if let (Some(goal), Some(counter)) = (definition.goal, self.counters.get_mut(&chain)) {
+                *counter = (*counter).min(goal.saturating_sub(1));
+            }
+            targets.extend(
+                self.chain_lookup[&chain]
+                    .iter()
+                    .copied()
+                    .filter(|other| self.achievements[*other].definition.tier > Some(tier)),
+            );
+        }
+
+        let mut changed = false;
+        for index in targets {
+            let entry = &mut self.achievements[index];
+            changed |= entry.unlocked;
+            entry.unlocked = false;
+            entry.unlocked_at = None;
+        }
+        self.dirty = true;
+        Ok(changed)
+    }
+
+    // Calls `callback` for every unlock from now on, right when it happens.
+    pub fn subscribe(
+        &mut self,
+        callback: impl FnMut(&AchievementUnlocked) + 'static,
+    ) -> SubscriptionId {
+        let id = self.next_subscription;
+        self.next_subscription += 1;
+        self.subscribers.push((id, Box::new(callback)));
+        id
+    }
+
+    #[allow(dead_code)]
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let count = self.subscribers.len();
+        self.subscribers
+            .retain(|(subscription, _)| *subscription != id);
+        self.subscribers.len() != count
+    }
+
+    fn publish_unlock(&mut self, index: usize, silent: bool) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+        let definition = &self.achievements[index].definition;
+        let event = AchievementUnlocked {
+            id: definition.id.clone(),
+            name: definition.name.clone(),
+            points: definition.points,
+            silent,
+        };
+        for (_, callback) in &mut self.subscribers {
+            callback(&event);
+        }
+    }
+
     pub fn take_notifications(&mut self) -> Vec<AchievementNotification> {
         self.notifications.drain(..).collect()
     }
 
-    pub fn save_to_json_file(&mut self, path: impl AsRef<Path>) -> Result<bool, String> {
+    // Replaces the unlock state with the player's progress file. Without one, unlocks
+    // imported from an old combined catalog are kept and written out on the next save.
+    pub fn load_progress_from_json_file(&mut self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        if !vfs::exists(path) && !vfs::exists(save::backup_path(path)) {
+            self.dirty = true;
+            return Ok(());
+        }
+
+        let progress: ProgressFile =
+            save::read_signed_with_backup(path, "achievement progress", |path, raw| {
+                serde_json::from_str(raw).map_err(|err| {
+                    format!(
+                        "failed to parse achievement progress {}: {err}",
+                        path.display()
+                    )
+                })
+            })?;
+        if progress.version > PROGRESS_FILE_VERSION {
+            return Err(format!(
+                "achievement progress version {} is newer than supported version {PROGRESS_FILE_VERSION}",
+                progress.version
+            ));
+        }
+
+        self.apply_progress(progress);
+        Ok(())
+    }
+
+    // Swaps in a re-read catalog while keeping unlocks, counters and pending toasts, e.g.
+    // after a designer edited the file.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    pub fn merge_catalog(&mut self, catalog: AchievementManager) {
+        let progress = self.progress_file();
+        let notifications = std::mem::take(&mut self.notifications);
+        let subscribers = std::mem::take(&mut self.subscribers);
+        let next_subscription = self.next_subscription;
+        let dirty = self.dirty;
+        *self = catalog;
+        self.apply_progress(progress);
+        self.notifications = notifications;
+        self.subscribers = subscribers;
+        self.next_subscription = next_subscription;
+        self.dirty = dirty;
+    }
+
+    fn apply_progress(&mut self, progress: ProgressFile) {
+        for entry in &mut self.achievements {
+            entry.unlocked = false;
+            entry.unlocked_at = None;
+        }
+        self.counters.clear();
+        self.trigger_counters.clear();
+        self.orphaned_progress = ProgressFile::default();
+
+        for (id, unlocked_at) in progress.unlocked {
+            match self.id_lookup.get(&id) {
+                Some(index) => {
+                    let entry = &mut self.achievements[*index];
+                    entry.unlocked = true;
+                    entry.unlocked_at = unlocked_at;
+                }
+                None => {
+                    self.orphaned_progress.unlocked.insert(id, unlocked_at);
+                }
+            }
+        }
+        for (chain, value) in progress.counters {
+            if self.chain_lookup.contains_key(&chain) {
+                self.counters.insert(chain, value);
+            } else {
+                self.orphaned_progress.counters.insert(chain, value);
+            }
+        }
+        for (trigger, value) in progress.triggers {
+            if self.trigger_lookup.contains_key(&trigger) {
+                self.trigger_counters.insert(trigger, value);
+            } else {
+                self.orphaned_progress.triggers.insert(trigger, value);
+            }
+        }
+    }
+
+    fn progress_file(&self) -> ProgressFile {
+        let mut unlocked = self.orphaned_progress.unlocked.clone();
+        unlocked.extend(
+            self.achievements
+                .iter()
+                .filter(|entry| entry.unlocked)
+                .map(|entry| (entry.definition.id.clone(), entry.unlocked_at)),
+        );
+        let mut counters = self.orphaned_progress.counters.clone();
+        counters.extend(
+            self.counters
+                .iter()
+                .filter(|(_, value)| **value > 0)
+                .map(|(chain, value)| (chain.clone(), *value)),
+        );
+        let mut triggers = self.orphaned_progress.triggers.clone();
+        triggers.extend(
+            self.trigger_counters
+                .iter()
+                .filter(|(_, value)| **value > 0)
+                .map(|(trigger, value)| (trigger.clone(), *value)),
+        );
+        ProgressFile {
+            version: PROGRESS_FILE_VERSION,
+            unlocked,
+            counters,
+            triggers,
+        }
+    }
+
+    // Writes the progress file; the catalog itself is never written back.
+    pub fn save_progress_to_json_file(&mut self, path: impl AsRef<Path>) -> Result<bool, String> {
         if !self.dirty {
             return Ok(false);
         }
 
         let path = path.as_ref();
         if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).map_err(|err| {
+            vfs::create_dir_all(parent).map_err(|err| {
                 format!(
-                    "failed to create achievements directory {}: {err}",
+                    "failed to create achievement progress directory {}: {err}",
                     parent.display()
                 )
             })?;
         }
 
-        let records: Vec<AchievementRecord> = self
-            .achievements
-            .iter()
-            .map(|entry| AchievementRecord {
-                id: entry.definition.id.clone(),
-                name: entry.definition.name.clone(),
-                description: entry.definition.description.clone(),
-                trigger: entry.definition.trigger.clone(),
-                unlocked: entry.unlocked,
-            })
-            .collect();
-
-        let json = serde_json::to_string_pretty(&records)
-            .map_err(|err| format!("failed to serialize achievements: {err}"))?;
+        let json = serde_json::to_string_pretty(&self.progress_file())
+            .map_err(|err| format!("failed to serialize achievement progress: {err}"))?;
 
-        fs::write(path, json).map_err(|err| {
+        save::write_signed(path, &json).map_err(|err| {
             format!(
-                "failed to write achievements json {}: {err}",
+                "failed to write achievement progress {}: {err}",
                 path.display()
             )
         })?;
@@ -236,6 +911,31 @@ impl AchievementManager {
     }
 
     fn grant_internal(&mut self, achievement_id: &str) -> bool {
+        self.unlock(achievement_id, true)
+    }
+
+    // Unlocks without a notification, for unlocks earned elsewhere (e.g. on Steam).
+    #[allow(dead_code)]
+    pub fn restore_unlocked(&mut self, achievement_id: &str) -> bool {
+        self.unlock(achievement_id, false)
+    }
+
+    #[allow(dead_code)]
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.achievements
+            .iter()
+            .map(|entry| entry.definition.id.as_str())
+    }
+
+    #[allow(dead_code)]
+    pub fn unlocked_ids(&self) -> impl Iterator<Item = &str> {
+        self.achievements
+            .iter()
+            .filter(|entry| entry.unlocked)
+            .map(|entry| entry.definition.id.as_str())
+    }
+
+    fn unlock(&mut self, achievement_id: &str, notify: bool) -> bool {
         let Some(index) = self.id_lookup.get(achievement_id).copied() else {
             return false;
         };
@@ -248,13 +948,56 @@ impl AchievementManager {
             return false;
         }
 
+        let unlocked_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|elapsed| elapsed.as_secs());
         entry.unlocked = true;
+        entry.unlocked_at = unlocked_at;
         self.dirty = true;
-        self.notifications.push_back(AchievementNotification {
-            name: entry.definition.name.clone(),
-            description: entry.definition.description.clone(),
-        });
+        if notify {
+            self.notifications.push_back(AchievementNotification {
+                name: entry.definition.name.clone(),
+                description: entry.definition.description.clone(),
+            });
+        }
+
+        let goal = entry.definition.goal;
+        let chain = entry.definition.chain.clone().zip(entry.definition.tier);
+        self.publish_unlock(index, !notify);
+        if let Some((chain, tier)) = chain {
+            self.unlock_lower_tiers(&chain, tier, goal, unlocked_at);
+        }
 
         true
     }
+
+    // A granted tier implies the ones below it: they unlock silently (the higher tier
+    // already notified) and the shared counter catches up to the granted goal.
+    fn unlock_lower_tiers(
+        &mut self,
+        chain: &str,
+        tier: AchievementTier,
+        goal: Option<u32>,
+        unlocked_at: Option<u64>,
+    ) {
+        if let Some(goal) = goal {
+            let counter = self.counters.entry(chain.to_owned()).or_default();
+            *counter = (*counter).max(goal);
+        }
+        let lower_tiers: Vec<usize> = self.chain_lookup[chain]
+            .iter()
+            .copied()
+            .filter(|index| {
+                let lower = &self.achievements[*index];
+                lower.definition.tier < Some(tier) && !lower.unlocked
+            })
+            .collect();
+        for index in lower_tiers {
+            let lower = &mut self.achievements[index];
+            lower.unlocked = true;
+            lower.unlocked_at = unlocked_at;
+            self.publish_unlock(index, true);
+        }
+    }
 }
@@ -1,22 +1,804 @@
 use std::{
-    collections::HashMap,
-    fs,
+    collections::{HashMap, VecDeque},
+    f32::consts::FRAC_PI_2,
     io::{BufReader, Cursor},
-    path::Path,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU32, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
     time::Duration,
 };
+use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sample, Sink, Source};
+use serde::Deserialize;
+
+use crate::audio_source::{
+    EffectParams, Effected, Envelope, LiveGain, LoopRegion, Panned, PcmClip, PcmSource, ToneSource,
+    ToneSpec, Waveform,
+};
 
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use crate::vfs;
+
+pub const DEFAULT_AUDIO_MANIFEST_PATH: &str = "src/data/audio.json";
 
 enum SoundClip {
     // Keep bytes in memory so playback has no file IO.
     FileBytes(Arc<[u8]>),
-    // Synthesized fallback tone for simple UI sounds.
-    Tone {
-        frequency_hz: u32,
-        duration: Duration,
+    // Decoded up front, e.g. to loop a region after an intro.
+    Decoded {
+        pcm: PcmClip,
+        loop_region: Option<LoopRegion>,
     },
+    // Synthesized fallback tone for simple UI sounds.
+    Tone(ToneSpec),
+}
+
+// Clip prepared by the loader thread.
+struct LoadJob {
+    token: u64,
+    sound_id: String,
+    source: LoadSource,
+}
+
+enum LoadSource {
+    // Read the file, and with `decode` also decode it.
+    File { path: PathBuf, decode: bool },
+    // Decode file bytes already in memory.
+    Bytes(Arc<[u8]>),
+}
+
+struct LoadDone {
+    token: u64,
+    sound_id: String,
+    result: Result<SoundClip, String>,
+}
+
+// Called on the main thread from update() once the file is registered or has failed.
+type LoadCallback = Box<dyn FnOnce(&str, Result<(), String>)>;
+
+struct PendingLoad {
+    // A later registration of the same id supersedes this load.
+    token: u64,
+    on_done: LoadCallback,
+}
+
+// Single background thread that reads sound files off the main thread.
+struct AudioLoader {
+    jobs: Sender<LoadJob>,
+    done: Receiver<LoadDone>,
+}
+
+impl AudioLoader {
+    fn spawn() -> Result<Self, String> {
+        let (jobs, job_queue) = mpsc::channel::<LoadJob>();
+        let (done_sender, done) = mpsc::channel();
+        thread::Builder::new()
+            .name("audio-loader".to_owned())
+            .spawn(move || {
+                // Ends when the engine drops its job sender.
+                for job in job_queue {
+                    let result = match job.source {
+                        LoadSource::File { path, decode } => {
+                            load_clip(&job.sound_id, &path, decode)
+                        }
+                        LoadSource::Bytes(bytes) => {
+                            decode_pcm(&job.sound_id, &bytes).map(|pcm| SoundClip::Decoded {
+                                pcm,
+                                loop_region: None,
+                            })
+                        }
+                    };
+                    let done = LoadDone {
+                        token: job.token,
+                        sound_id: job.sound_id,
+                        result,
+                    };
+                    if done_sender.send(done).is_err() {
+                        break;
+                    }
+                }
+            })
+            .map_err(|err| format!("failed to start audio loader thread: {err}"))?;
+        Ok(Self { jobs, done })
+    }
+}
+
+// Playable form of a registered clip.
+type ClipSource = Box<dyn Source<Item = f32> + Send>;
+
+// Loop cue file next to a track, in sample frames: {"loop_start": 441000, "loop_end": 1764000}.
+#[derive(Deserialize)]
+struct LoopCue {
+    loop_start: u64,
+    #[serde(default)]
+    loop_end: Option<u64>,
+}
+
+// {"sounds": [{"id": "door", "file": "assets/sfx/door.ogg", "bus": "sfx", "volume": 0.8,
+//   "pitch_variance": 0.05, "max_instances": 2, "preload": true}]}
+#[derive(Deserialize)]
+struct AudioManifest {
+    sounds: Vec<ManifestSound>,
+}
+
+#[derive(Deserialize)]
+struct ManifestSound {
+    id: String,
+    #[serde(default)]
+    file: Option<String>,
+    // Synthesized sound; with a file too it is the fallback when the file is missing.
+    #[serde(default)]
+    tone: Option<ManifestTone>,
+    #[serde(default)]
+    bus: Option<AudioBus>,
+    #[serde(default)]
+    volume: Option<f32>,
+    #[serde(default)]
+    pitch_variance: Option<f32>,
+    #[serde(default)]
+    max_instances: Option<usize>,
+    #[serde(default)]
+    preload: bool,
+    // Shown as a toast when captions are on, e.g. "[door creaks]".
+    #[serde(default)]
+    caption: Option<String>,
+    // Music tempo for beat events.
+    #[serde(default)]
+    bpm: Option<f32>,
+    #[serde(default)]
+    beat_offset_ms: u64,
+}
+
+// {"frequency_hz": 880, "duration_ms": 14, "waveform": "square", "sweep_to_hz": 1320}
+#[derive(Deserialize)]
+struct ManifestTone {
+    frequency_hz: f32,
+    duration_ms: u64,
+    #[serde(default)]
+    waveform: Waveform,
+    #[serde(default)]
+    sweep_to_hz: Option<f32>,
+    #[serde(default)]
+    attack_ms: Option<u64>,
+    #[serde(default)]
+    release_ms: Option<u64>,
+    #[serde(default)]
+    volume: Option<f32>,
+}
+
+impl ManifestTone {
+    fn spec(&self) -> ToneSpec {
+        let mut envelope = Envelope::default();
+        if let Some(attack_ms) = self.attack_ms {
+            envelope.attack = Duration::from_millis(attack_ms);
+        }
+        if let Some(release_ms) = self.release_ms {
+            envelope.release = Duration::from_millis(release_ms);
+        }
+        let mut spec = ToneSpec::new(self.frequency_hz, Duration::from_millis(self.duration_ms))
+            .with_waveform(self.waveform)
+            .with_envelope(envelope);
+        if let Some(to_hz) = self.sweep_to_hz {
+            spec = spec.with_sweep(to_hz);
+        }
+        if let Some(volume) = self.volume {
+            spec = spec.with_volume(volume);
+        }
+        spec
+    }
+}
+
+// Identifies a sound started with play().
+pub type PlaybackId = u64;
+
+// Reported by AudioEngine::finished_sounds() for the frame in which a sound ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlaybackFinished {
+    pub id: PlaybackId,
+    pub sound_id: String,
+    pub bus: AudioBus,
+}
+
+// Tempo of a music track for beat events; `offset` is where the first beat falls.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MusicTempo {
+    pub bpm: f32,
+    pub offset: Duration,
+    pub beats_per_bar: u32,
+}
+
+#[allow(dead_code)]
+impl MusicTempo {
+    pub fn new(bpm: f32) -> Self {
+        Self {
+            bpm,
+            offset: Duration::ZERO,
+            beats_per_bar: 4,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Duration) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_beats_per_bar(mut self, beats_per_bar: u32) -> Self {
+        self.beats_per_bar = beats_per_bar.max(1);
+        self
+    }
+
+    // Beats elapsed at `position`; None before the first beat.
+    fn beats_at(&self, position: Duration) -> Option<f32> {
+        let since_first = position.checked_sub(self.offset)?;
+        Some(since_first.as_secs_f32() * self.bpm.max(1.0) / 60.0)
+    }
+}
+
+// Reported by AudioEngine::beat_events() for the frame in which a music beat passed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BeatEvent {
+    pub sound_id: String,
+    // Counted from 0 at the track's first beat.
+    pub beat: u64,
+    pub bar: u64,
+    pub beat_in_bar: u32,
+}
+
+// Mixer group with its own volume; every bus is scaled by the master volume.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioBus {
+    Music,
+    // Looping background layers started with set_ambient_layer().
+    Ambience,
+    Sfx,
+    Voice,
+    Ui,
+}
+
+impl AudioBus {
+    pub const ALL: [AudioBus; 5] = [
+        Self::Music,
+        Self::Ambience,
+        Self::Sfx,
+        Self::Voice,
+        Self::Ui,
+    ];
+}
+
+// Options for AudioEngine::play_with().
+#[derive(Clone, Copy, Debug)]
+pub struct PlayOptions {
+    pub volume: f32,
+    // Ramp from silence instead of starting at full volume.
+    pub fade_in: Duration,
+    // Playback rate; like a tape, it shifts pitch along with speed.
+    pub speed: f32,
+    // Stereo balance from -1.0 (left) to 1.0 (right).
+    pub pan: f32,
+    // Repeat until stopped, honoring the clip's loop region.
+    pub looped: bool,
+}
+
+impl Default for PlayOptions {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            fade_in: Duration::ZERO,
+            speed: 1.0,
+            pan: 0.0,
+            looped: false,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl PlayOptions {
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn with_fade_in(mut self, duration: Duration) -> Self {
+        self.fade_in = duration;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_pan(mut self, pan: f32) -> Self {
+        self.pan = pan;
+        self
+    }
+
+    pub fn with_looped(mut self, looped: bool) -> Self {
+        self.looped = looped;
+        self
+    }
+}
+
+// What play() does when a sound already has its maximum number of instances playing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VoiceStealing {
+    // Fade out the oldest instance to make room for the new one.
+    #[default]
+    StopOldest,
+    // Keep the playing instances and fail the new play() call.
+    #[allow(dead_code)]
+    RejectNew,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstanceLimit {
+    pub max_instances: usize,
+    pub stealing: VoiceStealing,
+}
+
+// Per-sound settings applied by play() and play_with(), so every call site plays a sound
+// the same way.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SoundDefaults {
+    // None keeps the bus the caller plays it on.
+    pub bus: Option<AudioBus>,
+    pub volume: f32,
+    // Each play shifts the speed by a random amount of up to this much either way.
+    pub pitch_variance: f32,
+    pub instance_limit: Option<InstanceLimit>,
+}
+
+impl Default for SoundDefaults {
+    fn default() -> Self {
+        Self {
+            bus: None,
+            volume: 1.0,
+            pitch_variance: 0.0,
+            instance_limit: None,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl SoundDefaults {
+    pub fn with_bus(mut self, bus: AudioBus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume.max(0.0);
+        self
+    }
+
+    pub fn with_pitch_variance(mut self, variance: f32) -> Self {
+        self.pitch_variance = variance.clamp(0.0, 0.9);
+        self
+    }
+
+    // Extra instances steal the oldest one.
+    pub fn with_max_instances(mut self, max_instances: usize) -> Self {
+        self.instance_limit = Some(InstanceLimit {
+            max_instances,
+            stealing: VoiceStealing::StopOldest,
+        });
+        self
+    }
+
+    pub fn with_instance_limit(mut self, limit: InstanceLimit) -> Self {
+        self.instance_limit = Some(limit);
+        self
+    }
+}
+
+// Quieter plays than this (after bus and master volume) are not captioned.
+const CAPTION_MIN_VOLUME: f32 = 0.05;
+// Captions not taken by the UI are dropped past this many.
+const MAX_PENDING_CAPTIONS: usize = 8;
+
+// Idle sinks still take a mixer input each, so only a few are kept.
+const MAX_IDLE_SINKS: usize = 16;
+
+// Stolen instances fade this fast, so the steal never clicks.
+const VOICE_STEAL_FADE: Duration = Duration::from_millis(12);
+
+// Sounds attached to a scene object pan across this fraction of the stereo field
+// as the object crosses the screen.
+const POSITIONAL_PAN_RANGE: f32 = 0.8;
+// Screen widths past the edge over which an attached sound fades to silence.
+const POSITIONAL_FALLOFF: f32 = 1.0;
+
+fn clamp_speed(speed: f32) -> f32 {
+    if speed.is_finite() {
+        speed.clamp(0.05, 8.0)
+    } else {
+        1.0
+    }
+}
+
+// Linear ramp of a sound's fade gain.
+struct SoundFade {
+    from: f32,
+    to: f32,
+    started: Instant,
+    duration: Duration,
+    stop_when_done: bool,
+}
+
+struct SoundGains {
+    // Per-sound gain set through the handle.
+    volume: f32,
+    // Bus and master volume last applied by the engine.
+    mix: f32,
+    // Fade-in/out gain, driven by `ramp` while one is running.
+    fade: f32,
+    ramp: Option<SoundFade>,
+    // Distance attenuation of sounds attached to a scene object.
+    spatial: f32,
+}
+
+impl SoundGains {
+    fn output(&self) -> f32 {
+        self.volume * self.mix * self.fade * self.spatial
+    }
+}
+
+struct PlayingSound {
+    sink: Sink,
+    sound_id: String,
+    bus: AudioBus,
+    gains: Mutex<SoundGains>,
+    // Read by the Panned source on the audio thread.
+    pan: Arc<AtomicU32>,
+}
+
+impl PlayingSound {
+    fn gains(&self) -> MutexGuard<'_, SoundGains> {
+        self.gains.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn set_mix(&self, mix: f32) {
+        let mut gains = self.gains();
+        gains.mix = mix;
+        self.sink.set_volume(gains.output());
+    }
+
+    fn set_pan(&self, pan: f32) {
+        let pan = if pan.is_finite() {
+            pan.clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        self.pan.store(pan.to_bits(), Ordering::Relaxed);
+    }
+
+    fn set_spatial(&self, spatial: f32) {
+        let mut gains = self.gains();
+        gains.spatial = spatial;
+        self.sink.set_volume(gains.output());
+    }
+
+    fn start_fade(&self, to: f32, duration: Duration, stop_when_done: bool) {
+        let mut gains = self.gains();
+        gains.ramp = Some(SoundFade {
+            from: gains.fade,
+            to,
+            started: Instant::now(),
+            duration,
+            stop_when_done,
+        });
+    }
+
+    // Already fading out to a stop, e.g. after being stolen.
+    fn is_stopping(&self) -> bool {
+        self.gains()
+            .ramp
+            .as_ref()
+            .is_some_and(|ramp| ramp.stop_when_done)
+    }
+
+    // Moves the fade gain along its ramp; returns whether a ramp is still running.
+    fn step_fade(&self) -> bool {
+        let mut gains = self.gains();
+        let Some(ramp) = &gains.ramp else {
+            return false;
+        };
+        let t = (ramp.started.elapsed().as_secs_f32() / ramp.duration.as_secs_f32()).min(1.0);
+        let fade = ramp.from + (ramp.to - ramp.from) * t;
+        let stop = ramp.stop_when_done;
+
+        gains.fade = fade;
+        self.sink.set_volume(gains.output());
+        if t < 1.0 {
+            return true;
+        }
+        gains.ramp = None;
+        if stop {
+            self.sink.stop();
+        }
+        false
+    }
+}
+
+// Control over a sound started with AudioEngine::play(). Dropping the handle does not
+// stop the sound; the engine keeps it alive until it finishes.
+#[derive(Clone)]
+pub struct SoundHandle {
+    id: PlaybackId,
+    sound: Arc<PlayingSound>,
+}
+
+#[allow(dead_code)]
+impl SoundHandle {
+    pub fn id(&self) -> PlaybackId {
+        self.id
+    }
+
+    pub fn stop(&self) {
+        self.sound.sink.stop();
+    }
+
+    pub fn pause(&self) {
+        self.sound.sink.pause();
+    }
+
+    pub fn resume(&self) {
+        self.sound.sink.play();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.sound.sink.is_paused()
+    }
+
+    // Gain before bus and master volume.
+    pub fn set_volume(&self, volume: f32) {
+        let mut gains = self.sound.gains();
+        gains.volume = volume.max(0.0);
+        self.sound.sink.set_volume(gains.output());
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.sound.sink.speed()
+    }
+
+    pub fn pan(&self) -> f32 {
+        f32::from_bits(self.sound.pan.load(Ordering::Relaxed))
+    }
+
+    // -1.0 is fully left, 1.0 fully right. Overridden every frame for attached sounds.
+    pub fn set_pan(&self, pan: f32) {
+        self.sound.set_pan(pan);
+    }
+
+    // Changes pitch and tempo together.
+    pub fn set_speed(&self, speed: f32) {
+        self.sound.sink.set_speed(clamp_speed(speed));
+    }
+
+    // Ramps to silence, then stops; avoids the click of an abrupt stop().
+    pub fn stop_with_fade(&self, duration: Duration) {
+        if duration.is_zero() {
+            self.stop();
+        } else {
+            self.sound.start_fade(0.0, duration, true);
+        }
+    }
+
+    // True once the clip played out or was stopped.
+    pub fn is_finished(&self) -> bool {
+        self.sound.sink.empty()
+    }
+}
+
+// Looping background track on the dedicated music sink.
+struct MusicTrack {
+    sound_id: String,
+    sink: Sink,
+    // Playlist tracks already appended to `sink` after the current one.
+    upcoming: VecDeque<String>,
+}
+
+// Vertical-remix song playing as the current music: its stems run in sync on the music
+// sink and the intensity decides how they are blended.
+struct IntensityMusic {
+    song_id: String,
+    // Live gain of each stem, calmest first.
+    stem_gains: Vec<Arc<AtomicU32>>,
+    intensity: f32,
+    target: f32,
+}
+
+impl IntensityMusic {
+    fn apply_gains(&self) {
+        let mix = stem_mix(self.stem_gains.len(), self.intensity);
+        for (gain, value) in self.stem_gains.iter().zip(mix) {
+            gain.store(value.to_bits(), Ordering::Relaxed);
+        }
+    }
+}
+
+// Stems sit at even steps of the 0..1 intensity range; neighbors crossfade with equal
+// power so the overall loudness stays level.
+fn stem_mix(stems: usize, intensity: f32) -> impl Iterator<Item = f32> {
+    let position = intensity.clamp(0.0, 1.0) * stems.saturating_sub(1) as f32;
+    (0..stems).map(move |index| {
+        let weight = (1.0 - (position - index as f32).abs()).max(0.0);
+        (weight * FRAC_PI_2).sin()
+    })
+}
+
+// Time an intensity change takes to sweep the whole range.
+const INTENSITY_SWEEP_SECONDS: f32 = 2.0;
+
+// Music queue played back-to-back on the music sink.
+#[derive(Clone, Debug, Default)]
+pub struct Playlist {
+    tracks: Vec<String>,
+    shuffle: bool,
+    repeat: bool,
+    // Play order as indices into `tracks`; reshuffled each pass when shuffling.
+    order: Vec<usize>,
+    position: usize,
+    rng_state: u64,
+}
+
+#[allow(dead_code)]
+impl Playlist {
+    pub fn new(tracks: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let tracks: Vec<String> = tracks.into_iter().map(Into::into).collect();
+        Self {
+            order: (0..tracks.len()).collect(),
+            tracks,
+            shuffle: false,
+            repeat: true,
+            position: 0,
+            rng_state: clock_seed(),
+        }
+    }
+
+    pub fn with_shuffle(mut self, shuffle: bool) -> Self {
+        self.shuffle = shuffle;
+        self
+    }
+
+    // Start over after the last track (the default) instead of stopping.
+    pub fn with_repeat(mut self, repeat: bool) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn push(&mut self, sound_id: impl Into<String>) -> &mut Self {
+        self.order.push(self.tracks.len());
+        self.tracks.push(sound_id.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    fn next_track(&mut self) -> Option<String> {
+        if self.tracks.is_empty() {
+            return None;
+        }
+        if self.position == 0 && self.shuffle {
+            self.shuffle_order();
+        }
+        if self.position >= self.order.len() {
+            if !self.repeat {
+                return None;
+            }
+            let last = self.order.last().copied();
+            self.position = 0;
+            if self.shuffle {
+                self.shuffle_order();
+                // Do not play the same track twice in a row across passes.
+                if self.order.len() > 1 && self.order.first().copied() == last {
+                    self.order.swap(0, 1);
+                }
+            }
+        }
+        let track = self.tracks[self.order[self.position]].clone();
+        self.position += 1;
+        Some(track)
+    }
+
+    fn shuffle_order(&mut self) {
+        for i in (1..self.order.len()).rev() {
+            self.rng_state ^= self.rng_state << 13;
+            self.rng_state ^= self.rng_state >> 7;
+            self.rng_state ^= self.rng_state << 17;
+            let j = (self.rng_state % (i as u64 + 1)) as usize;
+            self.order.swap(i, j);
+        }
+    }
+}
+
+// Gain curve of a music crossfade.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum FadeCurve {
+    Linear,
+    // Keeps perceived loudness steady through the blend.
+    #[default]
+    EqualPower,
+    EaseInOut,
+}
+
+impl FadeCurve {
+    // (outgoing, incoming) gains at progress `t` in 0.0..=1.0.
+    fn gains(self, t: f32) -> (f32, f32) {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => (1.0 - t, t),
+            Self::EqualPower => ((t * FRAC_PI_2).cos(), (t * FRAC_PI_2).sin()),
+            Self::EaseInOut => {
+                let eased = t * t * (3.0 - 2.0 * t);
+                (1.0 - eased, eased)
+            }
+        }
+    }
+}
+
+// What happens to audio while the window is in the background.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FocusLossAudio {
+    KeepPlaying,
+    #[default]
+    Mute,
+    // Pauses every sound and the music, resuming them on refocus.
+    Pause,
+}
+
+impl FocusLossAudio {
+    pub const ALL: [FocusLossAudio; 3] = [Self::KeepPlaying, Self::Mute, Self::Pause];
+}
+
+// Lowers the music bus while anything plays on the voice bus.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ducking {
+    // Fraction of music volume removed while a voice plays; 0.0 disables ducking.
+    pub amount: f32,
+    // How quickly the music dips when a voice starts and recovers after it ends.
+    pub attack: Duration,
+    pub release: Duration,
+}
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Self {
+            amount: 0.5,
+            attack: Duration::from_millis(150),
+            release: Duration::from_millis(600),
+        }
+    }
+}
+
+struct MusicCrossfade {
+    // None when fading in from silence.
+    outgoing: Option<Sink>,
+    // Gain the outgoing track had when the fade started.
+    outgoing_gain: f32,
+    started: Instant,
+    duration: Duration,
+    curve: FadeCurve,
+}
+
+impl MusicCrossfade {
+    fn progress(&self) -> f32 {
+        self.started.elapsed().as_secs_f32() / self.duration.as_secs_f32()
+    }
+
+    // (outgoing, incoming) gains, before the music channel volume.
+    fn gains(&self) -> (f32, f32) {
+        let (outgoing, incoming) = self.curve.gains(self.progress());
+        (outgoing * self.outgoing_gain, incoming)
+    }
 }
 
 pub struct AudioEngine {
@@ -24,6 +806,53 @@ pub struct AudioEngine {
     _stream: OutputStream,
     handle: OutputStreamHandle,
     clips: HashMap<String, SoundClip>,
+    // Every started sound until it finishes, so handles stay valid after play() returns.
+    playing: HashMap<PlaybackId, Arc<PlayingSound>>,
+    loader: Option<AudioLoader>,
+    pending_loads: HashMap<String, PendingLoad>,
+    next_load_token: u64,
+    // Empty sinks of finished sounds, ready for the next play().
+    idle_sinks: Vec<Sink>,
+    // Sounds whose pan and volume follow a scene object, by object scene key.
+    attached: HashMap<PlaybackId, String>,
+    instance_limits: HashMap<String, InstanceLimit>,
+    sound_defaults: HashMap<String, SoundDefaults>,
+    rng_state: u64,
+    // Sounds that ended during the last update(), readable until the next one.
+    finished: Vec<PlaybackFinished>,
+    tempos: HashMap<String, MusicTempo>,
+    // Where each replaced or stopped track left off, so returning to it resumes there.
+    music_positions: HashMap<String, Duration>,
+    remember_music_positions: bool,
+    captions: HashMap<String, String>,
+    // Playing ambience loops by sound id; each layer's level is its fade gain.
+    ambience: HashMap<String, SoundHandle>,
+    captions_enabled: bool,
+    // Captions of sounds started since the last take_captions().
+    pending_captions: Vec<String>,
+    // Beats passed during the last update(), and the last beat reported for a track.
+    beats: Vec<BeatEvent>,
+    last_beat: Option<(String, u64)>,
+    next_playback_id: PlaybackId,
+    music: Option<MusicTrack>,
+    intensity_music: Option<IntensityMusic>,
+    // Feeds `music` while a playlist is active; explicit music calls clear it.
+    playlist: Option<Playlist>,
+    crossfade: Option<MusicCrossfade>,
+    master_volume: f32,
+    bus_volumes: HashMap<AudioBus, f32>,
+    // Shared with every source on the bus, so effect changes apply to sounds already playing.
+    bus_effects: HashMap<AudioBus, Arc<EffectParams>>,
+    muted: bool,
+    // Behavior applied when focus was lost, undone when it returns.
+    focus_loss: Option<FocusLossAudio>,
+    // Sounds paused by focus loss, so sounds paused through a handle stay paused.
+    focus_paused: Vec<PlaybackId>,
+    focus_paused_music: bool,
+    ducking: Ducking,
+    // Current music-bus multiplier from ducking, eased toward its target by update().
+    duck_gain: f32,
+    last_update_at: Option<Instant>,
 }
 
 impl AudioEngine {
@@ -35,6 +864,44 @@ impl AudioEngine {
             _stream: stream,
             handle,
             clips: HashMap::new(),
+            playing: HashMap::new(),
+            idle_sinks: Vec::new(),
+            loader: None,
+            pending_loads: HashMap::new(),
+            next_load_token: 0,
+            attached: HashMap::new(),
+            instance_limits: HashMap::new(),
+            sound_defaults: HashMap::new(),
+            rng_state: clock_seed(),
+            finished: Vec::new(),
+            tempos: HashMap::new(),
+            music_positions: HashMap::new(),
+            remember_music_positions: true,
+            captions: HashMap::new(),
+            ambience: HashMap::new(),
+            captions_enabled: false,
+            pending_captions: Vec::new(),
+            beats: Vec::new(),
+            last_beat: None,
+
+            next_playback_id: 0,
+            music: None,
+            intensity_music: None,
+            playlist: None,
+            crossfade: None,
+            master_volume: 1.0,
+            bus_volumes: HashMap::new(),
+            bus_effects: AudioBus::ALL
+                .into_iter()
+                .map(|bus| (bus, Arc::default()))
+                .collect(),
+            muted: false,
+            focus_loss: None,
+            focus_paused: Vec::new(),
+            focus_paused_music: false,
+            ducking: Ducking::default(),
+            duck_gain: 1.0,
+            last_update_at: None,
         })
     }
 
@@ -43,66 +910,1323 @@ impl AudioEngine {
         sound_id: impl Into<String>,
         path: impl AsRef<Path>,
     ) -> Result<(), String> {
-        let path = path.as_ref();
-        let bytes = fs::read(path)
-            .map_err(|err| format!("failed to read sound '{}': {err}", path.display()))?;
-        self.clips
-            .insert(sound_id.into(), SoundClip::FileBytes(bytes.into()));
+        let sound_id = sound_id.into();
+        let clip = load_clip(&sound_id, path.as_ref(), false)?;
+        self.pending_loads.remove(&sound_id);
+        self.clips.insert(sound_id, clip);
         Ok(())
     }
 
-    pub fn register_tone(
+    // Like register_sound_file(), but the file is read on a background thread, and with
+    // `decode` also decoded there, so large voice or music banks don't stall the frame.
+    // `on_done` runs during a later update(); the id can't be played until then.
+    #[allow(dead_code)]
+    pub fn register_sound_file_async(
         &mut self,
         sound_id: impl Into<String>,
-        frequency_hz: u32,
-        duration_ms: u64,
-    ) {
-        self.clips.insert(
-            sound_id.into(),
-            SoundClip::Tone {
-                frequency_hz,
-                duration: Duration::from_millis(duration_ms.max(1)),
-            },
-        );
+        path: impl AsRef<Path>,
+        decode: bool,
+        on_done: impl FnOnce(&str, Result<(), String>) + 'static,
+    ) -> Result<(), String> {
+        let source = LoadSource::File {
+            path: path.as_ref().to_owned(),
+            decode,
+        };
+        self.queue_load(sound_id.into(), source, Box::new(on_done))
     }
 
-    pub fn play(&self, sound_id: &str, volume: f32) -> Result<(), String> {
-        let clip = self
-            .clips
-            .get(sound_id)
-            .ok_or_else(|| format!("unknown sound id '{sound_id}'"))?;
-        let volume = volume.max(0.0);
+    // Like preload() for one sound, but decoded on the loader thread. Returns whether a
+    // decode was queued; if so `on_done` runs during a later update(), and until then the
+    // sound still plays from its file bytes.
+    pub fn preload_async(
+        &mut self,
+        sound_id: &str,
+        on_done: impl FnOnce(&str, Result<(), String>) + 'static,
+    ) -> Result<bool, String> {
+        let bytes = match self.clips.get(sound_id) {
+            Some(SoundClip::FileBytes(bytes)) => bytes.clone(),
+            Some(_) => return Ok(false),
+            None => return Err(format!("unknown sound id '{sound_id}'")),
+        };
+        self.queue_load(
+            sound_id.to_owned(),
+            LoadSource::Bytes(bytes),
+            Box::new(on_done),
+        )?;
+        Ok(true)
+    }
 
-        match clip {
-            SoundClip::FileBytes(bytes) => {
-                let cursor = Cursor::new(bytes.clone());
-                let decoder = Decoder::new(BufReader::new(cursor))
-                    .map_err(|err| format!("failed to decode sound '{sound_id}': {err}"))?;
-
-                let sink = Sink::try_new(&self.handle)
-                    .map_err(|err| format!("failed to create audio sink: {err}"))?;
-                sink.set_volume(volume);
-                sink.append(decoder);
-                // Detach so playback continues after this function returns.
-                sink.detach();
-            }
-            SoundClip::Tone {
-                frequency_hz,
-                duration,
-            } => {
-                let sink = Sink::try_new(&self.handle)
-                    .map_err(|err| format!("failed to create audio sink: {err}"))?;
-                sink.set_volume(volume);
-                sink.append(
-                    rodio::source::SineWave::new(*frequency_hz as f32)
-                        .take_duration(*duration)
-                        .amplify(0.20),
-                );
-                // Detach so playback continues after this function returns.
-                sink.detach();
+    fn queue_load(
+        &mut self,
+        sound_id: String,
+        source: LoadSource,
+        on_done: LoadCallback,
+    ) -> Result<(), String> {
+        // Started on first use; a loader whose thread has died is dropped and restarted.
+        let loader = match self.loader.take() {
+            Some(loader) => loader,
+            None => AudioLoader::spawn()?,
+        };
+
+        let token = self.next_load_token;
+        self.next_load_token = self.next_load_token.wrapping_add(1);
+        loader
+            .jobs
+            .send(LoadJob {
+                token,
+                sound_id: sound_id.clone(),
+                source,
+            })
+            .map_err(|_| "audio loader thread has stopped".to_owned())?;
+        self.loader = Some(loader);
+        self.pending_loads
+            .insert(sound_id, PendingLoad { token, on_done });
+        Ok(())
+    }
+
+    pub fn is_loading(&self, sound_id: &str) -> bool {
+        self.pending_loads.contains_key(sound_id)
+    }
+
+    fn poll_loads(&mut self) {
+        let Some(loader) = &self.loader else {
+            return;
+        };
+        let done: Vec<LoadDone> = loader.done.try_iter().collect();
+        for done in done {
+            let Some(pending) = self
+                .pending_loads
+                .remove(&done.sound_id)
+                .filter(|pending| pending.token == done.token)
+            else {
+                continue;
+            };
+            let result = done.result.map(|clip| {
+                self.clips.insert(done.sound_id.clone(), clip);
+            });
+            (pending.on_done)(&done.sound_id, result);
+        }
+    }
+
+    // Registers every sound listed in the manifest file. Failing entries are reported
+    // together; the others are still registered.
+    pub fn load_manifest(&mut self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let text = vfs::read_to_string(path)
+            .map_err(|err| format!("failed to read audio manifest '{}': {err}", path.display()))?;
+        let manifest: AudioManifest = serde_json::from_str(&text)
+            .map_err(|err| format!("failed to parse audio manifest '{}': {err}", path.display()))?;
+
+        let errors: Vec<String> = manifest
+            .sounds
+            .into_iter()
+            .filter_map(|sound| self.register_manifest_sound(sound).err())
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    fn register_manifest_sound(&mut self, sound: ManifestSound) -> Result<(), String> {
+        let id = sound.id;
+        if let Some(tone) = &sound.tone {
+            self.register_synth(id.clone(), tone.spec());
+        }
+        match &sound.file {
+            Some(file) => {
+                if let Err(err) = self.register_sound_file(id.clone(), file)
+                    && sound.tone.is_none()
+                {
+                    return Err(err);
+                }
             }
+            None if sound.tone.is_none() => {
+                return Err(format!("sound '{id}' needs a file or a tone"));
+            }
+            None => {}
         }
 
-        Ok(())
+        let mut defaults = SoundDefaults::default();
+        if let Some(bus) = sound.bus {
+            defaults = defaults.with_bus(bus);
+        }
+        if let Some(volume) = sound.volume {
+            defaults = defaults.with_volume(volume);
+        }
+        if let Some(variance) = sound.pitch_variance {
+            defaults = defaults.with_pitch_variance(variance);
+        }
+        if let Some(max_instances) = sound.max_instances {
+            defaults = defaults.with_max_instances(max_instances);
+        }
+        self.set_sound_defaults(&id, defaults)?;
+        if let Some(caption) = sound.caption {
+            self.set_caption(&id, caption)?;
+        }
+        if let Some(bpm) = sound.bpm {
+            let tempo =
+                MusicTempo::new(bpm).with_offset(Duration::from_millis(sound.beat_offset_ms));
+            self.set_music_tempo(&id, tempo)?;
+        }
+
+        if sound.preload {
+            self.preload([id.as_str()])?;
+        }
+        Ok(())
+    }
+
+    // Decodes file clips to PCM now so their first play() does not stall on decoding.
+    // Tones and already decoded clips are left as they are; unknown ids are reported.
+    pub fn preload<'a>(
+        &mut self,
+        sound_ids: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), String> {
+        let mut errors = Vec::new();
+        for sound_id in sound_ids {
+            let Some(clip) = self.clips.get_mut(sound_id) else {
+                errors.push(format!("unknown sound id '{sound_id}'"));
+                continue;
+            };
+            if let SoundClip::FileBytes(bytes) = clip {
+                match decode_pcm(sound_id, bytes) {
+                    Ok(pcm) => {
+                        *clip = SoundClip::Decoded {
+                            pcm,
+                            loop_region: None,
+                        }
+                    }
+                    Err(err) => errors.push(err),
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    // Looped playback of `sound_id` plays the intro before `region` once, then repeats
+    // the region. The clip is decoded to PCM here so the loop can jump back sample-exactly.
+    #[allow(dead_code)]
+    pub fn set_loop_region(&mut self, sound_id: &str, region: LoopRegion) -> Result<(), String> {
+        let clip = self
+            .clips
+            .get_mut(sound_id)
+            .ok_or_else(|| format!("unknown sound id '{sound_id}'"))?;
+
+        let pcm = match clip {
+            SoundClip::FileBytes(bytes) => decode_pcm(sound_id, bytes)?,
+            SoundClip::Decoded { loop_region, .. } => {
+                *loop_region = Some(region);
+                return Ok(());
+            }
+            SoundClip::Tone(_) => {
+                return Err(format!(
+                    "sound '{sound_id}' is a tone and has no loop region"
+                ));
+            }
+        };
+        if region.start_frame >= pcm.frame_count() {
+            return Err(format!(
+                "loop start {} is past the end of sound '{sound_id}'",
+                region.start_frame
+            ));
+        }
+        *clip = SoundClip::Decoded {
+            pcm,
+            loop_region: Some(region),
+        };
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn load_loop_cue(&mut self, sound_id: &str, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let text = vfs::read_to_string(path)
+            .map_err(|err| format!("failed to read loop cue '{}': {err}", path.display()))?;
+        let cue: LoopCue = serde_json::from_str(&text)
+            .map_err(|err| format!("failed to parse loop cue '{}': {err}", path.display()))?;
+        self.set_loop_region(
+            sound_id,
+            LoopRegion {
+                start_frame: cue.loop_start,
+                end_frame: cue.loop_end,
+            },
+        )
+    }
+
+    pub fn register_tone(
+        &mut self,
+        sound_id: impl Into<String>,
+        frequency_hz: u32,
+        duration_ms: u64,
+    ) {
+        self.register_synth(
+            sound_id,
+            ToneSpec::new(frequency_hz as f32, Duration::from_millis(duration_ms)),
+        );
+    }
+
+    // Waveform, envelope and sweep variant of register_tone().
+    pub fn register_synth(&mut self, sound_id: impl Into<String>, spec: ToneSpec) {
+        let sound_id = sound_id.into();
+        self.pending_loads.remove(&sound_id);
+        self.clips.insert(sound_id, SoundClip::Tone(spec));
+    }
+
+    #[allow(dead_code)]
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
+    }
+
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.max(0.0);
+        self.apply_volumes();
+    }
+
+    // Buses start at full volume.
+    pub fn bus_volume(&self, bus: AudioBus) -> f32 {
+        self.bus_volumes.get(&bus).copied().unwrap_or(1.0)
+    }
+
+    // Also applies to sounds on `bus` that are already playing.
+    pub fn set_bus_volume(&mut self, bus: AudioBus, volume: f32) {
+        self.bus_volumes.insert(bus, volume.max(0.0));
+        self.apply_volumes();
+    }
+
+    #[allow(dead_code)]
+    pub fn bus_lowpass(&self, bus: AudioBus) -> Option<f32> {
+        self.bus_effects.get(&bus)?.lowpass_hz()
+    }
+
+    // Muffles the bus, e.g. voices behind a door; None turns the filter off.
+    pub fn set_bus_lowpass(&mut self, bus: AudioBus, cutoff_hz: Option<f32>) {
+        if let Some(effects) = self.bus_effects.get(&bus) {
+            effects.set_lowpass_hz(cutoff_hz);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn bus_reverb(&self, bus: AudioBus) -> f32 {
+        self.bus_effects
+            .get(&bus)
+            .map_or(0.0, |effects| effects.reverb_mix())
+    }
+
+    // Wet mix of a small room reverb, e.g. for flashbacks; 0.0 turns it off.
+    pub fn set_bus_reverb(&mut self, bus: AudioBus, mix: f32) {
+        if let Some(effects) = self.bus_effects.get(&bus) {
+            effects.set_reverb_mix(mix);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    // Silences every bus without touching their volumes.
+    pub fn set_muted(&mut self, muted: bool) {
+        if self.muted != muted {
+            self.muted = muted;
+            self.apply_volumes();
+        }
+    }
+
+    // Hook for WindowEvent::Focused.
+    pub fn on_focus_changed(&mut self, focused: bool, behavior: FocusLossAudio) {
+        if focused {
+            match self.focus_loss.take() {
+                Some(FocusLossAudio::Mute) => self.apply_volumes(),
+                Some(FocusLossAudio::Pause) => {
+                    for id in std::mem::take(&mut self.focus_paused) {
+                        if let Some(sound) = self.playing.get(&id) {
+                            sound.sink.play();
+                        }
+                    }
+                    if std::mem::take(&mut self.focus_paused_music) {
+                        self.for_each_music_sink(Sink::play);
+                    }
+                }
+                Some(FocusLossAudio::KeepPlaying) | None => {}
+            }
+            return;
+        }
+
+        if self.focus_loss.is_some() {
+            return;
+        }
+        self.focus_loss = Some(behavior);
+        match behavior {
+            FocusLossAudio::KeepPlaying => {}
+            FocusLossAudio::Mute => self.apply_volumes(),
+            FocusLossAudio::Pause => {
+                for (id, sound) in &self.playing {
+                    if !sound.sink.is_paused() {
+                        sound.sink.pause();
+                        self.focus_paused.push(*id);
+                    }
+                }
+                if self
+                    .music
+                    .as_ref()
+                    .is_some_and(|track| !track.sink.is_paused())
+                {
+                    self.for_each_music_sink(Sink::pause);
+                    self.focus_paused_music = true;
+                }
+            }
+        }
+    }
+
+    // Fades all output to silence over `duration` and stops every sound. Blocks until
+    // done, so it is meant for shutdown, when no more frames are drawn; the browser,
+    // which cannot block, stops at once.
+    pub fn fade_out_all(&mut self, duration: Duration) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            const STEP: Duration = Duration::from_millis(10);
+            let volume = self.master_volume;
+            let start = Instant::now();
+            while start.elapsed() < duration {
+                let remaining = 1.0 - start.elapsed().as_secs_f32() / duration.as_secs_f32();
+                self.master_volume = volume * remaining.max(0.0);
+                self.apply_volumes();
+                thread::sleep(STEP);
+            }
+        }
+        self.master_volume = 0.0;
+        self.apply_volumes();
+        self.stop_music();
+        for (_, sound) in self.playing.drain() {
+            sound.sink.stop();
+        }
+        self.ambience.clear();
+        self.attached.clear();
+    }
+
+    // Hook for ApplicationHandler::suspended/resumed. A suspended app is in the background,
+    // so everything pauses whatever the focus-loss setting says.
+    pub fn on_suspended(&mut self, suspended: bool) {
+        if !suspended {
+            self.on_focus_changed(true, FocusLossAudio::Pause);
+            return;
+        }
+        if self.focus_loss != Some(FocusLossAudio::Pause) {
+            self.on_focus_changed(true, FocusLossAudio::Pause);
+            self.on_focus_changed(false, FocusLossAudio::Pause);
+        }
+    }
+
+    fn for_each_music_sink(&self, action: impl Fn(&Sink)) {
+        if let Some(track) = &self.music {
+            action(&track.sink);
+        }
+        if let Some(sink) = self
+            .crossfade
+            .as_ref()
+            .and_then(|fade| fade.outgoing.as_ref())
+        {
+            action(sink);
+        }
+    }
+
+    fn effective_volume(&self, bus: AudioBus, volume: f32) -> f32 {
+        if self.muted || self.focus_loss == Some(FocusLossAudio::Mute) {
+            return 0.0;
+        }
+        let duck = if bus == AudioBus::Music {
+            self.duck_gain
+        } else {
+            1.0
+        };
+        volume.max(0.0) * self.bus_volume(bus) * self.master_volume * duck
+    }
+
+    #[allow(dead_code)]
+    pub fn ducking(&self) -> Ducking {
+        self.ducking
+    }
+
+    pub fn set_ducking(&mut self, ducking: Ducking) {
+        self.ducking = Ducking {
+            amount: ducking.amount.clamp(0.0, 1.0),
+            ..ducking
+        };
+    }
+
+    fn is_voice_playing(&self) -> bool {
+        self.playing.values().any(|sound| {
+            sound.bus == AudioBus::Voice && !sound.sink.empty() && !sound.sink.is_paused()
+        })
+    }
+
+    fn duck_target(&self) -> f32 {
+        if self.is_voice_playing() {
+            1.0 - self.ducking.amount
+        } else {
+            1.0
+        }
+    }
+
+    fn update_ducking(&mut self, dt: f32) {
+        let target = self.duck_target();
+        if self.duck_gain == target {
+            return;
+        }
+        let ramp = if target < self.duck_gain {
+            self.ducking.attack
+        } else {
+            self.ducking.release
+        };
+        // Linear ramp that would cover the full 0..1 range in `ramp`.
+        let step = if ramp.is_zero() {
+            f32::INFINITY
+        } else {
+            dt / ramp.as_secs_f32()
+        };
+        self.duck_gain = if target < self.duck_gain {
+            (self.duck_gain - step).max(target)
+        } else {
+            (self.duck_gain + step).min(target)
+        };
+        self.apply_volumes();
+    }
+
+    fn apply_volumes(&self) {
+        for sound in self.playing.values() {
+            sound.set_mix(self.effective_volume(sound.bus, 1.0));
+        }
+        self.apply_music_gains();
+    }
+
+    pub fn play(
+        &mut self,
+        sound_id: &str,
+        bus: AudioBus,
+        volume: f32,
+    ) -> Result<SoundHandle, String> {
+        self.play_with(sound_id, bus, PlayOptions::default().with_volume(volume))
+    }
+
+    // The sound's set_sound_defaults() apply on top: its bus replaces `bus`, its volume
+    // scales `options.volume` and its pitch variance shifts `options.speed`.
+    pub fn play_with(
+        &mut self,
+        sound_id: &str,
+        bus: AudioBus,
+        options: PlayOptions,
+    ) -> Result<SoundHandle, String> {
+        self.enforce_instance_limit(sound_id)?;
+        let defaults = self.sound_defaults(sound_id);
+        let bus = defaults.bus.unwrap_or(bus);
+        let mut speed = options.speed;
+        if defaults.pitch_variance > 0.0 {
+            speed *= 1.0 + defaults.pitch_variance * self.random_signed();
+        }
+        let gains = SoundGains {
+            volume: options.volume.max(0.0) * defaults.volume,
+            mix: self.effective_volume(bus, 1.0),
+            fade: if options.fade_in.is_zero() { 1.0 } else { 0.0 },
+            ramp: None,
+            spatial: 1.0,
+        };
+        let pan = Arc::new(AtomicU32::new(0.0f32.to_bits()));
+
+        let sink = self.acquire_sink()?;
+        sink.set_volume(gains.output());
+        sink.set_speed(clamp_speed(speed));
+        self.append_clip(&sink, sound_id, options.looped, bus, Some(&pan))?;
+        let id = self.next_playback_id;
+        self.next_playback_id = self.next_playback_id.wrapping_add(1);
+
+        let sound = Arc::new(PlayingSound {
+            sink,
+            sound_id: sound_id.to_owned(),
+            bus,
+            gains: Mutex::new(gains),
+            pan,
+        });
+        sound.set_pan(options.pan);
+        if !options.fade_in.is_zero() {
+            sound.start_fade(1.0, options.fade_in, false);
+        }
+        self.playing.insert(id, sound.clone());
+        self.queue_caption(sound_id, &sound);
+        Ok(SoundHandle { id, sound })
+    }
+
+    // Sounds that finished or were stopped since the previous update(). Every reader sees
+    // the same list for one frame, so several systems can react to the same stinger.
+    #[allow(dead_code)]
+    pub fn finished_sounds(&self) -> &[PlaybackFinished] {
+        &self.finished
+    }
+
+    // Attaches defaults to a registered sound; its instance limit, if any, replaces the
+    // one from set_instance_limit().
+    pub fn set_sound_defaults(
+        &mut self,
+        sound_id: &str,
+        defaults: SoundDefaults,
+    ) -> Result<(), String> {
+        if !self.clips.contains_key(sound_id) {
+            return Err(format!("unknown sound id '{sound_id}'"));
+        }
+        if let Some(limit) = defaults.instance_limit {
+            self.instance_limits.insert(sound_id.to_owned(), limit);
+        }
+        self.sound_defaults.insert(sound_id.to_owned(), defaults);
+        Ok(())
+    }
+
+    pub fn sound_defaults(&self, sound_id: &str) -> SoundDefaults {
+        self.sound_defaults
+            .get(sound_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    // Xorshift step mapped to -1..1.
+    fn random_signed(&mut self) -> f32 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        (self.rng_state >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0
+    }
+
+    // Caption shown for `sound_id` while captions are enabled, e.g. "[door creaks]".
+    pub fn set_caption(
+        &mut self,
+        sound_id: &str,
+        caption: impl Into<String>,
+    ) -> Result<(), String> {
+        if !self.clips.contains_key(sound_id) {
+            return Err(format!("unknown sound id '{sound_id}'"));
+        }
+        self.captions.insert(sound_id.to_owned(), caption.into());
+        Ok(())
+    }
+
+    pub fn set_captions_enabled(&mut self, enabled: bool) {
+        self.captions_enabled = enabled;
+        if !enabled {
+            self.pending_captions.clear();
+        }
+    }
+
+    // Captions of audible sounds started since the previous call, oldest first.
+    pub fn take_captions(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending_captions)
+    }
+
+    fn queue_caption(&mut self, sound_id: &str, sound: &PlayingSound) {
+        if !self.captions_enabled {
+            return;
+        }
+        let Some(caption) = self.captions.get(sound_id) else {
+            return;
+        };
+        // Mute is ignored: players who rely on captions may well play with sound off.
+        let loudness = {
+            let gains = sound.gains();
+            gains.volume * gains.spatial * self.master_volume * self.bus_volume(sound.bus)
+        };
+        // A sound repeated within one frame is captioned once.
+        if loudness < CAPTION_MIN_VOLUME
+            || self.pending_captions.len() >= MAX_PENDING_CAPTIONS
+            || self.pending_captions.contains(caption)
+        {
+            return;
+        }
+        self.pending_captions.push(caption.clone());
+    }
+
+    // Caps how many instances of `sound_id` play at once, e.g. for rapid typewriter ticks.
+    #[allow(dead_code)]
+    pub fn set_instance_limit(&mut self, sound_id: impl Into<String>, limit: InstanceLimit) {
+        self.instance_limits.insert(sound_id.into(), limit);
+    }
+
+    #[allow(dead_code)]
+    pub fn clear_instance_limit(&mut self, sound_id: &str) {
+        self.instance_limits.remove(sound_id);
+    }
+
+    fn enforce_instance_limit(&mut self, sound_id: &str) -> Result<(), String> {
+        let Some(limit) = self.instance_limits.get(sound_id).copied() else {
+            return Ok(());
+        };
+        let mut instances: Vec<_> = self
+            .playing
+            .iter()
+            .filter(|(_, sound)| {
+                sound.sound_id == sound_id && !sound.sink.empty() && !sound.is_stopping()
+            })
+            .map(|(id, sound)| (*id, sound))
+            .collect();
+        if instances.len() < limit.max_instances.max(1) {
+            return Ok(());
+        }
+
+        match limit.stealing {
+            VoiceStealing::RejectNew => Err(format!(
+                "sound '{sound_id}' already has {} instances playing",
+                instances.len()
+            )),
+            VoiceStealing::StopOldest => {
+                // Playback ids grow with every play(), so the smallest ones are the oldest.
+                instances.sort_unstable_by_key(|(id, _)| *id);
+                let excess = instances.len() + 1 - limit.max_instances.max(1);
+                for (_, sound) in instances.into_iter().take(excess) {
+                    sound.start_fade(0.0, VOICE_STEAL_FADE, true);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Per-frame upkeep: advances fades and releases sounds that finished.
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let dt = self
+            .last_update_at
+            .map_or(0.0, |at| (now - at).as_secs_f32().min(0.1));
+        self.last_update_at = Some(now);
+
+        self.poll_loads();
+        self.update_ducking(dt);
+        self.update_music();
+        self.update_playlist();
+        self.update_beats();
+        self.update_intensity(dt);
+        self.finished.clear();
+        let ended: Vec<_> = self
+            .playing
+            .extract_if(|_, sound| {
+                sound.step_fade();
+                sound.sink.empty()
+            })
+            .collect();
+        for (id, sound) in ended {
+            self.finished.push(PlaybackFinished {
+                id,
+                sound_id: sound.sound_id.clone(),
+                bus: sound.bus,
+            });
+            self.recycle_sink(sound);
+        }
+        let playing = &self.playing;
+        self.attached.retain(|id, _| playing.contains_key(id));
+        self.ambience.retain(|_, handle| !handle.is_finished());
+    }
+
+    // Idle sinks are reused, so rapid one-shots such as typewriter ticks do not create a
+    // sink (and a mixer input) per play.
+    fn acquire_sink(&mut self) -> Result<Sink, String> {
+        if let Some(sink) = self.idle_sinks.pop() {
+            // A sink paused through a handle or by focus loss must not stay paused.
+            sink.play();
+            return Ok(sink);
+        }
+        Sink::try_new(&self.handle).map_err(|err| format!("failed to create audio sink: {err}"))
+    }
+
+    // Only sinks nobody holds a handle to are pooled; a handle must never end up
+    // controlling a different sound.
+    fn recycle_sink(&mut self, sound: Arc<PlayingSound>) {
+        if self.idle_sinks.len() >= MAX_IDLE_SINKS {
+            return;
+        }
+        if let Ok(sound) = Arc::try_unwrap(sound) {
+            self.idle_sinks.push(sound.sink);
+        }
+    }
+
+    // `object_key` is the GameObject2D scene key (e.g. "id:fountain"); see update_positions().
+    #[allow(dead_code)]
+    pub fn attach_to_object(&mut self, handle: &SoundHandle, object_key: impl Into<String>) {
+        self.attached.insert(handle.id, object_key.into());
+    }
+
+    #[allow(dead_code)]
+    pub fn detach_from_object(&mut self, handle: &SoundHandle) {
+        self.attached.remove(&handle.id);
+        handle.sound.set_spatial(1.0);
+    }
+
+    // `object_x` maps an object key to its horizontal screen position, -1.0 (left edge)
+    // to 1.0 (right edge); sounds of missing objects keep their last pan and volume.
+    pub fn update_positions(&mut self, object_x: impl Fn(&str) -> Option<f32>) {
+        for (id, key) in &self.attached {
+            let (Some(sound), Some(x)) = (self.playing.get(id), object_x(key)) else {
+                continue;
+            };
+            sound.set_pan(x.clamp(-1.0, 1.0) * POSITIONAL_PAN_RANGE);
+            let beyond_edge = (x.abs() - 1.0).max(0.0);
+            sound.set_spatial(1.0 - (beyond_edge / POSITIONAL_FALLOFF).min(1.0));
+        }
+    }
+
+    // True while a sound or music fade needs update() every frame.
+    // A playing voice counts too, so ducking releases as soon as it ends.
+    pub fn has_active_fades(&self) -> bool {
+        // Background loads are finished by update(), so they need frames too.
+        !self.pending_loads.is_empty()
+            || self.crossfade.is_some()
+            || self
+                .intensity_music
+                .as_ref()
+                .is_some_and(|song| song.intensity != song.target)
+            || self.duck_gain != self.duck_target()
+            || self.ducking.amount > 0.0 && self.is_voice_playing()
+            || self
+                .playing
+                .values()
+                .any(|sound| sound.gains().ramp.is_some())
+    }
+
+    // Tweens the ambience layer `sound_id` (e.g. wind, rain, crowd) to `volume` over
+    // `fade`, starting its loop from silence if it is not playing. A layer tweened to
+    // 0.0 stops once silent; all layers loop independently on the Ambience bus.
+    pub fn set_ambient_layer(
+        &mut self,
+        sound_id: &str,
+        volume: f32,
+        fade: Duration,
+    ) -> Result<(), String> {
+        let volume = volume.max(0.0);
+        let fade = fade.max(Duration::from_millis(1));
+        let handle = match self.ambience.get(sound_id) {
+            Some(handle) => handle.clone(),
+            None if volume == 0.0 => return Ok(()),
+            None => {
+                // Starts silent; the tween below replaces the fade-in.
+                let handle = self.play_with(
+                    sound_id,
+                    AudioBus::Ambience,
+                    PlayOptions::default().with_looped(true).with_fade_in(fade),
+                )?;
+                self.ambience.insert(sound_id.to_owned(), handle.clone());
+                handle
+            }
+        };
+        handle.sound.start_fade(volume, fade, volume == 0.0);
+        Ok(())
+    }
+
+    // Fades out and stops every ambience layer.
+    pub fn stop_ambience(&mut self, fade: Duration) {
+        for handle in self.ambience.values() {
+            handle.stop_with_fade(fade);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn ambient_layers(&self) -> impl Iterator<Item = &str> {
+        self.ambience.keys().map(String::as_str)
+    }
+
+    // Replaces the current track; the new one loops until stopped or replaced.
+    // Requesting the track that is already playing leaves it running.
+    pub fn play_music(&mut self, sound_id: &str) -> Result<(), String> {
+        if self.current_music() == Some(sound_id) {
+            return Ok(());
+        }
+
+        let sink = Sink::try_new(&self.handle)
+            .map_err(|err| format!("failed to create audio sink: {err}"))?;
+        self.append_clip(&sink, sound_id, true, AudioBus::Music, None)?;
+        self.seek_to_remembered(&sink, sound_id);
+
+        self.stop_music();
+        self.music = Some(MusicTrack {
+            sound_id: sound_id.to_owned(),
+            sink,
+            upcoming: VecDeque::new(),
+        });
+        self.apply_music_gains();
+        Ok(())
+    }
+
+    // Plays the playlist's tracks back-to-back; each next track is queued on the same
+    // sink before the current one ends, so the transition is gapless.
+    pub fn play_playlist(&mut self, mut playlist: Playlist) -> Result<(), String> {
+        let first = playlist
+            .next_track()
+            .ok_or_else(|| "playlist has no tracks".to_owned())?;
+        self.start_playlist_track(first, playlist)
+    }
+
+    // Cuts to the next playlist track.
+    pub fn skip_track(&mut self) -> Result<(), String> {
+        let Some(playlist) = self.playlist.take() else {
+            return Ok(());
+        };
+        // The next track may already be queued behind the current one.
+        let queued = self
+            .music
+            .as_mut()
+            .and_then(|track| track.upcoming.pop_front());
+        match queued {
+            Some(next) => self.start_playlist_track(next, playlist),
+            None => self.play_playlist(playlist),
+        }
+    }
+
+    fn start_playlist_track(&mut self, first: String, playlist: Playlist) -> Result<(), String> {
+        let sink = Sink::try_new(&self.handle)
+            .map_err(|err| format!("failed to create audio sink: {err}"))?;
+        self.append_clip(&sink, &first, false, AudioBus::Music, None)?;
+
+        self.stop_music();
+        self.music = Some(MusicTrack {
+            sound_id: first,
+            sink,
+            upcoming: VecDeque::new(),
+        });
+        self.playlist = Some(playlist);
+        self.apply_music_gains();
+        self.queue_next_playlist_track();
+        Ok(())
+    }
+
+    fn update_playlist(&mut self) {
+        let Some(track) = self.music.as_mut().filter(|_| self.playlist.is_some()) else {
+            return;
+        };
+        // The sink drops sources as they finish; shift our bookkeeping to match.
+        while track.sink.len() < track.upcoming.len() + 1 {
+            match track.upcoming.pop_front() {
+                Some(next) => track.sound_id = next,
+                None => break,
+            }
+        }
+        if track.sink.empty() {
+            self.music = None;
+            self.playlist = None;
+            return;
+        }
+        self.queue_next_playlist_track();
+    }
+
+    fn queue_next_playlist_track(&mut self) {
+        if !self
+            .music
+            .as_ref()
+            .is_some_and(|track| track.upcoming.is_empty())
+        {
+            return;
+        }
+        let Some(mut playlist) = self.playlist.take() else {
+            return;
+        };
+        // Skip tracks that fail to decode instead of stalling the playlist.
+        for _ in 0..playlist.tracks.len() {
+            let Some(next) = playlist.next_track() else {
+                break;
+            };
+            let Some(track) = self.music.as_ref() else {
+                break;
+            };
+            match self.append_clip(&track.sink, &next, false, AudioBus::Music, None) {
+                Ok(()) => {
+                    if let Some(track) = self.music.as_mut() {
+                        track.upcoming.push_back(next);
+                    }
+                    break;
+                }
+                Err(err) => log::warn!("playlist track skipped: {err}"),
+            }
+        }
+        self.playlist = Some(playlist);
+    }
+
+    // Plays `stems` of one song (calmest first, e.g. calm/tense/action) in sync as the
+    // current music, blended by `intensity` from 0.0 to 1.0. If the song is already
+    // playing only its intensity changes.
+    pub fn play_stems(
+        &mut self,
+        song_id: &str,
+        stems: &[String],
+        intensity: f32,
+    ) -> Result<(), String> {
+        if self.intensity_music.is_some() && self.current_music() == Some(song_id) {
+            self.set_music_intensity(intensity);
+            return Ok(());
+        }
+        if stems.is_empty() {
+            return Err(format!("song '{song_id}' has no stems"));
+        }
+        let sources = stems
+            .iter()
+            .map(|stem| self.clip_source(stem, true))
+            .collect::<Result<Vec<_>, _>>()?;
+        let channels = sources.iter().map(Source::channels).max().unwrap_or(2);
+        let (controller, mixer) =
+            rodio::dynamic_mixer::mixer::<f32>(channels, sources[0].sample_rate());
+
+        let intensity = intensity.clamp(0.0, 1.0);
+        let mut stem_gains = Vec::with_capacity(stems.len());
+        for (source, gain) in sources.into_iter().zip(stem_mix(stems.len(), intensity)) {
+            let gain = Arc::new(AtomicU32::new(gain.to_bits()));
+            // Added before the mixer plays, so every stem starts on the same sample.
+            controller.add(LiveGain::new(source, gain.clone()));
+            stem_gains.push(gain);
+        }
+
+        let sink = Sink::try_new(&self.handle)
+            .map_err(|err| format!("failed to create audio sink: {err}"))?;
+        append_source(
+            &sink,
+            mixer,
+            None,
+            self.bus_effects[&AudioBus::Music].clone(),
+        );
+
+        self.stop_music();
+        self.music = Some(MusicTrack {
+            sound_id: song_id.to_owned(),
+            sink,
+            upcoming: VecDeque::new(),
+        });
+        self.intensity_music = Some(IntensityMusic {
+            song_id: song_id.to_owned(),
+            stem_gains,
+            intensity,
+            target: intensity,
+        });
+        self.apply_music_gains();
+        Ok(())
+    }
+
+    // Glides the playing stem song toward `intensity`; ordinary tracks are unaffected.
+    pub fn set_music_intensity(&mut self, intensity: f32) {
+        if let Some(song) = self.intensity_music.as_mut() {
+            song.target = intensity.clamp(0.0, 1.0);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn music_intensity(&self) -> Option<f32> {
+        self.intensity_music.as_ref().map(|song| song.intensity)
+    }
+
+    fn update_intensity(&mut self, dt: f32) {
+        // Any other music call replaces the song.
+        if self
+            .intensity_music
+            .as_ref()
+            .is_some_and(|song| self.current_music() != Some(song.song_id.as_str()))
+        {
+            self.intensity_music = None;
+        }
+        let Some(song) = self.intensity_music.as_mut() else {
+            return;
+        };
+        if song.intensity == song.target {
+            return;
+        }
+        let step = dt / INTENSITY_SWEEP_SECONDS;
+        song.intensity = if song.intensity < song.target {
+            (song.intensity + step).min(song.target)
+        } else {
+            (song.intensity - step).max(song.target)
+        };
+        song.apply_gains();
+    }
+
+    pub fn stop_music(&mut self) {
+        self.remember_music_position();
+        if let Some(track) = self.music.take() {
+            track.sink.stop();
+        }
+        self.playlist = None;
+        self.stop_crossfade();
+    }
+
+    // Blends from the current track to `sound_id` over `duration`; without a current
+    // track the new one simply fades in. Call update() every frame while fading.
+    pub fn crossfade_to(
+        &mut self,
+        sound_id: &str,
+        duration: Duration,
+        curve: FadeCurve,
+    ) -> Result<(), String> {
+        if self.current_music() == Some(sound_id) {
+            return Ok(());
+        }
+        if duration.is_zero() {
+            return self.play_music(sound_id);
+        }
+
+        let sink = Sink::try_new(&self.handle)
+            .map_err(|err| format!("failed to create audio sink: {err}"))?;
+        sink.set_volume(0.0);
+        self.append_clip(&sink, sound_id, true, AudioBus::Music, None)?;
+        self.seek_to_remembered(&sink, sound_id);
+        self.remember_music_position();
+
+        // A fade still in progress is cut short; its incoming track becomes the outgoing one.
+        let outgoing_gain = self.crossfade.as_ref().map_or(1.0, |fade| fade.gains().1);
+        self.stop_crossfade();
+        self.playlist = None;
+        let previous = self.music.replace(MusicTrack {
+            sound_id: sound_id.to_owned(),
+            sink,
+            upcoming: VecDeque::new(),
+        });
+        self.crossfade = Some(MusicCrossfade {
+            outgoing: previous.map(|track| track.sink),
+            outgoing_gain,
+            started: Instant::now(),
+            duration,
+            curve,
+        });
+        self.apply_music_gains();
+        Ok(())
+    }
+
+    // On by default; when off, every play_music() or crossfade starts its track from zero.
+    #[allow(dead_code)]
+    pub fn set_remember_music_positions(&mut self, remember: bool) {
+        self.remember_music_positions = remember;
+        if !remember {
+            self.music_positions.clear();
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn forget_music_position(&mut self, sound_id: &str) {
+        self.music_positions.remove(sound_id);
+    }
+
+    // Playlist tracks always start from the top, so their positions are not kept.
+    fn remember_music_position(&mut self) {
+        if !self.remember_music_positions || self.playlist.is_some() {
+            return;
+        }
+        if let Some(track) = &self.music {
+            self.music_positions
+                .insert(track.sound_id.clone(), track.sink.get_pos());
+        }
+    }
+
+    fn seek_to_remembered(&mut self, sink: &Sink, sound_id: &str) {
+        let Some(position) = self.music_positions.remove(sound_id) else {
+            return;
+        };
+        if let Err(err) = sink.try_seek(position) {
+            log::warn!("music '{sound_id}' restarts from the top: {err}");
+        }
+    }
+
+    // Advances a running crossfade and drops the outgoing track once it has faded out.
+    fn update_music(&mut self) {
+        self.apply_music_gains();
+        if self
+            .crossfade
+            .as_ref()
+            .is_some_and(|fade| fade.progress() >= 1.0)
+        {
+            self.stop_crossfade();
+            self.apply_music_gains();
+        }
+    }
+
+    fn stop_crossfade(&mut self) {
+        if let Some(outgoing) = self.crossfade.take().and_then(|fade| fade.outgoing) {
+            outgoing.stop();
+        }
+    }
+
+    fn apply_music_gains(&self) {
+        let volume = self.effective_volume(AudioBus::Music, 1.0);
+        let (outgoing, incoming) = self
+            .crossfade
+            .as_ref()
+            .map_or((0.0, 1.0), MusicCrossfade::gains);
+        if let Some(sink) = self
+            .crossfade
+            .as_ref()
+            .and_then(|fade| fade.outgoing.as_ref())
+        {
+            sink.set_volume(volume * outgoing);
+        }
+        if let Some(track) = &self.music {
+            track.sink.set_volume(volume * incoming);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn current_music(&self) -> Option<&str> {
+        self.music.as_ref().map(|track| track.sound_id.as_str())
+    }
+
+    #[allow(dead_code)]
+    pub fn set_music_tempo(&mut self, sound_id: &str, tempo: MusicTempo) -> Result<(), String> {
+        if !self.clips.contains_key(sound_id) {
+            return Err(format!("unknown sound id '{sound_id}'"));
+        }
+        self.tempos.insert(sound_id.to_owned(), tempo);
+        Ok(())
+    }
+
+    // Fractional beats into the current track, for animation that follows the music
+    // smoothly; None without music, without a tempo, or before the first beat.
+    #[allow(dead_code)]
+    pub fn music_beat_position(&self) -> Option<f32> {
+        let track = self.music.as_ref()?;
+        self.tempos
+            .get(&track.sound_id)?
+            .beats_at(track.sink.get_pos())
+    }
+
+    // Beats the music passed since the previous update().
+    pub fn beat_events(&self) -> &[BeatEvent] {
+        &self.beats
+    }
+
+    fn update_beats(&mut self) {
+        self.beats.clear();
+        let Some(track) = self.music.as_ref() else {
+            self.last_beat = None;
+            return;
+        };
+        let Some(tempo) = self.tempos.get(&track.sound_id) else {
+            self.last_beat = None;
+            return;
+        };
+        let Some(beat) = tempo.beats_at(track.sink.get_pos()) else {
+            return;
+        };
+        let beat = beat.floor() as u64;
+
+        let first = match &self.last_beat {
+            // A new track, or the same one restarted, reports its current beat.
+            Some((sound_id, last)) if *sound_id == track.sound_id && *last <= beat => last + 1,
+            _ => beat,
+        };
+        // After a stall, report only the latest beat rather than a burst of old ones.
+        let first = if beat.saturating_sub(first) > 1 {
+            beat
+        } else {
+            first
+        };
+        let beats_per_bar = u64::from(tempo.beats_per_bar.max(1));
+        for beat in first..=beat {
+            self.beats.push(BeatEvent {
+                sound_id: track.sound_id.clone(),
+                beat,
+                bar: beat / beats_per_bar,
+                beat_in_bar: (beat % beats_per_bar) as u32,
+            });
+        }
+        self.last_beat = Some((track.sound_id.clone(), beat));
+    }
+
+    // With `pan` the clip is routed through a Panned source controlled by it; every clip
+    // gets the effects of `bus`.
+    fn append_clip(
+        &self,
+        sink: &Sink,
+        sound_id: &str,
+        looped: bool,
+        bus: AudioBus,
+        pan: Option<&Arc<AtomicU32>>,
+    ) -> Result<(), String> {
+        let source = self.clip_source(sound_id, looped)?;
+        let effects = self.bus_effects.get(&bus).cloned().unwrap_or_default();
+        append_source(sink, source, pan, effects);
+        Ok(())
+    }
+
+    fn clip_source(&self, sound_id: &str, looped: bool) -> Result<ClipSource, String> {
+        let clip = self
+            .clips
+            .get(sound_id)
+            .ok_or_else(|| format!("unknown sound id '{sound_id}'"))?;
+        let decode_error = |err| format!("failed to decode sound '{sound_id}': {err}");
+
+        Ok(match clip {
+            SoundClip::FileBytes(bytes) => {
+                let reader = BufReader::new(Cursor::new(bytes.clone()));
+                if looped {
+                    // Seeks back to the start on EOF, so the loop has no gap.
+                    let decoder = Decoder::new_looped(reader).map_err(decode_error)?;
+                    Box::new(decoder.convert_samples())
+                } else {
+                    Box::new(
+                        Decoder::new(reader)
+                            .map_err(decode_error)?
+                            .convert_samples(),
+                    )
+                }
+            }
+            SoundClip::Decoded { pcm, loop_region } => {
+                // Without a region a looped clip repeats from the start.
+                let region = looped.then(|| {
+                    loop_region.unwrap_or(LoopRegion {
+                        start_frame: 0,
+                        end_frame: None,
+                    })
+                });
+                Box::new(PcmSource::new(pcm, region).convert_samples())
+            }
+            SoundClip::Tone(spec) => {
+                let tone = ToneSource::new(*spec);
+                if looped {
+                    Box::new(tone.repeat_infinite())
+                } else {
+                    Box::new(tone)
+                }
+            }
+        })
+    }
+}
+
+fn clock_seed() -> u64 {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0x2545_F491_4F6C_DD1D, |time| time.as_nanos() as u64);
+    // Xorshift must not start at zero.
+    seed | 1
+}
+
+fn load_clip(sound_id: &str, path: &Path, decode: bool) -> Result<SoundClip, String> {
+    let bytes: Arc<[u8]> = vfs::read(path)
+        .map_err(|err| format!("failed to read sound '{}': {err}", path.display()))?
+        .into();
+    if !decode {
+        return Ok(SoundClip::FileBytes(bytes));
+    }
+    Ok(SoundClip::Decoded {
+        pcm: decode_pcm(sound_id, &bytes)?,
+        loop_region: None,
+    })
+}
+
+fn decode_pcm(sound_id: &str, bytes: &Arc<[u8]>) -> Result<PcmClip, String> {
+    let decoder = Decoder::new(BufReader::new(Cursor::new(bytes.clone())))
+        .map_err(|err| format!("failed to decode sound '{sound_id}': {err}"))?;
+    Ok(PcmClip::decode(decoder))
+}
+
+fn append_source<S>(
+    sink: &Sink,
+    source: S,
+    pan: Option<&Arc<AtomicU32>>,
+    effects: Arc<EffectParams>,
+) where
+    S: Source + Send + 'static,
+    S::Item: Sample + Send,
+{
+    match pan {
+        Some(pan) => sink.append(Effected::new(Panned::new(source, pan.clone()), effects)),
+        None => sink.append(Effected::new(source, effects)),
     }
 }
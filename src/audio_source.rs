@@ -0,0 +1,697 @@
+use std::{
+    ops::Range,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+use rodio::{Sample, Source, source::SeekError};
+use serde::Deserialize;
+
+// Interleaved 16-bit samples of a fully decoded clip.
+pub struct PcmClip {
+    pub samples: Arc<[i16]>,
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+impl PcmClip {
+    pub fn decode<S>(source: S) -> Self
+    where
+        S: Source<Item = i16>,
+    {
+        let channels = source.channels();
+        let sample_rate = source.sample_rate();
+        Self {
+            samples: source.collect(),
+            channels,
+            sample_rate,
+        }
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        (self.samples.len() / usize::from(self.channels.max(1))) as u64
+    }
+}
+
+// Loop region in sample frames; the end defaults to the end of the clip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoopRegion {
+    pub start_frame: u64,
+    pub end_frame: Option<u64>,
+}
+
+// Plays a PcmClip once, or plays through to the loop end and then repeats the loop region.
+pub struct PcmSource {
+    samples: Arc<[i16]>,
+    channels: u16,
+    sample_rate: u32,
+    position: usize,
+    // Sample (not frame) indices of the repeated region.
+    loop_range: Option<Range<usize>>,
+}
+
+impl PcmSource {
+    pub fn new(clip: &PcmClip, loop_region: Option<LoopRegion>) -> Self {
+        let channels = usize::from(clip.channels.max(1));
+        let len = clip.samples.len();
+        let loop_range = loop_region.and_then(|region| {
+            let start = (region.start_frame as usize)
+                .saturating_mul(channels)
+                .min(len);
+            let end = region
+                .end_frame
+                .map_or(len, |end| (end as usize).saturating_mul(channels).min(len));
+            (start < end).then_some(start..end)
+        });
+
+        Self {
+            samples: clip.samples.clone(),
+            channels: clip.channels,
+            sample_rate: clip.sample_rate,
+            position: 0,
+            loop_range,
+        }
+    }
+}
+
+impl Iterator for PcmSource {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if let Some(range) = &self.loop_range
+            && self.position >= range.end
+        {
+            self.position = range.start;
+        }
+        let sample = self.samples.get(self.position).copied()?;
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for PcmSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        if self.loop_range.is_some() {
+            return None;
+        }
+        let frames = self.samples.len() / usize::from(self.channels.max(1));
+        Some(Duration::from_secs_f64(
+            frames as f64 / f64::from(self.sample_rate.max(1)),
+        ))
+    }
+
+    // Positions past the loop end wrap into the loop region, as if it had been played.
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let channels = usize::from(self.channels.max(1));
+        let frame = (pos.as_secs_f64() * f64::from(self.sample_rate)) as usize;
+        let mut position = frame.saturating_mul(channels);
+        if let Some(range) = &self.loop_range
+            && position >= range.end
+        {
+            position = range.start + (position - range.start) % (range.end - range.start);
+        }
+        self.position = position.min(self.samples.len());
+        Ok(())
+    }
+}
+
+// Balance law: the far channel is attenuated, the near one stays at full level.
+fn balance_gains(pan: f32) -> (f32, f32) {
+    let pan = pan.clamp(-1.0, 1.0);
+    ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0))
+}
+
+// Applies a live stereo balance to `input`; mono input is widened to stereo.
+pub struct Panned<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    input: S,
+    // f32 bits, so the pan can change while the sink owns the source.
+    pan: Arc<AtomicU32>,
+    // Interleaved channel the next multichannel sample belongs to.
+    channel: u16,
+    // Right-hand copy of the last mono sample.
+    pending_right: Option<S::Item>,
+}
+
+impl<S> Panned<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    pub fn new(input: S, pan: Arc<AtomicU32>) -> Self {
+        Self {
+            input,
+            pan,
+            channel: 0,
+            pending_right: None,
+        }
+    }
+}
+
+impl<S> Iterator for Panned<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        if let Some(right) = self.pending_right.take() {
+            return Some(right);
+        }
+
+        let channels = self.input.channels();
+        let sample = self.input.next()?;
+        let (left, right) = balance_gains(f32::from_bits(self.pan.load(Ordering::Relaxed)));
+        if channels == 1 {
+            self.pending_right = Some(sample.amplify(right));
+            return Some(sample.amplify(left));
+        }
+
+        let gain = match self.channel {
+            0 => left,
+            1 => right,
+            _ => 1.0,
+        };
+        self.channel = (self.channel + 1) % channels.max(1);
+        Some(sample.amplify(gain))
+    }
+}
+
+impl<S> Source for Panned<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        let len = self.input.current_frame_len()?;
+        Some(if self.input.channels() == 1 {
+            len * 2
+        } else {
+            len
+        })
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels().max(2)
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Square,
+    #[allow(dead_code)]
+    Saw,
+    // White noise, resampled once per period so the frequency still sets its color.
+    Noise,
+}
+
+// ADSR envelope; the release is taken from the end of the tone's duration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Envelope {
+    pub attack: Duration,
+    pub decay: Duration,
+    // Level held between decay and release, 0..1.
+    pub sustain: f32,
+    pub release: Duration,
+}
+
+impl Default for Envelope {
+    // Short ramps so a bare tone starts and stops without clicks.
+    fn default() -> Self {
+        Self {
+            attack: Duration::from_millis(2),
+            decay: Duration::ZERO,
+            sustain: 1.0,
+            release: Duration::from_millis(4),
+        }
+    }
+}
+
+impl Envelope {
+    #[allow(dead_code)]
+    pub const fn new(attack: Duration, decay: Duration, sustain: f32, release: Duration) -> Self {
+        Self {
+            attack,
+            decay,
+            sustain,
+            release,
+        }
+    }
+
+    fn gain(&self, time: f32, total: f32) -> f32 {
+        let attack = self.attack.as_secs_f32();
+        let decay = self.decay.as_secs_f32();
+        let sustain = self.sustain.clamp(0.0, 1.0);
+
+        let level = if time < attack {
+            time / attack
+        } else if time < attack + decay {
+            1.0 - (1.0 - sustain) * (time - attack) / decay
+        } else {
+            sustain
+        };
+
+        let release = self.release.as_secs_f32();
+        let remaining = total - time;
+        if remaining < release {
+            level * (remaining / release).max(0.0)
+        } else {
+            level
+        }
+    }
+}
+
+// Description of a synthesized sound registered with AudioEngine::register_synth().
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneSpec {
+    pub waveform: Waveform,
+    pub frequency_hz: f32,
+    // Frequency reached at the end of the tone, swept linearly from frequency_hz.
+    pub sweep_to_hz: Option<f32>,
+    pub duration: Duration,
+    pub envelope: Envelope,
+    pub volume: f32,
+}
+
+impl ToneSpec {
+    pub fn new(frequency_hz: f32, duration: Duration) -> Self {
+        Self {
+            waveform: Waveform::Sine,
+            frequency_hz,
+            sweep_to_hz: None,
+            duration: duration.max(Duration::from_millis(1)),
+            envelope: Envelope::default(),
+            volume: 0.20,
+        }
+    }
+
+    pub fn with_waveform(mut self, waveform: Waveform) -> Self {
+        self.waveform = waveform;
+        self
+    }
+
+    pub fn with_sweep(mut self, to_hz: f32) -> Self {
+        self.sweep_to_hz = Some(to_hz);
+        self
+    }
+
+    pub fn with_envelope(mut self, envelope: Envelope) -> Self {
+        self.envelope = envelope;
+        self
+    }
+
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume.max(0.0);
+        self
+    }
+}
+
+const TONE_SAMPLE_RATE: u32 = 48_000;
+
+// Renders a ToneSpec as mono samples.
+pub struct ToneSource {
+    spec: ToneSpec,
+    index: u64,
+    total: u64,
+    // Position within the current period, 0..1.
+    phase: f32,
+    noise_state: u32,
+    noise_value: f32,
+}
+
+impl ToneSource {
+    pub fn new(spec: ToneSpec) -> Self {
+        let total = (spec.duration.as_secs_f64() * f64::from(TONE_SAMPLE_RATE)).ceil() as u64;
+        let mut source = Self {
+            spec,
+            index: 0,
+            total: total.max(1),
+            phase: 0.0,
+            noise_state: 0x9E37_79B9,
+            noise_value: 0.0,
+        };
+        source.noise_value = source.next_noise();
+        source
+    }
+
+    // xorshift32 mapped to -1..1.
+    fn next_noise(&mut self) -> f32 {
+        let mut x = self.noise_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.noise_state = x;
+        (x as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+}
+
+impl Iterator for ToneSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.index >= self.total {
+            return None;
+        }
+
+        let rate = TONE_SAMPLE_RATE as f32;
+        let time = self.index as f32 / rate;
+        let total = self.total as f32 / rate;
+        let frequency = match self.spec.sweep_to_hz {
+            Some(to_hz) => {
+                let progress = self.index as f32 / self.total as f32;
+                self.spec.frequency_hz + (to_hz - self.spec.frequency_hz) * progress
+            }
+            None => self.spec.frequency_hz,
+        };
+
+        let wave = match self.spec.waveform {
+            Waveform::Sine => (self.phase * std::f32::consts::TAU).sin(),
+            Waveform::Square => {
+                if self.phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Saw => self.phase * 2.0 - 1.0,
+            Waveform::Noise => self.noise_value,
+        };
+
+        self.phase += frequency.max(0.0) / rate;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            if self.spec.waveform == Waveform::Noise {
+                self.noise_value = self.next_noise();
+            }
+        }
+        self.index += 1;
+
+        Some(wave * self.spec.envelope.gain(time, total) * self.spec.volume)
+    }
+}
+
+impl Source for ToneSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((self.total - self.index) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        TONE_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.spec.duration)
+    }
+}
+
+// Live effect settings of one AudioEngine bus, read by every Effected source on it.
+#[derive(Debug)]
+pub struct EffectParams {
+    // f32 bits of the low-pass cutoff in Hz; 0.0 means off.
+    lowpass_hz: AtomicU32,
+    // f32 bits of the reverb wet mix, 0..1; 0.0 means off.
+    reverb_mix: AtomicU32,
+}
+
+impl Default for EffectParams {
+    fn default() -> Self {
+        Self {
+            lowpass_hz: AtomicU32::new(0.0f32.to_bits()),
+            reverb_mix: AtomicU32::new(0.0f32.to_bits()),
+        }
+    }
+}
+
+impl EffectParams {
+    pub fn lowpass_hz(&self) -> Option<f32> {
+        let cutoff = f32::from_bits(self.lowpass_hz.load(Ordering::Relaxed));
+        (cutoff > 0.0).then_some(cutoff)
+    }
+
+    pub fn set_lowpass_hz(&self, cutoff_hz: Option<f32>) {
+        let cutoff = cutoff_hz
+            .filter(|cutoff| cutoff.is_finite())
+            .map_or(0.0, |cutoff| cutoff.clamp(20.0, 20_000.0));
+        self.lowpass_hz.store(cutoff.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn reverb_mix(&self) -> f32 {
+        f32::from_bits(self.reverb_mix.load(Ordering::Relaxed))
+    }
+
+    pub fn set_reverb_mix(&self, mix: f32) {
+        let mix = if mix.is_finite() {
+            mix.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        self.reverb_mix.store(mix.to_bits(), Ordering::Relaxed);
+    }
+}
+
+// Freeverb comb lengths at 44.1 kHz; the right channel is offset for some stereo width.
+const REVERB_COMB_SAMPLES: [usize; 4] = [1116, 1188, 1277, 1356];
+const REVERB_STEREO_SPREAD: usize = 23;
+const REVERB_FEEDBACK: f32 = 0.78;
+const REVERB_DAMPING: f32 = 0.2;
+
+// Feedback delay line with a damped (low-passed) feedback path.
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    damped: f32,
+}
+
+impl Comb {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.0; len.max(1)],
+            index: 0,
+            damped: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.damped = output * (1.0 - REVERB_DAMPING) + self.damped * REVERB_DAMPING;
+        self.buffer[self.index] = input + self.damped * REVERB_FEEDBACK;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+// Applies the bus low-pass and reverb to `input`. The reverb tail ends with the source.
+pub struct Effected<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    input: S,
+    params: Arc<EffectParams>,
+    // Interleaved channel the next sample belongs to.
+    channel: usize,
+    lowpass_state: Vec<f32>,
+    lowpass_active: bool,
+    // Comb filters per channel, allocated the first time reverb is switched on.
+    reverb: Vec<[Comb; 4]>,
+    reverb_active: bool,
+}
+
+impl<S> Effected<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    pub fn new(input: S, params: Arc<EffectParams>) -> Self {
+        Self {
+            input,
+            params,
+            channel: 0,
+            lowpass_state: Vec::new(),
+            lowpass_active: false,
+            reverb: Vec::new(),
+            reverb_active: false,
+        }
+    }
+
+    fn reset_reverb(&mut self, channels: usize) {
+        let scale = self.input.sample_rate() as f32 / 44_100.0;
+        self.reverb = (0..channels)
+            .map(|channel| {
+                REVERB_COMB_SAMPLES.map(|len| {
+                    let len = len
+                        + if channel % 2 == 1 {
+                            REVERB_STEREO_SPREAD
+                        } else {
+                            0
+                        };
+                    Comb::new((len as f32 * scale) as usize)
+                })
+            })
+            .collect();
+    }
+}
+
+impl<S> Iterator for Effected<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let channels = usize::from(self.input.channels().max(1));
+        let mut sample = self.input.next()?.to_f32();
+        let channel = self.channel.min(channels - 1);
+        self.channel = (self.channel + 1) % channels;
+
+        match self.params.lowpass_hz() {
+            Some(cutoff) => {
+                if !self.lowpass_active || self.lowpass_state.len() != channels {
+                    // Start from the current sample so switching the filter on does not pop.
+                    self.lowpass_state = vec![sample; channels];
+                    self.lowpass_active = true;
+                }
+                let rate = self.input.sample_rate().max(1) as f32;
+                let alpha = 1.0 - (-std::f32::consts::TAU * cutoff / rate).exp();
+                let state = &mut self.lowpass_state[channel];
+                *state += alpha * (sample - *state);
+                sample = *state;
+            }
+            None => self.lowpass_active = false,
+        }
+
+        let mix = self.params.reverb_mix();
+        if mix > 0.0 {
+            if !self.reverb_active || self.reverb.len() != channels {
+                self.reset_reverb(channels);
+                self.reverb_active = true;
+            }
+            let wet: f32 = self.reverb[channel]
+                .iter_mut()
+                .map(|comb| comb.process(sample * 0.25))
+                .sum();
+            sample = sample * (1.0 - mix * 0.5) + wet * mix;
+        } else {
+            self.reverb_active = false;
+        }
+
+        Some(sample)
+    }
+}
+
+impl<S> Source for Effected<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+// Scales `input` by a gain that can change while the source plays.
+pub struct LiveGain<S> {
+    input: S,
+    // f32 bits, written by the engine and read on the audio thread.
+    gain: Arc<AtomicU32>,
+}
+
+impl<S> LiveGain<S> {
+    pub fn new(input: S, gain: Arc<AtomicU32>) -> Self {
+        Self { input, gain }
+    }
+}
+
+impl<S> Iterator for LiveGain<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let sample = self.input.next()?;
+        Some(sample.amplify(f32::from_bits(self.gain.load(Ordering::Relaxed))))
+    }
+}
+
+impl<S> Source for LiveGain<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
@@ -0,0 +1,115 @@
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+usage: game_engine [options]
+
+  --scene <file>     start from a save file (flags and script states); implies --skip-menu
+  --windowed         ignore the saved display mode for this run
+  --skip-menu        start a new game without showing the main menu
+  --profile <name>   keep saves and settings in a separate profile
+  --debug            show the debug overlay, log at debug level and enable the
+                     profiler window (F4)
+  --gpu <name>       render with the GPU whose name contains <name>
+  --low-power        prefer the integrated GPU
+  --allow-fallback-adapter
+                     use a software renderer when no GPU adapter works
+  --list-adapters    print the GPU adapters that can be used and exit
+  --pack <file>      read shipped files from a .pak or .zip instead of loose files
+  --mount <prefix>=<dir>
+                     read files under <prefix> from <dir> first, e.g.
+                     src/data=mods/data; may be repeated
+  --build-pack <file>
+                     write the shipped files into a .pak and exit
+  -h, --help         print this help";
+
+// Developer and tester switches; players normally start without any.
+#[derive(Clone, Debug, Default)]
+pub struct CliOptions {
+    pub scene: Option<PathBuf>,
+    pub windowed: bool,
+    pub skip_menu: bool,
+    pub profile: Option<String>,
+    pub debug: bool,
+    pub gpu: Option<String>,
+    pub low_power: bool,
+    pub allow_fallback_adapter: bool,
+    pub pack: Option<PathBuf>,
+    // (virtual prefix, directory), later ones taking precedence.
+    pub mounts: Vec<(PathBuf, PathBuf)>,
+}
+
+// Outcome of parsing that should end the process instead of starting the game.
+pub enum CliExit {
+    Help,
+    ListAdapters,
+    BuildPack(PathBuf),
+    Error(String),
+}
+
+impl CliOptions {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliExit> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .filter(|value| !value.starts_with("--"))
+                    .ok_or_else(|| CliExit::Error(format!("{name} needs a value")))
+            };
+            match arg.as_str() {
+                "--scene" => {
+                    options.scene = Some(PathBuf::from(value("--scene")?));
+                    options.skip_menu = true;
+                }
+                "--windowed" => options.windowed = true,
+                "--skip-menu" => options.skip_menu = true,
+                "--profile" => {
+                    let profile = value("--profile")?;
+                    if !is_valid_profile_name(&profile) {
+                        return Err(CliExit::Error(format!(
+                            "profile name {profile:?} may only use letters, digits, '-' and '_'"
+                        )));
+                    }
+                    options.profile = Some(profile);
+                }
+                "--debug" => options.debug = true,
+                "--gpu" => options.gpu = Some(value("--gpu")?),
+                "--low-power" => options.low_power = true,
+                "--allow-fallback-adapter" => options.allow_fallback_adapter = true,
+                "--list-adapters" => return Err(CliExit::ListAdapters),
+                "--pack" => options.pack = Some(PathBuf::from(value("--pack")?)),
+                "--mount" => {
+                    let mount = value("--mount")?;
+                    let Some((point, dir)) =
+                        mount.split_once('=').filter(|(_, dir)| !dir.is_empty())
+                    else {
+                        return Err(CliExit::Error(format!(
+                            "--mount needs <prefix>=<dir>, got {mount:?}"
+                        )));
+                    };
+                    options
+                        .mounts
+                        .push((PathBuf::from(point), PathBuf::from(dir)));
+                }
+                "--build-pack" => {
+                    return Err(CliExit::BuildPack(PathBuf::from(value("--build-pack")?)));
+                }
+                "-h" | "--help" => return Err(CliExit::Help),
+                other => return Err(CliExit::Error(format!("unknown option {other:?}"))),
+            }
+        }
+        Ok(options)
+    }
+
+    pub fn usage() -> &'static str {
+        USAGE
+    }
+}
+
+// Profiles become directory names, so keep them to one portable path component.
+fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'))
+}
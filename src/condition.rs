@@ -0,0 +1,265 @@
+use crate::flags::is_truthy;
+
+// Boolean unlock expressions such as `stats.choices_made >= 10 && flags.route_a`.
+// Variables are dotted names resolved by the caller. Comparisons are numeric when both
+// sides parse as numbers and textual otherwise; a bare value is truthy unless it is
+// missing, "", "0" or "false".
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    Value(Operand),
+    Compare(Operand, CompareOp, Operand),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operand {
+    Variable(String),
+    Literal(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Text(String),
+    Compare(CompareOp),
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+impl Condition {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Err("condition must not be empty".to_owned());
+        }
+
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let condition = parser.parse_or()?;
+        match parser.peek() {
+            None => Ok(condition),
+            Some(token) => Err(format!("unexpected {token:?} in condition {source:?}")),
+        }
+    }
+
+    // Missing variables resolve to an empty string.
+    pub fn evaluate(&self, resolve: &impl Fn(&str) -> Option<String>) -> bool {
+        match self {
+            Self::Value(operand) => is_truthy(&operand.resolve(resolve)),
+            Self::Compare(left, op, right) => {
+                compare(&left.resolve(resolve), *op, &right.resolve(resolve))
+            }
+            Self::Not(inner) => !inner.evaluate(resolve),
+            Self::And(left, right) => left.evaluate(resolve) && right.evaluate(resolve),
+            Self::Or(left, right) => left.evaluate(resolve) || right.evaluate(resolve),
+        }
+    }
+
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables = Vec::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables<'a>(&'a self, variables: &mut Vec<&'a str>) {
+        match self {
+            Self::Value(operand) => operand.collect_variable(variables),
+            Self::Compare(left, _, right) => {
+                left.collect_variable(variables);
+                right.collect_variable(variables);
+            }
+            Self::Not(inner) => inner.collect_variables(variables),
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.collect_variables(variables);
+                right.collect_variables(variables);
+            }
+        }
+    }
+}
+
+impl Operand {
+    fn resolve(&self, resolve: &impl Fn(&str) -> Option<String>) -> String {
+        match self {
+            Self::Variable(name) => resolve(name).unwrap_or_default(),
+            Self::Literal(value) => value.clone(),
+        }
+    }
+
+    fn collect_variable<'a>(&'a self, variables: &mut Vec<&'a str>) {
+        if let Self::Variable(name) = self {
+            variables.push(name);
+        }
+    }
+}
+
+fn compare(left: &str, op: CompareOp, right: &str) -> bool {
+    let ordering = match (left.trim().parse::<f64>(), right.trim().parse::<f64>()) {
+        (Ok(left), Ok(right)) => left.partial_cmp(&right),
+        _ => Some(left.cmp(right)),
+    };
+    let Some(ordering) = ordering else {
+        return false;
+    };
+
+    match op {
+        CompareOp::Eq => ordering.is_eq(),
+        CompareOp::Ne => ordering.is_ne(),
+        CompareOp::Lt => ordering.is_lt(),
+        CompareOp::Le => ordering.is_le(),
+        CompareOp::Gt => ordering.is_gt(),
+        CompareOp::Ge => ordering.is_ge(),
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((start, ch)) = chars.next() {
+        let mut next_is = |expected: char| chars.next_if(|(_, next)| *next == expected).is_some();
+        let token = match ch {
+            ch if ch.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '&' if next_is('&') => Token::And,
+            '|' if next_is('|') => Token::Or,
+            '=' if next_is('=') => Token::Compare(CompareOp::Eq),
+            '!' if next_is('=') => Token::Compare(CompareOp::Ne),
+            '!' => Token::Not,
+            '<' if next_is('=') => Token::Compare(CompareOp::Le),
+            '<' => Token::Compare(CompareOp::Lt),
+            '>' if next_is('=') => Token::Compare(CompareOp::Ge),
+            '>' => Token::Compare(CompareOp::Gt),
+            '"' | '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, next)) if next == ch => break,
+                        Some((_, next)) => text.push(next),
+                        None => {
+                            return Err(format!("unterminated string in condition {source:?}"));
+                        }
+                    }
+                }
+                Token::Text(text)
+            }
+            ch if ch.is_ascii_digit() || ch == '-' => {
+                let mut end = start + ch.len_utf8();
+                while let Some((index, next)) =
+                    chars.next_if(|(_, next)| next.is_ascii_digit() || *next == '.')
+                {
+                    end = index + next.len_utf8();
+                }
+                let number = &source[start..end];
+                if number.parse::<f64>().is_err() {
+                    return Err(format!("invalid number {number:?} in condition {source:?}"));
+                }
+                Token::Number(number.to_owned())
+            }
+            ch if ch.is_alphabetic() || ch == '_' => {
+                let mut end = start + ch.len_utf8();
+                while let Some((index, next)) =
+                    chars.next_if(|(_, next)| next.is_alphanumeric() || matches!(next, '_' | '.'))
+                {
+                    end = index + next.len_utf8();
+                }
+                Token::Ident(source[start..end].to_owned())
+            }
+            other => {
+                return Err(format!("unexpected {other:?} in condition {source:?}"));
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn parse_or(&mut self) -> Result<Condition, String> {
+        let mut condition = self.parse_and()?;
+        while self.eat(&Token::Or) {
+            condition = Condition::Or(Box::new(condition), Box::new(self.parse_and()?));
+        }
+        Ok(condition)
+    }
+
+    fn parse_and(&mut self) -> Result<Condition, String> {
+        let mut condition = self.parse_unary()?;
+        while self.eat(&Token::And) {
+            condition = Condition::And(Box::new(condition), Box::new(self.parse_unary()?));
+        }
+        Ok(condition)
+    }
+
+    fn parse_unary(&mut self) -> Result<Condition, String> {
+        if self.eat(&Token::Not) {
+            return Ok(Condition::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.eat(&Token::Open) {
+            let condition = self.parse_or()?;
+            if !self.eat(&Token::Close) {
+                return Err("missing closing parenthesis in condition".to_owned());
+            }
+            return Ok(condition);
+        }
+
+        let left = self.parse_operand()?;
+        let Some(Token::Compare(op)) = self.peek().cloned() else {
+            return Ok(Condition::Value(left));
+        };
+        self.position += 1;
+        Ok(Condition::Compare(left, op, self.parse_operand()?))
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, String> {
+        match self.next() {
+            Some(Token::Ident(name)) if matches!(name.as_str(), "true" | "false") => {
+                Ok(Operand::Literal(name))
+            }
+            Some(Token::Ident(name)) => Ok(Operand::Variable(name)),
+            Some(Token::Number(value) | Token::Text(value)) => Ok(Operand::Literal(value)),
+            Some(token) => Err(format!("expected a value in condition, found {token:?}")),
+            None => Err("condition ends where a value is expected".to_owned()),
+        }
+    }
+}
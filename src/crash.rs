@@ -0,0 +1,197 @@
+use std::{
+    backtrace::Backtrace,
+    fmt::Write as _,
+    panic::{self, PanicHookInfo},
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+    thread,
+};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::{logging, paths, vfs};
+
+// Relative to `paths::data_dir()`.
+pub const CRASH_DIR: &str = "crashes";
+// Log records included in a report, oldest first.
+const REPORT_LOG_LINES: usize = 100;
+const MESSAGE_BOX_TITLE: &str = "game_engine crashed";
+
+static GPU_INFO: Mutex<Option<String>> = Mutex::new(None);
+
+// Turns panics into crash reports: the report goes to `CRASH_DIR` and the log, and a
+// message box tells the player where to find it. Call right after `logging::init`.
+pub fn install() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // Keeps the usual stderr output for whoever runs the game from a terminal.
+        previous(info);
+
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let report = build_report(info, seconds);
+        log::error!("{info}");
+        let saved = write_report(&report, seconds);
+        if let Err(err) = &saved {
+            log::error!("{err}");
+        }
+        log::logger().flush();
+
+        // Background threads are restarted or given up on; only the event loop ending
+        // takes the game down with it.
+        if cfg!(target_arch = "wasm32") || thread::current().name() == Some("main") {
+            let text = match saved {
+                Ok(path) => format!(
+                    "The game ran into an error and has to close.\n\n\
+                     A crash report was saved to:\n{}\n\n\
+                     Please attach it when reporting the problem.",
+                    path.display()
+                ),
+                Err(_) => format!(
+                    "The game ran into an error and has to close.\n\n\
+                     The crash report could not be saved:\n\n{report}"
+                ),
+            };
+            show_message_box(MESSAGE_BOX_TITLE, &text);
+        }
+    }));
+}
+
+// Adapter the game renders with, for the reports of later crashes.
+pub fn set_gpu_info(info: &wgpu::AdapterInfo) {
+    let description = format!(
+        "{} ({:04x}:{:04x}), {:?}, {:?}, driver {} {}",
+        info.name,
+        info.vendor,
+        info.device,
+        info.device_type,
+        info.backend,
+        info.driver,
+        info.driver_info
+    );
+    *GPU_INFO.lock().unwrap_or_else(PoisonError::into_inner) = Some(description);
+}
+
+fn build_report(info: &PanicHookInfo<'_>, seconds: u64) -> String {
+    let gpu = GPU_INFO
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .unwrap_or_else(|| "not initialized".to_owned());
+
+    let mut report = String::new();
+    let _ = writeln!(
+        report,
+        "{} {} crash report",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+    let _ = writeln!(report, "time: {seconds}");
+    let _ = writeln!(
+        report,
+        "platform: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(
+        report,
+        "thread: {}",
+        thread::current().name().unwrap_or("unnamed")
+    );
+    let _ = writeln!(report, "gpu: {gpu}");
+    let _ = writeln!(report, "\n{info}");
+    let _ = writeln!(report, "\nbacktrace:\n{}", Backtrace::force_capture());
+    let _ = writeln!(report, "recent log:");
+    for entry in logging::recent(log::Level::Trace, REPORT_LOG_LINES) {
+        let _ = writeln!(
+            report,
+            "[{} {}] {}",
+            entry.level, entry.target, entry.message
+        );
+    }
+    report
+}
+
+fn write_report(report: &str, seconds: u64) -> Result<PathBuf, String> {
+    let dir = paths::user_path(CRASH_DIR);
+    vfs::create_dir_all(&dir).map_err(|err| {
+        format!(
+            "failed to create crash report directory {}: {err}",
+            dir.display()
+        )
+    })?;
+    let path = dir.join(format!("crash-{seconds}.txt"));
+    vfs::write(&path, report)
+        .map_err(|err| format!("failed to write crash report {}: {err}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(target_os = "windows")]
+fn show_message_box(title: &str, text: &str) {
+    use std::ffi::c_void;
+
+    #[link(name = "user32")]
+    unsafe extern "system" {
+        fn MessageBoxW(window: *mut c_void, text: *const u16, title: *const u16, kind: u32) -> i32;
+    }
+    const MB_ICONERROR: u32 = 0x10;
+
+    let wide = |text: &str| text.encode_utf16().chain([0]).collect::<Vec<u16>>();
+    let (text, title) = (wide(text), wide(title));
+    // SAFETY: both strings are NUL-terminated and outlive the call; no owner window.
+    unsafe {
+        MessageBoxW(
+            std::ptr::null_mut(),
+            text.as_ptr(),
+            title.as_ptr(),
+            MB_ICONERROR,
+        );
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn show_message_box(title: &str, text: &str) {
+    // Passed as arguments so the text needs no AppleScript quoting.
+    let _ = std::process::Command::new("osascript")
+        .args([
+            "-e",
+            "on run argv",
+            "-e",
+            "display alert (item 1 of argv) message (item 2 of argv) as critical",
+            "-e",
+            "end run",
+            title,
+            text,
+        ])
+        .status();
+}
+
+// Uses whichever dialog tool the desktop has; without one the report is only on disk.
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_arch = "wasm32")))]
+fn show_message_box(title: &str, text: &str) {
+    use std::process::Command;
+
+    let title_arg = format!("--title={title}");
+    let text_arg = format!("--text={text}");
+    let commands: [&[&str]; 3] = [
+        &["zenity", "--error", "--no-markup", &title_arg, &text_arg],
+        &["kdialog", "--title", title, "--error", text],
+        &["xmessage", "-center", text],
+    ];
+    for command in commands {
+        if Command::new(command[0])
+            .args(&command[1..])
+            .status()
+            .is_ok()
+        {
+            return;
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn show_message_box(title: &str, text: &str) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.alert_with_message(&format!("{title}\n\n{text}"));
+    }
+}
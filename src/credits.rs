@@ -0,0 +1,34 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::vfs;
+
+pub const DEFAULT_CREDITS_PATH: &str = "src/data/credits.json";
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CreditsSection {
+    pub title: String,
+    #[serde(default)]
+    pub names: Vec<String>,
+    // Optional illustration shown under the section title.
+    #[serde(default)]
+    pub image: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Credits {
+    #[serde(default)]
+    pub sections: Vec<CreditsSection>,
+}
+
+impl Credits {
+    pub fn load_from_json_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let raw = vfs::read_to_string(path)
+            .map_err(|err| format!("failed to read credits file {}: {err}", path.display()))?;
+
+        serde_json::from_str(&raw)
+            .map_err(|err| format!("failed to parse credits json {}: {err}", path.display()))
+    }
+}
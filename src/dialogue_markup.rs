@@ -0,0 +1,258 @@
+// Inline tags understood by the dialogue typewriter, e.g. "Wait{pause=0.5}... {speed=2}faster".
+// `{term=key}word{/term}` marks a glossary link rendered with a definition tooltip.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TypewriterControl {
+    // Hold the reveal for the given number of seconds.
+    Pause(f32),
+    // Multiplier for the configured typing speed; `{speed=1}` restores the default.
+    Speed(f32),
+    // Reveal everything up to `{/instant}` (or the end of the line) at once.
+    InstantStart,
+    InstantEnd,
+}
+
+// Glossary link over visible characters `start..end`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TermSpan {
+    pub key: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+// A run of visible text that is either plain or a glossary link.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextSegment {
+    pub text: String,
+    pub term: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DialogueMarkup {
+    // Visible text with all recognized tags removed.
+    pub text: String,
+    pub char_count: usize,
+    // Controls keyed by the visible character index they fire before.
+    pub controls: Vec<(usize, TypewriterControl)>,
+    pub terms: Vec<TermSpan>,
+}
+
+enum MarkupTag {
+    Control(TypewriterControl),
+    TermStart(String),
+    TermEnd,
+}
+
+// Unknown tags are kept as literal text; `{{` produces a literal brace.
+pub fn parse_dialogue_markup(source: &str) -> DialogueMarkup {
+    let mut markup = DialogueMarkup::default();
+    let mut open_term: Option<(String, usize)> = None;
+    let mut rest = source;
+
+    while let Some(start) = rest.find('{') {
+        markup.push_literal(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        if let Some(stripped) = after.strip_prefix('{') {
+            markup.push_literal("{");
+            rest = stripped;
+            continue;
+        }
+
+        let Some(end) = after.find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+
+        match parse_tag(&after[..end]) {
+            Some(MarkupTag::Control(control)) => {
+                markup.controls.push((markup.char_count, control));
+            }
+            Some(MarkupTag::TermStart(key)) => {
+                markup.close_term(open_term.take());
+                open_term = Some((key, markup.char_count));
+            }
+            Some(MarkupTag::TermEnd) => markup.close_term(open_term.take()),
+            None => markup.push_literal(&rest[start..start + 1 + end + 1]),
+        }
+        rest = &after[end + 1..];
+    }
+
+    markup.push_literal(rest);
+    // An unclosed term extends to the end of the line.
+    markup.close_term(open_term.take());
+    markup
+}
+
+fn parse_tag(body: &str) -> Option<MarkupTag> {
+    let body = body.trim();
+    match body {
+        "instant" => return Some(MarkupTag::Control(TypewriterControl::InstantStart)),
+        "/instant" => return Some(MarkupTag::Control(TypewriterControl::InstantEnd)),
+        "/term" => return Some(MarkupTag::TermEnd),
+        _ => {}
+    }
+
+    let (name, value) = body.split_once('=')?;
+    if name.trim() == "term" {
+        let key = value.trim();
+        return (!key.is_empty()).then(|| MarkupTag::TermStart(key.to_owned()));
+    }
+
+    let value: f32 = value.trim().parse().ok()?;
+    if !value.is_finite() {
+        return None;
+    }
+
+    match name.trim() {
+        "pause" => Some(MarkupTag::Control(TypewriterControl::Pause(value.max(0.0)))),
+        "speed" => Some(MarkupTag::Control(TypewriterControl::Speed(
+            value.clamp(0.05, 20.0),
+        ))),
+        _ => None,
+    }
+}
+
+impl DialogueMarkup {
+    fn push_literal(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.char_count += text.chars().count();
+    }
+
+    fn close_term(&mut self, open_term: Option<(String, usize)>) {
+        if let Some((key, start)) = open_term
+            && self.char_count > start
+        {
+            self.terms.push(TermSpan {
+                key,
+                start,
+                end: self.char_count,
+            });
+        }
+    }
+
+    // Splits the first `visible_chars` characters into plain and glossary-link runs.
+    pub fn segments(&self, visible_chars: usize) -> Vec<TextSegment> {
+        let mut segments = Vec::new();
+        let mut position = 0;
+        let mut chars = self.text.chars();
+
+        let mut take = |count: usize, term: Option<&str>| {
+            let text: String = chars.by_ref().take(count).collect();
+            if !text.is_empty() {
+                segments.push(TextSegment {
+                    text,
+                    term: term.map(str::to_owned),
+                });
+            }
+        };
+
+        for span in &self.terms {
+            if position >= visible_chars {
+                break;
+            }
+            let plain_end = span.start.min(visible_chars);
+            take(plain_end - position, None);
+            let term_end = span.end.min(visible_chars);
+            take(term_end.saturating_sub(plain_end), Some(span.key.as_str()));
+            position = term_end.max(plain_end);
+        }
+        take(visible_chars.saturating_sub(position), None);
+
+        segments
+    }
+}
+
+// Per-dialogue reveal progress driven by DialogueMarkup controls.
+#[derive(Clone, Debug)]
+pub struct TypewriterState {
+    shown: f32,
+    pause_remaining: f32,
+    next_control: usize,
+    speed: f32,
+    instant: bool,
+}
+
+impl Default for TypewriterState {
+    fn default() -> Self {
+        Self {
+            shown: 0.0,
+            pause_remaining: 0.0,
+            next_control: 0,
+            speed: 1.0,
+            instant: false,
+        }
+    }
+}
+
+impl TypewriterState {
+    pub fn shown_chars(&self) -> usize {
+        self.shown.floor() as usize
+    }
+
+    pub fn is_complete(&self, markup: &DialogueMarkup) -> bool {
+        self.shown_chars() >= markup.char_count
+    }
+
+    pub fn reveal_all(&mut self, markup: &DialogueMarkup) {
+        self.shown = markup.char_count as f32;
+        self.pause_remaining = 0.0;
+        self.next_control = markup.controls.len();
+    }
+
+    pub fn advance(&mut self, markup: &DialogueMarkup, mut dt: f32, chars_per_second: f32) {
+        let total = markup.char_count as f32;
+
+        loop {
+            // Fire every control placed at or before the current position.
+            while let Some((at, control)) = markup.controls.get(self.next_control).copied() {
+                if at > self.shown_chars() {
+                    break;
+                }
+                match control {
+                    TypewriterControl::Pause(seconds) => self.pause_remaining += seconds,
+                    TypewriterControl::Speed(multiplier) => self.speed = multiplier,
+                    TypewriterControl::InstantStart => self.instant = true,
+                    TypewriterControl::InstantEnd => self.instant = false,
+                }
+                self.next_control += 1;
+            }
+
+            if self.shown >= total {
+                self.shown = total;
+                return;
+            }
+
+            if self.pause_remaining > 0.0 {
+                if dt < self.pause_remaining {
+                    self.pause_remaining -= dt;
+                    return;
+                }
+                dt -= self.pause_remaining;
+                self.pause_remaining = 0.0;
+                continue;
+            }
+
+            // Reveal up to the next control so its effect starts at the right character.
+            let next_stop = markup
+                .controls
+                .get(self.next_control)
+                .map(|(at, _)| *at as f32)
+                .unwrap_or(total)
+                .min(total);
+
+            if self.instant {
+                self.shown = next_stop;
+                continue;
+            }
+
+            let rate = (chars_per_second * self.speed).max(0.01);
+            let needed = (next_stop - self.shown) / rate;
+            if dt < needed {
+                self.shown += dt * rate;
+                return;
+            }
+            dt -= needed;
+            self.shown = next_stop;
+        }
+    }
+}
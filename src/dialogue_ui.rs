@@ -1,23 +1,68 @@
-use std::collections::{HashMap, VecDeque};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+use web_time::Instant;
 
 use crate::{
-    achievements::{AchievementNotification, AchievementSnapshotItem},
-    audio::AudioEngine,
-    game_object::DialogueBoxObject,
+    achievements::{AchievementNotification, AchievementSnapshotItem, RarityClass},
+    audio::{AudioBus, AudioEngine, Ducking, FocusLossAudio, PlayOptions, SoundHandle},
+    credits::Credits,
+    dialogue_markup::{DialogueMarkup, TextSegment, TypewriterState, parse_dialogue_markup},
+    display::{DisplayInfo, DisplayMode, DisplaySettings},
+    flags::substitute_variables,
+    gallery::Gallery,
+    game_object::{DialogueAlign, DialogueAnchor, DialogueBoxObject, DialoguePlacement},
+    glossary::Glossary,
+    icon_cache::IconCache,
+    input::{Action, ActionMap, KeyBindingChange, KeyChord, TextEntry, is_modifier_key},
+    locale::{FALLBACK_LANGUAGE, Localization},
+    logging, profiler,
+    stats::{Stat, StatsSnapshot},
+    tex::RenderStats,
+    ui_theme::{self, CustomTheme, UiThemePalette},
+    vfs,
+    voice_bank::VoiceBank,
 };
 use egui::{
     Align, Align2, Color32, CornerRadius, Frame, Layout, Margin, RichText, Sense, Stroke, Ui,
 };
 use egui_wgpu::{Renderer, ScreenDescriptor};
 use egui_winit::State as EguiWinitState;
-use winit::{event::WindowEvent, window::Window};
+use winit::{
+    event::{ElementState, WindowEvent},
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
+    window::Window,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UiCommand {
     None,
     StartGame,
+    // Start the game from the latest save instead of the beginning.
+    ContinueGame,
     SkipWait,
     ExitApp,
+    // Write the profiler history to a file.
+    ExportProfile,
+}
+
+// Feedback sounds for interactive UI elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UiSound {
+    Hover,
+    Click,
+    Confirm,
+}
+
+impl UiSound {
+    pub const fn default_sound_id(self) -> &'static str {
+        match self {
+            Self::Hover => "ui_hover",
+            Self::Click => "ui_click",
+            Self::Confirm => "ui_confirm",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,15 +71,231 @@ enum SettingsTab {
     Text,
     Interface,
     Notifications,
+    Controls,
 }
 
 impl SettingsTab {
-    const fn title(self) -> &'static str {
+    const fn title_key(self) -> &'static str {
+        match self {
+            Self::Audio => "settings.tab.audio",
+            Self::Text => "settings.tab.text",
+            Self::Interface => "settings.tab.interface",
+            Self::Notifications => "settings.tab.notifications",
+            Self::Controls => "settings.tab.controls",
+        }
+    }
+}
+
+// Stable color per span name so the same span keeps its color across frames.
+fn profiler_scope_color(name: &str) -> Color32 {
+    let hash = name.bytes().fold(0u32, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(u32::from(byte))
+    });
+    let hue = (hash % 360) as f32 / 360.0;
+    egui::ecolor::Hsva::new(hue, 0.45, 0.9, 1.0).into()
+}
+
+const fn action_title_key(action: Action) -> &'static str {
+    match action {
+        Action::Advance => "action.advance",
+        Action::Exit => "action.exit",
+        Action::Auto => "action.auto",
+        Action::FastForward => "action.fast_forward",
+        Action::ToggleBacklog => "action.toggle_backlog",
+        Action::QuickSave => "action.quick_save",
+        Action::QuickLoad => "action.quick_load",
+        Action::Pause => "action.pause",
+        Action::ToggleFullscreen => "action.toggle_fullscreen",
+        Action::ToggleDebugOverlay => "action.toggle_debug_overlay",
+        Action::ToggleProfiler => "action.toggle_profiler",
+        Action::HideUi => "action.hide_ui",
+    }
+}
+
+// `{$name}` variables are substituted before typewriter tags are parsed.
+fn resolve_dialogue_markup(text: &str, variables: &HashMap<String, String>) -> DialogueMarkup {
+    parse_dialogue_markup(&substitute_variables(text, variables))
+}
+
+// Largest size with the aspect ratio of `size` that fits inside `bounds`.
+fn fit_size(size: egui::Vec2, bounds: egui::Vec2) -> egui::Vec2 {
+    if size.x <= 0.0 || size.y <= 0.0 {
+        return bounds;
+    }
+    size * (bounds.x / size.x).min(bounds.y / size.y)
+}
+
+fn key_label(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    name.strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(name.as_str())
+        .to_owned()
+}
+
+fn chord_label(chord: KeyChord) -> String {
+    let mut label = String::new();
+    for (held, name) in [
+        (chord.ctrl, "Ctrl+"),
+        (chord.shift, "Shift+"),
+        (chord.alt, "Alt+"),
+    ] {
+        if held {
+            label.push_str(name);
+        }
+    }
+    label.push_str(&key_label(chord.key));
+    label
+}
+
+// Decoration drawn behind dialogue and speaker text for readability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextEffect {
+    None,
+    Outline,
+    Shadow,
+}
+
+impl TextEffect {
+    const ALL: [TextEffect; 3] = [TextEffect::None, TextEffect::Outline, TextEffect::Shadow];
+
+    const fn title_key(self) -> &'static str {
+        match self {
+            Self::None => "text_effect.none",
+            Self::Outline => "text_effect.outline",
+            Self::Shadow => "text_effect.shadow",
+        }
+    }
+}
+
+// Present modes offered in the settings; unsupported ones fall back to Fifo when applied.
+const PRESENT_MODES: [wgpu::PresentMode; 3] = [
+    wgpu::PresentMode::Fifo,
+    wgpu::PresentMode::Mailbox,
+    wgpu::PresentMode::Immediate,
+];
+
+const fn present_mode_title_key(mode: wgpu::PresentMode) -> &'static str {
+    match mode {
+        wgpu::PresentMode::Mailbox => "present_mode.mailbox",
+        wgpu::PresentMode::Immediate => "present_mode.immediate",
+        _ => "present_mode.fifo",
+    }
+}
+
+const fn display_mode_title_key(mode: DisplayMode) -> &'static str {
+    match mode {
+        DisplayMode::Windowed => "display_mode.windowed",
+        DisplayMode::Borderless => "display_mode.borderless",
+        DisplayMode::Exclusive => "display_mode.exclusive",
+    }
+}
+
+const fn focus_loss_title_key(behavior: FocusLossAudio) -> &'static str {
+    match behavior {
+        FocusLossAudio::KeepPlaying => "focus_loss.keep_playing",
+        FocusLossAudio::Mute => "focus_loss.mute",
+        FocusLossAudio::Pause => "focus_loss.pause",
+    }
+}
+
+// Adds `label`, painting its outline or shadow copies before the text itself.
+fn add_effect_label(
+    ui: &mut Ui,
+    label: egui::Label,
+    effect: TextEffect,
+    effect_color: Color32,
+    effect_size: f32,
+) -> egui::Response {
+    const OUTLINE_DIRECTIONS: [(f32, f32); 8] = [
+        (-1.0, -1.0),
+        (0.0, -1.0),
+        (1.0, -1.0),
+        (-1.0, 0.0),
+        (1.0, 0.0),
+        (-1.0, 1.0),
+        (0.0, 1.0),
+        (1.0, 1.0),
+    ];
+
+    if effect == TextEffect::None {
+        return ui.add(label);
+    }
+
+    let (pos, galley, response) = label.layout_in_ui(ui);
+    if ui.is_rect_visible(response.rect) {
+        let painter = ui.painter();
+        match effect {
+            TextEffect::None => {}
+            TextEffect::Outline => {
+                for (x, y) in OUTLINE_DIRECTIONS {
+                    painter.galley_with_override_text_color(
+                        pos + egui::vec2(x, y) * effect_size,
+                        galley.clone(),
+                        effect_color,
+                    );
+                }
+            }
+            TextEffect::Shadow => {
+                painter.galley_with_override_text_color(
+                    pos + egui::vec2(effect_size, effect_size),
+                    galley.clone(),
+                    effect_color,
+                );
+            }
+        }
+        painter.galley(pos, galley, ui.visuals().text_color());
+    }
+    response
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AchievementFilter {
+    All,
+    Unlocked,
+    Locked,
+}
+
+impl AchievementFilter {
+    const fn title_key(self) -> &'static str {
+        match self {
+            Self::All => "achievements.filter.all",
+            Self::Unlocked => "achievements.filter.unlocked",
+            Self::Locked => "achievements.filter.locked",
+        }
+    }
+
+    fn matches(self, achievement: &AchievementSnapshotItem) -> bool {
+        match self {
+            Self::All => true,
+            Self::Unlocked => achievement.unlocked,
+            Self::Locked => !achievement.unlocked,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AchievementSort {
+    Catalog,
+    Alphabetical,
+    RecentUnlocks,
+    Rarity,
+}
+
+impl AchievementSort {
+    const ALL: [AchievementSort; 4] = [
+        AchievementSort::Catalog,
+        AchievementSort::Alphabetical,
+        AchievementSort::RecentUnlocks,
+        AchievementSort::Rarity,
+    ];
+
+    const fn title_key(self) -> &'static str {
         match self {
-            Self::Audio => "Аудио",
-            Self::Text => "Текст",
-            Self::Interface => "Интерфейс",
-            Self::Notifications => "Уведомления",
+            Self::Catalog => "achievements.sort.catalog",
+            Self::Alphabetical => "achievements.sort.alphabetical",
+            Self::RecentUnlocks => "achievements.sort.recent",
+            Self::Rarity => "achievements.sort.rarity",
         }
     }
 }
@@ -44,30 +305,66 @@ enum UiThemePreset {
     DeepSea,
     Forest,
     Ember,
+    HighContrast,
+    ColorblindSafe,
+    // Index into DialogueUi::custom_themes.
+    Custom(usize),
 }
 
 impl UiThemePreset {
-    const fn title(self) -> &'static str {
+    const BUILTIN: [UiThemePreset; 5] = [
+        UiThemePreset::DeepSea,
+        UiThemePreset::Forest,
+        UiThemePreset::Ember,
+        UiThemePreset::HighContrast,
+        UiThemePreset::ColorblindSafe,
+    ];
+
+    const fn title_key(self) -> &'static str {
         match self {
-            Self::DeepSea => "Морская",
-            Self::Forest => "Лесная",
-            Self::Ember => "Янтарная",
+            Self::DeepSea => "theme.deep_sea",
+            Self::Forest => "theme.forest",
+            Self::Ember => "theme.ember",
+            Self::HighContrast => "theme.high_contrast",
+            Self::ColorblindSafe => "theme.colorblind_safe",
+            Self::Custom(_) => "theme.custom",
         }
     }
 }
 
 #[derive(Debug, Clone)]
 struct UiSettings {
+    language: String,
     master_volume: f32,
     typewriter_sound_enabled: bool,
     typewriter_sound_volume: f32,
+    ui_sounds_enabled: bool,
+    ui_sound_volume: f32,
+    voice_volume: f32,
+    music_volume: f32,
+    ambience_volume: f32,
+    // How much the music dips while a voice line plays.
+    music_ducking: f32,
+    sound_captions: bool,
+    muted: bool,
+    focus_loss_audio: FocusLossAudio,
     typewriter_enabled: bool,
     typing_chars_per_second: f32,
     show_typing_caret: bool,
     show_speaker_name: bool,
     allow_dialogue_click_skip: bool,
+    auto_advance: bool,
+    // Base pause after a line is fully revealed; long lines add per-character time.
+    auto_advance_delay: f32,
+    // Voiced lines advance when the clip ends plus `voice_buffer` seconds.
+    auto_advance_wait_for_voice: bool,
+    voice_buffer: f32,
     dialogue_text_size: f32,
     speaker_text_size: f32,
+    text_effect: TextEffect,
+    text_effect_color: Color32,
+    // Outline width or shadow offset in points.
+    text_effect_size: f32,
     dialogue_box_opacity: f32,
     dialogue_box_height_ratio: f32,
     dialogue_corner_radius: u8,
@@ -76,7 +373,11 @@ struct UiSettings {
     menu_title_size: f32,
     menu_button_text_size: f32,
     animation_speed: f32,
+    display: DisplaySettings,
     theme_preset: UiThemePreset,
+    // WCAG-style contrast floor applied to theme text colors when enabled.
+    enforce_min_contrast: bool,
+    min_contrast_ratio: f32,
     popup_enabled: bool,
     popup_duration: f32,
     show_achievement_descriptions: bool,
@@ -87,16 +388,33 @@ struct UiSettings {
 impl Default for UiSettings {
     fn default() -> Self {
         Self {
+            language: FALLBACK_LANGUAGE.to_owned(),
             master_volume: 1.0,
             typewriter_sound_enabled: true,
             typewriter_sound_volume: 0.20,
+            ui_sounds_enabled: true,
+            ui_sound_volume: 0.35,
+            voice_volume: 1.0,
+            music_volume: 0.7,
+            ambience_volume: 0.8,
+            music_ducking: 0.5,
+            sound_captions: false,
+            muted: false,
+            focus_loss_audio: FocusLossAudio::default(),
             typewriter_enabled: true,
             typing_chars_per_second: 40.0,
             show_typing_caret: true,
             show_speaker_name: true,
             allow_dialogue_click_skip: true,
+            auto_advance: false,
+            auto_advance_delay: 1.2,
+            auto_advance_wait_for_voice: true,
+            voice_buffer: 0.6,
             dialogue_text_size: 27.0,
             speaker_text_size: 21.0,
+            text_effect: TextEffect::None,
+            text_effect_color: Color32::from_rgba_unmultiplied(0, 0, 0, 200),
+            text_effect_size: 1.5,
             dialogue_box_opacity: 0.92,
             dialogue_box_height_ratio: 0.16,
             dialogue_corner_radius: 12,
@@ -105,7 +423,10 @@ impl Default for UiSettings {
             menu_title_size: 38.0,
             menu_button_text_size: 26.0,
             animation_speed: 1.0,
+            display: DisplaySettings::default(),
             theme_preset: UiThemePreset::DeepSea,
+            enforce_min_contrast: false,
+            min_contrast_ratio: 4.5,
             popup_enabled: true,
             popup_duration: 3.8,
             show_achievement_descriptions: true,
@@ -115,51 +436,235 @@ impl Default for UiSettings {
     }
 }
 
-#[derive(Clone, Copy)]
-struct UiThemePalette {
-    menu_fill: Color32,
-    menu_stroke: Color32,
-    menu_title: Color32,
-    settings_fill: Color32,
-    settings_stroke: Color32,
-    settings_title: Color32,
-    dialogue_fill_rgb: [u8; 3],
-    dialogue_stroke: Color32,
-    dialogue_speaker: Color32,
-    dialogue_text: Color32,
-    skip_ready: Color32,
-    skip_wait: Color32,
-    popup_fill: Color32,
-    popup_stroke: Color32,
-    popup_title: Color32,
-    popup_name: Color32,
-    popup_body: Color32,
-}
-
 pub struct DialogueUi {
     egui_ctx: egui::Context,
-    egui_state: EguiWinitState,
+    // None without a window; input then comes from `run_headless`.
+    egui_state: Option<EguiWinitState>,
     egui_renderer: Renderer,
     dialogue_objects: Vec<DialogueBoxObject>,
     dialogue_lookup: HashMap<String, usize>,
+    speaker_placements: HashMap<String, DialoguePlacement>,
     // Per-dialogue character progress used by the typewriter effect.
-    typing_progress: HashMap<String, f32>,
+    typing_progress: HashMap<String, TypewriterState>,
+    // Show/hide animation progress per scene key: 0.0 = hidden, 1.0 = fully shown.
+    dialogue_visibility: HashMap<String, f32>,
     typewriter_sound_id: Option<String>,
     // True when at least one new character appeared in this frame.
     typewriter_sound_pending: bool,
+    // Counts ticks so each one gets a slightly different pitch.
+    typewriter_ticks: u32,
+    ui_sound_ids: HashMap<UiSound, String>,
+    // UI sounds requested during the current frame, played after egui finishes.
+    pending_ui_sounds: Vec<UiSound>,
+    // Hovered sound-enabled widget, used to play the hover sound only on enter.
+    hovered_widget: Option<egui::Id>,
+    frame_hovered_widget: Option<egui::Id>,
+    // Voice clip state of visible dialogue lines keyed by scene key.
+    voice_lines: HashMap<String, VoiceLine>,
+    // Clips of hidden or restarted lines, stopped on the next frame with audio access.
+    // Time since the visible lines became ready to auto-advance.
+    auto_advance_elapsed: f32,
     main_menu_enabled: bool,
+    // Share of the scene's assets that are ready, while a scene loads.
+    loading_progress: Option<f32>,
+    // Screenshot mode: dialogue boxes and HUD are not drawn.
+    ui_hidden: bool,
+    paused: bool,
+    // Set every frame while the fast-forward action is held.
+    fast_forward: bool,
+    // Lines shown so far, oldest first, with markup tags resolved.
+    backlog: VecDeque<BacklogEntry>,
+    // Lines recorded since the last take_lines_read().
+    lines_read: u32,
+    backlog_open: bool,
+    continue_available: bool,
     settings_open: bool,
     settings_tab: SettingsTab,
     achievements_open: bool,
+    stats_open: bool,
+    stats_snapshot: StatsSnapshot,
+    gallery: Gallery,
+    gallery_open: bool,
+    // Id of the entry shown full-screen over the menu.
+    gallery_viewer: Option<String>,
+    credits: Credits,
+    credits_open: bool,
+    credits_scroll: f32,
+    credits_speed: f32,
+    credits_paused: bool,
+    // Lazily uploaded menu illustrations keyed by path; `None` marks a failed load.
+    ui_textures: HashMap<String, Option<egui::TextureHandle>>,
     achievements_snapshot: Vec<AchievementSnapshotItem>,
-    achievement_notifications: VecDeque<AchievementNotification>,
-    active_achievement_popup: Option<ActiveAchievementPopup>,
+    achievement_icons: IconCache,
+    achievement_filter: AchievementFilter,
+    achievement_category_filter: Option<String>,
+    achievement_sort: AchievementSort,
+    toast_queue: VecDeque<Toast>,
+    active_toasts: Vec<ActiveToast>,
+    next_toast_id: u64,
+    // Values substituted into `{$name}` placeholders of dialogue text.
+    text_variables: HashMap<String, String>,
+    persistent_flags: HashMap<String, String>,
+    glossary: Glossary,
+    // Voice clips for lines with an id but no explicit `voice`.
+    voice_bank: VoiceBank,
+    // Glossary entry pinned open by clicking a term link, with its screen position.
+    pinned_glossary_term: Option<(String, egui::Pos2)>,
+    text_input: Option<TextInputPrompt>,
+    submitted_inputs: Vec<(String, String)>,
+    // Mirror of the app's ActionMap, refreshed every frame.
+    key_bindings: Vec<(Action, Vec<KeyChord>)>,
+    binding_capture: Option<BindingCapture>,
+    // Modifier pressed during capture; bound on its own if released without another key.
+    capture_modifier_key: Option<KeyCode>,
+    modifiers: ModifiersState,
+    binding_message: Option<String>,
+    binding_changes: Vec<KeyBindingChange>,
+    // The reset button needs a second, explicit confirmation.
+    achievement_reset_armed: bool,
+    achievement_reset_requested: bool,
+    custom_themes: Vec<CustomTheme>,
+    themes_path: std::path::PathBuf,
+    debug_overlay_visible: bool,
+    profiler_visible: bool,
+    // Wall-clock frame times in milliseconds, newest last.
+    frame_times: VecDeque<f32>,
+    last_render_at: Option<Instant>,
+    render_stats: RenderStats,
+    // egui draw calls of the previous frame.
+    ui_paint_jobs: usize,
+    // When egui wants its next frame (hover fades, tooltips); `Duration::MAX` when idle.
+    repaint_delay: Duration,
+    locale: Localization,
     settings: UiSettings,
+    available_displays: Vec<DisplayInfo>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct BindingCapture {
+    action: Action,
+    slot: Option<usize>,
+}
+
+const DEBUG_FRAME_HISTORY: usize = 120;
+// Latest warnings and errors listed under the frame graph.
+const DEBUG_LOG_LINES: usize = 6;
+
+// Dialogue box show/hide animation at animation_speed 1.0.
+const DIALOGUE_FADE_SECONDS: f32 = 0.25;
+const DIALOGUE_SLIDE_DISTANCE: f32 = 28.0;
+
+// Reading-time heuristic for unvoiced lines.
+const AUTO_ADVANCE_SECONDS_PER_CHAR: f32 = 0.04;
+// Delay between lines while fast-forwarding.
+const FAST_FORWARD_ADVANCE_SECONDS: f32 = 0.08;
+const MAX_BACKLOG_ENTRIES: usize = 200;
+
+struct BacklogEntry {
+    speaker: String,
+    text: String,
+}
+
+struct VoiceLine {
+    playback: Option<SoundHandle>,
+    // Also true when the clip failed to start, so auto-advance does not stall.
+    finished: bool,
+}
+
+// Typewriter ticks vary their pitch by up to this fraction so repeats sound less mechanical.
+const TYPEWRITER_PITCH_VARIATION: f32 = 0.06;
+
+// Scrambles the tick counter into a speed in 1.0 ± TYPEWRITER_PITCH_VARIATION.
+fn typewriter_tick_speed(tick: u32) -> f32 {
+    let mut hash = tick.wrapping_mul(0x9E37_79B9);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x85EB_CA6B);
+    hash ^= hash >> 13;
+    let unit = (hash & 0xFFFF) as f32 / 65535.0;
+    1.0 + (unit * 2.0 - 1.0) * TYPEWRITER_PITCH_VARIATION
+}
+
+// Interrupted voice lines fade out this quickly instead of cutting off with a click.
+const VOICE_STOP_FADE: Duration = Duration::from_millis(80);
+// Sound captions are brief; a new sound usually follows soon.
+const CAPTION_TOAST_SECONDS: f32 = 2.0;
+
+impl VoiceLine {
+    fn stop(&self) {
+        if let Some(playback) = &self.playback {
+            playback.stop_with_fade(VOICE_STOP_FADE);
+        }
+    }
+}
+
+const ACHIEVEMENT_ICON_SIZE: f32 = 48.0;
+
+const TOAST_SLIDE_SECONDS: f32 = 0.35;
+const TOAST_WIDTH: f32 = 380.0;
+const TOAST_GAP: f32 = 10.0;
+const MAX_STACKED_TOASTS: usize = 3;
+
+// Styling category of a toast notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Achievement,
+    Info,
+    Success,
+    Error,
+    // Sound caption for deaf and hard-of-hearing players.
+    Caption,
+}
+
+impl ToastKind {
+    const fn heading_key(self) -> &'static str {
+        match self {
+            Self::Achievement => "popup.title",
+            Self::Info => "toast.info",
+            Self::Success => "toast.success",
+            Self::Error => "toast.error",
+            Self::Caption => "toast.caption",
+        }
+    }
+}
+
+struct Toast {
+    kind: ToastKind,
+    title: String,
+    body: String,
+    // Time fully shown, excluding the slide in/out animation.
+    duration: f32,
+}
+
+struct ActiveToast {
+    id: u64,
+    toast: Toast,
+    elapsed: f32,
+    duration: f32,
+    // Measured on the previous frame; used to stack the toasts below this one.
+    height: f32,
+    // Animated towards the stack position so toasts glide up when one leaves.
+    y_offset: f32,
 }
 
-struct ActiveAchievementPopup {
-    notification: AchievementNotification,
-    remaining: f32,
+impl ActiveToast {
+    // 0.0 = fully hidden off-screen, 1.0 = fully shown.
+    fn visibility(&self) -> f32 {
+        let intro = (self.elapsed / TOAST_SLIDE_SECONDS).clamp(0.0, 1.0);
+        let outro = ((self.duration - self.elapsed) / TOAST_SLIDE_SECONDS).clamp(0.0, 1.0);
+        let t = intro.min(outro);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    fn remaining_fraction(&self) -> f32 {
+        (1.0 - self.elapsed / self.duration).clamp(0.0, 1.0)
+    }
+}
+
+struct TextInputPrompt {
+    variable: String,
+    prompt: String,
+    // Latest copy of the InputState text entry that edits the answer.
+    entry: TextEntry,
 }
 
 impl DialogueUi {
@@ -168,34 +673,99 @@ impl DialogueUi {
         device: &wgpu::Device,
         surface_format: wgpu::TextureFormat,
     ) -> Self {
-        let egui_ctx = egui::Context::default();
-        let egui_state = EguiWinitState::new(
-            egui_ctx.clone(),
+        let mut dialogue_ui = Self::headless(device, surface_format);
+        dialogue_ui.egui_state = Some(EguiWinitState::new(
+            dialogue_ui.egui_ctx.clone(),
             egui::ViewportId::ROOT,
             window,
             Some(window.scale_factor() as f32),
             window.theme(),
             Some(device.limits().max_texture_dimension_2d as usize),
-        );
+        ));
+        dialogue_ui
+    }
+
+    // Without a window: driven by `run_headless` instead of `render`.
+    pub fn headless(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+        let egui_ctx = egui::Context::default();
         let egui_renderer = Renderer::new(device, surface_format, Default::default());
 
         Self {
             egui_ctx,
-            egui_state,
+            egui_state: None,
             egui_renderer,
             dialogue_objects: Vec::new(),
             dialogue_lookup: HashMap::new(),
+            speaker_placements: HashMap::new(),
             typing_progress: HashMap::new(),
+            dialogue_visibility: HashMap::new(),
             typewriter_sound_id: None,
             typewriter_sound_pending: false,
+            typewriter_ticks: 0,
+            ui_sound_ids: HashMap::new(),
+            pending_ui_sounds: Vec::new(),
+            hovered_widget: None,
+            frame_hovered_widget: None,
+            voice_lines: HashMap::new(),
+            auto_advance_elapsed: 0.0,
             main_menu_enabled: true,
+            loading_progress: None,
+            ui_hidden: false,
+            paused: false,
+            fast_forward: false,
+            backlog: VecDeque::new(),
+            lines_read: 0,
+            backlog_open: false,
+            continue_available: false,
             settings_open: false,
             settings_tab: SettingsTab::Audio,
             achievements_open: false,
+            stats_open: false,
+            stats_snapshot: StatsSnapshot::default(),
+            gallery: Gallery::default(),
+            gallery_open: false,
+            gallery_viewer: None,
+            credits: Credits::default(),
+            credits_open: false,
+            credits_scroll: 0.0,
+            credits_speed: 1.0,
+            credits_paused: false,
+            ui_textures: HashMap::new(),
             achievements_snapshot: Vec::new(),
-            achievement_notifications: VecDeque::new(),
-            active_achievement_popup: None,
+            achievement_icons: IconCache::default(),
+            achievement_filter: AchievementFilter::All,
+            achievement_category_filter: None,
+            achievement_sort: AchievementSort::Catalog,
+            toast_queue: VecDeque::new(),
+            active_toasts: Vec::new(),
+            next_toast_id: 0,
+            text_variables: HashMap::new(),
+            persistent_flags: HashMap::new(),
+            glossary: Glossary::default(),
+            voice_bank: VoiceBank::default(),
+            pinned_glossary_term: None,
+            text_input: None,
+            submitted_inputs: Vec::new(),
+            key_bindings: ActionMap::default().bindings(),
+            binding_capture: None,
+            capture_modifier_key: None,
+            modifiers: ModifiersState::empty(),
+            binding_message: None,
+            binding_changes: Vec::new(),
+            achievement_reset_armed: false,
+            achievement_reset_requested: false,
+            custom_themes: Vec::new(),
+            themes_path: ui_theme::DEFAULT_THEMES_PATH.into(),
+            debug_overlay_visible: false,
+            profiler_visible: false,
+            frame_times: VecDeque::with_capacity(DEBUG_FRAME_HISTORY),
+            last_render_at: None,
+            render_stats: RenderStats::default(),
+            ui_paint_jobs: 0,
+            repaint_delay: Duration::MAX,
+            locale: Localization::default(),
             settings: UiSettings::default(),
+            available_displays: Vec::new(),
         }
     }
 
@@ -205,6 +775,11 @@ impl DialogueUi {
         self
     }
 
+    pub fn set_ui_sound(&mut self, kind: UiSound, sound_id: impl Into<String>) -> &mut Self {
+        self.ui_sound_ids.insert(kind, sound_id.into());
+        self
+    }
+
     #[allow(dead_code)]
     pub fn clear_typewriter_sound(&mut self) -> &mut Self {
         self.typewriter_sound_id = None;
@@ -216,10 +791,31 @@ impl DialogueUi {
         if !enabled {
             self.settings_open = false;
             self.achievements_open = false;
+            self.gallery_open = false;
+            self.gallery_viewer = None;
+            self.credits_open = false;
+            self.binding_capture = None;
         }
         self
     }
 
+    // Enables the main menu "Continue" button; set when a latest-save marker exists.
+    pub fn set_continue_available(&mut self, available: bool) -> &mut Self {
+        self.continue_available = available;
+        self
+    }
+
+    pub fn set_gallery(&mut self, gallery: Gallery) -> &mut Self {
+        self.gallery = gallery;
+        self.gallery_viewer = None;
+        self
+    }
+
+    pub fn set_credits(&mut self, credits: Credits) -> &mut Self {
+        self.credits = credits;
+        self
+    }
+
     pub fn set_achievements_snapshot(
         &mut self,
         achievements: Vec<AchievementSnapshotItem>,
@@ -228,24 +824,265 @@ impl DialogueUi {
         self
     }
 
+    pub fn set_stats_snapshot(&mut self, stats: StatsSnapshot) -> &mut Self {
+        self.stats_snapshot = stats;
+        self
+    }
+
+    pub fn take_lines_read(&mut self) -> u32 {
+        std::mem::take(&mut self.lines_read)
+    }
+
     pub fn enqueue_achievement_notifications(
         &mut self,
         notifications: Vec<AchievementNotification>,
     ) -> &mut Self {
-        self.achievement_notifications.extend(notifications);
+        if !self.settings.popup_enabled {
+            return self;
+        }
+
+        let duration = self.settings.popup_duration.clamp(1.0, 8.0);
+        for notification in notifications {
+            self.push_toast(
+                ToastKind::Achievement,
+                notification.name,
+                notification.description,
+                duration,
+            );
+        }
+        self
+    }
+
+    // Queues a notification shown in the top-right stack for `duration` seconds.
+    pub fn push_toast(
+        &mut self,
+        kind: ToastKind,
+        title: impl Into<String>,
+        body: impl Into<String>,
+        duration: f32,
+    ) -> &mut Self {
+        self.toast_queue.push_back(Toast {
+            kind,
+            title: title.into(),
+            body: body.into(),
+            duration: duration.max(0.5),
+        });
         self
     }
 
-    pub fn has_active_achievement_popup(&self) -> bool {
-        if !self.settings.popup_enabled {
-            return false;
+    pub fn has_active_toast(&self) -> bool {
+        !self.active_toasts.is_empty() || !self.toast_queue.is_empty()
+    }
+
+    pub fn toggle_debug_overlay(&mut self) -> &mut Self {
+        self.debug_overlay_visible = !self.debug_overlay_visible;
+        self
+    }
+
+    // For a new GPU device after the old one was lost. egui's textures lived on the old
+    // device, so the context starts over and cached images load again on first use.
+    pub fn recreate_renderer(
+        &mut self,
+        window: &Window,
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+    ) {
+        self.egui_ctx = egui::Context::default();
+        self.egui_state = Some(EguiWinitState::new(
+            self.egui_ctx.clone(),
+            egui::ViewportId::ROOT,
+            window,
+            Some(window.scale_factor() as f32),
+            window.theme(),
+            Some(device.limits().max_texture_dimension_2d as usize),
+        ));
+        self.egui_renderer = Renderer::new(device, surface_format, Default::default());
+        self.ui_textures.clear();
+        self.achievement_icons = IconCache::default();
+    }
+
+    pub fn is_debug_overlay_visible(&self) -> bool {
+        self.debug_overlay_visible
+    }
+
+    // Only opens while profiling is enabled; there is nothing to show otherwise.
+    pub fn toggle_profiler(&mut self) -> &mut Self {
+        self.profiler_visible = !self.profiler_visible && profiler::is_enabled();
+        self
+    }
+
+    pub fn is_profiler_visible(&self) -> bool {
+        self.profiler_visible
+    }
+
+    // Delay after the last frame before egui needs another one, None when it is idle.
+    pub fn repaint_delay(&self) -> Option<Duration> {
+        (self.repaint_delay != Duration::MAX).then_some(self.repaint_delay)
+    }
+
+    pub fn set_render_stats(&mut self, stats: RenderStats) -> &mut Self {
+        self.render_stats = stats;
+        self
+    }
+
+    // Localized UI string, for engine messages such as toasts.
+    pub fn tr<'a>(&'a self, key: &'a str) -> &'a str {
+        self.locale.tr(key)
+    }
+
+    pub fn set_key_bindings(&mut self, bindings: Vec<(Action, Vec<KeyChord>)>) -> &mut Self {
+        self.key_bindings = bindings;
+        self
+    }
+
+    pub fn take_binding_changes(&mut self) -> Vec<KeyBindingChange> {
+        std::mem::take(&mut self.binding_changes)
+    }
+
+    pub fn take_achievement_reset_request(&mut self) -> bool {
+        std::mem::take(&mut self.achievement_reset_requested)
+    }
+
+    // While true, keyboard input is reserved for the rebinding prompt.
+    pub fn focus_loss_audio(&self) -> FocusLossAudio {
+        self.settings.focus_loss_audio
+    }
+
+    pub fn display_settings(&self) -> &DisplaySettings {
+        &self.settings.display
+    }
+
+    pub fn set_display_settings(&mut self, settings: DisplaySettings) -> &mut Self {
+        self.settings.display = settings;
+        self
+    }
+
+    // Monitors offered by the resolution picker.
+    pub fn set_available_displays(&mut self, displays: Vec<DisplayInfo>) -> &mut Self {
+        self.available_displays = displays;
+        self
+    }
+
+    pub fn toggle_display_mode(&mut self) -> &mut Self {
+        self.settings.display.toggle_fullscreen();
+        self
+    }
+
+    pub fn is_capturing_key_binding(&self) -> bool {
+        self.binding_capture.is_some()
+    }
+
+    // True while the pointer is over or dragging an egui widget.
+    #[allow(dead_code)]
+    pub fn wants_pointer_input(&self) -> bool {
+        self.egui_ctx.wants_pointer_input()
+    }
+
+    // True while a widget such as a text field or slider has keyboard focus.
+    pub fn wants_keyboard_input(&self) -> bool {
+        self.egui_ctx.wants_keyboard_input()
+    }
+
+    // Default placement for dialogues of `speaker` that do not set their own.
+    #[allow(dead_code)]
+    pub fn set_speaker_placement(
+        &mut self,
+        speaker: impl Into<String>,
+        anchor: DialogueAnchor,
+        align: DialogueAlign,
+    ) -> &mut Self {
+        self.speaker_placements
+            .insert(speaker.into(), DialoguePlacement::new(anchor, align));
+        self
+    }
+
+    fn placement_for(&self, dialogue: &DialogueBoxObject) -> DialoguePlacement {
+        dialogue
+            .placement
+            .or_else(|| self.speaker_placements.get(&dialogue.speaker).copied())
+            .unwrap_or_default()
+    }
+
+    // Adds language tables from `dir` to the built-in Russian and English ones.
+    pub fn load_locale_directory(
+        &mut self,
+        dir: impl AsRef<std::path::Path>,
+    ) -> Result<usize, String> {
+        self.locale.load_directory(dir)
+    }
+
+    // Custom themes are saved back to the same file from the settings editor.
+    pub fn load_custom_themes(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<usize, String> {
+        let path = path.as_ref();
+        self.themes_path = path.to_path_buf();
+        self.custom_themes = ui_theme::load_custom_themes(path)?;
+        Ok(self.custom_themes.len())
+    }
+
+    pub fn set_glossary(&mut self, glossary: Glossary) -> &mut Self {
+        self.glossary = glossary;
+        self
+    }
+
+    pub fn set_voice_bank(&mut self, voice_bank: VoiceBank) -> &mut Self {
+        self.voice_bank = voice_bank;
+        self
+    }
+
+    pub fn set_text_variables(&mut self, variables: HashMap<String, String>) -> &mut Self {
+        self.text_variables = variables;
+        self
+    }
+
+    pub fn set_persistent_flags(&mut self, flags: HashMap<String, String>) -> &mut Self {
+        self.persistent_flags = flags;
+        self
+    }
+
+    // Opens the input box; the submitted value is returned by take_submitted_inputs().
+    pub fn request_text_input(&mut self, variable: impl Into<String>, prompt: impl Into<String>) {
+        self.text_input = Some(TextInputPrompt {
+            variable: variable.into(),
+            prompt: prompt.into(),
+            entry: TextEntry::default(),
+        });
+    }
+
+    pub fn is_awaiting_text_input(&self) -> bool {
+        self.text_input.is_some()
+    }
+
+    // Shows the line being typed; the prompt draws it but never edits it.
+    pub fn set_text_entry(&mut self, entry: Option<&TextEntry>) {
+        if let Some(prompt) = self.text_input.as_mut() {
+            prompt.entry = entry.cloned().unwrap_or_default();
         }
+    }
+
+    // Empty values are rejected; the box stays open until the player types something.
+    pub fn submit_text_input(&mut self, value: &str) {
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+        if let Some(prompt) = self.text_input.take() {
+            self.submitted_inputs
+                .push((prompt.variable, value.to_owned()));
+        }
+    }
 
-        self.active_achievement_popup.is_some() || !self.achievement_notifications.is_empty()
+    pub fn take_submitted_inputs(&mut self) -> Vec<(String, String)> {
+        std::mem::take(&mut self.submitted_inputs)
     }
 
     pub fn apply_dialogue_object(&mut self, dialogue: DialogueBoxObject) {
         let key = dialogue.scene_key();
+        if !dialogue.hidden {
+            self.record_backlog(&dialogue);
+        }
 
         if let Some(index) = self.dialogue_lookup.get(&key).copied() {
             let mut reset_typing = true;
@@ -256,23 +1093,137 @@ impl DialogueUi {
                 *existing = dialogue;
             }
             if reset_typing {
-                self.typing_progress.insert(key.clone(), 0.0);
+                self.typing_progress
+                    .insert(key.clone(), TypewriterState::default());
+                // Replay the voice once the line is shown again.
+                if let Some(line) = self.voice_lines.remove(&key) {
+                    line.stop();
+                }
+                self.auto_advance_elapsed = 0.0;
             }
             self.rebuild_dialogue_lookup();
             return;
         }
 
         self.dialogue_objects.push(dialogue);
-        self.typing_progress.insert(key, 0.0);
+        self.typing_progress.insert(key, TypewriterState::default());
         self.rebuild_dialogue_lookup();
     }
 
-    pub fn on_window_event(&mut self, window: &Window, event: &WindowEvent) -> bool {
-        self.egui_state.on_window_event(window, event).repaint
+    // Records a line once when it is first shown or its text changes.
+    fn record_backlog(&mut self, dialogue: &DialogueBoxObject) {
+        if let Some(existing) = self
+            .dialogue_lookup
+            .get(&dialogue.scene_key())
+            .and_then(|index| self.dialogue_objects.get(*index))
+            && !existing.hidden
+            && existing.text == dialogue.text
+        {
+            return;
+        }
+
+        self.lines_read += 1;
+        if self.backlog.len() == MAX_BACKLOG_ENTRIES {
+            self.backlog.pop_front();
+        }
+        self.backlog.push_back(BacklogEntry {
+            speaker: dialogue.speaker.clone(),
+            text: resolve_dialogue_markup(&dialogue.text, &self.text_variables).text,
+        });
     }
 
-    pub fn render(
-        &mut self,
+    // Drops every dialogue box, e.g. before a save is loaded into a fresh scene.
+    pub fn clear_dialogues(&mut self) {
+        self.dialogue_objects.clear();
+        self.dialogue_lookup.clear();
+        self.typing_progress.clear();
+        self.dialogue_visibility.clear();
+        for (_, line) in self.voice_lines.drain() {
+            line.stop();
+        }
+        self.text_input = None;
+        self.pinned_glossary_term = None;
+        self.auto_advance_elapsed = 0.0;
+    }
+
+    pub fn on_window_event(
+        &mut self,
+        window: &Window,
+        event: &WindowEvent,
+    ) -> egui_winit::EventResponse {
+        if let WindowEvent::ModifiersChanged(modifiers) = event {
+            self.modifiers = modifiers.state();
+        }
+
+        if let (Some(capture), WindowEvent::KeyboardInput { event, .. }) =
+            (self.binding_capture, event)
+            && let PhysicalKey::Code(code) = event.physical_key
+        {
+            let chord = match event.state {
+                ElementState::Pressed if event.repeat => None,
+                // Wait for the next key to form a chord such as Ctrl+S.
+                ElementState::Pressed if is_modifier_key(code) => {
+                    self.capture_modifier_key = Some(code);
+                    None
+                }
+                ElementState::Pressed => Some(KeyChord::with_modifiers(code, self.modifiers)),
+                ElementState::Released if self.capture_modifier_key == Some(code) => {
+                    Some(KeyChord::new(code))
+                }
+                ElementState::Released => None,
+            };
+            if let Some(chord) = chord {
+                self.finish_binding_capture(capture, chord);
+            }
+            return egui_winit::EventResponse {
+                repaint: true,
+                consumed: true,
+            };
+        }
+
+        match self.egui_state.as_mut() {
+            Some(egui_state) => egui_state.on_window_event(window, event),
+            None => egui_winit::EventResponse::default(),
+        }
+    }
+
+    fn finish_binding_capture(&mut self, capture: BindingCapture, key: KeyChord) {
+        self.binding_capture = None;
+        self.capture_modifier_key = None;
+
+        // Plain Escape cancels the capture instead of being bound.
+        if key == KeyChord::new(KeyCode::Escape) {
+            self.binding_message = None;
+            return;
+        }
+
+        let conflict = self
+            .key_bindings
+            .iter()
+            .find(|(action, keys)| *action != capture.action && keys.contains(&key))
+            .map(|(action, _)| *action);
+        if let Some(other) = conflict {
+            self.binding_message = Some(self.locale.tr_args(
+                "controls.conflict",
+                &[
+                    ("key", chord_label(key)),
+                    ("action", self.locale.tr(action_title_key(other)).to_owned()),
+                ],
+            ));
+            return;
+        }
+
+        // The app applies the change and sends back a fresh snapshot next frame.
+        self.binding_changes.push(KeyBindingChange::Set {
+            action: capture.action,
+            slot: capture.slot,
+            key,
+        });
+        self.binding_message = None;
+    }
+
+    pub fn render(
+        &mut self,
         window: &Window,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
@@ -280,51 +1231,37 @@ impl DialogueUi {
         dt: f32,
         audio: Option<&mut AudioEngine>,
     ) -> UiCommand {
-        self.typewriter_sound_pending = false;
-
-        let egui_ctx = self.egui_ctx.clone();
-        egui_ctx.set_pixels_per_point(self.settings.ui_scale.clamp(0.75, 1.6));
-
-        let raw_input = self.egui_state.take_egui_input(window);
-        let mut ui_command = UiCommand::None;
-        let full_output = egui_ctx.run(raw_input, |ctx| {
-            if self.main_menu_enabled {
-                ui_command = self.draw_main_menu(ctx);
-            } else if self.draw_dialogue_boxes(ctx, dt) {
-                ui_command = UiCommand::SkipWait;
-            }
-
-            self.draw_achievement_popup(ctx, dt);
-        });
-
-        // Play at most one tick sound per frame if typing advanced.
-        if self.typewriter_sound_pending && self.settings.typewriter_sound_enabled {
-            if let (Some(sound_id), Some(audio)) = (self.typewriter_sound_id.as_deref(), audio) {
-                let volume = self.settings.master_volume * self.settings.typewriter_sound_volume;
-                if volume > 0.0 {
-                    if let Err(err) = audio.play(sound_id, volume) {
-                        eprintln!("typewriter sound playback failed: {err}");
-                    }
-                }
-            }
+        let raw_input = self
+            .egui_state
+            .as_mut()
+            .map_or_else(egui::RawInput::default, |egui_state| {
+                egui_state.take_egui_input(window)
+            });
+        let (ui_command, full_output) = self.run_frame(raw_input, dt, audio);
+        if let Some(egui_state) = self.egui_state.as_mut() {
+            egui_state.handle_platform_output(window, full_output.platform_output);
         }
 
-        self.egui_state
-            .handle_platform_output(window, full_output.platform_output);
-
+        let egui_ctx = self.egui_ctx.clone();
         let pixels_per_point = egui_winit::pixels_per_point(&egui_ctx, window);
+        let tessellate_scope = profiler::scope("egui_tessellate");
         let paint_jobs = egui_ctx.tessellate(full_output.shapes, pixels_per_point);
+        drop(tessellate_scope);
+        self.ui_paint_jobs = paint_jobs.len();
         let size = window.inner_size();
         let screen_descriptor = ScreenDescriptor {
             size_in_pixels: [size.width.max(1), size.height.max(1)],
             pixels_per_point,
         };
 
+        let upload_scope = profiler::scope("egui_texture_upload");
         for (id, image_delta) in full_output.textures_delta.set {
             self.egui_renderer
                 .update_texture(device, queue, id, &image_delta);
         }
+        drop(upload_scope);
 
+        let _submit_scope = profiler::scope("egui_submit");
         let mut encoder =
             device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
@@ -367,6 +1304,142 @@ impl DialogueUi {
         ui_command
     }
 
+    // One UI pass without painting, for runs without a window; `raw_input` carries the
+    // screen size and any synthetic egui events.
+    #[allow(dead_code)]
+    pub fn run_headless(
+        &mut self,
+        raw_input: egui::RawInput,
+        dt: f32,
+        audio: Option<&mut AudioEngine>,
+    ) -> UiCommand {
+        let (ui_command, _) = self.run_frame(raw_input, dt, audio);
+        ui_command
+    }
+
+    // Everything of a frame but presenting it: audio settings, the egui pass, UI sounds.
+    fn run_frame(
+        &mut self,
+        raw_input: egui::RawInput,
+        dt: f32,
+        mut audio: Option<&mut AudioEngine>,
+    ) -> (UiCommand, egui::FullOutput) {
+        self.update_voice_lines(audio.as_deref_mut());
+        if let Some(audio) = audio.as_deref_mut() {
+            let _scope = profiler::scope("audio_update");
+            // The settings sliders are the bus volumes.
+            audio.set_master_volume(self.settings.master_volume);
+            audio.set_muted(self.settings.muted);
+            audio.set_bus_volume(AudioBus::Sfx, self.settings.typewriter_sound_volume);
+            audio.set_bus_volume(AudioBus::Ui, self.settings.ui_sound_volume);
+            audio.set_bus_volume(AudioBus::Voice, self.settings.voice_volume);
+            audio.set_bus_volume(AudioBus::Music, self.settings.music_volume);
+            audio.set_bus_volume(AudioBus::Ambience, self.settings.ambience_volume);
+            audio.set_ducking(Ducking {
+                amount: self.settings.music_ducking,
+                ..Ducking::default()
+            });
+            audio.set_captions_enabled(self.settings.sound_captions);
+            audio.update();
+            for caption in audio.take_captions() {
+                self.push_toast(ToastKind::Caption, caption, "", CAPTION_TOAST_SECONDS);
+            }
+        }
+        self.frame_hovered_widget = None;
+
+        // Measured here rather than taken from `dt`, which is zero outside of gameplay.
+        let now = Instant::now();
+        if let Some(last) = self.last_render_at.replace(now) {
+            if self.frame_times.len() == DEBUG_FRAME_HISTORY {
+                self.frame_times.pop_front();
+            }
+            self.frame_times
+                .push_back((now - last).as_secs_f32() * 1000.0);
+        }
+
+        if self.locale.active_code() != self.settings.language
+            && !self.locale.set_language(&self.settings.language)
+        {
+            self.settings.language = self.locale.active_code().to_owned();
+        }
+
+        let egui_ctx = self.egui_ctx.clone();
+        // Relative to the monitor's scale factor, so the UI keeps its size across displays.
+        egui_ctx.set_zoom_factor(self.settings.ui_scale.clamp(0.75, 1.6));
+
+        let mut ui_command = UiCommand::None;
+        let egui_scope = profiler::scope("egui_run");
+        let full_output = egui_ctx.run(raw_input, |ctx| {
+            if self.main_menu_enabled {
+                ui_command = self.draw_main_menu(ctx, dt);
+            } else if let Some(progress) = self.loading_progress {
+                self.draw_loading_screen(ctx, progress);
+            } else if !self.ui_hidden {
+                if self.draw_dialogue_boxes(ctx, dt) {
+                    ui_command = UiCommand::SkipWait;
+                }
+                self.draw_text_input_prompt(ctx);
+                if self.backlog_open {
+                    self.draw_backlog_window(ctx);
+                }
+                if self.paused {
+                    self.draw_pause_overlay(ctx);
+                }
+            }
+
+            if !self.ui_hidden {
+                self.draw_toasts(ctx, dt);
+            }
+            if self.debug_overlay_visible {
+                self.draw_debug_overlay(ctx);
+            }
+            if self.profiler_visible && self.draw_profiler_window(ctx) {
+                ui_command = UiCommand::ExportProfile;
+            }
+        });
+        drop(egui_scope);
+
+        if ui_command == UiCommand::None && self.update_auto_advance(dt) {
+            ui_command = UiCommand::SkipWait;
+        }
+
+        if self.frame_hovered_widget.is_some() && self.frame_hovered_widget != self.hovered_widget {
+            self.pending_ui_sounds.push(UiSound::Hover);
+        }
+        self.hovered_widget = self.frame_hovered_widget;
+        let ui_sounds = std::mem::take(&mut self.pending_ui_sounds);
+
+        if let Some(audio) = audio {
+            // Play at most one tick sound per frame if typing advanced.
+            if self.typewriter_sound_pending
+                && self.settings.typewriter_sound_enabled
+                && let Some(sound_id) = self.typewriter_sound_id.as_deref()
+            {
+                self.typewriter_ticks = self.typewriter_ticks.wrapping_add(1);
+                let options =
+                    PlayOptions::default().with_speed(typewriter_tick_speed(self.typewriter_ticks));
+                if let Err(err) = audio.play_with(sound_id, AudioBus::Sfx, options) {
+                    log::warn!("typewriter sound playback failed: {err}");
+                }
+            }
+
+            // One UI sound per frame; a click supersedes the hover sound of the same frame.
+            let ui_sound = ui_sounds.iter().copied().max_by_key(|kind| *kind as u8);
+            if let Some(kind) = ui_sound.filter(|_| self.settings.ui_sounds_enabled)
+                && let Some(sound_id) = self.ui_sound_ids.get(&kind)
+                && let Err(err) = audio.play(sound_id, AudioBus::Ui, 1.0)
+            {
+                log::warn!("ui sound playback failed: {err}");
+            }
+        }
+
+        self.repaint_delay = full_output
+            .viewport_output
+            .get(&egui::ViewportId::ROOT)
+            .map_or(Duration::MAX, |viewport| viewport.repaint_delay);
+        (ui_command, full_output)
+    }
+
     pub fn has_active_typewriter_animation(&self) -> bool {
         if self.main_menu_enabled || !self.settings.typewriter_enabled {
             return false;
@@ -377,57 +1450,293 @@ impl DialogueUi {
             .iter()
             .filter(|dialogue| !dialogue.hidden)
             .any(|dialogue| {
-                let key = dialogue.scene_key();
-                let shown = self.typing_progress.get(&key).copied().unwrap_or(0.0);
-                shown < dialogue.text.chars().count() as f32
+                let markup = resolve_dialogue_markup(&dialogue.text, &self.text_variables);
+                self.typing_progress
+                    .get(&dialogue.scene_key())
+                    .is_none_or(|state| !state.is_complete(&markup))
             })
     }
 
     pub fn can_skip_wait(&self) -> bool {
-        !self.has_active_typewriter_animation()
+        self.text_input.is_none() && !self.has_active_typewriter_animation()
+    }
+
+    // Some shows the loading screen in place of the dialogue UI.
+    pub fn set_loading_progress(&mut self, progress: Option<f32>) {
+        self.loading_progress = progress.map(|progress| progress.clamp(0.0, 1.0));
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_fast_forward(&mut self, active: bool) {
+        self.fast_forward = active;
+    }
+
+    // Returns the new state so the caller can report it.
+    pub fn toggle_auto_advance(&mut self) -> bool {
+        self.settings.auto_advance = !self.settings.auto_advance;
+        self.auto_advance_elapsed = 0.0;
+        self.settings.auto_advance
+    }
+
+    pub fn toggle_backlog(&mut self) {
+        self.backlog_open = !self.backlog_open;
+    }
+
+    pub fn is_backlog_open(&self) -> bool {
+        self.backlog_open
+    }
+
+    pub fn has_visible_dialogue(&self) -> bool {
+        self.dialogue_objects
+            .iter()
+            .any(|dialogue| !dialogue.hidden)
+    }
+
+    // Shown lines with variables substituted and markup stripped, e.g. for headless runs.
+    #[allow(dead_code)]
+    pub fn visible_dialogue_texts(&self) -> Vec<String> {
+        self.dialogue_objects
+            .iter()
+            .filter(|dialogue| !dialogue.hidden)
+            .map(|dialogue| resolve_dialogue_markup(&dialogue.text, &self.text_variables).text)
+            .collect()
+    }
+
+    pub fn set_ui_hidden(&mut self, hidden: bool) {
+        self.ui_hidden = hidden;
+    }
+
+    pub fn is_ui_hidden(&self) -> bool {
+        self.ui_hidden
+    }
+
+    // True while a dialogue box is sliding in or out.
+    pub fn has_active_dialogue_transition(&self) -> bool {
+        !self.main_menu_enabled
+            && !self.ui_hidden
+            && self.dialogue_objects.iter().any(|dialogue| {
+                let visibility = self
+                    .dialogue_visibility
+                    .get(&dialogue.scene_key())
+                    .copied()
+                    .unwrap_or(0.0);
+                if dialogue.hidden {
+                    visibility > 0.0
+                } else {
+                    visibility < 1.0
+                }
+            })
+    }
+
+    // True while an auto-advance timer or voice clip needs frames to progress.
+    pub fn is_auto_advance_pending(&self) -> bool {
+        (self.settings.auto_advance || self.fast_forward)
+            && !self.main_menu_enabled
+            && !self.ui_hidden
+            && !self.paused
+            && self.has_visible_dialogue()
+    }
+
+    // Starts voice clips of newly shown lines and collects playback-finished notifications.
+    fn update_voice_lines(&mut self, audio: Option<&mut AudioEngine>) {
+        let visible: Vec<(String, Option<String>, Option<String>)> = if self.main_menu_enabled {
+            Vec::new()
+        } else {
+            self.dialogue_objects
+                .iter()
+                .filter(|dialogue| !dialogue.hidden)
+                .map(|dialogue| {
+                    (
+                        dialogue.scene_key(),
+                        dialogue.voice.clone(),
+                        dialogue.id.clone(),
+                    )
+                })
+                .collect()
+        };
+
+        self.voice_lines.retain(|key, line| {
+            let shown = visible.iter().any(|(visible_key, ..)| visible_key == key);
+            if !shown {
+                line.stop();
+            }
+            shown
+        });
+
+        let Some(audio) = audio else {
+            // Without audio output voiced lines fall back to the plain delay.
+            for (key, voice, id) in visible {
+                let banked = id.is_some_and(|id| self.voice_bank.has_voice(&id));
+                if voice.is_some() || banked {
+                    self.voice_lines.entry(key).or_insert(VoiceLine {
+                        playback: None,
+                        finished: true,
+                    });
+                }
+            }
+            return;
+        };
+
+        for line in self.voice_lines.values_mut() {
+            if line.playback.as_ref().is_some_and(SoundHandle::is_finished) {
+                line.playback = None;
+                line.finished = true;
+            }
+        }
+
+        for (key, voice, id) in visible {
+            if self.voice_lines.contains_key(&key) {
+                continue;
+            }
+            let sound_id =
+                voice.or_else(|| id.and_then(|id| self.voice_bank.sound_for_line(&id, audio)));
+            let Some(sound_id) = sound_id else {
+                continue;
+            };
+            let playback = match audio.play(&sound_id, AudioBus::Voice, 1.0) {
+                Ok(handle) => Some(handle),
+                Err(err) => {
+                    log::warn!("voice playback failed: {err}");
+                    None
+                }
+            };
+            self.voice_lines.insert(
+                key,
+                VoiceLine {
+                    finished: playback.is_none(),
+                    playback,
+                },
+            );
+        }
+    }
+
+    // Returns true once the visible lines have been on screen long enough to advance.
+    fn update_auto_advance(&mut self, dt: f32) -> bool {
+        if !self.is_auto_advance_pending() || !self.can_skip_wait() {
+            self.auto_advance_elapsed = 0.0;
+            return false;
+        }
+
+        let visible = self
+            .dialogue_objects
+            .iter()
+            .filter(|dialogue| !dialogue.hidden);
+        let mut voiced = false;
+        let mut voice_playing = false;
+        let mut char_count = 0;
+        for dialogue in visible {
+            char_count += resolve_dialogue_markup(&dialogue.text, &self.text_variables).char_count;
+            let banked = dialogue
+                .id
+                .as_deref()
+                .is_some_and(|id| self.voice_bank.has_voice(id));
+            if dialogue.voice.is_some() || banked {
+                voiced = true;
+                voice_playing |= self
+                    .voice_lines
+                    .get(&dialogue.scene_key())
+                    .is_none_or(|line| !line.finished);
+            }
+        }
+
+        let wait_for_voice =
+            voiced && self.settings.auto_advance_wait_for_voice && !self.fast_forward;
+        if wait_for_voice && voice_playing {
+            // The buffer is counted from the moment the clip ends.
+            self.auto_advance_elapsed = 0.0;
+            return false;
+        }
+
+        self.auto_advance_elapsed += dt.max(0.0);
+        let required = if self.fast_forward {
+            FAST_FORWARD_ADVANCE_SECONDS
+        } else if wait_for_voice {
+            self.settings.voice_buffer
+        } else {
+            self.settings.auto_advance_delay + char_count as f32 * AUTO_ADVANCE_SECONDS_PER_CHAR
+        };
+        if self.auto_advance_elapsed < required {
+            return false;
+        }
+
+        self.auto_advance_elapsed = 0.0;
+        true
     }
 
     fn draw_dialogue_boxes(&mut self, ctx: &egui::Context, dt: f32) -> bool {
         let mut skip_requested = false;
+        let anim_dt = dt.max(0.0) * self.settings.animation_speed.clamp(0.2, 2.0);
+
+        // Hidden boxes keep being drawn until their fade-out finishes.
+        let fade_step = anim_dt / DIALOGUE_FADE_SECONDS;
+        for dialogue in &self.dialogue_objects {
+            let key = dialogue.scene_key();
+            if dialogue.hidden && !self.dialogue_visibility.contains_key(&key) {
+                continue;
+            }
+            let visibility = self.dialogue_visibility.entry(key).or_insert(0.0);
+            *visibility = if dialogue.hidden {
+                (*visibility - fade_step).max(0.0)
+            } else {
+                (*visibility + fade_step).min(1.0)
+            };
+        }
+        self.dialogue_visibility
+            .retain(|_, visibility| *visibility > 0.0);
 
         let visible_dialogues: Vec<_> = self
             .dialogue_objects
             .iter()
-            .filter(|dialogue| !dialogue.hidden)
-            .map(|dialogue| (dialogue.scene_key(), dialogue))
+            .filter_map(|dialogue| {
+                let key = dialogue.scene_key();
+                let visibility = self.dialogue_visibility.get(&key).copied()?;
+                let markup = resolve_dialogue_markup(&dialogue.text, &self.text_variables);
+                Some((key, dialogue, markup, visibility))
+            })
             .collect();
 
         if visible_dialogues.is_empty() {
+            self.pinned_glossary_term = None;
             return false;
         }
 
         let palette = self.theme_palette();
-        let viewport = ctx.viewport_rect();
-        let max_width = (viewport.width() - 18.0).max(240.0);
-        let box_width = (viewport.width() * 0.90).clamp(240.0, max_width);
-        let box_height =
-            (viewport.height() * self.settings.dialogue_box_height_ratio).clamp(104.0, 180.0);
-        let x = viewport.left() + (viewport.width() - box_width) * 0.5;
-        let mut y = viewport.bottom() - box_height - 14.0;
+        let placements: Vec<DialoguePlacement> = visible_dialogues
+            .iter()
+            .map(|(_, dialogue, _, _)| self.placement_for(dialogue))
+            .collect();
+        let layouts = self.layout_dialogue_boxes(ctx.viewport_rect(), &placements);
 
-        let mut displayed_texts: Vec<String> = Vec::with_capacity(visible_dialogues.len());
+        let mut displayed_texts: Vec<Vec<TextSegment>> =
+            Vec::with_capacity(visible_dialogues.len());
         let mut all_dialogues_revealed = true;
-        let anim_dt = dt.max(0.0) * self.settings.animation_speed.clamp(0.2, 2.0);
 
-        for (key, dialogue) in &visible_dialogues {
-            let total_chars = dialogue.text.chars().count();
-            let shown_progress = self.typing_progress.entry(key.clone()).or_insert(0.0);
-            let previous_chars = shown_progress.floor() as usize;
+        for (key, dialogue, markup, _) in &visible_dialogues {
+            let total_chars = markup.char_count;
+            let typewriter = self.typing_progress.entry(key.clone()).or_default();
+            let previous_chars = typewriter.shown_chars();
 
-            if self.settings.typewriter_enabled {
-                *shown_progress = (*shown_progress
-                    + anim_dt * self.settings.typing_chars_per_second)
-                    .min(total_chars as f32);
+            // A box fading out keeps its text frozen and does not hold up skipping.
+            if dialogue.hidden {
+                displayed_texts.push(markup.segments(previous_chars.min(total_chars)));
+                continue;
+            }
+
+            // Inline {pause}/{speed}/{instant} tags are applied by the typewriter state.
+            if self.settings.typewriter_enabled && !self.fast_forward {
+                typewriter.advance(markup, anim_dt, self.settings.typing_chars_per_second);
             } else {
-                *shown_progress = total_chars as f32;
+                typewriter.reveal_all(markup);
             }
 
-            let shown_chars = shown_progress.floor() as usize;
+            let shown_chars = typewriter.shown_chars().min(total_chars);
 
             // If new characters were revealed this frame, schedule a typewriter tick.
             if shown_chars > previous_chars {
@@ -435,10 +1744,13 @@ impl DialogueUi {
             }
 
             // Render only the visible text prefix plus a caret while typing is active.
-            let mut displayed_text: String = dialogue.text.chars().take(shown_chars).collect();
+            let mut displayed_text = markup.segments(shown_chars);
             if shown_chars < total_chars {
                 if self.settings.show_typing_caret {
-                    displayed_text.push('|');
+                    displayed_text.push(TextSegment {
+                        text: "|".to_owned(),
+                        term: None,
+                    });
                 }
                 all_dialogues_revealed = false;
             }
@@ -447,20 +1759,37 @@ impl DialogueUi {
         }
 
         let fill_alpha = (self.settings.dialogue_box_opacity.clamp(0.15, 1.0) * 255.0) as u8;
+        let mut clicked_term: Option<(String, egui::Pos2)> = None;
 
-        for (index, (_key, dialogue)) in visible_dialogues.iter().enumerate() {
+        for (index, (_key, dialogue, _markup, visibility)) in visible_dialogues.iter().enumerate() {
             let displayed_text = &displayed_texts[index];
+            let eased = visibility * visibility * (3.0 - 2.0 * visibility);
+            // Slide in from the screen edge the box is anchored to.
+            let slide = match placements[index].anchor {
+                DialogueAnchor::Top => -DIALOGUE_SLIDE_DISTANCE,
+                DialogueAnchor::Middle | DialogueAnchor::Bottom => DIALOGUE_SLIDE_DISTANCE,
+            };
+            let rect = layouts[index].translate(egui::vec2(0.0, (1.0 - eased) * slide));
 
             egui::Area::new(egui::Id::new(("dialogue_box", index)))
                 .order(egui::Order::Foreground)
-                .fixed_pos(egui::pos2(x, y))
+                .fixed_pos(rect.min)
+                .interactable(!dialogue.hidden)
                 .show(ctx, |ui| {
-                    ui.set_min_width(box_width);
-                    ui.set_max_width(box_width);
-                    ui.set_min_height(box_height);
-                    ui.set_max_height(box_height);
+                    ui.set_opacity(eased);
+                    ui.set_min_width(rect.width());
+                    ui.set_max_width(rect.width());
+                    ui.set_min_height(rect.height());
+                    ui.set_max_height(rect.height());
+
+                    // Registered before the contents so term links stay clickable on top of it.
+                    let click_response = ui.interact(
+                        rect,
+                        egui::Id::new(("dialogue_box_click", index)),
+                        Sense::click(),
+                    );
 
-                    let frame_response = Frame::new()
+                    Frame::new()
                         .inner_margin(Margin::symmetric(22, 14))
                         .fill(Color32::from_rgba_unmultiplied(
                             palette.dialogue_fill_rgb[0],
@@ -473,18 +1802,59 @@ impl DialogueUi {
                         .show(ui, |ui| {
                             ui.with_layout(Layout::top_down(Align::Min), |ui| {
                                 ui.spacing_mut().item_spacing.y = 8.0;
+                                let effect = self.settings.text_effect;
+                                let effect_color = self.settings.text_effect_color;
+                                let effect_size = self.settings.text_effect_size;
                                 if self.settings.show_speaker_name && !dialogue.speaker.is_empty() {
-                                    ui.label(
-                                        RichText::new(dialogue.speaker.as_str())
-                                            .size(self.settings.speaker_text_size)
-                                            .color(palette.dialogue_speaker),
+                                    add_effect_label(
+                                        ui,
+                                        egui::Label::new(
+                                            RichText::new(dialogue.speaker.as_str())
+                                                .size(self.settings.speaker_text_size)
+                                                .color(palette.dialogue_speaker),
+                                        ),
+                                        effect,
+                                        effect_color,
+                                        effect_size,
                                     );
                                 }
-                                ui.label(
-                                    RichText::new(displayed_text)
-                                        .size(self.settings.dialogue_text_size)
-                                        .color(palette.dialogue_text),
-                                );
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.spacing_mut().item_spacing.x = 0.0;
+                                    for segment in displayed_text {
+                                        let text = RichText::new(segment.text.as_str())
+                                            .size(self.settings.dialogue_text_size);
+                                        let Some(term) = segment.term.as_deref() else {
+                                            add_effect_label(
+                                                ui,
+                                                egui::Label::new(text.color(palette.dialogue_text)),
+                                                effect,
+                                                effect_color,
+                                                effect_size,
+                                            );
+                                            continue;
+                                        };
+
+                                        let response = add_effect_label(
+                                            ui,
+                                            egui::Label::new(
+                                                text.color(palette.skip_ready).underline(),
+                                            )
+                                            .sense(Sense::click()),
+                                            effect,
+                                            effect_color,
+                                            effect_size,
+                                        )
+                                        .on_hover_ui(|ui| {
+                                            self.draw_glossary_entry(ui, term, &segment.text);
+                                        });
+                                        if response.clicked() {
+                                            clicked_term = Some((
+                                                term.to_owned(),
+                                                response.rect.left_bottom(),
+                                            ));
+                                        }
+                                    }
+                                });
                                 ui.separator();
 
                                 let skip_enabled = all_dialogues_revealed
@@ -495,9 +1865,9 @@ impl DialogueUi {
                                     palette.skip_wait
                                 };
                                 let skip_label = if all_dialogues_revealed {
-                                    "Пропустить"
+                                    self.locale.tr("dialogue.skip")
                                 } else {
-                                    "Печать..."
+                                    self.locale.tr("dialogue.typing")
                                 };
                                 let skip_link = ui.add_enabled(
                                     skip_enabled,
@@ -512,11 +1882,6 @@ impl DialogueUi {
                             });
                         });
 
-                    let click_response = ui.interact(
-                        frame_response.response.rect,
-                        egui::Id::new(("dialogue_box_click", index)),
-                        Sense::click(),
-                    );
                     if all_dialogues_revealed
                         && self.settings.allow_dialogue_click_skip
                         && click_response.clicked()
@@ -524,94 +1889,463 @@ impl DialogueUi {
                         skip_requested = true;
                     }
                 });
+        }
 
-            y -= box_height + 12.0;
+        let term_just_pinned = clicked_term.is_some();
+        if term_just_pinned {
+            self.pinned_glossary_term = clicked_term;
         }
+        self.draw_pinned_glossary_term(ctx, term_just_pinned);
 
         skip_requested
     }
 
-    fn draw_main_menu(&mut self, ctx: &egui::Context) -> UiCommand {
-        let mut command = UiCommand::None;
+    fn draw_glossary_entry(&self, ui: &mut Ui, key: &str, fallback_title: &str) {
+        ui.set_max_width(320.0);
+        match self.glossary.get(key) {
+            Some(entry) => {
+                ui.label(
+                    RichText::new(entry.title.as_deref().unwrap_or(fallback_title))
+                        .size(18.0)
+                        .strong(),
+                );
+                ui.label(RichText::new(entry.definition.as_str()).size(16.0));
+            }
+            None => {
+                ui.label(RichText::new(fallback_title).size(18.0).strong());
+                ui.label(RichText::new(self.locale.tr("glossary.missing")).size(16.0));
+            }
+        }
+    }
+
+    fn draw_pinned_glossary_term(&mut self, ctx: &egui::Context, just_pinned: bool) {
+        let Some((key, position)) = self.pinned_glossary_term.clone() else {
+            return;
+        };
+
         let palette = self.theme_palette();
+        let mut should_close = false;
+        let response = egui::Area::new(egui::Id::new("glossary_popup"))
+            .order(egui::Order::Tooltip)
+            .fixed_pos(position + egui::vec2(0.0, 6.0))
+            .show(ctx, |ui| {
+                Frame::new()
+                    .inner_margin(Margin::symmetric(12, 10))
+                    .fill(palette.settings_fill)
+                    .stroke(Stroke::new(1.5, palette.dialogue_stroke))
+                    .corner_radius(CornerRadius::same(8))
+                    .show(ui, |ui| {
+                        self.draw_glossary_entry(ui, &key, &key);
+                        if ui
+                            .add(
+                                egui::Label::new(
+                                    RichText::new(self.locale.tr("glossary.close"))
+                                        .size(15.0)
+                                        .color(palette.skip_ready),
+                                )
+                                .sense(Sense::click()),
+                            )
+                            .clicked()
+                        {
+                            should_close = true;
+                        }
+                    });
+            })
+            .response;
 
-        if !self.achievements_open && !self.settings_open {
-            egui::Area::new(egui::Id::new("main_menu_root"))
-                .order(egui::Order::Foreground)
-                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
-                .show(ctx, |ui| {
-                    Frame::new()
-                        .inner_margin(Margin::symmetric(26, 20))
-                        .fill(palette.menu_fill)
-                        .stroke(Stroke::new(2.0, palette.menu_stroke))
-                        .corner_radius(CornerRadius::same(16))
-                        .show(ui, |ui| {
-                            ui.vertical_centered(|ui| {
-                                ui.add_space(4.0);
-                                ui.label(
-                                    RichText::new("Главное меню")
-                                        .size(self.settings.menu_title_size)
-                                        .color(palette.menu_title),
-                                );
-                                ui.add_space(12.0);
+        // Any click outside the popup dismisses it as well.
+        let clicked_elsewhere = !just_pinned
+            && ctx.input(|input| input.pointer.any_click())
+            && !response.contains_pointer();
+        if should_close || clicked_elsewhere {
+            self.pinned_glossary_term = None;
+        }
+    }
 
-                                let button_size = if self.settings.compact_menu_buttons {
-                                    egui::vec2(228.0, 40.0)
-                                } else {
-                                    egui::vec2(250.0, 48.0)
-                                };
+    // Stacks boxes per anchor and shrinks them so every stack fits its share of the screen.
+    fn layout_dialogue_boxes(
+        &self,
+        viewport: egui::Rect,
+        placements: &[DialoguePlacement],
+    ) -> Vec<egui::Rect> {
+        const MARGIN: f32 = 14.0;
+        const GAP: f32 = 12.0;
 
-                                if ui
-                                    .add_sized(
-                                        button_size,
-                                        egui::Button::new(
-                                            RichText::new("Играть")
-                                                .size(self.settings.menu_button_text_size),
-                                        ),
-                                    )
-                                    .clicked()
-                                {
-                                    command = UiCommand::StartGame;
-                                }
+        let anchors = [
+            DialogueAnchor::Top,
+            DialogueAnchor::Middle,
+            DialogueAnchor::Bottom,
+        ];
+        let count_for = |anchor: DialogueAnchor| {
+            placements
+                .iter()
+                .filter(|placement| placement.anchor == anchor)
+                .count()
+        };
+        let anchors_in_use = anchors
+            .iter()
+            .filter(|anchor| count_for(**anchor) > 0)
+            .count()
+            .max(1);
 
-                                if ui
-                                    .add_sized(
+        let base_height =
+            (viewport.height() * self.settings.dialogue_box_height_ratio).clamp(104.0, 180.0);
+        let region_height = (viewport.height() - MARGIN * 2.0) / anchors_in_use as f32;
+        let max_width = (viewport.width() - 18.0).max(240.0);
+
+        let mut stack_index: HashMap<DialogueAnchor, usize> = HashMap::new();
+        placements
+            .iter()
+            .map(|placement| {
+                let count = count_for(placement.anchor);
+                let box_height = ((region_height - GAP * (count - 1) as f32) / count as f32)
+                    .min(base_height)
+                    .max(48.0);
+                let box_width = match placement.align {
+                    DialogueAlign::Center => (viewport.width() * 0.90).clamp(240.0, max_width),
+                    DialogueAlign::Left | DialogueAlign::Right => {
+                        (viewport.width() * 0.55).clamp(240.0, max_width)
+                    }
+                };
+
+                let x = match placement.align {
+                    DialogueAlign::Left => viewport.left() + MARGIN,
+                    DialogueAlign::Center => viewport.left() + (viewport.width() - box_width) * 0.5,
+                    DialogueAlign::Right => viewport.right() - MARGIN - box_width,
+                };
+
+                let slot = stack_index.entry(placement.anchor).or_insert(0);
+                let offset = *slot as f32 * (box_height + GAP);
+                *slot += 1;
+
+                // Bottom stacks grow upwards, top stacks downwards, middle around the center.
+                let y = match placement.anchor {
+                    DialogueAnchor::Bottom => viewport.bottom() - MARGIN - box_height - offset,
+                    DialogueAnchor::Top => viewport.top() + MARGIN + offset,
+                    DialogueAnchor::Middle => {
+                        let stack_height = count as f32 * box_height + (count - 1) as f32 * GAP;
+                        viewport.center().y - stack_height * 0.5 + offset
+                    }
+                };
+
+                egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(box_width, box_height))
+            })
+            .collect()
+    }
+
+    fn draw_backlog_window(&mut self, ctx: &egui::Context) {
+        let palette = self.theme_palette();
+        let mut open = true;
+        egui::Window::new(self.locale.tr("backlog.title"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([560.0, 420.0])
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                if self.backlog.is_empty() {
+                    ui.label(RichText::new(self.locale.tr("backlog.empty")).size(17.0));
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for entry in &self.backlog {
+                            if !entry.speaker.is_empty() {
+                                ui.label(
+                                    RichText::new(entry.speaker.as_str())
+                                        .size(self.settings.speaker_text_size * 0.85)
+                                        .color(palette.dialogue_speaker),
+                                );
+                            }
+                            ui.label(
+                                RichText::new(entry.text.as_str())
+                                    .size(self.settings.dialogue_text_size * 0.75)
+                                    .color(palette.dialogue_text),
+                            );
+                            ui.add_space(8.0);
+                        }
+                    });
+            });
+        self.backlog_open = open;
+    }
+
+    fn draw_pause_overlay(&self, ctx: &egui::Context) {
+        let palette = self.theme_palette();
+        egui::Area::new(egui::Id::new("pause_overlay"))
+            .order(egui::Order::Foreground)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                Frame::new()
+                    .inner_margin(Margin::symmetric(28, 16))
+                    .fill(palette.menu_fill)
+                    .stroke(Stroke::new(2.0, palette.menu_stroke))
+                    .corner_radius(CornerRadius::same(16))
+                    .show(ui, |ui| {
+                        ui.label(
+                            RichText::new(self.locale.tr("pause.title"))
+                                .size(self.settings.menu_title_size)
+                                .color(palette.menu_title),
+                        );
+                        let key = self
+                            .key_bindings
+                            .iter()
+                            .find(|(action, _)| *action == Action::Pause)
+                            .and_then(|(_, keys)| keys.first())
+                            .map(|key| chord_label(*key))
+                            .unwrap_or_default();
+                        ui.label(
+                            RichText::new(self.locale.tr_args("pause.hint", &[("key", key)]))
+                                .size(16.0)
+                                .color(palette.dialogue_text),
+                        );
+                    });
+            });
+    }
+
+    fn draw_loading_screen(&self, ctx: &egui::Context, progress: f32) {
+        let palette = self.theme_palette();
+        egui::Area::new(egui::Id::new("loading_screen"))
+            .order(egui::Order::Foreground)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                Frame::new()
+                    .inner_margin(Margin::symmetric(28, 16))
+                    .fill(palette.menu_fill)
+                    .stroke(Stroke::new(2.0, palette.menu_stroke))
+                    .corner_radius(CornerRadius::same(16))
+                    .show(ui, |ui| {
+                        ui.label(
+                            RichText::new(self.locale.tr("loading.title"))
+                                .size(self.settings.menu_title_size)
+                                .color(palette.menu_title),
+                        );
+                        ui.add(
+                            egui::ProgressBar::new(progress)
+                                .desired_width(320.0)
+                                .show_percentage(),
+                        );
+                    });
+            });
+    }
+
+    fn draw_text_input_prompt(&mut self, ctx: &egui::Context) {
+        let palette = self.theme_palette();
+        let fill_alpha = (self.settings.dialogue_box_opacity.clamp(0.15, 1.0) * 255.0) as u8;
+        let Some(prompt) = self.text_input.as_mut() else {
+            return;
+        };
+
+        let mut submitted = false;
+        egui::Area::new(egui::Id::new("text_input_prompt"))
+            .order(egui::Order::Foreground)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                Frame::new()
+                    .inner_margin(Margin::symmetric(22, 16))
+                    .fill(Color32::from_rgba_unmultiplied(
+                        palette.dialogue_fill_rgb[0],
+                        palette.dialogue_fill_rgb[1],
+                        palette.dialogue_fill_rgb[2],
+                        fill_alpha,
+                    ))
+                    .stroke(Stroke::new(2.0, palette.dialogue_stroke))
+                    .corner_radius(CornerRadius::same(self.settings.dialogue_corner_radius))
+                    .show(ui, |ui| {
+                        ui.set_min_width(360.0);
+                        ui.label(
+                            RichText::new(prompt.prompt.as_str())
+                                .size(self.settings.speaker_text_size)
+                                .color(palette.dialogue_speaker),
+                        );
+                        ui.add_space(8.0);
+
+                        // Typing goes through InputState; draw the line with a caret after
+                        // any IME composition.
+                        let text = prompt.entry.display_text();
+                        let caret = text
+                            .char_indices()
+                            .nth(prompt.entry.cursor() + prompt.entry.preedit().chars().count())
+                            .map_or(text.len(), |(offset, _)| offset);
+                        let (before, after) = text.split_at(caret);
+                        Frame::new()
+                            .inner_margin(Margin::symmetric(8, 6))
+                            .stroke(Stroke::new(1.0, palette.dialogue_stroke))
+                            .corner_radius(CornerRadius::same(6))
+                            .show(ui, |ui| {
+                                ui.set_min_width(ui.available_width());
+                                ui.label(
+                                    RichText::new(format!("{before}|{after}"))
+                                        .size(self.settings.dialogue_text_size)
+                                        .color(palette.dialogue_text),
+                                );
+                            });
+
+                        ui.add_space(8.0);
+                        let can_submit = !prompt.entry.text().trim().is_empty();
+                        if ui
+                            .add_enabled(
+                                can_submit,
+                                egui::Button::new(
+                                    RichText::new(self.locale.tr("text_input.submit")).size(18.0),
+                                ),
+                            )
+                            .clicked()
+                        {
+                            submitted = true;
+                        }
+                    });
+            });
+
+        if submitted {
+            let value = prompt.entry.text().to_owned();
+            self.submit_text_input(&value);
+        }
+    }
+
+    fn draw_main_menu(&mut self, ctx: &egui::Context, dt: f32) -> UiCommand {
+        let mut command = UiCommand::None;
+        let palette = self.theme_palette();
+
+        if !self.achievements_open
+            && !self.settings_open
+            && !self.gallery_open
+            && !self.credits_open
+        {
+            egui::Area::new(egui::Id::new("main_menu_root"))
+                .order(egui::Order::Foreground)
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    Frame::new()
+                        .inner_margin(Margin::symmetric(26, 20))
+                        .fill(palette.menu_fill)
+                        .stroke(Stroke::new(2.0, palette.menu_stroke))
+                        .corner_radius(CornerRadius::same(16))
+                        .show(ui, |ui| {
+                            ui.vertical_centered(|ui| {
+                                ui.add_space(4.0);
+                                ui.label(
+                                    RichText::new(self.locale.tr("menu.title"))
+                                        .size(self.settings.menu_title_size)
+                                        .color(palette.menu_title),
+                                );
+                                ui.add_space(12.0);
+
+                                let button_size = if self.settings.compact_menu_buttons {
+                                    egui::vec2(228.0, 40.0)
+                                } else {
+                                    egui::vec2(250.0, 48.0)
+                                };
+
+                                if self.track_ui_sound(
+                                    &ui.add_enabled(
+                                        self.continue_available,
+                                        egui::Button::new(
+                                            RichText::new(self.locale.tr("menu.continue"))
+                                                .size(self.settings.menu_button_text_size),
+                                        )
+                                        .min_size(button_size),
+                                    ),
+                                    UiSound::Confirm,
+                                ) {
+                                    command = UiCommand::ContinueGame;
+                                }
+
+                                if self.track_ui_sound(
+                                    &ui.add_sized(
                                         button_size,
                                         egui::Button::new(
-                                            RichText::new("Настройки")
+                                            RichText::new(self.locale.tr("menu.play"))
                                                 .size(self.settings.menu_button_text_size),
                                         ),
-                                    )
-                                    .clicked()
-                                {
+                                    ),
+                                    UiSound::Confirm,
+                                ) {
+                                    command = UiCommand::StartGame;
+                                }
+
+                                if self.track_ui_sound(
+                                    &ui.add_sized(
+                                        button_size,
+                                        egui::Button::new(
+                                            RichText::new(self.locale.tr("menu.settings"))
+                                                .size(self.settings.menu_button_text_size),
+                                        ),
+                                    ),
+                                    UiSound::Click,
+                                ) {
                                     self.settings_open = true;
                                     self.achievements_open = false;
                                 }
 
-                                if ui
-                                    .add_sized(
+                                if self.track_ui_sound(
+                                    &ui.add_sized(
                                         button_size,
                                         egui::Button::new(
-                                            RichText::new("Достижения")
+                                            RichText::new(self.locale.tr("menu.achievements"))
                                                 .size(self.settings.menu_button_text_size),
                                         ),
-                                    )
-                                    .clicked()
-                                {
+                                    ),
+                                    UiSound::Click,
+                                ) {
                                     self.achievements_open = true;
                                     self.settings_open = false;
                                 }
 
-                                if ui
-                                    .add_sized(
+                                if self.track_ui_sound(
+                                    &ui.add_sized(
                                         button_size,
                                         egui::Button::new(
-                                            RichText::new("Выход")
+                                            RichText::new(self.locale.tr("menu.stats"))
                                                 .size(self.settings.menu_button_text_size),
                                         ),
-                                    )
-                                    .clicked()
-                                {
+                                    ),
+                                    UiSound::Click,
+                                ) {
+                                    self.stats_open = true;
+                                }
+
+                                if self.track_ui_sound(
+                                    &ui.add_sized(
+                                        button_size,
+                                        egui::Button::new(
+                                            RichText::new(self.locale.tr("menu.gallery"))
+                                                .size(self.settings.menu_button_text_size),
+                                        ),
+                                    ),
+                                    UiSound::Click,
+                                ) {
+                                    self.gallery_open = true;
+                                }
+
+                                if self.track_ui_sound(
+                                    &ui.add_sized(
+                                        button_size,
+                                        egui::Button::new(
+                                            RichText::new(self.locale.tr("menu.credits"))
+                                                .size(self.settings.menu_button_text_size),
+                                        ),
+                                    ),
+                                    UiSound::Click,
+                                ) {
+                                    self.credits_open = true;
+                                    self.credits_scroll = 0.0;
+                                    self.credits_paused = false;
+                                }
+
+                                if self.track_ui_sound(
+                                    &ui.add_sized(
+                                        button_size,
+                                        egui::Button::new(
+                                            RichText::new(self.locale.tr("menu.exit"))
+                                                .size(self.settings.menu_button_text_size),
+                                        ),
+                                    ),
+                                    UiSound::Confirm,
+                                ) {
                                     command = UiCommand::ExitApp;
                                 }
                             });
@@ -627,13 +2361,29 @@ impl DialogueUi {
             self.draw_achievements_window(ctx);
         }
 
+        if self.stats_open {
+            self.draw_stats_window(ctx);
+        }
+
+        if self.gallery_open {
+            // The viewer opens on the frame after a thumbnail click so that click
+            // does not immediately dismiss it.
+            self.draw_gallery_viewer(ctx);
+            self.draw_gallery_window(ctx);
+        }
+
+        if self.credits_open {
+            self.draw_credits(ctx, dt);
+        }
+
         command
     }
 
     fn draw_settings_window(&mut self, ctx: &egui::Context, palette: UiThemePalette) {
         let mut should_close = false;
 
-        egui::Window::new("Настройки")
+        egui::Window::new(self.locale.tr("settings.window_title"))
+            .id(egui::Id::new("settings_window"))
             .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
             .default_size([760.0, 560.0])
             .collapsible(false)
@@ -646,12 +2396,12 @@ impl DialogueUi {
                     .corner_radius(CornerRadius::same(14))
                     .show(ui, |ui| {
                         ui.label(
-                            RichText::new("Гибкая настройка интерфейса")
+                            RichText::new(self.locale.tr("settings.heading"))
                                 .size(28.0)
                                 .color(palette.settings_title),
                         );
                         ui.label(
-                            RichText::new("Выбранные параметры применяются сразу.")
+                            RichText::new(self.locale.tr("settings.subheading"))
                                 .size(16.0)
                                 .color(Color32::from_rgb(176, 190, 201)),
                         );
@@ -662,6 +2412,7 @@ impl DialogueUi {
                             self.draw_tab_button(ui, SettingsTab::Text);
                             self.draw_tab_button(ui, SettingsTab::Interface);
                             self.draw_tab_button(ui, SettingsTab::Notifications);
+                            self.draw_tab_button(ui, SettingsTab::Controls);
                         });
 
                         ui.add_space(6.0);
@@ -673,6 +2424,7 @@ impl DialogueUi {
                             SettingsTab::Text => self.draw_text_settings(ui),
                             SettingsTab::Interface => self.draw_interface_settings(ui),
                             SettingsTab::Notifications => self.draw_notification_settings(ui),
+                            SettingsTab::Controls => self.draw_controls_settings(ui),
                         });
 
                         ui.add_space(8.0);
@@ -680,17 +2432,21 @@ impl DialogueUi {
                         ui.add_space(6.0);
 
                         ui.horizontal(|ui| {
-                            if ui
-                                .button(RichText::new("Сбросить по умолчанию").size(18.0))
-                                .clicked()
-                            {
+                            if self.track_ui_sound(
+                                &ui.button(
+                                    RichText::new(self.locale.tr("settings.reset")).size(18.0),
+                                ),
+                                UiSound::Confirm,
+                            ) {
                                 self.settings = UiSettings::default();
                             }
 
-                            if ui
-                                .button(RichText::new("Закрыть настройки").size(18.0))
-                                .clicked()
-                            {
+                            if self.track_ui_sound(
+                                &ui.button(
+                                    RichText::new(self.locale.tr("settings.close")).size(18.0),
+                                ),
+                                UiSound::Click,
+                            ) {
                                 should_close = true;
                             }
                         });
@@ -699,6 +2455,7 @@ impl DialogueUi {
 
         if should_close {
             self.settings_open = false;
+            self.binding_capture = None;
         }
     }
 
@@ -716,155 +2473,577 @@ impl DialogueUi {
             .stroke(Stroke::new(1.0, Color32::from_rgb(94, 130, 160)))
             .corner_radius(CornerRadius::same(10))
             .show(ui, |ui| {
-                if ui
-                    .selectable_label(is_active, RichText::new(tab.title()).size(18.0))
-                    .clicked()
-                {
+                if self.track_ui_sound(
+                    &ui.selectable_label(
+                        is_active,
+                        RichText::new(self.locale.tr(tab.title_key())).size(18.0),
+                    ),
+                    UiSound::Click,
+                ) {
                     self.settings_tab = tab;
                 }
             });
     }
 
+    // Queues hover/click feedback for `response`; returns whether it was clicked.
+    fn track_ui_sound(&mut self, response: &egui::Response, click_sound: UiSound) -> bool {
+        if response.hovered() {
+            self.frame_hovered_widget = Some(response.id);
+        }
+        let clicked = response.clicked();
+        if clicked {
+            self.pending_ui_sounds.push(click_sound);
+        }
+        clicked
+    }
+
     fn draw_audio_settings(&mut self, ui: &mut Ui) {
-        ui.label(RichText::new("Аудио").size(24.0));
+        ui.label(RichText::new(self.locale.tr("settings.audio.heading")).size(24.0));
         ui.add_space(6.0);
 
-        ui.add(
-            egui::Slider::new(&mut self.settings.master_volume, 0.0..=1.0).text("Общая громкость"),
+        ui.checkbox(
+            &mut self.settings.muted,
+            self.locale.tr("settings.audio.mute"),
+        );
+        ui.add_enabled(
+            !self.settings.muted,
+            egui::Slider::new(&mut self.settings.master_volume, 0.0..=1.0)
+                .text(self.locale.tr("settings.audio.master_volume")),
         );
         ui.checkbox(
             &mut self.settings.typewriter_sound_enabled,
-            "Включить звук печати",
+            self.locale.tr("settings.audio.typewriter_sound"),
         );
         ui.add_enabled(
             self.settings.typewriter_sound_enabled,
             egui::Slider::new(&mut self.settings.typewriter_sound_volume, 0.0..=1.0)
-                .text("Громкость звука печати"),
+                .text(self.locale.tr("settings.audio.typewriter_volume")),
+        );
+        ui.checkbox(
+            &mut self.settings.ui_sounds_enabled,
+            self.locale.tr("settings.audio.ui_sounds"),
+        );
+        ui.add_enabled(
+            self.settings.ui_sounds_enabled,
+            egui::Slider::new(&mut self.settings.ui_sound_volume, 0.0..=1.0)
+                .text(self.locale.tr("settings.audio.ui_volume")),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.settings.voice_volume, 0.0..=1.0)
+                .text(self.locale.tr("settings.audio.voice_volume")),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.settings.music_volume, 0.0..=1.0)
+                .text(self.locale.tr("settings.audio.music_volume")),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.settings.ambience_volume, 0.0..=1.0)
+                .text(self.locale.tr("settings.audio.ambience_volume")),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.settings.music_ducking, 0.0..=1.0)
+                .text(self.locale.tr("settings.audio.music_ducking")),
+        );
+        egui::ComboBox::from_label(self.locale.tr("settings.audio.focus_loss"))
+            .selected_text(
+                self.locale
+                    .tr(focus_loss_title_key(self.settings.focus_loss_audio)),
+            )
+            .show_ui(ui, |ui| {
+                for behavior in FocusLossAudio::ALL {
+                    ui.selectable_value(
+                        &mut self.settings.focus_loss_audio,
+                        behavior,
+                        self.locale.tr(focus_loss_title_key(behavior)),
+                    );
+                }
+            });
+        ui.checkbox(
+            &mut self.settings.sound_captions,
+            self.locale.tr("settings.audio.captions"),
         );
 
         ui.add_space(8.0);
         ui.label(
-            RichText::new("Подсказка: для тихого режима поставьте 0.0 в 'Общая громкость'.")
+            RichText::new(self.locale.tr("settings.audio.hint"))
                 .size(15.0)
                 .color(Color32::from_rgb(155, 168, 181)),
         );
     }
 
     fn draw_text_settings(&mut self, ui: &mut Ui) {
-        ui.label(RichText::new("Текст и диалоги").size(24.0));
+        ui.label(RichText::new(self.locale.tr("settings.text.heading")).size(24.0));
         ui.add_space(6.0);
 
-        ui.checkbox(&mut self.settings.typewriter_enabled, "Эффект печати");
+        ui.checkbox(
+            &mut self.settings.typewriter_enabled,
+            self.locale.tr("settings.text.typewriter"),
+        );
         ui.add_enabled(
             self.settings.typewriter_enabled,
             egui::Slider::new(&mut self.settings.typing_chars_per_second, 8.0..=120.0)
-                .text("Скорость печати (симв/с)"),
+                .text(self.locale.tr("settings.text.typing_speed")),
         );
         ui.checkbox(
             &mut self.settings.show_typing_caret,
-            "Показывать курсор печати",
+            self.locale.tr("settings.text.caret"),
         );
         ui.checkbox(
             &mut self.settings.allow_dialogue_click_skip,
-            "Разрешить пропуск кликом",
+            self.locale.tr("settings.text.click_skip"),
+        );
+        ui.checkbox(
+            &mut self.settings.auto_advance,
+            self.locale.tr("settings.text.auto_advance"),
+        );
+        ui.add_enabled(
+            self.settings.auto_advance,
+            egui::Slider::new(&mut self.settings.auto_advance_delay, 0.3..=5.0)
+                .text(self.locale.tr("settings.text.auto_advance_delay")),
+        );
+        ui.add_enabled(
+            self.settings.auto_advance,
+            egui::Checkbox::new(
+                &mut self.settings.auto_advance_wait_for_voice,
+                self.locale.tr("settings.text.wait_for_voice"),
+            ),
+        );
+        ui.add_enabled(
+            self.settings.auto_advance && self.settings.auto_advance_wait_for_voice,
+            egui::Slider::new(&mut self.settings.voice_buffer, 0.0..=3.0)
+                .text(self.locale.tr("settings.text.voice_buffer")),
         );
         ui.checkbox(
             &mut self.settings.show_speaker_name,
-            "Показывать имя говорящего",
+            self.locale.tr("settings.text.speaker_name"),
         );
         ui.add(
             egui::Slider::new(&mut self.settings.speaker_text_size, 14.0..=32.0)
-                .text("Размер имени"),
+                .text(self.locale.tr("settings.text.speaker_size")),
         );
         ui.add(
             egui::Slider::new(&mut self.settings.dialogue_text_size, 18.0..=42.0)
-                .text("Размер текста"),
+                .text(self.locale.tr("settings.text.text_size")),
+        );
+
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label(self.locale.tr("settings.text.effect"))
+                .selected_text(self.locale.tr(self.settings.text_effect.title_key()))
+                .show_ui(ui, |ui| {
+                    for effect in TextEffect::ALL {
+                        ui.selectable_value(
+                            &mut self.settings.text_effect,
+                            effect,
+                            self.locale.tr(effect.title_key()),
+                        );
+                    }
+                });
+            if self.settings.text_effect != TextEffect::None {
+                egui::color_picker::color_edit_button_srgba(
+                    ui,
+                    &mut self.settings.text_effect_color,
+                    egui::color_picker::Alpha::OnlyBlend,
+                );
+            }
+        });
+        ui.add_enabled(
+            self.settings.text_effect != TextEffect::None,
+            egui::Slider::new(&mut self.settings.text_effect_size, 0.5..=4.0)
+                .text(self.locale.tr("settings.text.effect_size")),
         );
     }
 
     fn draw_interface_settings(&mut self, ui: &mut Ui) {
-        ui.label(RichText::new("Интерфейс").size(24.0));
+        ui.label(RichText::new(self.locale.tr("settings.interface.heading")).size(24.0));
         ui.add_space(6.0);
 
-        ui.add(egui::Slider::new(&mut self.settings.ui_scale, 0.75..=1.60).text("Масштаб UI"));
+        let languages = self.locale.languages();
+        let current_language = languages
+            .iter()
+            .find(|(code, _)| *code == self.settings.language)
+            .map(|(_, name)| name.clone())
+            .unwrap_or_else(|| self.settings.language.clone());
+        egui::ComboBox::from_label(self.locale.tr("settings.interface.language"))
+            .selected_text(current_language)
+            .show_ui(ui, |ui| {
+                for (code, name) in languages {
+                    ui.selectable_value(&mut self.settings.language, code, name);
+                }
+            });
+
+        ui.add(
+            egui::Slider::new(&mut self.settings.ui_scale, 0.75..=1.60)
+                .text(self.locale.tr("settings.interface.ui_scale")),
+        );
         ui.checkbox(
             &mut self.settings.compact_menu_buttons,
-            "Компактные кнопки меню",
+            self.locale.tr("settings.interface.compact_buttons"),
         );
         ui.add(
             egui::Slider::new(&mut self.settings.menu_title_size, 28.0..=56.0)
-                .text("Размер заголовка меню"),
+                .text(self.locale.tr("settings.interface.menu_title_size")),
         );
         ui.add(
             egui::Slider::new(&mut self.settings.menu_button_text_size, 18.0..=34.0)
-                .text("Размер текста кнопок"),
+                .text(self.locale.tr("settings.interface.menu_button_size")),
         );
         ui.add(
             egui::Slider::new(&mut self.settings.dialogue_box_opacity, 0.2..=1.0)
-                .text("Прозрачность диалогового окна"),
+                .text(self.locale.tr("settings.interface.dialogue_opacity")),
         );
         ui.add(
             egui::Slider::new(&mut self.settings.dialogue_box_height_ratio, 0.12..=0.26)
-                .text("Высота диалогового окна"),
+                .text(self.locale.tr("settings.interface.dialogue_height")),
         );
         ui.add(
             egui::Slider::new(&mut self.settings.dialogue_corner_radius, 4..=24)
-                .text("Скругление диалогового окна"),
+                .text(self.locale.tr("settings.interface.dialogue_radius")),
         );
         ui.add(
             egui::Slider::new(&mut self.settings.animation_speed, 0.2..=2.0)
-                .text("Скорость анимаций"),
+                .text(self.locale.tr("settings.interface.animation_speed")),
         );
 
         ui.add_space(6.0);
-        ui.label(RichText::new("Цветовая тема").size(20.0));
-        ui.horizontal(|ui| {
-            ui.selectable_value(
-                &mut self.settings.theme_preset,
-                UiThemePreset::DeepSea,
-                UiThemePreset::DeepSea.title(),
-            );
-            ui.selectable_value(
-                &mut self.settings.theme_preset,
-                UiThemePreset::Forest,
-                UiThemePreset::Forest.title(),
-            );
-            ui.selectable_value(
-                &mut self.settings.theme_preset,
-                UiThemePreset::Ember,
-                UiThemePreset::Ember.title(),
-            );
-        });
-    }
+        self.draw_display_settings(ui);
 
-    fn draw_notification_settings(&mut self, ui: &mut Ui) {
-        ui.label(RichText::new("Уведомления и достижения").size(24.0));
         ui.add_space(6.0);
+        ui.label(RichText::new(self.locale.tr("settings.interface.theme")).size(20.0));
+        ui.horizontal_wrapped(|ui| {
+            for preset in UiThemePreset::BUILTIN {
+                ui.selectable_value(
+                    &mut self.settings.theme_preset,
+                    preset,
+                    self.locale.tr(preset.title_key()),
+                );
+            }
+            for (index, theme) in self.custom_themes.iter().enumerate() {
+                ui.selectable_value(
+                    &mut self.settings.theme_preset,
+                    UiThemePreset::Custom(index),
+                    theme.name.as_str(),
+                );
+            }
+        });
 
+        ui.add_space(6.0);
         ui.checkbox(
-            &mut self.settings.popup_enabled,
-            "Показывать всплывающее окно достижения",
+            &mut self.settings.enforce_min_contrast,
+            self.locale.tr("settings.interface.enforce_contrast"),
         );
         ui.add_enabled(
-            self.settings.popup_enabled,
-            egui::Slider::new(&mut self.settings.popup_duration, 1.0..=8.0)
-                .text("Длительность попапа (сек.)"),
-        );
-        ui.checkbox(
-            &mut self.settings.show_achievement_descriptions,
-            "Показывать описание в списке достижений",
+            self.settings.enforce_min_contrast,
+            egui::Slider::new(&mut self.settings.min_contrast_ratio, 3.0..=7.0)
+                .step_by(0.5)
+                .text(self.locale.tr("settings.interface.min_contrast")),
         );
+
+        ui.add_space(6.0);
+        self.draw_theme_editor(ui);
+    }
+
+    fn draw_display_settings(&mut self, ui: &mut Ui) {
+        let display = &mut self.settings.display;
+        egui::ComboBox::from_label(self.locale.tr("settings.interface.display_mode"))
+            .selected_text(self.locale.tr(display_mode_title_key(display.mode)))
+            .show_ui(ui, |ui| {
+                for mode in DisplayMode::ALL {
+                    ui.selectable_value(
+                        &mut display.mode,
+                        mode,
+                        self.locale.tr(display_mode_title_key(mode)),
+                    );
+                }
+            });
+        if display.mode != DisplayMode::Windowed {
+            display.last_fullscreen_mode = display.mode;
+        }
+
+        let automatic = self.locale.tr("settings.interface.display_auto");
+        egui::ComboBox::from_label(self.locale.tr("settings.interface.display"))
+            .selected_text(display.monitor.as_deref().unwrap_or(automatic))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut display.monitor, None, automatic);
+                for info in &self.available_displays {
+                    ui.selectable_value(
+                        &mut display.monitor,
+                        Some(info.name.clone()),
+                        info.name.as_str(),
+                    );
+                }
+            });
+
+        // Without an explicit monitor the list follows the first one, usually the primary.
+        let resolutions = display
+            .monitor
+            .as_deref()
+            .and_then(|name| {
+                self.available_displays
+                    .iter()
+                    .find(|info| info.name == name)
+            })
+            .or(self.available_displays.first())
+            .map_or(&[][..], |info| info.resolutions.as_slice());
+        let resolution_label = |resolution: Option<[u32; 2]>| match resolution {
+            Some([width, height]) => format!("{width} × {height}"),
+            None => self
+                .locale
+                .tr("settings.interface.resolution_current")
+                .to_owned(),
+        };
+        ui.add_enabled_ui(display.mode != DisplayMode::Borderless, |ui| {
+            egui::ComboBox::from_label(self.locale.tr("settings.interface.resolution"))
+                .selected_text(resolution_label(display.resolution))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut display.resolution, None, resolution_label(None));
+                    for &resolution in resolutions {
+                        ui.selectable_value(
+                            &mut display.resolution,
+                            Some(resolution),
+                            resolution_label(Some(resolution)),
+                        );
+                    }
+                });
+        });
+
+        egui::ComboBox::from_label(self.locale.tr("settings.interface.present_mode"))
+            .selected_text(self.locale.tr(present_mode_title_key(display.present_mode)))
+            .show_ui(ui, |ui| {
+                for mode in PRESENT_MODES {
+                    ui.selectable_value(
+                        &mut display.present_mode,
+                        mode,
+                        self.locale.tr(present_mode_title_key(mode)),
+                    );
+                }
+            });
         ui.checkbox(
-            &mut self.settings.high_contrast_locked_achievements,
-            "Контрастные заблокированные карточки",
+            &mut display.fps_limit_enabled,
+            self.locale.tr("settings.interface.fps_limit_enabled"),
         );
-        ui.add(
-            egui::Slider::new(&mut self.settings.achievement_list_spacing, 2.0..=18.0)
-                .text("Отступ между карточками достижений"),
+        ui.add_enabled(
+            display.fps_limit_enabled,
+            egui::Slider::new(&mut display.fps_limit, 30..=240)
+                .text(self.locale.tr("settings.interface.fps_limit")),
         );
     }
 
+    fn draw_theme_editor(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            if ui.button(self.locale.tr("theme.editor.create")).clicked() {
+                let name = self.locale.tr_args(
+                    "theme.editor.default_name",
+                    &[("index", (self.custom_themes.len() + 1).to_string())],
+                );
+                self.custom_themes.push(CustomTheme {
+                    name,
+                    palette: self.theme_palette(),
+                });
+                self.settings.theme_preset = UiThemePreset::Custom(self.custom_themes.len() - 1);
+            }
+
+            if ui.button(self.locale.tr("theme.editor.save")).clicked() {
+                let (kind, title_key, body) =
+                    match ui_theme::save_custom_themes(&self.themes_path, &self.custom_themes) {
+                        Ok(()) => (ToastKind::Success, "theme.editor.saved", String::new()),
+                        Err(err) => {
+                            log::error!("{err}");
+                            (ToastKind::Error, "theme.editor.save_failed", err)
+                        }
+                    };
+                let title = self.locale.tr(title_key).to_owned();
+                self.push_toast(kind, title, body, 3.0);
+            }
+        });
+
+        let UiThemePreset::Custom(index) = self.settings.theme_preset else {
+            ui.label(RichText::new(self.locale.tr("theme.editor.hint")).italics());
+            return;
+        };
+        let Some(theme) = self.custom_themes.get_mut(index) else {
+            return;
+        };
+
+        let mut delete_requested = false;
+        ui.horizontal(|ui| {
+            ui.label(self.locale.tr("theme.editor.name"));
+            ui.text_edit_singleline(&mut theme.name);
+            delete_requested = ui.button(self.locale.tr("theme.editor.delete")).clicked();
+        });
+
+        egui::Grid::new("custom_theme_colors")
+            .num_columns(2)
+            .spacing([12.0, 4.0])
+            .show(ui, |ui| {
+                ui.label(self.locale.tr("theme.field.dialogue_fill"));
+                ui.color_edit_button_srgb(&mut theme.palette.dialogue_fill_rgb);
+                ui.end_row();
+
+                for (label_key, color) in theme.palette.colors_mut() {
+                    ui.label(self.locale.tr(label_key));
+                    egui::color_picker::color_edit_button_srgba(
+                        ui,
+                        color,
+                        egui::color_picker::Alpha::OnlyBlend,
+                    );
+                    ui.end_row();
+                }
+            });
+
+        if delete_requested {
+            self.custom_themes.remove(index);
+            self.settings.theme_preset = UiThemePreset::DeepSea;
+        }
+    }
+
+    fn draw_notification_settings(&mut self, ui: &mut Ui) {
+        ui.label(RichText::new(self.locale.tr("settings.notifications.heading")).size(24.0));
+        ui.add_space(6.0);
+
+        ui.checkbox(
+            &mut self.settings.popup_enabled,
+            self.locale.tr("settings.notifications.popup"),
+        );
+        ui.add_enabled(
+            self.settings.popup_enabled,
+            egui::Slider::new(&mut self.settings.popup_duration, 1.0..=8.0)
+                .text(self.locale.tr("settings.notifications.popup_duration")),
+        );
+        ui.checkbox(
+            &mut self.settings.show_achievement_descriptions,
+            self.locale.tr("settings.notifications.descriptions"),
+        );
+        ui.checkbox(
+            &mut self.settings.high_contrast_locked_achievements,
+            self.locale.tr("settings.notifications.high_contrast"),
+        );
+        ui.add(
+            egui::Slider::new(&mut self.settings.achievement_list_spacing, 2.0..=18.0)
+                .text(self.locale.tr("settings.notifications.spacing")),
+        );
+
+        ui.add_space(10.0);
+        ui.separator();
+        if !self.achievement_reset_armed {
+            if ui
+                .button(
+                    RichText::new(self.locale.tr("settings.notifications.reset_achievements"))
+                        .size(17.0),
+                )
+                .clicked()
+            {
+                self.achievement_reset_armed = true;
+            }
+            return;
+        }
+
+        ui.label(
+            RichText::new(self.locale.tr("settings.notifications.reset_warning"))
+                .size(16.0)
+                .color(Color32::from_rgb(231, 150, 150)),
+        );
+        ui.horizontal(|ui| {
+            if ui
+                .button(
+                    RichText::new(self.locale.tr("settings.notifications.reset_confirm"))
+                        .size(17.0)
+                        .color(Color32::from_rgb(240, 120, 120)),
+                )
+                .clicked()
+            {
+                self.achievement_reset_armed = false;
+                self.achievement_reset_requested = true;
+            }
+            if ui
+                .button(
+                    RichText::new(self.locale.tr("settings.notifications.reset_cancel")).size(17.0),
+                )
+                .clicked()
+            {
+                self.achievement_reset_armed = false;
+            }
+        });
+    }
+
+    fn draw_controls_settings(&mut self, ui: &mut Ui) {
+        ui.label(RichText::new(self.locale.tr("settings.controls.heading")).size(24.0));
+        ui.add_space(6.0);
+
+        let bindings = self.key_bindings.clone();
+        for (action, keys) in bindings {
+            ui.horizontal(|ui| {
+                ui.add_sized(
+                    [240.0, 24.0],
+                    egui::Label::new(
+                        RichText::new(self.locale.tr(action_title_key(action))).size(18.0),
+                    ),
+                );
+
+                for (slot, key) in keys.iter().enumerate() {
+                    let capture = BindingCapture {
+                        action,
+                        slot: Some(slot),
+                    };
+                    let label = if self.binding_capture == Some(capture) {
+                        self.locale.tr("settings.controls.press_key").to_owned()
+                    } else {
+                        chord_label(*key)
+                    };
+                    let response = ui.button(RichText::new(label).size(17.0));
+                    if response.clicked() {
+                        self.binding_capture = Some(capture);
+                        self.capture_modifier_key = None;
+                        self.binding_message = None;
+                    } else if response.secondary_clicked() {
+                        self.binding_capture = None;
+                        self.binding_message = None;
+                        self.binding_changes
+                            .push(KeyBindingChange::Remove { action, slot });
+                    }
+                }
+
+                let capture = BindingCapture { action, slot: None };
+                let label = if self.binding_capture == Some(capture) {
+                    self.locale.tr("settings.controls.press_key")
+                } else {
+                    "+"
+                };
+                if ui.button(RichText::new(label).size(17.0)).clicked() {
+                    self.binding_capture = Some(capture);
+                    self.capture_modifier_key = None;
+                    self.binding_message = None;
+                }
+            });
+        }
+
+        if let Some(message) = self.binding_message.as_deref() {
+            ui.add_space(6.0);
+            ui.label(
+                RichText::new(message)
+                    .size(16.0)
+                    .color(Color32::from_rgb(236, 148, 120)),
+            );
+        }
+
+        ui.add_space(8.0);
+        ui.label(
+            RichText::new(self.locale.tr("settings.controls.hint"))
+                .size(15.0)
+                .color(Color32::from_rgb(155, 168, 181)),
+        );
+        if ui
+            .button(RichText::new(self.locale.tr("settings.controls.reset")).size(17.0))
+            .clicked()
+        {
+            self.binding_capture = None;
+            self.binding_message = None;
+            self.binding_changes.push(KeyBindingChange::ResetDefaults);
+        }
+    }
+
     fn draw_achievements_window(&mut self, ctx: &egui::Context) {
+        self.achievement_icons.update(ctx);
         let mut should_close = false;
         let unlocked_count = self
             .achievements_snapshot
@@ -872,77 +3051,167 @@ impl DialogueUi {
             .filter(|achievement| achievement.unlocked)
             .count();
         let total_count = self.achievements_snapshot.len();
+        let completion = if total_count == 0 {
+            0.0
+        } else {
+            unlocked_count as f32 / total_count as f32
+        };
+        let (earned_points, total_points) =
+            self.achievements_snapshot
+                .iter()
+                .fold((0, 0), |(earned, total), achievement| {
+                    let points = achievement.points.unwrap_or(0);
+                    let earned = if achievement.unlocked {
+                        earned + points
+                    } else {
+                        earned
+                    };
+                    (earned, total + points)
+                });
+
+        let mut categories: Vec<String> = Vec::new();
+        for category in self
+            .achievements_snapshot
+            .iter()
+            .filter_map(|achievement| achievement.category.as_ref())
+        {
+            if !categories.contains(category) {
+                categories.push(category.clone());
+            }
+        }
 
-        egui::Window::new("Достижения")
+        egui::Window::new(self.locale.tr("achievements.window_title"))
+            .id(egui::Id::new("achievements_window"))
             .anchor(Align2::CENTER_BOTTOM, [0.0, -48.0])
             .default_size([540.0, 440.0])
             .resizable(true)
             .collapsible(false)
             .show(ctx, |ui| {
                 ui.label(
-                    RichText::new(format!("Открыто: {unlocked_count}/{total_count}")).size(22.0),
+                    RichText::new(self.locale.tr_args(
+                        "achievements.unlocked_count",
+                        &[
+                            ("unlocked", unlocked_count.to_string()),
+                            ("total", total_count.to_string()),
+                        ],
+                    ))
+                    .size(22.0),
                 );
+                if total_points > 0 {
+                    ui.label(
+                        RichText::new(self.locale.tr_args(
+                            "achievements.score",
+                            &[
+                                ("earned", earned_points.to_string()),
+                                ("total", total_points.to_string()),
+                            ],
+                        ))
+                        .size(18.0),
+                    );
+                }
+                ui.add(
+                    egui::ProgressBar::new(completion)
+                        .desired_height(10.0)
+                        .text(format!("{:.0}%", completion * 100.0)),
+                );
+                ui.add_space(6.0);
+
+                ui.horizontal_wrapped(|ui| {
+                    for filter in [
+                        AchievementFilter::All,
+                        AchievementFilter::Unlocked,
+                        AchievementFilter::Locked,
+                    ] {
+                        ui.selectable_value(
+                            &mut self.achievement_filter,
+                            filter,
+                            self.locale.tr(filter.title_key()),
+                        );
+                    }
+
+                    ui.separator();
+                    egui::ComboBox::from_id_salt("achievement_category_filter")
+                        .selected_text(
+                            self.achievement_category_filter
+                                .as_deref()
+                                .unwrap_or(self.locale.tr("achievements.all_categories")),
+                        )
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.achievement_category_filter,
+                                None,
+                                self.locale.tr("achievements.all_categories"),
+                            );
+                            for category in &categories {
+                                ui.selectable_value(
+                                    &mut self.achievement_category_filter,
+                                    Some(category.clone()),
+                                    category.as_str(),
+                                );
+                            }
+                        });
+
+                    egui::ComboBox::from_id_salt("achievement_sort")
+                        .selected_text(self.locale.tr(self.achievement_sort.title_key()))
+                        .show_ui(ui, |ui| {
+                            for sort in AchievementSort::ALL {
+                                ui.selectable_value(
+                                    &mut self.achievement_sort,
+                                    sort,
+                                    self.locale.tr(sort.title_key()),
+                                );
+                            }
+                        });
+                });
                 ui.add_space(8.0);
 
+                let visible_achievements = self.filtered_achievements();
                 egui::ScrollArea::vertical()
                     .auto_shrink([false, false])
                     .show(ui, |ui| {
-                        for achievement in &self.achievements_snapshot {
-                            let (status, border, title_color, body_color, fill) =
-                                if achievement.unlocked {
-                                    (
-                                        "Открыто",
-                                        Color32::from_rgb(114, 185, 113),
-                                        Color32::from_rgb(222, 250, 201),
-                                        Color32::from_rgb(214, 238, 207),
-                                        Color32::from_rgba_unmultiplied(24, 52, 24, 214),
-                                    )
-                                } else if self.settings.high_contrast_locked_achievements {
-                                    (
-                                        "Заблокировано",
-                                        Color32::from_rgb(154, 93, 93),
-                                        Color32::from_rgb(231, 191, 191),
-                                        Color32::from_rgb(223, 175, 175),
-                                        Color32::from_rgba_unmultiplied(48, 22, 22, 220),
-                                    )
-                                } else {
-                                    (
-                                        "Заблокировано",
-                                        Color32::from_rgb(94, 109, 122),
-                                        Color32::from_rgb(148, 165, 176),
-                                        Color32::from_rgb(128, 140, 149),
-                                        Color32::from_rgba_unmultiplied(19, 24, 30, 214),
-                                    )
-                                };
-
-                            Frame::new()
-                                .inner_margin(Margin::symmetric(14, 10))
-                                .fill(fill)
-                                .stroke(Stroke::new(1.0, border))
-                                .corner_radius(CornerRadius::same(10))
-                                .show(ui, |ui| {
-                                    ui.label(
-                                        RichText::new(format!("{} [{}]", achievement.name, status))
-                                            .size(20.0)
-                                            .color(title_color),
-                                    );
+                        if visible_achievements.is_empty() {
+                            ui.label(
+                                RichText::new(self.locale.tr("achievements.empty_filter"))
+                                    .size(17.0)
+                                    .color(Color32::from_rgb(155, 168, 181)),
+                            );
+                        }
 
-                                    if self.settings.show_achievement_descriptions {
-                                        ui.label(
-                                            RichText::new(achievement.description.as_str())
-                                                .size(17.0)
-                                                .color(body_color),
+                        let mut drawn_chains: Vec<&str> = Vec::new();
+                        for achievement in visible_achievements.iter().copied() {
+                            match achievement.chain.as_deref() {
+                                // Tiers of a chain share one card at the first tier's slot.
+                                Some(chain) if drawn_chains.contains(&chain) => continue,
+                                Some(chain) => {
+                                    drawn_chains.push(chain);
+                                    let mut tiers: Vec<&AchievementSnapshotItem> =
+                                        visible_achievements
+                                            .iter()
+                                            .copied()
+                                            .filter(|other| other.chain.as_deref() == Some(chain))
+                                            .collect();
+                                    tiers.sort_by_key(|tier| tier.tier);
+                                    self.draw_achievement_chain(ui, chain, &tiers);
+                                }
+                                None => self.draw_achievement_card(ui, achievement, |ui| {
+                                    if let Some(goal) =
+                                        achievement.goal.filter(|_| !achievement.unlocked)
+                                    {
+                                        self.draw_achievement_progress(
+                                            ui,
+                                            achievement.progress,
+                                            goal,
                                         );
                                     }
-                                });
-
+                                }),
+                            }
                             ui.add_space(self.settings.achievement_list_spacing);
                         }
                     });
 
                 ui.add_space(4.0);
                 if ui
-                    .button(RichText::new("Закрыть список достижений").size(19.0))
+                    .button(RichText::new(self.locale.tr("achievements.close")).size(19.0))
                     .clicked()
                 {
                     should_close = true;
@@ -954,55 +3223,837 @@ impl DialogueUi {
         }
     }
 
-    fn draw_achievement_popup(&mut self, ctx: &egui::Context, dt: f32) {
-        if !self.settings.popup_enabled {
-            self.active_achievement_popup = None;
-            self.achievement_notifications.clear();
-            return;
-        }
+    fn draw_achievement_card(
+        &self,
+        ui: &mut Ui,
+        achievement: &AchievementSnapshotItem,
+        add_contents: impl FnOnce(&mut Ui),
+    ) {
+        let (status, border, title_color, body_color, fill) = if achievement.unlocked {
+            (
+                self.locale.tr("achievements.status.unlocked"),
+                Color32::from_rgb(114, 185, 113),
+                Color32::from_rgb(222, 250, 201),
+                Color32::from_rgb(214, 238, 207),
+                Color32::from_rgba_unmultiplied(24, 52, 24, 214),
+            )
+        } else if self.settings.high_contrast_locked_achievements {
+            (
+                self.locale.tr("achievements.status.locked"),
+                Color32::from_rgb(154, 93, 93),
+                Color32::from_rgb(231, 191, 191),
+                Color32::from_rgb(223, 175, 175),
+                Color32::from_rgba_unmultiplied(48, 22, 22, 220),
+            )
+        } else {
+            (
+                self.locale.tr("achievements.status.locked"),
+                Color32::from_rgb(94, 109, 122),
+                Color32::from_rgb(148, 165, 176),
+                Color32::from_rgb(128, 140, 149),
+                Color32::from_rgba_unmultiplied(19, 24, 30, 214),
+            )
+        };
+        let (title_color, body_color) = if self.settings.enforce_min_contrast {
+            let ratio = self.settings.min_contrast_ratio;
+            (
+                ui_theme::ensure_contrast(title_color, fill, ratio),
+                ui_theme::ensure_contrast(body_color, fill, ratio),
+            )
+        } else {
+            (title_color, body_color)
+        };
+
+        Frame::new()
+            .inner_margin(Margin::symmetric(14, 10))
+            .fill(fill)
+            .stroke(Stroke::new(1.0, border))
+            .corner_radius(CornerRadius::same(10))
+            .show(ui, |ui| {
+                ui.horizontal_top(|ui| {
+                    let (icon, tint) = self.achievement_icon(ui.ctx(), achievement);
+                    ui.add(
+                        egui::Image::new(&icon)
+                            .fit_to_exact_size(egui::vec2(
+                                ACHIEVEMENT_ICON_SIZE,
+                                ACHIEVEMENT_ICON_SIZE,
+                            ))
+                            .tint(tint),
+                    );
+                    ui.vertical(|ui| {
+                        // The manager already blanked the text of locked hidden entries.
+                        let masked = achievement.hidden && !achievement.unlocked;
+                        let name = if masked {
+                            self.locale.tr("achievements.hidden.name")
+                        } else {
+                            achievement.name.as_str()
+                        };
+                        let title = match achievement.tier {
+                            Some(tier) => format!(
+                                "{} ({}) [{}]",
+                                name,
+                                self.locale.tr(tier.title_key()),
+                                status
+                            ),
+                            None => format!("{} [{}]", name, status),
+                        };
+                        ui.label(RichText::new(title).size(20.0).color(title_color));
+
+                        if self.settings.show_achievement_descriptions {
+                            ui.label(
+                                RichText::new(if masked {
+                                    self.locale.tr("achievements.hidden.description")
+                                } else {
+                                    achievement.description.as_str()
+                                })
+                                .size(17.0)
+                                .color(body_color),
+                            );
+                        }
+
+                        let mut details = Vec::new();
+                        if let Some(category) = achievement.category.as_deref() {
+                            details.push(category.to_owned());
+                        }
+                        if let Some(rarity) = achievement.rarity {
+                            details.push(format!(
+                                "{} ({})",
+                                self.locale
+                                    .tr(RarityClass::from_percent(rarity).title_key()),
+                                self.locale.tr_args(
+                                    "achievements.rarity",
+                                    &[("percent", format!("{rarity:.1}"))],
+                                )
+                            ));
+                        }
+                        if let Some(points) = achievement.points {
+                            details.push(
+                                self.locale.tr_args(
+                                    "achievements.points",
+                                    &[("points", points.to_string())],
+                                ),
+                            );
+                        }
+                        if !details.is_empty() {
+                            ui.label(
+                                RichText::new(details.join(" · "))
+                                    .size(14.0)
+                                    .color(body_color),
+                            );
+                        }
 
-        if self.active_achievement_popup.is_none() {
-            if let Some(next) = self.achievement_notifications.pop_front() {
-                self.active_achievement_popup = Some(ActiveAchievementPopup {
-                    notification: next,
-                    remaining: self.settings.popup_duration.clamp(1.0, 8.0),
+                        add_contents(ui);
+                    });
                 });
+            });
+    }
+
+    // Locked entries without a locked icon show the regular one dimmed; hidden ones and
+    // entries without icons get the placeholder.
+    fn achievement_icon(
+        &self,
+        ctx: &egui::Context,
+        achievement: &AchievementSnapshotItem,
+    ) -> (egui::TextureHandle, Color32) {
+        let (path, tint) = if achievement.unlocked {
+            (achievement.icon.as_deref(), Color32::WHITE)
+        } else if let Some(locked) = achievement.locked_icon.as_deref() {
+            (Some(locked), Color32::WHITE)
+        } else {
+            (achievement.icon.as_deref(), Color32::from_gray(90))
+        };
+        match path {
+            Some(path) => (self.achievement_icons.icon(ctx, path), tint),
+            None => (self.achievement_icons.placeholder(ctx), Color32::WHITE),
+        }
+    }
+
+    fn draw_achievement_progress(&self, ui: &mut Ui, progress: u32, goal: u32) {
+        let progress = progress.min(goal);
+        ui.add(
+            egui::ProgressBar::new(progress as f32 / goal.max(1) as f32)
+                .desired_height(8.0)
+                .text(self.locale.tr_args(
+                    "achievements.progress",
+                    &[
+                        ("progress", progress.to_string()),
+                        ("goal", goal.to_string()),
+                    ],
+                )),
+        );
+    }
+
+    // One expandable card for a tier chain, headed by its best unlocked tier.
+    fn draw_achievement_chain(&self, ui: &mut Ui, chain: &str, tiers: &[&AchievementSnapshotItem]) {
+        let Some(headline) = tiers
+            .iter()
+            .rev()
+            .find(|tier| tier.unlocked)
+            .or(tiers.first())
+            .copied()
+        else {
+            return;
+        };
+
+        self.draw_achievement_card(ui, headline, |ui| {
+            let next_goal = tiers
+                .iter()
+                .filter(|tier| !tier.unlocked)
+                .find_map(|tier| tier.goal);
+            if let Some(goal) = next_goal {
+                self.draw_achievement_progress(ui, headline.progress, goal);
             }
+
+            egui::CollapsingHeader::new(
+                self.locale
+                    .tr_args("achievements.tiers", &[("count", tiers.len().to_string())]),
+            )
+            .id_salt(("achievement_chain", chain))
+            .show(ui, |ui| {
+                for tier in tiers {
+                    self.draw_achievement_card(ui, tier, |_| {});
+                    ui.add_space(4.0);
+                }
+            });
+        });
+    }
+
+    fn draw_gallery_window(&mut self, ctx: &egui::Context) {
+        const THUMBNAIL_SIZE: egui::Vec2 = egui::vec2(180.0, 110.0);
+
+        let mut should_close = false;
+        let mut open_viewer = None;
+        let unlocked: Vec<bool> = self
+            .gallery
+            .entries()
+            .iter()
+            .map(|entry| {
+                entry.is_unlocked(
+                    &self.text_variables,
+                    &self.persistent_flags,
+                    &self.achievements_snapshot,
+                )
+            })
+            .collect();
+        let unlocked_count = unlocked.iter().filter(|unlocked| **unlocked).count();
+
+        egui::Window::new(self.locale.tr("gallery.window_title"))
+            .id(egui::Id::new("gallery_window"))
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .default_size([640.0, 460.0])
+            .resizable(true)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(
+                    RichText::new(self.locale.tr_args(
+                        "gallery.unlocked_count",
+                        &[
+                            ("unlocked", unlocked_count.to_string()),
+                            ("total", unlocked.len().to_string()),
+                        ],
+                    ))
+                    .size(22.0),
+                );
+                ui.add_space(6.0);
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        if unlocked.is_empty() {
+                            ui.label(
+                                RichText::new(self.locale.tr("gallery.empty"))
+                                    .size(17.0)
+                                    .color(Color32::from_rgb(155, 168, 181)),
+                            );
+                        }
+
+                        ui.horizontal_wrapped(|ui| {
+                            for (index, is_unlocked) in unlocked.iter().copied().enumerate() {
+                                let texture = if is_unlocked {
+                                    self.gallery_texture(ctx, index)
+                                } else {
+                                    None
+                                };
+                                let title = if is_unlocked {
+                                    self.gallery.entries()[index].title.clone()
+                                } else {
+                                    self.locale.tr("gallery.locked").to_owned()
+                                };
+
+                                Frame::new()
+                                    .inner_margin(Margin::same(8))
+                                    .fill(Color32::from_rgba_unmultiplied(19, 24, 30, 214))
+                                    .stroke(Stroke::new(1.0, Color32::from_rgb(94, 109, 122)))
+                                    .corner_radius(CornerRadius::same(10))
+                                    .show(ui, |ui| {
+                                        ui.vertical(|ui| {
+                                            ui.set_width(THUMBNAIL_SIZE.x);
+                                            if let Some(texture) = texture {
+                                                let size =
+                                                    fit_size(texture.size_vec2(), THUMBNAIL_SIZE);
+                                                let response = ui
+                                                    .add(
+                                                        egui::Image::new((texture.id(), size))
+                                                            .sense(Sense::click()),
+                                                    )
+                                                    .on_hover_cursor(
+                                                        egui::CursorIcon::PointingHand,
+                                                    );
+                                                if response.clicked() {
+                                                    open_viewer = Some(
+                                                        self.gallery.entries()[index].id.clone(),
+                                                    );
+                                                }
+                                            } else {
+                                                let (rect, _) = ui.allocate_exact_size(
+                                                    THUMBNAIL_SIZE,
+                                                    Sense::hover(),
+                                                );
+                                                ui.painter().rect_filled(
+                                                    rect,
+                                                    CornerRadius::same(8),
+                                                    Color32::from_rgb(28, 34, 42),
+                                                );
+                                                ui.painter().text(
+                                                    rect.center(),
+                                                    Align2::CENTER_CENTER,
+                                                    "?",
+                                                    egui::FontId::proportional(40.0),
+                                                    Color32::from_rgb(94, 109, 122),
+                                                );
+                                            }
+                                            ui.label(RichText::new(title).size(17.0));
+                                        });
+                                    });
+                            }
+                        });
+                    });
+
+                ui.add_space(4.0);
+                if ui
+                    .button(RichText::new(self.locale.tr("gallery.close")).size(19.0))
+                    .clicked()
+                {
+                    should_close = true;
+                }
+            });
+
+        if open_viewer.is_some() {
+            self.gallery_viewer = open_viewer;
         }
+        if should_close {
+            self.gallery_open = false;
+            self.gallery_viewer = None;
+        }
+    }
 
-        let Some(active) = self.active_achievement_popup.as_ref() else {
+    fn draw_gallery_viewer(&mut self, ctx: &egui::Context) {
+        let Some(index) = self.gallery_viewer.as_deref().and_then(|id| {
+            self.gallery
+                .entries()
+                .iter()
+                .position(|entry| entry.id == id)
+        }) else {
+            self.gallery_viewer = None;
+            return;
+        };
+        let Some(texture) = self.gallery_texture(ctx, index) else {
+            self.gallery_viewer = None;
             return;
         };
+        let entry = &self.gallery.entries()[index];
+        let screen = ctx.content_rect();
+        let mut should_close = ctx.input(|input| input.key_pressed(egui::Key::Escape));
 
-        let palette = self.theme_palette();
-        egui::Area::new(egui::Id::new("achievement_popup"))
+        egui::Area::new(egui::Id::new("gallery_viewer"))
             .order(egui::Order::Foreground)
-            .anchor(Align2::RIGHT_TOP, [-18.0, 18.0])
+            .fixed_pos(screen.min)
             .show(ctx, |ui| {
-                ui.set_max_width(420.0);
-                Frame::new()
-                    .inner_margin(Margin::symmetric(16, 12))
-                    .fill(palette.popup_fill)
-                    .stroke(Stroke::new(2.0, palette.popup_stroke))
-                    .corner_radius(CornerRadius::same(10))
+                let response = ui.allocate_rect(screen, Sense::click());
+                ui.painter().rect_filled(
+                    screen,
+                    CornerRadius::ZERO,
+                    Color32::from_rgba_unmultiplied(0, 0, 0, 235),
+                );
+
+                let image_size = fit_size(texture.size_vec2(), screen.size() * 0.9);
+                let image_rect = egui::Rect::from_center_size(screen.center(), image_size);
+                egui::Image::new((texture.id(), image_size)).paint_at(ui, image_rect);
+
+                let caption = match entry.description.as_deref() {
+                    Some(description) => format!("{} — {description}", entry.title),
+                    None => entry.title.clone(),
+                };
+                ui.painter().text(
+                    egui::pos2(screen.center().x, screen.max.y - 28.0),
+                    Align2::CENTER_CENTER,
+                    caption,
+                    egui::FontId::proportional(20.0),
+                    Color32::from_rgb(230, 230, 230),
+                );
+                ui.painter().text(
+                    egui::pos2(screen.center().x, screen.min.y + 20.0),
+                    Align2::CENTER_CENTER,
+                    self.locale.tr("gallery.viewer_hint"),
+                    egui::FontId::proportional(15.0),
+                    Color32::from_rgb(155, 168, 181),
+                );
+
+                should_close |= response.clicked();
+            });
+
+        if should_close {
+            self.gallery_viewer = None;
+        }
+    }
+
+    fn draw_stats_window(&mut self, ctx: &egui::Context) {
+        let mut should_close = false;
+        let stats = self.stats_snapshot;
+
+        egui::Window::new(self.locale.tr("stats.window_title"))
+            .id(egui::Id::new("stats_window"))
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .default_size([420.0, 280.0])
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("stats_grid")
+                    .num_columns(2)
+                    .spacing([32.0, 10.0])
                     .show(ui, |ui| {
-                        ui.label(
-                            RichText::new("Достижение получено!")
-                                .size(20.0)
-                                .color(palette.popup_title),
+                        for stat in Stat::ALL {
+                            let value = match stat {
+                                Stat::PlaytimeMinutes => {
+                                    let minutes = stats.value(stat);
+                                    self.locale.tr_args(
+                                        "stats.playtime_value",
+                                        &[
+                                            ("hours", (minutes / 60).to_string()),
+                                            ("minutes", (minutes % 60).to_string()),
+                                        ],
+                                    )
+                                }
+                                _ => stats.value(stat).to_string(),
+                            };
+                            ui.label(RichText::new(self.locale.tr(stat.title_key())).size(18.0));
+                            ui.label(RichText::new(value).size(18.0).strong());
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(10.0);
+                if ui
+                    .button(RichText::new(self.locale.tr("stats.close")).size(19.0))
+                    .clicked()
+                {
+                    should_close = true;
+                }
+            });
+
+        if should_close {
+            self.stats_open = false;
+        }
+    }
+
+    fn draw_credits(&mut self, ctx: &egui::Context, dt: f32) {
+        const BASE_SCROLL_SPEED: f32 = 45.0;
+
+        let screen = ctx.content_rect();
+        let mut should_close = ctx.input(|input| input.key_pressed(egui::Key::Escape));
+        let mut max_offset = 0.0;
+        let sections = self.credits.sections.clone();
+
+        egui::Area::new(egui::Id::new("credits_screen"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(screen.min)
+            .show(ctx, |ui| {
+                ui.painter().rect_filled(
+                    screen,
+                    CornerRadius::ZERO,
+                    Color32::from_rgba_unmultiplied(0, 0, 0, 240),
+                );
+                ui.set_width(screen.width());
+
+                let viewport_height = (screen.height() - 64.0).max(120.0);
+                let output = egui::ScrollArea::vertical()
+                    .id_salt("credits_scroll")
+                    .max_height(viewport_height)
+                    .auto_shrink([false, false])
+                    .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysHidden)
+                    .vertical_scroll_offset(self.credits_scroll)
+                    .show(ui, |ui| {
+                        ui.vertical_centered(|ui| {
+                            // Start and end off-screen so the roll enters and leaves fully.
+                            ui.add_space(viewport_height);
+                            for section in &sections {
+                                ui.label(
+                                    RichText::new(section.title.as_str())
+                                        .size(30.0)
+                                        .color(Color32::from_rgb(244, 228, 157)),
+                                );
+                                ui.add_space(8.0);
+                                if let Some(texture) = section
+                                    .image
+                                    .as_deref()
+                                    .and_then(|path| self.ui_texture(ctx, path))
+                                {
+                                    let size =
+                                        fit_size(texture.size_vec2(), egui::vec2(360.0, 220.0));
+                                    ui.add(egui::Image::new((texture.id(), size)));
+                                    ui.add_space(8.0);
+                                }
+                                for name in &section.names {
+                                    ui.label(
+                                        RichText::new(name.as_str())
+                                            .size(22.0)
+                                            .color(Color32::from_rgb(220, 234, 248)),
+                                    );
+                                }
+                                ui.add_space(36.0);
+                            }
+                            ui.add_space(viewport_height);
+                        });
+                    });
+                max_offset = (output.content_size.y - output.inner_rect.height()).max(0.0);
+
+                ui.horizontal(|ui| {
+                    ui.add_space(16.0);
+                    let pause_key = if self.credits_paused {
+                        "credits.resume"
+                    } else {
+                        "credits.pause"
+                    };
+                    if ui.button(self.locale.tr(pause_key)).clicked() {
+                        self.credits_paused = !self.credits_paused;
+                    }
+                    ui.add(
+                        egui::Slider::new(&mut self.credits_speed, 0.25..=4.0)
+                            .text(self.locale.tr("credits.speed")),
+                    );
+                    if ui.button(self.locale.tr("credits.close")).clicked() {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if !self.credits_paused {
+            self.credits_scroll += BASE_SCROLL_SPEED * self.credits_speed * dt;
+        }
+        self.credits_scroll = self.credits_scroll.min(max_offset);
+
+        if should_close {
+            self.credits_open = false;
+        }
+    }
+
+    fn gallery_texture(
+        &mut self,
+        ctx: &egui::Context,
+        index: usize,
+    ) -> Option<egui::TextureHandle> {
+        let path = self.gallery.entries().get(index)?.image.clone();
+        self.ui_texture(ctx, &path)
+    }
+
+    fn ui_texture(&mut self, ctx: &egui::Context, path: &str) -> Option<egui::TextureHandle> {
+        self.ui_textures
+            .entry(path.to_owned())
+            .or_insert_with(|| match vfs::read_image(path) {
+                Ok(image) => {
+                    let rgba = image.to_rgba8();
+                    let size = [rgba.width() as usize, rgba.height() as usize];
+                    let color_image = egui::ColorImage::from_rgba_unmultiplied(size, &rgba);
+                    Some(ctx.load_texture(path, color_image, egui::TextureOptions::LINEAR))
+                }
+                Err(err) => {
+                    log::warn!("failed to load ui image '{path}': {err}");
+                    None
+                }
+            })
+            .clone()
+    }
+
+    fn filtered_achievements(&self) -> Vec<&AchievementSnapshotItem> {
+        let mut achievements: Vec<&AchievementSnapshotItem> = self
+            .achievements_snapshot
+            .iter()
+            .filter(|achievement| self.achievement_filter.matches(achievement))
+            .filter(|achievement| {
+                self.achievement_category_filter.is_none()
+                    || achievement.category == self.achievement_category_filter
+            })
+            .collect();
+
+        // Stable sorts keep catalog order among equal entries.
+        match self.achievement_sort {
+            AchievementSort::Catalog => {}
+            AchievementSort::Alphabetical => {
+                achievements.sort_by_key(|achievement| achievement.name.to_lowercase());
+            }
+            AchievementSort::RecentUnlocks => {
+                achievements.sort_by_key(|achievement| {
+                    (
+                        !achievement.unlocked,
+                        std::cmp::Reverse(achievement.unlocked_at),
+                    )
+                });
+            }
+            AchievementSort::Rarity => {
+                achievements.sort_by(|a, b| match (a.rarity, b.rarity) {
+                    (Some(a), Some(b)) => a.total_cmp(&b),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                });
+            }
+        }
+
+        achievements
+    }
+
+    // Flame chart of the latest profiled frame above per-span timings; returns whether export
+    // was clicked.
+    fn draw_profiler_window(&mut self, ctx: &egui::Context) -> bool {
+        const CHART_SIZE: egui::Vec2 = egui::vec2(520.0, 0.0);
+        const ROW_HEIGHT: f32 = 18.0;
+
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let latest = profiler::latest();
+        let stats = profiler::scope_stats();
+        let mut open = self.profiler_visible;
+        let mut export = false;
+        egui::Window::new(self.locale.tr("profiler.title"))
+            .id(egui::Id::new("profiler_window"))
+            .open(&mut open)
+            .default_pos([12.0, 180.0])
+            .resizable(false)
+            .show(ctx, |ui| {
+                let Some(frame) = latest else {
+                    ui.label(self.locale.tr("profiler.waiting"));
+                    return;
+                };
+                ui.label(self.locale.tr_args(
+                    "profiler.frame_time",
+                    &[("ms", format!("{:.2}", ms(frame.duration)))],
+                ));
+
+                let rows = frame
+                    .scopes
+                    .iter()
+                    .map(|scope| scope.depth + 1)
+                    .max()
+                    .unwrap_or(1);
+                let (rect, response) = ui.allocate_exact_size(
+                    CHART_SIZE + egui::vec2(0.0, rows as f32 * ROW_HEIGHT),
+                    Sense::hover(),
+                );
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, CornerRadius::ZERO, Color32::from_black_alpha(120));
+                let frame_seconds = frame.duration.as_secs_f32().max(f32::EPSILON);
+                let pointer = response.hover_pos();
+                let mut hovered = None;
+                for scope in &frame.scopes {
+                    let left =
+                        rect.left() + scope.start.as_secs_f32() / frame_seconds * rect.width();
+                    let width =
+                        (scope.duration.as_secs_f32() / frame_seconds * rect.width()).max(1.0);
+                    let top = rect.top() + scope.depth as f32 * ROW_HEIGHT;
+                    let bar = egui::Rect::from_min_size(
+                        egui::pos2(left, top),
+                        egui::vec2(width, ROW_HEIGHT - 2.0),
+                    );
+                    painter.rect_filled(
+                        bar,
+                        CornerRadius::same(2),
+                        profiler_scope_color(scope.name),
+                    );
+                    if width > 48.0 {
+                        painter.text(
+                            bar.left_center() + egui::vec2(4.0, 0.0),
+                            Align2::LEFT_CENTER,
+                            scope.name,
+                            egui::FontId::monospace(11.0),
+                            Color32::BLACK,
                         );
-                        ui.label(
-                            RichText::new(active.notification.name.as_str())
-                                .size(24.0)
-                                .color(palette.popup_name),
+                    }
+                    if pointer.is_some_and(|pointer| bar.contains(pointer)) {
+                        hovered = Some(scope);
+                    }
+                }
+                if let Some(scope) = hovered {
+                    response.on_hover_text_at_pointer(format!(
+                        "{} {:.3} ms",
+                        scope.name,
+                        ms(scope.duration)
+                    ));
+                }
+
+                ui.add_space(6.0);
+                egui::Grid::new("profiler_scopes")
+                    .striped(true)
+                    .num_columns(4)
+                    .show(ui, |ui| {
+                        for key in [
+                            "profiler.scope",
+                            "profiler.last",
+                            "profiler.average",
+                            "profiler.max",
+                        ] {
+                            ui.strong(self.locale.tr(key));
+                        }
+                        ui.end_row();
+                        for stat in &stats {
+                            let indent = "  ".repeat(stat.depth);
+                            ui.monospace(format!("{indent}{}", stat.name));
+                            ui.monospace(format!("{:.3}", ms(stat.last)));
+                            ui.monospace(format!("{:.3}", ms(stat.average)));
+                            ui.monospace(format!("{:.3}", ms(stat.max)));
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(6.0);
+                export = ui.button(self.locale.tr("profiler.export")).clicked();
+            });
+        self.profiler_visible = open;
+        export
+    }
+
+    fn draw_debug_overlay(&self, ctx: &egui::Context) {
+        const GRAPH_SIZE: egui::Vec2 = egui::vec2(240.0, 64.0);
+        const FRAME_BUDGET_MS: f32 = 1000.0 / 60.0;
+
+        let recent = self.frame_times.iter().rev().take(30);
+        let recent_count = recent.len().max(1) as f32;
+        let average_ms = recent.sum::<f32>() / recent_count;
+        let fps = if average_ms > 0.0 {
+            1000.0 / average_ms
+        } else {
+            0.0
+        };
+        let last_ms = self.frame_times.back().copied().unwrap_or(0.0);
+        let stats = self.render_stats;
+
+        egui::Area::new(egui::Id::new("debug_overlay"))
+            .order(egui::Order::Tooltip)
+            .anchor(Align2::LEFT_TOP, [12.0, 12.0])
+            .interactable(false)
+            .show(ctx, |ui| {
+                Frame::new()
+                    .inner_margin(Margin::symmetric(10, 8))
+                    .fill(Color32::from_rgba_unmultiplied(0, 0, 0, 190))
+                    .corner_radius(CornerRadius::same(6))
+                    .show(ui, |ui| {
+                        let text = |text: String| {
+                            RichText::new(text)
+                                .monospace()
+                                .size(13.0)
+                                .color(Color32::from_rgb(220, 234, 248))
+                        };
+                        ui.label(text(format!(
+                            "FPS {fps:>5.1}   {last_ms:>5.2} ms (avg {average_ms:.2})"
+                        )));
+                        ui.label(text(self.locale.tr_args(
+                            "debug.objects",
+                            &[
+                                ("visible", stats.visible_objects.to_string()),
+                                ("total", stats.object_count.to_string()),
+                            ],
+                        )));
+                        ui.label(text(self.locale.tr_args(
+                            "debug.draw_calls",
+                            &[
+                                ("scene", stats.draw_calls.to_string()),
+                                ("ui", self.ui_paint_jobs.to_string()),
+                            ],
+                        )));
+
+                        let (rect, _) = ui.allocate_exact_size(GRAPH_SIZE, Sense::hover());
+                        let painter = ui.painter();
+                        painter.rect_filled(
+                            rect,
+                            CornerRadius::ZERO,
+                            Color32::from_black_alpha(120),
                         );
-                        ui.label(
-                            RichText::new(active.notification.description.as_str())
-                                .size(18.0)
-                                .color(palette.popup_body),
+
+                        // Scale so two 60 Hz frame budgets always fit; spikes extend the range.
+                        let peak_ms = self
+                            .frame_times
+                            .iter()
+                            .copied()
+                            .fold(FRAME_BUDGET_MS * 2.0, f32::max);
+                        let y_for =
+                            |ms: f32| rect.bottom() - (ms / peak_ms).min(1.0) * rect.height();
+
+                        let budget_y = y_for(FRAME_BUDGET_MS);
+                        painter.hline(
+                            rect.x_range(),
+                            budget_y,
+                            Stroke::new(1.0, Color32::from_rgb(96, 106, 112)),
                         );
+
+                        let step = rect.width() / (DEBUG_FRAME_HISTORY - 1) as f32;
+                        let start = DEBUG_FRAME_HISTORY - self.frame_times.len();
+                        let points: Vec<egui::Pos2> = self
+                            .frame_times
+                            .iter()
+                            .enumerate()
+                            .map(|(index, ms)| {
+                                egui::pos2(rect.left() + (start + index) as f32 * step, y_for(*ms))
+                            })
+                            .collect();
+                        painter.add(egui::Shape::line(
+                            points,
+                            Stroke::new(1.5, Color32::from_rgb(132, 219, 104)),
+                        ));
+
+                        for entry in logging::recent(log::Level::Warn, DEBUG_LOG_LINES) {
+                            let color = match entry.level {
+                                log::Level::Error => Color32::from_rgb(240, 120, 110),
+                                _ => Color32::from_rgb(236, 200, 110),
+                            };
+                            ui.label(
+                                text(format!("{} {}", entry.target, entry.message)).color(color),
+                            );
+                        }
                     });
             });
+    }
+
+    fn draw_toasts(&mut self, ctx: &egui::Context, dt: f32) {
+        // The notifications setting only mutes achievement popups; engine messages still show.
+        if !self.settings.popup_enabled {
+            self.active_toasts
+                .retain(|active| active.toast.kind != ToastKind::Achievement);
+            self.toast_queue
+                .retain(|toast| toast.kind != ToastKind::Achievement);
+        }
+
+        // Queued toasts join the stack as soon as there is room for them.
+        while self.active_toasts.len() < MAX_STACKED_TOASTS {
+            let Some(next) = self.toast_queue.pop_front() else {
+                break;
+            };
+            let y_offset = self
+                .active_toasts
+                .iter()
+                .map(|active| active.height + TOAST_GAP)
+                .sum();
+            self.active_toasts.push(ActiveToast {
+                id: self.next_toast_id,
+                duration: next.duration + TOAST_SLIDE_SECONDS * 2.0,
+                toast: next,
+                elapsed: 0.0,
+                height: 0.0,
+                y_offset,
+            });
+            self.next_toast_id = self.next_toast_id.wrapping_add(1);
+        }
+
+        if self.active_toasts.is_empty() {
+            return;
+        }
 
         let time_step = if dt > 0.0 {
             dt * self.settings.animation_speed.clamp(0.2, 2.0)
@@ -1010,17 +4061,117 @@ impl DialogueUi {
             1.0 / 60.0
         };
 
-        if let Some(active) = self.active_achievement_popup.as_mut() {
-            active.remaining -= time_step;
-            if active.remaining <= 0.0 {
-                self.active_achievement_popup = None;
-            }
+        let palette = self.theme_palette();
+        let mut target_y = 0.0;
+        for active in &mut self.active_toasts {
+            active.y_offset += (target_y - active.y_offset) * (time_step * 12.0).min(1.0);
+
+            let (fill, stroke, heading_color, title_color, body_color) = match active.toast.kind {
+                ToastKind::Achievement => (
+                    palette.popup_fill,
+                    palette.popup_stroke,
+                    palette.popup_title,
+                    palette.popup_name,
+                    palette.popup_body,
+                ),
+                ToastKind::Info | ToastKind::Caption => (
+                    palette.settings_fill,
+                    palette.settings_stroke,
+                    palette.settings_title,
+                    palette.settings_title,
+                    palette.dialogue_text,
+                ),
+                ToastKind::Success => (
+                    Color32::from_rgba_unmultiplied(18, 44, 40, 235),
+                    Color32::from_rgb(104, 206, 178),
+                    Color32::from_rgb(214, 250, 238),
+                    Color32::from_rgb(190, 246, 226),
+                    Color32::from_rgb(180, 226, 212),
+                ),
+                ToastKind::Error => (
+                    Color32::from_rgba_unmultiplied(56, 18, 18, 240),
+                    Color32::from_rgb(236, 110, 96),
+                    Color32::from_rgb(255, 214, 206),
+                    Color32::from_rgb(255, 190, 178),
+                    Color32::from_rgb(238, 196, 188),
+                ),
+            };
+
+            let visibility = active.visibility();
+            let slide_x = (1.0 - visibility) * (TOAST_WIDTH + 24.0);
+            let response = egui::Area::new(egui::Id::new(("toast", active.id)))
+                .order(egui::Order::Foreground)
+                .anchor(Align2::RIGHT_TOP, [-18.0 + slide_x, 18.0 + active.y_offset])
+                .constrain(false)
+                .interactable(false)
+                .show(ctx, |ui| {
+                    ui.multiply_opacity(visibility);
+                    ui.set_width(TOAST_WIDTH);
+                    Frame::new()
+                        .inner_margin(Margin::symmetric(16, 12))
+                        .fill(fill)
+                        .stroke(Stroke::new(2.0, stroke))
+                        .corner_radius(CornerRadius::same(10))
+                        .show(ui, |ui| {
+                            ui.label(
+                                RichText::new(self.locale.tr(active.toast.kind.heading_key()))
+                                    .size(20.0)
+                                    .color(heading_color),
+                            );
+                            ui.label(
+                                RichText::new(active.toast.title.as_str())
+                                    .size(24.0)
+                                    .color(title_color),
+                            );
+                            if !active.toast.body.is_empty() {
+                                ui.label(
+                                    RichText::new(active.toast.body.as_str())
+                                        .size(18.0)
+                                        .color(body_color),
+                                );
+                            }
+                            ui.add_space(4.0);
+                            ui.add(
+                                egui::ProgressBar::new(active.remaining_fraction())
+                                    .desired_height(4.0)
+                                    .fill(stroke),
+                            );
+                        });
+                })
+                .response;
+
+            active.height = response.rect.height();
+            target_y += active.height + TOAST_GAP;
+            active.elapsed += time_step;
         }
+
+        self.active_toasts
+            .retain(|active| active.elapsed < active.duration);
     }
 
     fn theme_palette(&self) -> UiThemePalette {
+        let palette = self.base_theme_palette();
+        if self.settings.enforce_min_contrast {
+            palette.with_min_contrast(self.settings.min_contrast_ratio)
+        } else {
+            palette
+        }
+    }
+
+    fn base_theme_palette(&self) -> UiThemePalette {
         match self.settings.theme_preset {
-            UiThemePreset::DeepSea => UiThemePalette {
+            UiThemePreset::Custom(index) => self
+                .custom_themes
+                .get(index)
+                .map(|theme| theme.palette)
+                .unwrap_or_else(|| Self::builtin_palette(UiThemePreset::DeepSea)),
+            preset => Self::builtin_palette(preset),
+        }
+    }
+
+    fn builtin_palette(preset: UiThemePreset) -> UiThemePalette {
+        match preset {
+            UiThemePreset::DeepSea | UiThemePreset::Custom(_) => UiThemePalette {
                 menu_fill: Color32::from_rgba_unmultiplied(8, 18, 30, 238),
                 menu_stroke: Color32::from_rgb(120, 140, 90),
                 menu_title: Color32::from_rgb(244, 228, 157),
@@ -1077,6 +4228,45 @@ impl DialogueUi {
                 popup_name: Color32::from_rgb(255, 208, 156),
                 popup_body: Color32::from_rgb(239, 200, 163),
             },
+            UiThemePreset::HighContrast => UiThemePalette {
+                menu_fill: Color32::from_rgba_unmultiplied(0, 0, 0, 250),
+                menu_stroke: Color32::WHITE,
+                menu_title: Color32::from_rgb(255, 230, 0),
+                settings_fill: Color32::from_rgba_unmultiplied(0, 0, 0, 250),
+                settings_stroke: Color32::WHITE,
+                settings_title: Color32::from_rgb(255, 230, 0),
+                dialogue_fill_rgb: [0, 0, 0],
+                dialogue_stroke: Color32::WHITE,
+                dialogue_speaker: Color32::from_rgb(255, 230, 0),
+                dialogue_text: Color32::WHITE,
+                skip_ready: Color32::from_rgb(0, 230, 255),
+                skip_wait: Color32::from_rgb(170, 170, 170),
+                popup_fill: Color32::from_rgba_unmultiplied(0, 0, 0, 245),
+                popup_stroke: Color32::from_rgb(255, 230, 0),
+                popup_title: Color32::WHITE,
+                popup_name: Color32::from_rgb(255, 230, 0),
+                popup_body: Color32::from_rgb(230, 230, 230),
+            },
+            // Okabe-Ito colors, distinguishable under common color vision deficiencies.
+            UiThemePreset::ColorblindSafe => UiThemePalette {
+                menu_fill: Color32::from_rgba_unmultiplied(16, 20, 32, 238),
+                menu_stroke: Color32::from_rgb(86, 180, 233),
+                menu_title: Color32::from_rgb(240, 228, 66),
+                settings_fill: Color32::from_rgba_unmultiplied(16, 20, 32, 238),
+                settings_stroke: Color32::from_rgb(86, 180, 233),
+                settings_title: Color32::from_rgb(240, 240, 240),
+                dialogue_fill_rgb: [14, 18, 28],
+                dialogue_stroke: Color32::from_rgb(86, 180, 233),
+                dialogue_speaker: Color32::from_rgb(230, 159, 0),
+                dialogue_text: Color32::from_rgb(240, 240, 240),
+                skip_ready: Color32::from_rgb(86, 180, 233),
+                skip_wait: Color32::from_rgb(140, 140, 150),
+                popup_fill: Color32::from_rgba_unmultiplied(10, 30, 48, 235),
+                popup_stroke: Color32::from_rgb(86, 180, 233),
+                popup_title: Color32::from_rgb(240, 240, 240),
+                popup_name: Color32::from_rgb(240, 228, 66),
+                popup_body: Color32::from_rgb(214, 222, 230),
+            },
         }
     }
 
@@ -0,0 +1,257 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use winit::{
+    dpi::PhysicalSize,
+    event_loop::ActiveEventLoop,
+    monitor::MonitorHandle,
+    window::{Fullscreen, Window},
+};
+
+use crate::vfs;
+
+// Relative to `paths::data_dir()`.
+pub const DEFAULT_DISPLAY_SETTINGS_PATH: &str = "display.json";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    // Fullscreen window at the desktop resolution; alt-tab friendly.
+    Borderless,
+    // Takes over the monitor's video mode.
+    Exclusive,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [Self::Windowed, Self::Borderless, Self::Exclusive];
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerPreference {
+    // Usually the discrete GPU.
+    #[default]
+    HighPerformance,
+    // Usually the integrated GPU; easier on laptop batteries.
+    LowPower,
+}
+
+impl PowerPreference {
+    pub fn to_wgpu(self) -> wgpu::PowerPreference {
+        match self {
+            Self::HighPerformance => wgpu::PowerPreference::HighPerformance,
+            Self::LowPower => wgpu::PowerPreference::LowPower,
+        }
+    }
+}
+
+// Which GPU to render with. Only read at startup and not shown in the settings screen;
+// `--gpu`, `--low-power` and `--allow-fallback-adapter` override it for one run.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GpuSettings {
+    pub power_preference: PowerPreference,
+    // Case-insensitive part of the adapter name, e.g. "nvidia"; None lets wgpu choose.
+    pub adapter: Option<String>,
+    // Allows a software renderer (WARP, llvmpipe) when no hardware adapter works.
+    pub allow_fallback_adapter: bool,
+}
+
+// Window and swapchain options from the Interface tab, kept in their own file so they
+// apply before the first frame.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub mode: DisplayMode,
+    // Mode the fullscreen toggle returns to from windowed.
+    pub last_fullscreen_mode: DisplayMode,
+    // Monitor name; None follows the monitor the window is on.
+    pub monitor: Option<String>,
+    // Window size, or the exclusive video mode; None keeps the current size.
+    pub resolution: Option<[u32; 2]>,
+    #[serde(with = "present_mode_name")]
+    pub present_mode: wgpu::PresentMode,
+    pub fps_limit_enabled: bool,
+    pub fps_limit: u32,
+    pub gpu: GpuSettings,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::Windowed,
+            last_fullscreen_mode: DisplayMode::Borderless,
+            monitor: None,
+            resolution: None,
+            present_mode: wgpu::PresentMode::Fifo,
+            fps_limit_enabled: false,
+            fps_limit: 60,
+            gpu: GpuSettings::default(),
+        }
+    }
+}
+
+// A monitor and the resolutions it offers, largest first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisplayInfo {
+    pub name: String,
+    pub resolutions: Vec<[u32; 2]>,
+}
+
+impl DisplaySettings {
+    pub fn load_from_json_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        if !vfs::exists(path) {
+            return Ok(Self::default());
+        }
+
+        let raw = vfs::read_to_string(path)
+            .map_err(|err| format!("failed to read display settings {}: {err}", path.display()))?;
+        serde_json::from_str(&raw)
+            .map_err(|err| format!("failed to parse display settings {}: {err}", path.display()))
+    }
+
+    pub fn save_to_json_file(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            vfs::create_dir_all(parent).map_err(|err| {
+                format!(
+                    "failed to create display settings directory {}: {err}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|err| format!("failed to serialize display settings: {err}"))?;
+        vfs::write(path, json)
+            .map_err(|err| format!("failed to write display settings {}: {err}", path.display()))
+    }
+
+    pub fn frame_limit(&self) -> Option<u32> {
+        self.fps_limit_enabled.then_some(self.fps_limit)
+    }
+
+    // Whether both settings put the window in the same place and size.
+    pub fn same_window(&self, other: &Self) -> bool {
+        self.mode == other.mode
+            && self.monitor == other.monitor
+            && self.resolution == other.resolution
+    }
+
+    // Switches between windowed and the last used fullscreen mode.
+    pub fn toggle_fullscreen(&mut self) {
+        self.mode = match self.mode {
+            DisplayMode::Windowed => self.last_fullscreen_mode,
+            _ => DisplayMode::Windowed,
+        };
+    }
+
+    // Exclusive uses the chosen resolution at the highest refresh rate, or the monitor's
+    // largest mode; it falls back to borderless when the platform reports no video modes.
+    pub fn fullscreen(&self, window: &Window) -> Option<Fullscreen> {
+        let monitor = self.target_monitor(window);
+        match self.mode {
+            DisplayMode::Windowed => None,
+            DisplayMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+            DisplayMode::Exclusive => {
+                let video_mode = monitor.and_then(|monitor| {
+                    monitor
+                        .video_modes()
+                        .filter(|mode| {
+                            self.resolution
+                                .is_none_or(|resolution| size_array(mode.size()) == resolution)
+                        })
+                        .max_by_key(|mode| {
+                            let size = mode.size();
+                            (size.width * size.height, mode.refresh_rate_millihertz())
+                        })
+                });
+                match video_mode {
+                    Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                    None => {
+                        log::warn!("no exclusive video mode available, using borderless");
+                        Some(Fullscreen::Borderless(None))
+                    }
+                }
+            }
+        }
+    }
+
+    // Inner size to request in windowed mode.
+    pub fn window_size(&self) -> Option<PhysicalSize<u32>> {
+        match self.mode {
+            DisplayMode::Windowed => self
+                .resolution
+                .map(|[width, height]| PhysicalSize::new(width, height)),
+            _ => None,
+        }
+    }
+
+    fn target_monitor(&self, window: &Window) -> Option<MonitorHandle> {
+        self.monitor
+            .as_deref()
+            .and_then(|name| {
+                window
+                    .available_monitors()
+                    .find(|monitor| monitor.name().as_deref() == Some(name))
+            })
+            .or_else(|| window.current_monitor())
+    }
+}
+
+pub fn available_displays(event_loop: &ActiveEventLoop) -> Vec<DisplayInfo> {
+    event_loop
+        .available_monitors()
+        .enumerate()
+        .map(|(index, monitor)| {
+            let mut resolutions: Vec<[u32; 2]> = monitor
+                .video_modes()
+                .map(|mode| size_array(mode.size()))
+                .collect();
+            resolutions.sort_unstable_by_key(|[width, height]| std::cmp::Reverse(width * height));
+            resolutions.dedup();
+            DisplayInfo {
+                name: monitor
+                    .name()
+                    .unwrap_or_else(|| format!("Display {}", index + 1)),
+                resolutions,
+            }
+        })
+        .collect()
+}
+
+fn size_array(size: PhysicalSize<u32>) -> [u32; 2] {
+    [size.width, size.height]
+}
+
+// wgpu only derives serde behind a feature, so present modes are stored by name.
+mod present_mode_name {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        mode: &wgpu::PresentMode,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match mode {
+            wgpu::PresentMode::Mailbox => "mailbox",
+            wgpu::PresentMode::Immediate => "immediate",
+            _ => "fifo",
+        })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<wgpu::PresentMode, D::Error> {
+        Ok(match String::deserialize(deserializer)?.as_str() {
+            "mailbox" => wgpu::PresentMode::Mailbox,
+            "immediate" => wgpu::PresentMode::Immediate,
+            _ => wgpu::PresentMode::Fifo,
+        })
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use crate::{save, vfs};
+
+// Cross-playthrough flags (endings seen, unlocked CGs), relative to `paths::data_dir()`.
+pub const DEFAULT_PERSISTENT_FLAGS_PATH: &str = "persistent_flags.json";
+
+// Named string values shared between scripts and the UI (player name, choices, etc.).
+// The same type backs the persistent store, which survives new games and is never part
+// of a save slot.
+#[derive(Clone, Debug, Default)]
+pub struct FlagStore {
+    values: HashMap<String, String>,
+    // Bumped on every change so observers can skip work when nothing moved.
+    revision: u64,
+    saved_revision: u64,
+}
+
+impl FlagStore {
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.values.insert(key.into(), value.into());
+        self.revision += 1;
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, String> {
+        self.values.clone()
+    }
+
+    // Replaces every value, e.g. when a save game is loaded.
+    pub fn restore(&mut self, values: HashMap<String, String>) {
+        self.values = values;
+        self.revision += 1;
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // Truthy unless missing, "", "0" or "false".
+    #[allow(dead_code)]
+    pub fn is_set(&self, key: &str) -> bool {
+        self.get(key).is_some_and(is_truthy)
+    }
+
+    // A missing file starts empty.
+    pub fn load_from_json_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        if !vfs::exists(path) && !vfs::exists(save::backup_path(path)) {
+            return Ok(Self::default());
+        }
+
+        let values = save::read_signed_with_backup(path, "flags file", |path, raw| {
+            serde_json::from_str(raw)
+                .map_err(|err| format!("failed to parse flags json {}: {err}", path.display()))
+        })?;
+
+        Ok(Self {
+            values,
+            ..Self::default()
+        })
+    }
+
+    // Writes only when a value changed since the last save.
+    pub fn save_to_json_file(&mut self, path: impl AsRef<Path>) -> Result<bool, String> {
+        if self.revision == self.saved_revision {
+            return Ok(false);
+        }
+
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            vfs::create_dir_all(parent).map_err(|err| {
+                format!(
+                    "failed to create flags directory {}: {err}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        let values: BTreeMap<&String, &String> = self.values.iter().collect();
+        let json = serde_json::to_string_pretty(&values)
+            .map_err(|err| format!("failed to serialize flags: {err}"))?;
+
+        save::write_signed(path, &json)
+            .map_err(|err| format!("failed to write flags json {}: {err}", path.display()))?;
+
+        self.saved_revision = self.revision;
+        Ok(true)
+    }
+}
+
+pub fn is_truthy(value: &str) -> bool {
+    !matches!(value.trim(), "" | "0" | "false")
+}
+
+// Replaces `{$name}` placeholders with values from `variables`.
+// Unknown variables are left untouched so authoring mistakes stay visible.
+pub fn substitute_variables(text: &str, variables: &HashMap<String, String>) -> String {
+    if !text.contains("{$") {
+        return text.to_owned();
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{$") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            result.push_str(&rest[start..]);
+            return result;
+        };
+
+        let name = after[..end].trim();
+        match variables.get(name) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..start + 2 + end + 1]),
+        }
+        rest = &after[end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
@@ -0,0 +1,78 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::{achievements::AchievementSnapshotItem, flags, vfs};
+
+pub const DEFAULT_GALLERY_PATH: &str = "src/data/gallery.json";
+
+// One unlockable illustration. Entries without unlock conditions are always visible;
+// otherwise any satisfied condition unlocks the entry.
+#[derive(Clone, Debug, Deserialize)]
+pub struct GalleryEntry {
+    pub id: String,
+    pub title: String,
+    pub image: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Flag that must be set to a truthy value (anything except "", "0" and "false").
+    #[serde(default)]
+    pub unlock_flag: Option<String>,
+    // Same, read from the persistent store so the entry stays unlocked in new games.
+    #[serde(default)]
+    pub unlock_persistent_flag: Option<String>,
+    #[serde(default)]
+    pub unlock_achievement: Option<String>,
+}
+
+impl GalleryEntry {
+    pub fn is_unlocked(
+        &self,
+        flags: &HashMap<String, String>,
+        persistent_flags: &HashMap<String, String>,
+        achievements: &[AchievementSnapshotItem],
+    ) -> bool {
+        if self.unlock_flag.is_none()
+            && self.unlock_persistent_flag.is_none()
+            && self.unlock_achievement.is_none()
+        {
+            return true;
+        }
+
+        let flag_set = |flag: Option<&str>, flags: &HashMap<String, String>| {
+            flag.and_then(|flag| flags.get(flag))
+                .is_some_and(|value| flags::is_truthy(value))
+        };
+        let flag_unlocked = flag_set(self.unlock_flag.as_deref(), flags)
+            || flag_set(self.unlock_persistent_flag.as_deref(), persistent_flags);
+        let achievement_unlocked = self.unlock_achievement.as_deref().is_some_and(|id| {
+            achievements
+                .iter()
+                .any(|achievement| achievement.id == id && achievement.unlocked)
+        });
+
+        flag_unlocked || achievement_unlocked
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Gallery {
+    entries: Vec<GalleryEntry>,
+}
+
+impl Gallery {
+    pub fn load_from_json_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let raw = vfs::read_to_string(path)
+            .map_err(|err| format!("failed to read gallery file {}: {err}", path.display()))?;
+
+        let entries: Vec<GalleryEntry> = serde_json::from_str(&raw)
+            .map_err(|err| format!("failed to parse gallery json {}: {err}", path.display()))?;
+
+        Ok(Self { entries })
+    }
+
+    pub fn entries(&self) -> &[GalleryEntry] {
+        &self.entries
+    }
+}
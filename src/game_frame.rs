@@ -0,0 +1,210 @@
+use crate::{
+    achievements::AchievementManager,
+    audio::AudioEngine,
+    dialogue_ui::{DialogueUi, ToastKind, UiCommand},
+    flags::FlagStore,
+    input::{Action, ActionMap, InputContext, InputState, TextEntryResult},
+    profiler,
+    scene_script::{SceneRunner, ScriptContext, ScriptSignal, SignalRoutes},
+    stats::{Stat, StatsManager},
+    tex::Tex,
+};
+
+// Timeline speed-up while the fast-forward action is held.
+const FAST_FORWARD_TIME_SCALE: f32 = 4.0;
+
+// Which action contexts this frame's input is matched against.
+pub fn set_input_contexts(action_map: &mut ActionMap, dialogue_ui: &DialogueUi, in_game: bool) {
+    action_map.set_context_active(InputContext::Gameplay, in_game);
+    action_map.set_context_active(
+        InputContext::Dialogue,
+        in_game && dialogue_ui.has_visible_dialogue(),
+    );
+    action_map.set_context_active(
+        InputContext::Menu,
+        !in_game || dialogue_ui.is_backlog_open(),
+    );
+}
+
+// What `GameFrame::update` decided, for the rest of the frame.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameUpdate {
+    // Scaled time the timeline and the UI advance by.
+    pub dt: f32,
+    pub paused: bool,
+    // Letter-key actions are live: in game, UI shown and no text field focused.
+    pub gameplay_keys: bool,
+}
+
+// The game state one frame advances, borrowed from `App` or `HeadlessEngine` so both run
+// the same update around their own windowing, loading and persistence.
+pub struct GameFrame<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    pub tex: &'a mut Tex,
+    pub dialogue_ui: &'a mut DialogueUi,
+    pub scene_runner: Option<&'a mut SceneRunner>,
+    pub achievements: &'a mut AchievementManager,
+    pub stats: &'a mut StatsManager,
+    pub flags: &'a mut FlagStore,
+    pub persistent_flags: &'a mut FlagStore,
+    pub audio: Option<&'a mut AudioEngine>,
+    pub input: &'a mut InputState,
+    pub action_map: &'a ActionMap,
+    pub signal_routes: &'a SignalRoutes,
+    pub in_game: bool,
+}
+
+impl GameFrame<'_> {
+    // Everything before the UI pass: gameplay keys, script signals, the fixed-step script
+    // update over `raw_dt` seconds and the snapshots the UI shows.
+    pub fn update(
+        &mut self,
+        tick_accumulator: &mut f32,
+        raw_dt: f32,
+    ) -> Result<FrameUpdate, String> {
+        let dialogue_ui = &mut *self.dialogue_ui;
+        // While the UI is hidden the next press only restores it.
+        let ui_hidden = dialogue_ui.is_ui_hidden();
+
+        // Letter-key actions stay quiet while the player types into a prompt.
+        let gameplay_keys = self.in_game
+            && !ui_hidden
+            && !dialogue_ui.is_awaiting_text_input()
+            && !dialogue_ui.wants_keyboard_input();
+        if gameplay_keys {
+            let pressed = |action| self.action_map.just_pressed(action, self.input);
+            if pressed(Action::Pause) {
+                dialogue_ui.toggle_pause();
+            }
+            if pressed(Action::ToggleBacklog) {
+                dialogue_ui.toggle_backlog();
+            }
+            if pressed(Action::Auto) {
+                let key = if dialogue_ui.toggle_auto_advance() {
+                    "toast.auto_on"
+                } else {
+                    "toast.auto_off"
+                };
+                let title = dialogue_ui.tr(key).to_owned();
+                dialogue_ui.push_toast(ToastKind::Info, title, String::new(), 1.5);
+            }
+        }
+
+        let paused = dialogue_ui.is_paused();
+        let fast_forward =
+            gameplay_keys && !paused && self.action_map.is_down(Action::FastForward, self.input);
+        dialogue_ui.set_fast_forward(fast_forward);
+
+        if self.in_game
+            && !ui_hidden
+            && !paused
+            && !dialogue_ui.is_backlog_open()
+            && let Some(scene_runner) = self.scene_runner.as_deref_mut()
+        {
+            // Broadcast to all scripts (used for dialogue skip/close behavior).
+            for signal in self
+                .signal_routes
+                .pressed_signals(self.action_map, self.input)
+            {
+                if signal == ScriptSignal::SkipWait && !dialogue_ui.can_skip_wait() {
+                    continue;
+                }
+                scene_runner.send_signal(signal);
+            }
+            if let Some(audio) = self.audio.as_deref() {
+                for beat in audio.beat_events() {
+                    scene_runner.send_signal(ScriptSignal::Beat(beat.clone()));
+                }
+            }
+        }
+
+        let dt = if self.in_game {
+            self.stats.add_playtime(raw_dt);
+            // Hidden UI and pause stop the timeline and the typewriter.
+            if ui_hidden || paused {
+                0.0
+            } else if fast_forward {
+                raw_dt * FAST_FORWARD_TIME_SCALE
+            } else {
+                raw_dt
+            }
+        } else {
+            0.0
+        };
+
+        if self.in_game
+            && let Some(scene_runner) = self.scene_runner.as_deref_mut()
+        {
+            let mut script_context = ScriptContext {
+                device: self.device,
+                queue: self.queue,
+                tex: self.tex,
+                dialogue_ui: self.dialogue_ui,
+                achievements: self.achievements,
+                stats: self.stats,
+                flags: self.flags,
+                persistent_flags: self.persistent_flags,
+                audio: self.audio.as_deref_mut(),
+                input: self.input,
+            };
+            // Fixed-step lifecycle update for all active scripts; leftover time carries
+            // over to the next frame.
+            let _scope = profiler::scope("script_update");
+            scene_runner.update_fixed(tick_accumulator, dt, &mut script_context)?;
+        }
+
+        // The input prompt's answer is typed through the InputState text-entry mode.
+        let dialogue_ui = &mut *self.dialogue_ui;
+        if let Some(TextEntryResult::Submitted(value)) = self.input.take_text_entry_result() {
+            dialogue_ui.submit_text_input(&value);
+        }
+        if !dialogue_ui.is_awaiting_text_input() {
+            self.input.end_text_entry();
+        } else if !self.input.is_text_entry_active() {
+            // Escape and empty answers start over; the prompt itself cannot be dismissed.
+            self.input.begin_text_entry(String::new(), None);
+        }
+        dialogue_ui.set_text_entry(self.input.text_entry());
+
+        dialogue_ui.set_achievements_snapshot(self.achievements.snapshot());
+        dialogue_ui.enqueue_achievement_notifications(self.achievements.take_notifications());
+        dialogue_ui.set_text_variables(self.flags.snapshot());
+        dialogue_ui.set_persistent_flags(self.persistent_flags.snapshot());
+        dialogue_ui.set_key_bindings(self.action_map.bindings());
+
+        Ok(FrameUpdate {
+            dt,
+            paused,
+            gameplay_keys,
+        })
+    }
+
+    // Everything after the UI pass that feeds back into the game: dialogue skips, prompt
+    // answers, stats and achievement progress.
+    pub fn after_ui(&mut self, ui_command: UiCommand, paused: bool) {
+        let dialogue_ui = &mut *self.dialogue_ui;
+        if ui_command == UiCommand::SkipWait
+            && self.in_game
+            && !paused
+            && dialogue_ui.can_skip_wait()
+            && let Some(scene_runner) = self.scene_runner.as_deref_mut()
+        {
+            scene_runner.send_signal(ScriptSignal::SkipWait);
+        }
+
+        // Answers to input prompts are the player's choices.
+        for (variable, value) in dialogue_ui.take_submitted_inputs() {
+            self.flags.set(variable, value);
+            self.stats.record_choice();
+        }
+        self.stats.add_lines_read(dialogue_ui.take_lines_read());
+        for stat in Stat::ALL {
+            self.achievements
+                .sync_progress(stat.key(), self.stats.value(stat));
+        }
+        self.achievements
+            .evaluate_conditions(self.stats, self.flags);
+        dialogue_ui.set_stats_snapshot(self.stats.snapshot());
+    }
+}
@@ -28,12 +28,46 @@ pub struct GameObject2D {
     pub hidden: bool,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DialogueAnchor {
+    Top,
+    Middle,
+    #[default]
+    Bottom,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum DialogueAlign {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+// Screen placement of a dialogue box; boxes sharing an anchor are stacked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DialoguePlacement {
+    pub anchor: DialogueAnchor,
+    pub align: DialogueAlign,
+}
+
+impl DialoguePlacement {
+    pub const fn new(anchor: DialogueAnchor, align: DialogueAlign) -> Self {
+        Self { anchor, align }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DialogueBoxObject {
     pub id: Option<String>,
     pub speaker: String,
     pub text: String,
     pub hidden: bool,
+    // None falls back to the speaker's default placement in DialogueUi.
+    pub placement: Option<DialoguePlacement>,
+    // Registered AudioEngine sound played when the line appears.
+    pub voice: Option<String>,
 }
 
 impl DialogueBoxObject {
@@ -43,6 +77,8 @@ impl DialogueBoxObject {
             speaker: speaker.into(),
             text: text.into(),
             hidden: false,
+            placement: None,
+            voice: None,
         }
     }
 
@@ -64,6 +100,18 @@ impl DialogueBoxObject {
         self
     }
 
+    #[allow(dead_code)]
+    pub fn with_placement(mut self, anchor: DialogueAnchor, align: DialogueAlign) -> Self {
+        self.placement = Some(DialoguePlacement::new(anchor, align));
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_voice(mut self, sound_id: impl Into<String>) -> Self {
+        self.voice = Some(sound_id.into());
+        self
+    }
+
     pub fn scene_key(&self) -> String {
         if let Some(id) = &self.id {
             return format!("id:{id}");
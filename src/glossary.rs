@@ -0,0 +1,37 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::vfs;
+
+pub const DEFAULT_GLOSSARY_PATH: &str = "src/data/glossary.json";
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GlossaryEntry {
+    #[serde(default)]
+    pub title: Option<String>,
+    pub definition: String,
+}
+
+// Definitions shown for `{term=key}...{/term}` links in dialogue text.
+#[derive(Clone, Debug, Default)]
+pub struct Glossary {
+    entries: HashMap<String, GlossaryEntry>,
+}
+
+impl Glossary {
+    pub fn load_from_json_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let raw = vfs::read_to_string(path)
+            .map_err(|err| format!("failed to read glossary file {}: {err}", path.display()))?;
+
+        let entries: HashMap<String, GlossaryEntry> = serde_json::from_str(&raw)
+            .map_err(|err| format!("failed to parse glossary json {}: {err}", path.display()))?;
+
+        Ok(Self { entries })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&GlossaryEntry> {
+        self.entries.get(key)
+    }
+}
@@ -0,0 +1,370 @@
+use winit::{
+    event::MouseButton,
+    keyboard::{KeyCode, ModifiersState},
+};
+
+use crate::{
+    achievements::AchievementManager,
+    dialogue_ui::{DialogueUi, UiCommand},
+    flags::FlagStore,
+    game_frame::{self, FrameUpdate, GameFrame},
+    input::{Action, ActionMap, InputState},
+    scene_script::{
+        SIMULATION_TICK, SceneRunner, SceneScript, ScriptContext, ScriptSignal, SignalRoutes,
+    },
+    scripts,
+    stats::StatsManager,
+    tex::Tex,
+};
+
+// Size the UI lays out for; nothing is presented.
+const SCREEN_SIZE: [u32; 2] = [1280, 720];
+const SURFACE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+
+// Runs the game loop without a window or GPU, for story-flow tests: scripts, the dialogue
+// UI, stats and achievements advance as in the app, with input injected by the caller.
+// Rendering goes to wgpu's no-op backend, there is no audio, and nothing is read from or
+// written to the user data directory.
+#[allow(dead_code)]
+pub struct HeadlessEngine {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    tex: Tex,
+    dialogue_ui: DialogueUi,
+    scene_runner: SceneRunner,
+    achievements: AchievementManager,
+    stats: StatsManager,
+    flags: FlagStore,
+    persistent_flags: FlagStore,
+    input: InputState,
+    action_map: ActionMap,
+    signal_routes: SignalRoutes,
+    tick_accumulator: f32,
+    started: bool,
+}
+
+#[allow(dead_code)]
+impl HeadlessEngine {
+    // The achievements catalog is the built-in one, with no progress.
+    pub fn new(scripts: Vec<Box<dyn SceneScript>>) -> Result<Self, String> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::NOOP,
+            backend_options: wgpu::BackendOptions {
+                noop: wgpu::NoopBackendOptions { enable: true },
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .map_err(|err| format!("failed to create the no-op GPU adapter: {err}"))?;
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()))
+                .map_err(|err| format!("failed to open the no-op GPU device: {err}"))?;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: SURFACE_FORMAT,
+            width: SCREEN_SIZE[0],
+            height: SCREEN_SIZE[1],
+            present_mode: wgpu::PresentMode::Fifo,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: Vec::new(),
+        };
+        let tex = Tex::init(&config, &adapter, &device, &queue);
+        let mut dialogue_ui = DialogueUi::headless(&device, SURFACE_FORMAT);
+        dialogue_ui.set_main_menu_enabled(false);
+        let achievements = AchievementManager::from_definitions(
+            scripts::achievements_catalog::create_all_achievements(),
+        )?;
+
+        Ok(Self {
+            device,
+            queue,
+            tex,
+            dialogue_ui,
+            scene_runner: SceneRunner::with_scripts(scripts),
+            achievements,
+            stats: StatsManager::default(),
+            flags: FlagStore::default(),
+            persistent_flags: FlagStore::default(),
+            input: InputState::default(),
+            action_map: ActionMap::default(),
+            signal_routes: SignalRoutes::default(),
+            tick_accumulator: 0.0,
+            started: false,
+        })
+    }
+
+    pub fn with_achievements(mut self, achievements: AchievementManager) -> Self {
+        self.achievements = achievements;
+        self
+    }
+
+    pub fn with_flags(mut self, flags: FlagStore) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn with_persistent_flags(mut self, persistent_flags: FlagStore) -> Self {
+        self.persistent_flags = persistent_flags;
+        self
+    }
+
+    // One frame of `dt` seconds through the same update as the app; the first also starts
+    // the scripts. Script errors end the run instead of panicking.
+    pub fn step(&mut self, dt: f32) -> Result<UiCommand, String> {
+        self.input.begin_frame();
+        game_frame::set_input_contexts(&mut self.action_map, &self.dialogue_ui, true);
+
+        if !self.started {
+            let mut script_context = ScriptContext {
+                device: &self.device,
+                queue: &self.queue,
+                tex: &mut self.tex,
+                dialogue_ui: &mut self.dialogue_ui,
+                achievements: &mut self.achievements,
+                stats: &mut self.stats,
+                flags: &mut self.flags,
+                persistent_flags: &mut self.persistent_flags,
+                audio: None,
+                input: &self.input,
+            };
+            self.scene_runner.update(0.0, &mut script_context)?;
+            self.started = true;
+        }
+
+        let mut frame = GameFrame {
+            device: &self.device,
+            queue: &self.queue,
+            tex: &mut self.tex,
+            dialogue_ui: &mut self.dialogue_ui,
+            scene_runner: Some(&mut self.scene_runner),
+            achievements: &mut self.achievements,
+            stats: &mut self.stats,
+            flags: &mut self.flags,
+            persistent_flags: &mut self.persistent_flags,
+            audio: None,
+            input: &mut self.input,
+            action_map: &self.action_map,
+            signal_routes: &self.signal_routes,
+            in_game: true,
+        };
+        let FrameUpdate { dt, paused, .. } = frame.update(&mut self.tick_accumulator, dt)?;
+
+        let raw_input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(SCREEN_SIZE[0] as f32, SCREEN_SIZE[1] as f32),
+            )),
+            ..Default::default()
+        };
+        let ui_command = frame.dialogue_ui.run_headless(raw_input, dt, None);
+        frame.after_ui(ui_command, paused);
+
+        self.input.end_frame();
+        Ok(ui_command)
+    }
+
+    // Steps in simulation ticks until `seconds` have passed.
+    pub fn run_for(&mut self, seconds: f32) -> Result<(), String> {
+        let mut elapsed = 0.0;
+        while elapsed < seconds {
+            self.step(SIMULATION_TICK)?;
+            elapsed += SIMULATION_TICK;
+        }
+        Ok(())
+    }
+
+    // Steps until `done` holds or `max_seconds` pass; returns whether it held.
+    pub fn run_until(
+        &mut self,
+        max_seconds: f32,
+        mut done: impl FnMut(&Self) -> bool,
+    ) -> Result<bool, String> {
+        let mut elapsed = 0.0;
+        while !done(self) {
+            if elapsed >= max_seconds {
+                return Ok(false);
+            }
+            self.step(SIMULATION_TICK)?;
+            elapsed += SIMULATION_TICK;
+        }
+        Ok(true)
+    }
+
+    // Held until `release_key`; the next step sees it as just pressed.
+    pub fn press_key(&mut self, key: KeyCode) {
+        self.input.press_key(key);
+    }
+
+    pub fn release_key(&mut self, key: KeyCode) {
+        self.input.release_key(key);
+    }
+
+    pub fn press_mouse_button(&mut self, button: MouseButton) {
+        self.input.press_mouse_button(button);
+    }
+
+    pub fn release_mouse_button(&mut self, button: MouseButton) {
+        self.input.release_mouse_button(button);
+    }
+
+    // Taps the first key bound to `action` over two steps of `dt`.
+    pub fn tap(&mut self, action: Action, dt: f32) -> Result<(), String> {
+        let Some(chord) = self.action_map.keys(action).first().copied() else {
+            return Err(format!("no key is bound to {action:?}"));
+        };
+        let mut modifiers = ModifiersState::empty();
+        modifiers.set(ModifiersState::CONTROL, chord.ctrl);
+        modifiers.set(ModifiersState::SHIFT, chord.shift);
+        modifiers.set(ModifiersState::ALT, chord.alt);
+        self.input.set_modifiers(modifiers);
+        self.input.press_key(chord.key);
+        self.step(dt)?;
+        self.input.release_key(chord.key);
+        self.input.set_modifiers(ModifiersState::empty());
+        self.step(dt)?;
+        Ok(())
+    }
+
+    // Types into the open input prompt and submits it; the answer lands on the next step.
+    pub fn submit_text(&mut self, text: &str) {
+        self.input.submit_text_entry(text);
+    }
+
+    pub fn send_signal(&mut self, signal: ScriptSignal) {
+        self.scene_runner.send_signal(signal);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.scene_runner.is_finished()
+    }
+
+    pub fn dialogue_ui(&self) -> &DialogueUi {
+        &self.dialogue_ui
+    }
+
+    pub fn dialogue_ui_mut(&mut self) -> &mut DialogueUi {
+        &mut self.dialogue_ui
+    }
+
+    pub fn scene_runner(&self) -> &SceneRunner {
+        &self.scene_runner
+    }
+
+    pub fn achievements(&self) -> &AchievementManager {
+        &self.achievements
+    }
+
+    pub fn stats(&self) -> &StatsManager {
+        &self.stats
+    }
+
+    pub fn flags(&self) -> &FlagStore {
+        &self.flags
+    }
+
+    pub fn persistent_flags(&self) -> &FlagStore {
+        &self.persistent_flags
+    }
+
+    pub fn tex(&self) -> &Tex {
+        &self.tex
+    }
+
+    pub fn action_map_mut(&mut self) -> &mut ActionMap {
+        &mut self.action_map
+    }
+
+    pub fn signal_routes_mut(&mut self) -> &mut SignalRoutes {
+        &mut self.signal_routes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeadlessEngine;
+    use crate::{
+        achievements::{AchievementDefinition, AchievementManager},
+        game_object::DialogueBoxObject,
+        input::Action,
+        scene_script::{SIMULATION_TICK, TimelineScript, ask_input, spawn, wait},
+        stats::Stat,
+    };
+
+    fn introduction_achievement() -> AchievementDefinition {
+        AchievementDefinition {
+            id: "introduced".to_owned(),
+            name: "Introduced".to_owned(),
+            description: "Tell Ajzakun your name.".to_owned(),
+            trigger: None,
+            category: None,
+            rarity: None,
+            chain: None,
+            tier: None,
+            goal: None,
+            threshold: None,
+            condition: Some("flags.name == \"Aiko\" && stats.choices_made >= 1".to_owned()),
+            hidden: false,
+            points: None,
+            icon: None,
+            locked_icon: None,
+        }
+    }
+
+    #[test]
+    fn story_flow_reaches_the_end_through_advance_and_a_prompt() {
+        let timeline = TimelineScript::new(vec![
+            spawn(DialogueBoxObject::new("Hello there.", "Ajzakun").with_id("line")),
+            // Only skipped by the player, never waited out in this test.
+            wait(600.0),
+            ask_input("name", "What is your name?"),
+            spawn(DialogueBoxObject::new("Nice to meet you, {$name}.", "Ajzakun").with_id("line")),
+        ]);
+        let achievements = AchievementManager::from_definitions(vec![introduction_achievement()])
+            .expect("valid achievement");
+        let mut engine = HeadlessEngine::new(vec![Box::new(timeline)])
+            .expect("headless engine")
+            .with_achievements(achievements);
+
+        // The first step starts the timeline; Advance only skips once the line has finished
+        // typing.
+        engine.step(SIMULATION_TICK).unwrap();
+        assert!(engine.dialogue_ui().has_active_typewriter_animation());
+        assert!(
+            engine
+                .run_until(5.0, |engine| engine.dialogue_ui().can_skip_wait())
+                .unwrap()
+        );
+        assert!(engine.dialogue_ui().has_visible_dialogue());
+        assert!(!engine.dialogue_ui().is_awaiting_text_input());
+        assert!(!engine.is_finished());
+
+        engine.tap(Action::Advance, SIMULATION_TICK).unwrap();
+        assert!(
+            engine
+                .run_until(1.0, |engine| engine.dialogue_ui().is_awaiting_text_input())
+                .unwrap()
+        );
+        assert!(!engine.achievements().is_unlocked("introduced"));
+
+        engine.submit_text("Aiko");
+        assert!(
+            engine
+                .run_until(5.0, |engine| engine.is_finished())
+                .unwrap()
+        );
+
+        assert_eq!(engine.flags().get("name"), Some("Aiko"));
+        assert_eq!(engine.stats().value(Stat::ChoicesMade), 1);
+        assert_eq!(engine.stats().value(Stat::LinesRead), 2);
+        assert_eq!(engine.stats().value(Stat::ScenesCompleted), 1);
+        assert!(engine.achievements().is_unlocked("introduced"));
+        assert_eq!(
+            engine.dialogue_ui().visible_dialogue_texts(),
+            ["Nice to meet you, Aiko."]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use web_time::Instant;
+
+// Modification-time polling is plenty for hand-edited data files.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Reports changes to a data file so dev builds can reload it without a restart.
+pub struct FileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_poll: Instant,
+}
+
+impl FileWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let modified = modified_time(&path);
+        Self {
+            path,
+            modified,
+            last_poll: Instant::now(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // When `poll` next looks at the file.
+    pub fn next_poll(&self) -> Instant {
+        self.last_poll + POLL_INTERVAL
+    }
+
+    // True once per change; checks the file at most every `POLL_INTERVAL`.
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+
+        let modified = modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -0,0 +1,174 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use crate::vfs;
+
+const PLACEHOLDER_SIZE: usize = 64;
+
+struct DecodedIcon {
+    path: String,
+    result: Result<egui::ColorImage, String>,
+}
+
+enum IconState {
+    Loading,
+    Ready(egui::TextureHandle),
+    // Logged once; the placeholder stands in from then on.
+    Failed,
+}
+
+// Achievement icons by path. Files are decoded on a background thread and uploaded as
+// egui textures by `update`, so drawing never waits on image decode; until an icon is
+// ready, or when it fails to load, the shared placeholder is returned.
+pub struct IconCache {
+    // None when the decoder thread could not start (always on wasm32); icons are then
+    // decoded on first use instead.
+    jobs: Option<Sender<String>>,
+    done: Option<Receiver<DecodedIcon>>,
+    icons: RefCell<HashMap<String, IconState>>,
+    placeholder: RefCell<Option<egui::TextureHandle>>,
+}
+
+impl Default for IconCache {
+    fn default() -> Self {
+        let (jobs, job_queue) = mpsc::channel::<String>();
+        let (done_sender, done) = mpsc::channel();
+        let spawned = thread::Builder::new()
+            .name("icon-loader".to_owned())
+            .spawn(move || {
+                // Ends when the cache drops its job sender.
+                for path in job_queue {
+                    let result = decode_icon(&path);
+                    if done_sender.send(DecodedIcon { path, result }).is_err() {
+                        break;
+                    }
+                }
+            });
+        let (jobs, done) = match spawned {
+            Ok(_) => (Some(jobs), Some(done)),
+            Err(err) => {
+                log::error!("failed to start icon loader thread: {err}");
+                (None, None)
+            }
+        };
+
+        Self {
+            jobs,
+            done,
+            icons: RefCell::new(HashMap::new()),
+            placeholder: RefCell::new(None),
+        }
+    }
+}
+
+impl IconCache {
+    // Uploads icons decoded since the last frame; call once per frame before drawing.
+    pub fn update(&self, ctx: &egui::Context) {
+        let Some(done) = self.done.as_ref() else {
+            return;
+        };
+
+        let mut icons = self.icons.borrow_mut();
+        let mut uploaded = false;
+        for decoded in done.try_iter() {
+            let state = match decoded.result {
+                Ok(image) => {
+                    uploaded = true;
+                    IconState::Ready(ctx.load_texture(
+                        &decoded.path,
+                        image,
+                        egui::TextureOptions::LINEAR,
+                    ))
+                }
+                Err(err) => {
+                    log::warn!("{err}");
+                    IconState::Failed
+                }
+            };
+            icons.insert(decoded.path, state);
+        }
+        if uploaded {
+            ctx.request_repaint();
+        }
+    }
+
+    // Texture for `path`, queuing the decode on first use.
+    pub fn icon(&self, ctx: &egui::Context, path: &str) -> egui::TextureHandle {
+        let mut icons = self.icons.borrow_mut();
+        match icons.get(path) {
+            Some(IconState::Ready(texture)) => return texture.clone(),
+            Some(IconState::Loading | IconState::Failed) => {}
+            None => {
+                let state = match self.jobs.as_ref() {
+                    Some(jobs) if jobs.send(path.to_owned()).is_ok() => IconState::Loading,
+                    Some(_) => IconState::Failed,
+                    None => match decode_icon(path) {
+                        Ok(image) => {
+                            let texture =
+                                ctx.load_texture(path, image, egui::TextureOptions::LINEAR);
+                            icons.insert(path.to_owned(), IconState::Ready(texture.clone()));
+                            return texture;
+                        }
+                        Err(err) => {
+                            log::warn!("{err}");
+                            IconState::Failed
+                        }
+                    },
+                };
+                icons.insert(path.to_owned(), state);
+            }
+        }
+        drop(icons);
+
+        self.placeholder(ctx)
+    }
+
+    pub fn placeholder(&self, ctx: &egui::Context) -> egui::TextureHandle {
+        self.placeholder
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                ctx.load_texture(
+                    "achievement_icon_placeholder",
+                    placeholder_image(),
+                    egui::TextureOptions::LINEAR,
+                )
+            })
+            .clone()
+    }
+}
+
+fn decode_icon(path: &str) -> Result<egui::ColorImage, String> {
+    let image = vfs::read_image(path)
+        .map_err(|err| format!("failed to load achievement icon '{path}': {err}"))?;
+    let rgba = image.to_rgba8();
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(size, &rgba))
+}
+
+// Dark tile with a lighter frame, neutral enough to sit under any theme.
+fn placeholder_image() -> egui::ColorImage {
+    const BORDER: usize = 4;
+
+    let mut image = egui::ColorImage::filled(
+        [PLACEHOLDER_SIZE, PLACEHOLDER_SIZE],
+        egui::Color32::TRANSPARENT,
+    );
+    for y in 0..PLACEHOLDER_SIZE {
+        for x in 0..PLACEHOLDER_SIZE {
+            let edge = x
+                .min(y)
+                .min(PLACEHOLDER_SIZE - 1 - x)
+                .min(PLACEHOLDER_SIZE - 1 - y);
+            image.pixels[y * PLACEHOLDER_SIZE + x] = if edge < BORDER {
+                egui::Color32::from_rgb(92, 104, 116)
+            } else {
+                egui::Color32::from_rgb(38, 45, 53)
+            };
+        }
+    }
+    image
+}
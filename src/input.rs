@@ -1,14 +1,262 @@
-use std::collections::{HashSet, VecDeque};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::Path,
+    time::Duration,
+};
+use web_time::Instant;
 
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
 use winit::{
-    event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
-    keyboard::{KeyCode, PhysicalKey},
+    event::{DeviceEvent, ElementState, Ime, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+use crate::vfs;
+
+// Relative to `paths::data_dir()`.
+pub const DEFAULT_KEYBINDINGS_PATH: &str = "keybindings.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Action {
-    SkipWait,
+    // Skip the typewriter or continue to the next line.
+    Advance,
     Exit,
+    Auto,
+    // Held: reveal text instantly and advance lines quickly.
+    FastForward,
+    ToggleBacklog,
+    QuickSave,
+    QuickLoad,
+    HideUi,
+    Pause,
+    ToggleFullscreen,
+    ToggleDebugOverlay,
+    // Only does something when profiling is on (`--debug`).
+    ToggleProfiler,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::Advance,
+        Action::Exit,
+        Action::Auto,
+        Action::FastForward,
+        Action::ToggleBacklog,
+        Action::QuickSave,
+        Action::QuickLoad,
+        Action::HideUi,
+        Action::Pause,
+        Action::ToggleFullscreen,
+        Action::ToggleDebugOverlay,
+        Action::ToggleProfiler,
+    ];
+}
+
+// Layers of input handling ordered by priority: while several are active, only
+// actions allowed in the highest one fire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InputContext {
+    Gameplay,
+    Dialogue,
+    Menu,
+    #[allow(dead_code)]
+    Console,
+}
+
+impl Action {
+    pub const fn allowed_in(self, context: InputContext) -> bool {
+        use InputContext::{Console, Dialogue, Gameplay, Menu};
+        match self {
+            Action::Exit => true,
+            Action::Advance | Action::QuickSave | Action::QuickLoad | Action::HideUi => {
+                matches!(context, Gameplay | Dialogue)
+            }
+            Action::Auto | Action::FastForward => matches!(context, Dialogue),
+            // Also allowed in menus so the same key closes the backlog or resumes.
+            Action::ToggleBacklog | Action::Pause => matches!(context, Gameplay | Dialogue | Menu),
+            Action::ToggleFullscreen | Action::ToggleDebugOverlay | Action::ToggleProfiler => {
+                !matches!(context, Console)
+            }
+        }
+    }
+}
+
+// Continuous actions read with ActionMap::axis()/axis_2d().
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[allow(dead_code)]
+pub enum AxisAction {
+    MoveX,
+    MoveY,
+    LookX,
+    LookY,
+}
+
+impl AxisAction {
+    #[allow(dead_code)]
+    pub const ALL: [AxisAction; 4] = [
+        AxisAction::MoveX,
+        AxisAction::MoveY,
+        AxisAction::LookX,
+        AxisAction::LookY,
+    ];
+}
+
+// Stick values inside this magnitude are treated as centered.
+const STICK_DEADZONE: f32 = 0.15;
+
+// Second click of a double-click must land within this time and distance of the first.
+const DOUBLE_CLICK_WINDOW: Duration = Duration::from_millis(350);
+const DOUBLE_CLICK_MAX_DISTANCE: f32 = 6.0;
+
+// Line-based wheels are converted to pixels so both kinds of device scroll alike.
+const SCROLL_LINE_PIXELS: f32 = 24.0;
+// Rate at which pending scroll is released by the smoothed delta, per second.
+const SCROLL_SMOOTHING: f32 = 18.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AxisSource {
+    // -1.0 while `negative` is held, +1.0 while `positive` is held.
+    Keys {
+        negative: KeyCode,
+        positive: KeyCode,
+    },
+    // Relative mouse motion of the current frame in device units times `sensitivity`.
+    MouseX {
+        sensitivity: f32,
+    },
+    MouseY {
+        sensitivity: f32,
+    },
+    // Raw controller axis in -1.0..=1.0, fed through InputState::set_stick_axis().
+    Stick {
+        axis: u32,
+    },
+}
+
+pub const fn is_modifier_key(key: KeyCode) -> bool {
+    matches!(
+        key,
+        KeyCode::ControlLeft
+            | KeyCode::ControlRight
+            | KeyCode::ShiftLeft
+            | KeyCode::ShiftRight
+            | KeyCode::AltLeft
+            | KeyCode::AltRight
+            | KeyCode::SuperLeft
+            | KeyCode::SuperRight
+    )
+}
+
+// A key plus the exact Ctrl/Shift/Alt state it needs, so Ctrl+S and plain S stay distinct.
+// Bindings of a modifier key itself ignore the modifier state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "KeyChordRepr")]
+pub struct KeyChord {
+    pub key: KeyCode,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ctrl: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub shift: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub alt: bool,
+}
+
+// Keybinding files may list plain key names as well as chord objects.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyChordRepr {
+    Key(KeyCode),
+    Chord {
+        key: KeyCode,
+        #[serde(default)]
+        ctrl: bool,
+        #[serde(default)]
+        shift: bool,
+        #[serde(default)]
+        alt: bool,
+    },
+}
+
+impl From<KeyChordRepr> for KeyChord {
+    fn from(value: KeyChordRepr) -> Self {
+        match value {
+            KeyChordRepr::Key(key) => Self::new(key),
+            KeyChordRepr::Chord {
+                key,
+                ctrl,
+                shift,
+                alt,
+            } => Self {
+                key,
+                ctrl,
+                shift,
+                alt,
+            },
+        }
+    }
+}
+
+impl KeyChord {
+    pub const fn new(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    pub const fn with_ctrl(mut self) -> Self {
+        self.ctrl = true;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub const fn with_shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    pub const fn with_alt(mut self) -> Self {
+        self.alt = true;
+        self
+    }
+
+    pub fn with_modifiers(key: KeyCode, modifiers: ModifiersState) -> Self {
+        if is_modifier_key(key) {
+            return Self::new(key);
+        }
+        Self {
+            key,
+            ctrl: modifiers.control_key(),
+            shift: modifiers.shift_key(),
+            alt: modifiers.alt_key(),
+        }
+    }
+
+    pub fn matches_modifiers(&self, modifiers: ModifiersState) -> bool {
+        is_modifier_key(self.key)
+            || (modifiers.control_key() == self.ctrl
+                && modifiers.shift_key() == self.shift
+                && modifiers.alt_key() == self.alt)
+    }
+}
+
+// Rebinding request produced by the settings UI and applied to the app's ActionMap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyBindingChange {
+    // Replace the key in `slot`, or append a new binding when `slot` is None.
+    Set {
+        action: Action,
+        slot: Option<usize>,
+        key: KeyChord,
+    },
+    Remove {
+        action: Action,
+        slot: usize,
+    },
+    ResetDefaults,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -22,28 +270,482 @@ pub enum InputEvent {
     MouseWheel { delta_y: f32 },
 }
 
+// Queued event with the time it arrived and the frame it belongs to, for reaction
+// timing, input buffering windows and replays.
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
+pub struct TimedInputEvent {
+    pub event: InputEvent,
+    pub at: Instant,
+    pub frame: u64,
+}
+
+// A button press kept to pair with the next one into a double-click.
+#[derive(Debug, Clone, Copy)]
+struct Click {
+    button: MouseButton,
+    at: Instant,
+    position: Option<(f32, f32)>,
+}
+
+// Actions missing from a keybindings file keep their default keys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ActionMap {
-    skip_wait_keys: Vec<KeyCode>,
-    exit_keys: Vec<KeyCode>,
+    #[serde(rename = "advance", alias = "skip_wait")]
+    advance_keys: Vec<KeyChord>,
+    #[serde(rename = "exit")]
+    exit_keys: Vec<KeyChord>,
+    #[serde(rename = "auto")]
+    auto_keys: Vec<KeyChord>,
+    #[serde(rename = "fast_forward")]
+    fast_forward_keys: Vec<KeyChord>,
+    #[serde(rename = "toggle_backlog")]
+    backlog_keys: Vec<KeyChord>,
+    #[serde(rename = "quick_save")]
+    quick_save_keys: Vec<KeyChord>,
+    #[serde(rename = "quick_load")]
+    quick_load_keys: Vec<KeyChord>,
+    #[serde(rename = "hide_ui")]
+    hide_ui_keys: Vec<KeyChord>,
+    #[serde(rename = "pause")]
+    pause_keys: Vec<KeyChord>,
+    #[serde(rename = "toggle_fullscreen")]
+    fullscreen_keys: Vec<KeyChord>,
+    #[serde(rename = "toggle_debug_overlay")]
+    debug_overlay_keys: Vec<KeyChord>,
+    #[serde(rename = "toggle_profiler")]
+    profiler_keys: Vec<KeyChord>,
+    axes: HashMap<AxisAction, Vec<AxisSource>>,
+    #[serde(skip)]
+    active_contexts: Vec<InputContext>,
 }
 
 impl Default for ActionMap {
     fn default() -> Self {
         Self {
-            skip_wait_keys: vec![KeyCode::Space, KeyCode::Enter],
-            exit_keys: vec![KeyCode::Escape],
+            advance_keys: vec![KeyChord::new(KeyCode::Space), KeyChord::new(KeyCode::Enter)],
+            exit_keys: vec![KeyChord::new(KeyCode::Escape)],
+            auto_keys: vec![KeyChord::new(KeyCode::KeyA)],
+            fast_forward_keys: vec![
+                KeyChord::new(KeyCode::ControlLeft),
+                KeyChord::new(KeyCode::ControlRight),
+            ],
+            backlog_keys: vec![KeyChord::new(KeyCode::KeyL)],
+            quick_save_keys: vec![
+                KeyChord::new(KeyCode::F5),
+                KeyChord::new(KeyCode::KeyS).with_ctrl(),
+            ],
+            quick_load_keys: vec![
+                KeyChord::new(KeyCode::F9),
+                KeyChord::new(KeyCode::KeyL).with_ctrl(),
+            ],
+            hide_ui_keys: vec![KeyChord::new(KeyCode::KeyH)],
+            pause_keys: vec![KeyChord::new(KeyCode::KeyP)],
+            fullscreen_keys: vec![
+                KeyChord::new(KeyCode::F11),
+                KeyChord::new(KeyCode::Enter).with_alt(),
+            ],
+            debug_overlay_keys: vec![KeyChord::new(KeyCode::F3)],
+            profiler_keys: vec![KeyChord::new(KeyCode::F4)],
+            axes: HashMap::from([
+                (
+                    AxisAction::MoveX,
+                    vec![
+                        AxisSource::Keys {
+                            negative: KeyCode::KeyA,
+                            positive: KeyCode::KeyD,
+                        },
+                        AxisSource::Keys {
+                            negative: KeyCode::ArrowLeft,
+                            positive: KeyCode::ArrowRight,
+                        },
+                        AxisSource::Stick { axis: 0 },
+                    ],
+                ),
+                (
+                    AxisAction::MoveY,
+                    vec![
+                        AxisSource::Keys {
+                            negative: KeyCode::KeyS,
+                            positive: KeyCode::KeyW,
+                        },
+                        AxisSource::Keys {
+                            negative: KeyCode::ArrowDown,
+                            positive: KeyCode::ArrowUp,
+                        },
+                        AxisSource::Stick { axis: 1 },
+                    ],
+                ),
+                (
+                    AxisAction::LookX,
+                    vec![
+                        AxisSource::MouseX { sensitivity: 0.1 },
+                        AxisSource::Stick { axis: 2 },
+                    ],
+                ),
+                (
+                    AxisAction::LookY,
+                    vec![
+                        AxisSource::MouseY { sensitivity: 0.1 },
+                        AxisSource::Stick { axis: 3 },
+                    ],
+                ),
+            ]),
+            active_contexts: Vec::new(),
         }
     }
 }
 
 impl ActionMap {
+    // A missing file yields the default bindings.
+    pub fn load_from_json_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        if !vfs::exists(path) {
+            return Ok(Self::default());
+        }
+
+        let raw = vfs::read_to_string(path)
+            .map_err(|err| format!("failed to read keybindings {}: {err}", path.display()))?;
+        serde_json::from_str(&raw)
+            .map_err(|err| format!("failed to parse keybindings {}: {err}", path.display()))
+    }
+
+    pub fn save_to_json_file(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            vfs::create_dir_all(parent).map_err(|err| {
+                format!(
+                    "failed to create keybindings directory {}: {err}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|err| format!("failed to serialize keybindings: {err}"))?;
+        vfs::write(path, json)
+            .map_err(|err| format!("failed to write keybindings {}: {err}", path.display()))
+    }
+
+    pub fn set_context_active(&mut self, context: InputContext, active: bool) {
+        if !active {
+            self.active_contexts.retain(|existing| *existing != context);
+        } else if !self.active_contexts.contains(&context) {
+            self.active_contexts.push(context);
+        }
+    }
+
+    // Highest-priority active context; Gameplay when none is set.
+    pub fn active_context(&self) -> InputContext {
+        self.active_contexts
+            .iter()
+            .max()
+            .copied()
+            .unwrap_or(InputContext::Gameplay)
+    }
+
     pub fn just_pressed(&self, action: Action, input: &InputState) -> bool {
-        let keys = match action {
-            Action::SkipWait => &self.skip_wait_keys,
+        action.allowed_in(self.active_context())
+            && self
+                .keys(action)
+                .iter()
+                .any(|chord| input.was_chord_just_pressed(*chord))
+    }
+
+    // Like just_pressed, but also true on synthetic repeats while a bound chord is held.
+    #[allow(dead_code)]
+    pub fn pressed_or_repeated(&self, action: Action, input: &InputState) -> bool {
+        action.allowed_in(self.active_context())
+            && self.keys(action).iter().any(|chord| {
+                input.was_key_pressed_or_repeated(chord.key)
+                    && chord.matches_modifiers(input.modifiers())
+            })
+    }
+
+    // Key and stick input is combined and clamped to -1.0..=1.0; mouse motion is added unclamped.
+    #[allow(dead_code)]
+    pub fn axis(&self, action: AxisAction, input: &InputState) -> f32 {
+        let mut digital = 0.0;
+        let mut mouse = 0.0;
+        for source in self.axis_sources(action) {
+            match *source {
+                AxisSource::Keys { negative, positive } => {
+                    if input.is_key_down(positive) {
+                        digital += 1.0;
+                    }
+                    if input.is_key_down(negative) {
+                        digital -= 1.0;
+                    }
+                }
+                AxisSource::MouseX { sensitivity } => mouse += input.mouse_delta().x * sensitivity,
+                AxisSource::MouseY { sensitivity } => mouse += input.mouse_delta().y * sensitivity,
+                AxisSource::Stick { axis } => digital += input.stick_axis(axis),
+            }
+        }
+        digital.clamp(-1.0, 1.0) + mouse
+    }
+
+    #[allow(dead_code)]
+    pub fn axis_2d(&self, x: AxisAction, y: AxisAction, input: &InputState) -> Vec2 {
+        Vec2::new(self.axis(x, input), self.axis(y, input))
+    }
+
+    #[allow(dead_code)]
+    pub fn axis_sources(&self, action: AxisAction) -> &[AxisSource] {
+        self.axes.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    #[allow(dead_code)]
+    pub fn set_axis_sources(&mut self, action: AxisAction, sources: Vec<AxisSource>) {
+        self.axes.insert(action, sources);
+    }
+
+    pub fn is_down(&self, action: Action, input: &InputState) -> bool {
+        action.allowed_in(self.active_context())
+            && self
+                .keys(action)
+                .iter()
+                .any(|chord| input.is_chord_down(*chord))
+    }
+
+    // Not gated by context, so a hold that started before a menu opened still ends.
+    #[allow(dead_code)]
+    pub fn just_released(&self, action: Action, input: &InputState) -> bool {
+        self.keys(action)
+            .iter()
+            .any(|chord| input.was_key_just_released(chord.key))
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyChord] {
+        match action {
+            Action::Advance => &self.advance_keys,
             Action::Exit => &self.exit_keys,
+            Action::Auto => &self.auto_keys,
+            Action::FastForward => &self.fast_forward_keys,
+            Action::ToggleBacklog => &self.backlog_keys,
+            Action::QuickSave => &self.quick_save_keys,
+            Action::QuickLoad => &self.quick_load_keys,
+            Action::HideUi => &self.hide_ui_keys,
+            Action::Pause => &self.pause_keys,
+            Action::ToggleFullscreen => &self.fullscreen_keys,
+            Action::ToggleDebugOverlay => &self.debug_overlay_keys,
+            Action::ToggleProfiler => &self.profiler_keys,
+        }
+    }
+
+    fn keys_mut(&mut self, action: Action) -> &mut Vec<KeyChord> {
+        match action {
+            Action::Advance => &mut self.advance_keys,
+            Action::Exit => &mut self.exit_keys,
+            Action::Auto => &mut self.auto_keys,
+            Action::FastForward => &mut self.fast_forward_keys,
+            Action::ToggleBacklog => &mut self.backlog_keys,
+            Action::QuickSave => &mut self.quick_save_keys,
+            Action::QuickLoad => &mut self.quick_load_keys,
+            Action::HideUi => &mut self.hide_ui_keys,
+            Action::Pause => &mut self.pause_keys,
+            Action::ToggleFullscreen => &mut self.fullscreen_keys,
+            Action::ToggleDebugOverlay => &mut self.debug_overlay_keys,
+            Action::ToggleProfiler => &mut self.profiler_keys,
+        }
+    }
+
+    pub fn bindings(&self) -> Vec<(Action, Vec<KeyChord>)> {
+        Action::ALL
+            .iter()
+            .map(|action| (*action, self.keys(*action).to_vec()))
+            .collect()
+    }
+
+    // Adds `key` as an extra binding for `action`.
+    #[allow(dead_code)]
+    pub fn bind(&mut self, action: Action, key: KeyChord) {
+        self.apply_change(KeyBindingChange::Set {
+            action,
+            slot: None,
+            key,
+        });
+    }
+
+    #[allow(dead_code)]
+    pub fn unbind(&mut self, action: Action, key: KeyChord) {
+        self.keys_mut(action).retain(|bound| *bound != key);
+    }
+
+    // Replaces every binding of `action`; duplicate keys are dropped.
+    #[allow(dead_code)]
+    pub fn set_keys(&mut self, action: Action, keys: impl IntoIterator<Item = KeyChord>) {
+        let bound = self.keys_mut(action);
+        bound.clear();
+        for key in keys {
+            if !bound.contains(&key) {
+                bound.push(key);
+            }
+        }
+    }
+
+    pub fn apply_change(&mut self, change: KeyBindingChange) {
+        match change {
+            KeyBindingChange::Set { action, slot, key } => {
+                let keys = self.keys_mut(action);
+                match slot.filter(|slot| *slot < keys.len()) {
+                    Some(slot) => keys[slot] = key,
+                    None if !keys.contains(&key) => keys.push(key),
+                    None => {}
+                }
+                // Drop duplicates when a slot is set to a key this action already uses.
+                let mut seen = Vec::with_capacity(keys.len());
+                keys.retain(|key| {
+                    let first = !seen.contains(key);
+                    seen.push(*key);
+                    first
+                });
+            }
+            KeyBindingChange::Remove { action, slot } => {
+                let keys = self.keys_mut(action);
+                if slot < keys.len() {
+                    keys.remove(slot);
+                }
+            }
+            // Bindings only: which contexts are active stays as the frame set it.
+            KeyBindingChange::ResetDefaults => {
+                *self = Self {
+                    active_contexts: std::mem::take(&mut self.active_contexts),
+                    ..Self::default()
+                };
+            }
+        }
+    }
+}
+
+// Line of text typed while InputState is in text-entry mode.
+#[derive(Debug, Clone, Default)]
+pub struct TextEntry {
+    text: String,
+    // Caret position in characters, not bytes.
+    cursor: usize,
+    // Uncommitted IME composition, drawn at the caret but not part of `text` yet.
+    preedit: String,
+    max_chars: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextEntryResult {
+    Submitted(String),
+    Cancelled,
+}
+
+impl TextEntry {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn preedit(&self) -> &str {
+        &self.preedit
+    }
+
+    // Text with the IME composition spliced in at the caret, for display.
+    pub fn display_text(&self) -> String {
+        let mut text = self.text.clone();
+        text.insert_str(self.byte_offset(self.cursor), &self.preedit);
+        text
+    }
+
+    fn byte_offset(&self, chars: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(chars)
+            .map(|(offset, _)| offset)
+            .unwrap_or(self.text.len())
+    }
+
+    fn char_count(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn insert(&mut self, input: &str) {
+        for ch in input.chars().filter(|ch| !ch.is_control()) {
+            if self.max_chars.is_some_and(|max| self.char_count() >= max) {
+                break;
+            }
+            self.text.insert(self.byte_offset(self.cursor), ch);
+            self.cursor += 1;
+        }
+    }
+
+    fn on_ime(&mut self, ime: &Ime) {
+        match ime {
+            Ime::Preedit(text, _) => self.preedit = text.clone(),
+            Ime::Commit(text) => {
+                self.preedit.clear();
+                self.insert(text);
+            }
+            Ime::Enabled | Ime::Disabled => self.preedit.clear(),
+        }
+    }
+
+    // Enter submits and Escape cancels; other keys edit the line.
+    fn on_key(&mut self, event: &KeyEvent) -> Option<TextEntryResult> {
+        // The IME owns the keyboard while a composition is in progress.
+        if !self.preedit.is_empty() {
+            return None;
+        }
+
+        let code = match event.physical_key {
+            PhysicalKey::Code(code) => Some(code),
+            PhysicalKey::Unidentified(_) => None,
         };
+        match code {
+            Some(KeyCode::Enter | KeyCode::NumpadEnter) if !event.repeat => {
+                return Some(TextEntryResult::Submitted(self.text.clone()));
+            }
+            Some(KeyCode::Escape) if !event.repeat => return Some(TextEntryResult::Cancelled),
+            Some(KeyCode::Backspace) => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    self.text.remove(self.byte_offset(self.cursor));
+                }
+            }
+            Some(KeyCode::Delete) => {
+                if self.cursor < self.char_count() {
+                    self.text.remove(self.byte_offset(self.cursor));
+                }
+            }
+            Some(KeyCode::ArrowLeft) => self.cursor = self.cursor.saturating_sub(1),
+            Some(KeyCode::ArrowRight) => self.cursor = (self.cursor + 1).min(self.char_count()),
+            Some(KeyCode::Home) => self.cursor = 0,
+            Some(KeyCode::End) => self.cursor = self.char_count(),
+            _ => {
+                if let Some(text) = event.text.as_deref() {
+                    self.insert(text);
+                }
+            }
+        }
+        None
+    }
+}
 
-        keys.iter().any(|key| input.was_key_just_pressed(*key))
+// Synthetic key repeat: the first repeat fires after `delay`, then every `interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyRepeat {
+    pub delay: Duration,
+    pub interval: Duration,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(400),
+            interval: Duration::from_millis(50),
+        }
     }
 }
 
@@ -52,58 +754,104 @@ pub struct InputState {
     pressed_keys: HashSet<KeyCode>,
     just_pressed_keys: HashSet<KeyCode>,
     just_released_keys: HashSet<KeyCode>,
+    // Held keys and buttons whose press egui consumed; they never count as action input.
+    ui_owned_keys: HashSet<KeyCode>,
+    ui_owned_mouse_buttons: HashSet<MouseButton>,
     pressed_mouse_buttons: HashSet<MouseButton>,
     just_pressed_mouse_buttons: HashSet<MouseButton>,
     just_released_mouse_buttons: HashSet<MouseButton>,
-    events: VecDeque<InputEvent>,
+    events: VecDeque<TimedInputEvent>,
+    // Most recent player activity of any kind, including input egui consumed.
+    last_activity_at: Option<Instant>,
+    // Incremented by end_frame.
+    frame: u64,
     cursor_position: Option<(f32, f32)>,
+    // Raw mouse motion accumulated during the current frame.
+    mouse_delta: Vec2,
+    stick_axes: HashMap<u32, f32>,
+    modifiers: ModifiersState,
+    // Press times of currently held keys and buttons, for hold queries.
+    key_pressed_at: HashMap<KeyCode, Instant>,
+    mouse_pressed_at: HashMap<MouseButton, Instant>,
+    // Previous click, kept until it expires or forms a double-click.
+    last_click: Option<Click>,
+    double_clicked_buttons: HashSet<MouseButton>,
+    // Wheel movement in pixels: this frame's raw total, and what smoothing has yet to release.
+    scroll_raw: Vec2,
+    scroll_pending: Vec2,
+    scroll_delta: Vec2,
+    last_frame_at: Option<Instant>,
+    key_repeat: KeyRepeat,
+    // When each held key fires its next synthetic repeat.
+    next_repeat_at: HashMap<KeyCode, Instant>,
+    // Repeats that fired since the previous begin_frame.
+    key_repeats: HashMap<KeyCode, u32>,
+    // While set, key presses edit this line instead of reaching actions.
+    text_entry: Option<TextEntry>,
+    text_entry_result: Option<TextEntryResult>,
 }
 
 impl InputState {
-    pub fn on_window_event(&mut self, event: &WindowEvent) -> bool {
-        match event {
-            WindowEvent::KeyboardInput { event, .. } => {
-                let PhysicalKey::Code(code) = event.physical_key else {
-                    return false;
-                };
+    // With `ui_consumed` presses are still tracked as held (so they are not re-reported on
+    // repeat), but never count as pressed, held or released for actions.
+    pub fn on_window_event(&mut self, event: &WindowEvent, ui_consumed: bool) -> bool {
+        if matches!(
+            event,
+            WindowEvent::KeyboardInput { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::CursorMoved { .. }
+                | WindowEvent::MouseWheel { .. }
+                | WindowEvent::Ime(_)
+        ) {
+            self.last_activity_at = Some(Instant::now());
+        }
 
-                match event.state {
-                    ElementState::Pressed => {
-                        // "just pressed" only on first press (ignore key repeat).
-                        if self.pressed_keys.insert(code) {
-                            self.just_pressed_keys.insert(code);
-                            self.events.push_back(InputEvent::KeyPressed(code));
-                            return true;
-                        }
-                    }
-                    ElementState::Released => {
-                        self.pressed_keys.remove(&code);
-                        self.just_released_keys.insert(code);
-                        self.events.push_back(InputEvent::KeyReleased(code));
-                        return true;
-                    }
+        if let Some(entry) = self.text_entry.as_mut() {
+            match event {
+                WindowEvent::Ime(ime) => {
+                    entry.on_ime(ime);
+                    return true;
                 }
-            }
-            WindowEvent::MouseInput { state, button, .. } => match state {
-                ElementState::Pressed => {
-                    if self.pressed_mouse_buttons.insert(*button) {
-                        self.just_pressed_mouse_buttons.insert(*button);
-                        self.events.push_back(InputEvent::MousePressed(*button));
-                        return true;
+                WindowEvent::KeyboardInput { event, .. }
+                    if event.state == ElementState::Pressed =>
+                {
+                    if let Some(result) = entry.on_key(event) {
+                        self.text_entry = None;
+                        self.text_entry_result = Some(result);
                     }
+                    return true;
                 }
-                ElementState::Released => {
-                    self.pressed_mouse_buttons.remove(button);
-                    self.just_released_mouse_buttons.insert(*button);
-                    self.events.push_back(InputEvent::MouseReleased(*button));
+                // Releases go with their presses; only keys held from before the entry
+                // still release normally.
+                WindowEvent::KeyboardInput { event, .. }
+                    if !matches!(
+                        event.physical_key,
+                        PhysicalKey::Code(code) if self.pressed_keys.contains(&code)
+                    ) =>
+                {
                     return true;
                 }
-            },
+                _ => {}
+            }
+        }
+
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                let PhysicalKey::Code(code) = event.physical_key else {
+                    return false;
+                };
+                return self.on_key(code, event.state, ui_consumed);
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                return self.on_mouse_button(*button, *state, ui_consumed);
+            }
             WindowEvent::CursorMoved { position, .. } => {
                 let pos = (position.x as f32, position.y as f32);
                 self.cursor_position = Some(pos);
-                self.events
-                    .push_back(InputEvent::CursorMoved { x: pos.0, y: pos.1 });
+                self.push_event(InputEvent::CursorMoved { x: pos.0, y: pos.1 });
                 return true;
             }
             WindowEvent::MouseWheel { delta, .. } => {
@@ -111,7 +859,16 @@ impl InputState {
                     MouseScrollDelta::LineDelta(_, y) => *y,
                     MouseScrollDelta::PixelDelta(pos) => pos.y as f32,
                 };
-                self.events.push_back(InputEvent::MouseWheel { delta_y });
+                self.push_event(InputEvent::MouseWheel { delta_y });
+                // Scrolling an egui widget must not also scroll whatever sits behind it.
+                if !ui_consumed {
+                    let pixels = match delta {
+                        MouseScrollDelta::LineDelta(x, y) => Vec2::new(*x, *y) * SCROLL_LINE_PIXELS,
+                        MouseScrollDelta::PixelDelta(pos) => Vec2::new(pos.x as f32, pos.y as f32),
+                    };
+                    self.scroll_raw += pixels;
+                    self.scroll_pending += pixels;
+                }
                 return true;
             }
             _ => {}
@@ -120,7 +877,210 @@ impl InputState {
         false
     }
 
+    // Synthetic input for runs without a window, e.g. `HeadlessEngine`; behaves like the
+    // matching window event that egui did not consume.
     #[allow(dead_code)]
+    pub fn press_key(&mut self, code: KeyCode) {
+        self.last_activity_at = Some(Instant::now());
+        self.on_key(code, ElementState::Pressed, false);
+    }
+
+    #[allow(dead_code)]
+    pub fn release_key(&mut self, code: KeyCode) {
+        self.last_activity_at = Some(Instant::now());
+        self.on_key(code, ElementState::Released, false);
+    }
+
+    #[allow(dead_code)]
+    pub fn press_mouse_button(&mut self, button: MouseButton) {
+        self.last_activity_at = Some(Instant::now());
+        self.on_mouse_button(button, ElementState::Pressed, false);
+    }
+
+    #[allow(dead_code)]
+    pub fn release_mouse_button(&mut self, button: MouseButton) {
+        self.last_activity_at = Some(Instant::now());
+        self.on_mouse_button(button, ElementState::Released, false);
+    }
+
+    #[allow(dead_code)]
+    pub fn set_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    fn on_key(&mut self, code: KeyCode, state: ElementState, ui_consumed: bool) -> bool {
+        match state {
+            ElementState::Pressed => {
+                // "just pressed" only on first press (ignore key repeat).
+                if !self.pressed_keys.insert(code) {
+                    return false;
+                }
+                if ui_consumed {
+                    self.ui_owned_keys.insert(code);
+                    return false;
+                }
+                let now = Instant::now();
+                self.key_pressed_at.insert(code, now);
+                self.next_repeat_at
+                    .insert(code, now + self.key_repeat.delay);
+                self.just_pressed_keys.insert(code);
+                self.push_event(InputEvent::KeyPressed(code));
+                true
+            }
+            ElementState::Released => {
+                // Keys pressed while text entry swallowed them were never tracked.
+                if !self.pressed_keys.remove(&code) {
+                    return false;
+                }
+                // A release that ends a consumed press is not action input either.
+                if self.ui_owned_keys.remove(&code) {
+                    return false;
+                }
+                self.key_pressed_at.remove(&code);
+                self.next_repeat_at.remove(&code);
+                self.just_released_keys.insert(code);
+                self.push_event(InputEvent::KeyReleased(code));
+                true
+            }
+        }
+    }
+
+    fn on_mouse_button(
+        &mut self,
+        button: MouseButton,
+        state: ElementState,
+        ui_consumed: bool,
+    ) -> bool {
+        match state {
+            ElementState::Pressed => {
+                if !self.pressed_mouse_buttons.insert(button) {
+                    return false;
+                }
+                if ui_consumed {
+                    self.ui_owned_mouse_buttons.insert(button);
+                    return false;
+                }
+                self.register_click(button);
+                self.just_pressed_mouse_buttons.insert(button);
+                self.push_event(InputEvent::MousePressed(button));
+                true
+            }
+            ElementState::Released => {
+                self.pressed_mouse_buttons.remove(&button);
+                if self.ui_owned_mouse_buttons.remove(&button) {
+                    return false;
+                }
+                self.mouse_pressed_at.remove(&button);
+                self.just_released_mouse_buttons.insert(button);
+                self.push_event(InputEvent::MouseReleased(button));
+                true
+            }
+        }
+    }
+
+    fn push_event(&mut self, event: InputEvent) {
+        self.events.push_back(TimedInputEvent {
+            event,
+            at: Instant::now(),
+            frame: self.frame,
+        });
+    }
+
+    fn register_click(&mut self, button: MouseButton) {
+        let now = Instant::now();
+        self.mouse_pressed_at.insert(button, now);
+
+        let is_double = self.last_click.is_some_and(|click| {
+            let close = match (click.position, self.cursor_position) {
+                (Some(a), Some(b)) => {
+                    Vec2::new(a.0 - b.0, a.1 - b.1).length() <= DOUBLE_CLICK_MAX_DISTANCE
+                }
+                _ => true,
+            };
+            click.button == button && now - click.at <= DOUBLE_CLICK_WINDOW && close
+        });
+        if is_double {
+            self.double_clicked_buttons.insert(button);
+            // A third click starts a new pair instead of chaining.
+            self.last_click = None;
+        } else {
+            self.last_click = Some(Click {
+                button,
+                at: now,
+                position: self.cursor_position,
+            });
+        }
+    }
+
+    // The window must have IME enabled (Window::set_ime_allowed) for composed input.
+    pub fn begin_text_entry(&mut self, initial: impl Into<String>, max_chars: Option<usize>) {
+        let text: String = initial.into();
+        self.text_entry = Some(TextEntry {
+            cursor: text.chars().count(),
+            text,
+            preedit: String::new(),
+            max_chars,
+        });
+        self.text_entry_result = None;
+    }
+
+    pub fn text_entry(&self) -> Option<&TextEntry> {
+        self.text_entry.as_ref()
+    }
+
+    pub fn is_text_entry_active(&self) -> bool {
+        self.text_entry.is_some()
+    }
+
+    // Drops the entry without a result, e.g. when the prompt it belongs to closes.
+    pub fn end_text_entry(&mut self) {
+        self.text_entry = None;
+        self.text_entry_result = None;
+    }
+
+    // Synthetic typing for runs without a window, e.g. `HeadlessEngine`: appends `text` to
+    // the open entry and submits it like Enter. Does nothing without an entry.
+    #[allow(dead_code)]
+    pub fn submit_text_entry(&mut self, text: &str) {
+        if let Some(mut entry) = self.text_entry.take() {
+            entry.insert(text);
+            self.text_entry_result = Some(TextEntryResult::Submitted(entry.text));
+        }
+    }
+
+    // Set once Enter or Escape ends the entry; taking it clears the result.
+    pub fn take_text_entry_result(&mut self) -> Option<TextEntryResult> {
+        self.text_entry_result.take()
+    }
+
+    // Relative mouse motion arrives as a device event, independent of the cursor.
+    pub fn on_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta } = event {
+            self.last_activity_at = Some(Instant::now());
+            self.mouse_delta += Vec2::new(delta.0 as f32, delta.1 as f32);
+        }
+    }
+
+    // Entry point for a controller backend; values are clamped and deadzoned.
+    #[allow(dead_code)]
+    pub fn set_stick_axis(&mut self, axis: u32, value: f32) {
+        let value = value.clamp(-1.0, 1.0);
+        let value = if value.abs() < STICK_DEADZONE {
+            0.0
+        } else {
+            value
+        };
+        self.stick_axes.insert(axis, value);
+    }
+
+    pub fn stick_axis(&self, axis: u32) -> f32 {
+        self.stick_axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta
+    }
+
     pub fn is_key_down(&self, key: KeyCode) -> bool {
         self.pressed_keys.contains(&key)
     }
@@ -129,26 +1089,176 @@ impl InputState {
         self.just_pressed_keys.contains(&key)
     }
 
+    pub fn was_chord_just_pressed(&self, chord: KeyChord) -> bool {
+        self.was_key_just_pressed(chord.key) && chord.matches_modifiers(self.modifiers)
+    }
+
+    // Keys held since a press egui consumed do not count, so typing never fast-forwards.
+    pub fn is_chord_down(&self, chord: KeyChord) -> bool {
+        self.is_key_down(chord.key)
+            && !self.ui_owned_keys.contains(&chord.key)
+            && chord.matches_modifiers(self.modifiers)
+    }
+
     #[allow(dead_code)]
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
     pub fn was_key_just_released(&self, key: KeyCode) -> bool {
         self.just_released_keys.contains(&key)
     }
 
+    pub fn was_mouse_just_pressed(&self, button: MouseButton) -> bool {
+        self.just_pressed_mouse_buttons.contains(&button)
+    }
+
+    #[allow(dead_code)]
+    pub fn was_double_clicked(&self, button: MouseButton) -> bool {
+        self.double_clicked_buttons.contains(&button)
+    }
+
+    // How long `key` has been held; None while it is up.
+    pub fn key_held_duration(&self, key: KeyCode) -> Option<Duration> {
+        self.key_pressed_at.get(&key).map(Instant::elapsed)
+    }
+
+    #[allow(dead_code)]
+    pub fn is_held_for(&self, key: KeyCode, duration: Duration) -> bool {
+        self.key_held_duration(key)
+            .is_some_and(|held| held >= duration)
+    }
+
+    // 0.0 when up, rising to 1.0 once `key` has been held for `duration`; drives hold-to-confirm rings.
+    #[allow(dead_code)]
+    pub fn hold_progress(&self, key: KeyCode, duration: Duration) -> f32 {
+        let Some(held) = self.key_held_duration(key) else {
+            return 0.0;
+        };
+        if duration.is_zero() {
+            return 1.0;
+        }
+        (held.as_secs_f32() / duration.as_secs_f32()).min(1.0)
+    }
+
+    pub fn mouse_held_duration(&self, button: MouseButton) -> Option<Duration> {
+        self.mouse_pressed_at.get(&button).map(Instant::elapsed)
+    }
+
+    #[allow(dead_code)]
+    pub fn is_mouse_held_for(&self, button: MouseButton, duration: Duration) -> bool {
+        self.mouse_held_duration(button)
+            .is_some_and(|held| held >= duration)
+    }
+
+    #[allow(dead_code)]
+    pub fn key_repeat(&self) -> KeyRepeat {
+        self.key_repeat
+    }
+
+    #[allow(dead_code)]
+    pub fn set_key_repeat(&mut self, repeat: KeyRepeat) {
+        self.key_repeat = repeat;
+    }
+
+    // Synthetic repeats of `key` this frame; several can fire when frames are slow.
+    #[allow(dead_code)]
+    pub fn key_repeat_count(&self, key: KeyCode) -> u32 {
+        self.key_repeats.get(&key).copied().unwrap_or(0)
+    }
+
+    // Initial press or a synthetic repeat; the usual query for scrolling lists.
+    #[allow(dead_code)]
+    pub fn was_key_pressed_or_repeated(&self, key: KeyCode) -> bool {
+        self.was_key_just_pressed(key) || self.key_repeat_count(key) > 0
+    }
+
+    // Smoothed wheel movement in pixels for this frame; positive y scrolls up.
+    #[allow(dead_code)]
+    pub fn scroll_delta(&self) -> Vec2 {
+        self.scroll_delta
+    }
+
+    // Unsmoothed wheel movement received since the previous frame.
+    #[allow(dead_code)]
+    pub fn raw_scroll_delta(&self) -> Vec2 {
+        self.scroll_raw
+    }
+
+    // Time since the player last touched the keyboard or mouse, for dimming or attract mode.
+    #[allow(dead_code)]
+    pub fn idle_duration(&self) -> Duration {
+        self.last_activity_at
+            .map_or(Duration::ZERO, |at| at.elapsed())
+    }
+
+    #[allow(dead_code)]
+    pub fn is_idle_for(&self, duration: Duration) -> bool {
+        self.idle_duration() >= duration
+    }
+
+    // Any key or mouse button pressed this frame.
+    pub fn has_any_press(&self) -> bool {
+        !self.just_pressed_keys.is_empty() || !self.just_pressed_mouse_buttons.is_empty()
+    }
+
     #[allow(dead_code)]
     pub fn cursor_position(&self) -> Option<(f32, f32)> {
         self.cursor_position
     }
 
     #[allow(dead_code)]
-    pub fn events(&self) -> &VecDeque<InputEvent> {
+    pub fn events(&self) -> &VecDeque<TimedInputEvent> {
         &self.events
     }
 
+    // Fires the synthetic repeats that came due; call before querying input each frame.
+    pub fn begin_frame(&mut self) {
+        let now = Instant::now();
+        let dt = self
+            .last_frame_at
+            .map_or(0.0, |at| (now - at).as_secs_f32().min(0.1));
+        self.last_frame_at = Some(now);
+        // Idle time counts from the first frame until the first input arrives.
+        self.last_activity_at.get_or_insert(now);
+
+        // Release a fraction of the pending scroll each frame, snapping the small tail.
+        let released = 1.0 - (-dt * SCROLL_SMOOTHING).exp();
+        self.scroll_delta = if self.scroll_pending.length() < 0.5 {
+            self.scroll_pending
+        } else {
+            self.scroll_pending * released
+        };
+        self.scroll_pending -= self.scroll_delta;
+
+        let interval = self.key_repeat.interval.max(Duration::from_millis(1));
+        self.key_repeats.clear();
+        for (key, due) in &mut self.next_repeat_at {
+            let mut count = 0;
+            while *due <= now {
+                count += 1;
+                *due += interval;
+            }
+            if count > 0 {
+                self.key_repeats.insert(*key, count);
+            }
+        }
+    }
+
     pub fn end_frame(&mut self) {
         self.just_pressed_keys.clear();
         self.just_released_keys.clear();
         self.just_pressed_mouse_buttons.clear();
         self.just_released_mouse_buttons.clear();
+        self.double_clicked_buttons.clear();
         self.events.clear();
+        self.mouse_delta = Vec2::ZERO;
+        self.scroll_raw = Vec2::ZERO;
+        self.frame += 1;
+    }
+
+    #[allow(dead_code)]
+    pub fn frame_index(&self) -> u64 {
+        self.frame
     }
 }
@@ -0,0 +1,256 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+// Optional tamper detection for player data. With a key configured, signed files start with
+// a `#hmac-sha256:<hex>` line covering the rest of the file. The key ships inside the
+// binary, so this stops casual edits, not a determined player.
+const SIGNATURE_PREFIX: &str = "#hmac-sha256:";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegrityMode {
+    // Signatures are written but never checked.
+    Off,
+    // Files failing the check still load and are reported through `take_flagged`.
+    Flag,
+    // Files failing the check count as corrupt, so loading falls back to the backup.
+    Reject,
+}
+
+struct IntegrityConfig {
+    key: Vec<u8>,
+    mode: IntegrityMode,
+}
+
+static CONFIG: OnceLock<IntegrityConfig> = OnceLock::new();
+static FLAGGED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+// Build-time opt-in: GAME_ENGINE_SAVE_KEY enables signing and GAME_ENGINE_SAVE_INTEGRITY
+// picks `flag` (default), `reject` or `off` for files that fail the check.
+pub fn configure_from_build_env() {
+    let Some(key) = option_env!("GAME_ENGINE_SAVE_KEY").filter(|key| !key.is_empty()) else {
+        return;
+    };
+    let mode = match option_env!("GAME_ENGINE_SAVE_INTEGRITY") {
+        Some("reject") => IntegrityMode::Reject,
+        Some("off") => IntegrityMode::Off,
+        _ => IntegrityMode::Flag,
+    };
+    let _ = CONFIG.set(IntegrityConfig {
+        key: key.as_bytes().to_vec(),
+        mode,
+    });
+}
+
+// Prepends the signature line; unchanged when no key is configured.
+pub fn sign(contents: &str) -> String {
+    match CONFIG.get() {
+        Some(config) => format!(
+            "{SIGNATURE_PREFIX}{}\n{contents}",
+            to_hex(&hmac_sha256(&config.key, contents.as_bytes()))
+        ),
+        None => contents.to_owned(),
+    }
+}
+
+// Checks and strips the signature line, returning the signed body. Without a key the line
+// is only stripped, so files stay readable after signing is turned off.
+pub fn verify<'a>(path: &Path, raw: &'a str) -> Result<&'a str, String> {
+    let (signature, body) = match raw.strip_prefix(SIGNATURE_PREFIX) {
+        Some(rest) => {
+            let (signature, body) = rest.split_once('\n').unwrap_or((rest, ""));
+            (Some(signature.trim()), body)
+        }
+        None => (None, raw),
+    };
+
+    let Some(config) = CONFIG
+        .get()
+        .filter(|config| config.mode != IntegrityMode::Off)
+    else {
+        return Ok(body);
+    };
+    let expected = to_hex(&hmac_sha256(&config.key, body.as_bytes()));
+    if signature.is_some_and(|signature| constant_time_eq(signature, &expected)) {
+        return Ok(body);
+    }
+
+    let problem = if signature.is_some() {
+        "signature mismatch"
+    } else {
+        "missing signature"
+    };
+    match config.mode {
+        IntegrityMode::Reject => Err(format!("{problem} in {}", path.display())),
+        _ => {
+            log::warn!("{problem} in {}, loading it anyway", path.display());
+            if let Ok(mut flagged) = FLAGGED.lock() {
+                flagged.push(path.to_path_buf());
+            }
+            Ok(body)
+        }
+    }
+}
+
+// Files loaded despite a failed check since the last call.
+pub fn take_flagged() -> Vec<PathBuf> {
+    FLAGGED
+        .lock()
+        .map(|mut flagged| flagged.drain(..).collect())
+        .unwrap_or_default()
+}
+
+fn constant_time_eq(left: &str, right: &str) -> bool {
+    left.len() == right.len()
+        && left
+            .bytes()
+            .zip(right.bytes())
+            .fold(0u8, |diff, (left, right)| diff | (left ^ right))
+            == 0
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+// RFC 2104 with SHA-256.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+
+    let mut block_key = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block_key[..32].copy_from_slice(&sha256(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = block_key.map(|byte| byte ^ 0x36).to_vec();
+    inner.extend_from_slice(message);
+    let mut outer = block_key.map(|byte| byte ^ 0x5c).to_vec();
+    outer.extend_from_slice(&sha256(&inner));
+    sha256(&outer)
+}
+
+// FIPS 180-4.
+fn sha256(message: &[u8]) -> [u8; 32] {
+    const K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4,
+        0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe,
+        0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f,
+        0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7,
+        0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc,
+        0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
+        0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116,
+        0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7,
+        0xc67178f2,
+    ];
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((message.len() as u64) * 8).to_be_bytes());
+
+    for chunk in padded.chunks_exact(64) {
+        let mut words = [0u32; 64];
+        for (word, bytes) in words.iter_mut().zip(chunk.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for index in 16..64 {
+            let s0 = words[index - 15].rotate_right(7)
+                ^ words[index - 15].rotate_right(18)
+                ^ (words[index - 15] >> 3);
+            let s1 = words[index - 2].rotate_right(17)
+                ^ words[index - 2].rotate_right(19)
+                ^ (words[index - 2] >> 10);
+            words[index] = words[index - 16]
+                .wrapping_add(s0)
+                .wrapping_add(words[index - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for index in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let temp1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(K[index])
+                .wrapping_add(words[index]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(majority);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+
+        for (value, added) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *value = value.wrapping_add(added);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (bytes, value) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hmac_sha256, sha256, to_hex};
+
+    // FIPS 180-4 examples, one and two blocks, plus the empty message.
+    #[test]
+    fn sha256_matches_known_answers() {
+        assert_eq!(
+            to_hex(&sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            to_hex(&sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            to_hex(&sha256(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    // RFC 4231 test cases 1, 2 and 6 (a key longer than the block size).
+    #[test]
+    fn hmac_sha256_matches_rfc_4231() {
+        assert_eq!(
+            to_hex(&hmac_sha256(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            to_hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+}
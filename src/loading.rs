@@ -0,0 +1,168 @@
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use image::DynamicImage;
+
+use crate::{audio::AudioEngine, scene_script::SceneAssets, tex::Tex, vfs};
+
+struct DecodedImage {
+    path: String,
+    result: Result<DynamicImage, String>,
+}
+
+// Gets a scene's assets resident before it starts. Images are decoded on a background
+// thread and uploaded by `poll`; sounds are decoded on the audio loader thread. Assets
+// that fail are logged and count as done, so a broken file never blocks the start.
+pub struct SceneLoader {
+    total: usize,
+    textures: usize,
+    uploaded: usize,
+    // None when the decoder thread could not start (always on wasm32); `poll` then
+    // decodes one image per frame from `queued` so the progress bar still moves.
+    decoded: Option<Receiver<DecodedImage>>,
+    queued: VecDeque<String>,
+    // Sounds whose decode was handed to the audio loader.
+    sounds: Vec<String>,
+    sounds_done: usize,
+}
+
+impl SceneLoader {
+    pub fn start(assets: SceneAssets, tex: &Tex, audio: Option<&mut AudioEngine>) -> Self {
+        let textures: Vec<String> = assets
+            .textures
+            .into_iter()
+            .filter(|path| !tex.has_texture(path))
+            .collect();
+        let sounds = audio.map_or_else(Vec::new, |audio| queue_sounds(audio, assets.sounds));
+        let textures_len = textures.len();
+        let total = textures_len + sounds.len();
+
+        let (decoded, queued) = if textures.is_empty() {
+            (None, VecDeque::new())
+        } else {
+            match spawn_decoder(textures.clone()) {
+                Ok(decoded) => (Some(decoded), VecDeque::new()),
+                Err(err) => {
+                    log::warn!("{err}");
+                    (None, textures.into())
+                }
+            }
+        };
+        log::debug!("loading {total} scene assets");
+
+        Self {
+            total,
+            textures: textures_len,
+            uploaded: 0,
+            decoded,
+            queued,
+            sounds,
+            sounds_done: 0,
+        }
+    }
+
+    // Uploads the images decoded since the last call; call once per frame.
+    pub fn poll(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        tex: &mut Tex,
+        audio: Option<&AudioEngine>,
+    ) {
+        let mut images = Vec::new();
+        let mut decoder_gone = false;
+        if let Some(decoded) = self.decoded.as_ref() {
+            loop {
+                match decoded.try_recv() {
+                    Ok(image) => images.push(image),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        decoder_gone = true;
+                        break;
+                    }
+                }
+            }
+        }
+        if let Some(path) = self.queued.pop_front() {
+            let result = vfs::read_image(&path);
+            images.push(DecodedImage { path, result });
+        }
+        for image in images {
+            match image.result {
+                Ok(decoded) => tex.upload_texture(device, queue, &image.path, decoded),
+                Err(err) => log::warn!("failed to load texture '{}': {err}", image.path),
+            }
+            self.uploaded += 1;
+        }
+        if decoder_gone && self.uploaded < self.textures {
+            log::warn!("scene loader thread stopped early");
+            self.uploaded = self.textures;
+        }
+
+        // The engine gone means nothing is left to wait for.
+        self.sounds_done = audio.map_or(self.sounds.len(), |audio| {
+            self.sounds
+                .iter()
+                .filter(|sound_id| !audio.is_loading(sound_id))
+                .count()
+        });
+    }
+
+    // 0..1; an empty scene is done from the start.
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        (self.uploaded + self.sounds_done) as f32 / self.total as f32
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.uploaded + self.sounds_done >= self.total
+    }
+}
+
+// Ends after the last path, or early once the loader is dropped.
+fn spawn_decoder(paths: Vec<String>) -> Result<Receiver<DecodedImage>, String> {
+    let (sender, decoded) = mpsc::channel();
+    thread::Builder::new()
+        .name("scene-loader".to_owned())
+        .spawn(move || {
+            for path in paths {
+                let result = vfs::read_image(&path);
+                if sender.send(DecodedImage { path, result }).is_err() {
+                    break;
+                }
+            }
+        })
+        .map_err(|err| format!("failed to start scene loader thread: {err}"))?;
+    Ok(decoded)
+}
+
+// Returns the sounds left decoding in the background; the rest are decoded here
+// when the loader thread is unavailable, or are resident already.
+fn queue_sounds(
+    audio: &mut AudioEngine,
+    sound_ids: impl IntoIterator<Item = String>,
+) -> Vec<String> {
+    let mut queued = Vec::new();
+    for sound_id in sound_ids {
+        let on_done = |sound_id: &str, result: Result<(), String>| {
+            if let Err(err) = result {
+                log::warn!("failed to preload sound '{sound_id}': {err}");
+            }
+        };
+        match audio.preload_async(&sound_id, on_done) {
+            Ok(true) => queued.push(sound_id),
+            Ok(false) => {}
+            Err(_) => {
+                if let Err(err) = audio.preload([sound_id.as_str()]) {
+                    log::warn!("failed to preload sound '{sound_id}': {err}");
+                }
+            }
+        }
+    }
+    queued
+}
@@ -0,0 +1,720 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::vfs;
+
+pub const DEFAULT_LOCALES_DIR: &str = "assets/locales";
+// Used for keys missing from the active language.
+pub const FALLBACK_LANGUAGE: &str = "ru";
+
+const BUILTIN_RU: &[(&str, &str)] = &[
+    ("settings.tab.audio", "Аудио"),
+    ("settings.tab.text", "Текст"),
+    ("settings.tab.interface", "Интерфейс"),
+    ("settings.tab.notifications", "Уведомления"),
+    ("settings.tab.controls", "Управление"),
+    ("action.advance", "Продолжить / пропустить"),
+    ("action.exit", "Выход"),
+    ("action.auto", "Автопродолжение"),
+    ("action.fast_forward", "Перемотка (удерживать)"),
+    ("action.toggle_backlog", "История реплик"),
+    ("action.quick_save", "Быстрое сохранение"),
+    ("action.quick_load", "Быстрая загрузка"),
+    ("action.pause", "Пауза"),
+    ("action.toggle_fullscreen", "Полноэкранный режим"),
+    ("action.toggle_debug_overlay", "Отладочная информация"),
+    ("action.toggle_profiler", "Профилировщик"),
+    ("action.hide_ui", "Скрыть интерфейс"),
+    ("achievements.filter.all", "Все"),
+    ("achievements.filter.unlocked", "Открытые"),
+    ("achievements.filter.locked", "Закрытые"),
+    ("achievements.sort.catalog", "По порядку"),
+    ("achievements.sort.alphabetical", "По алфавиту"),
+    ("achievements.sort.recent", "Недавно открытые"),
+    ("achievements.sort.rarity", "По редкости"),
+    ("theme.deep_sea", "Морская"),
+    ("theme.forest", "Лесная"),
+    ("theme.ember", "Янтарная"),
+    ("theme.high_contrast", "Высокий контраст"),
+    ("theme.colorblind_safe", "Для дальтоников"),
+    (
+        "settings.interface.enforce_contrast",
+        "Минимальная контрастность текста",
+    ),
+    ("settings.interface.min_contrast", "Коэффициент контраста"),
+    ("theme.custom", "Своя"),
+    ("theme.editor.create", "Новая тема из текущей"),
+    ("theme.editor.save", "Сохранить темы"),
+    ("theme.editor.saved", "Темы сохранены."),
+    ("theme.editor.save_failed", "Не удалось сохранить темы."),
+    ("theme.editor.default_name", "Моя тема {index}"),
+    (
+        "theme.editor.hint",
+        "Создайте свою тему, чтобы изменить цвета.",
+    ),
+    ("theme.editor.name", "Название"),
+    ("theme.editor.delete", "Удалить"),
+    ("theme.field.dialogue_fill", "Фон диалога"),
+    ("theme.field.menu_fill", "Фон меню"),
+    ("theme.field.menu_stroke", "Рамка меню"),
+    ("theme.field.menu_title", "Заголовок меню"),
+    ("theme.field.settings_fill", "Фон настроек"),
+    ("theme.field.settings_stroke", "Рамка настроек"),
+    ("theme.field.settings_title", "Заголовок настроек"),
+    ("theme.field.dialogue_stroke", "Рамка диалога"),
+    ("theme.field.dialogue_speaker", "Имя говорящего"),
+    ("theme.field.dialogue_text", "Текст диалога"),
+    ("theme.field.skip_ready", "Пропуск доступен"),
+    ("theme.field.skip_wait", "Пропуск недоступен"),
+    ("theme.field.popup_fill", "Фон уведомления"),
+    ("theme.field.popup_stroke", "Рамка уведомления"),
+    ("theme.field.popup_title", "Заголовок уведомления"),
+    ("theme.field.popup_name", "Название достижения"),
+    ("theme.field.popup_body", "Текст уведомления"),
+    (
+        "controls.conflict",
+        "Клавиша {key} уже назначена на «{action}».",
+    ),
+    ("dialogue.skip", "Пропустить"),
+    ("dialogue.typing", "Печать..."),
+    ("glossary.missing", "Описание не найдено."),
+    ("glossary.close", "Закрыть"),
+    ("text_input.submit", "Подтвердить"),
+    ("menu.title", "Главное меню"),
+    ("menu.continue", "Продолжить"),
+    ("menu.play", "Играть"),
+    ("menu.settings", "Настройки"),
+    ("menu.achievements", "Достижения"),
+    ("menu.stats", "Статистика"),
+    ("menu.gallery", "Галерея"),
+    ("menu.credits", "Титры"),
+    ("credits.pause", "Пауза"),
+    ("credits.resume", "Продолжить"),
+    ("credits.speed", "Скорость"),
+    ("credits.close", "Закрыть"),
+    ("gallery.window_title", "Галерея"),
+    (
+        "gallery.unlocked_count",
+        "Открыто иллюстраций: {unlocked}/{total}",
+    ),
+    ("gallery.empty", "В галерее пока нет иллюстраций."),
+    ("gallery.locked", "Закрыто"),
+    ("gallery.close", "Закрыть"),
+    (
+        "gallery.viewer_hint",
+        "Нажмите в любом месте или Esc, чтобы закрыть",
+    ),
+    ("menu.exit", "Выход"),
+    ("settings.window_title", "Настройки"),
+    ("settings.heading", "Гибкая настройка интерфейса"),
+    (
+        "settings.subheading",
+        "Выбранные параметры применяются сразу.",
+    ),
+    ("settings.reset", "Сбросить по умолчанию"),
+    ("settings.close", "Закрыть настройки"),
+    ("settings.audio.heading", "Аудио"),
+    ("settings.audio.master_volume", "Общая громкость"),
+    ("settings.audio.typewriter_sound", "Включить звук печати"),
+    ("settings.audio.typewriter_volume", "Громкость звука печати"),
+    ("settings.audio.ui_sounds", "Звуки интерфейса"),
+    ("settings.audio.ui_volume", "Громкость звуков интерфейса"),
+    ("settings.audio.voice_volume", "Громкость озвучки"),
+    ("settings.audio.music_volume", "Громкость музыки"),
+    ("settings.audio.ambience_volume", "Громкость окружения"),
+    (
+        "settings.audio.music_ducking",
+        "Приглушение музыки при озвучке",
+    ),
+    ("settings.audio.mute", "Выключить звук"),
+    ("settings.audio.focus_loss", "Звук в фоновом окне"),
+    ("settings.audio.captions", "Субтитры для звуков"),
+    ("focus_loss.keep_playing", "Продолжать"),
+    ("focus_loss.mute", "Выключать"),
+    ("focus_loss.pause", "Ставить на паузу"),
+    (
+        "settings.audio.hint",
+        "Подсказка: для тихого режима поставьте 0.0 в 'Общая громкость'.",
+    ),
+    ("settings.text.heading", "Текст и диалоги"),
+    ("settings.text.typewriter", "Эффект печати"),
+    ("settings.text.typing_speed", "Скорость печати (симв/с)"),
+    ("settings.text.caret", "Показывать курсор печати"),
+    ("settings.text.click_skip", "Разрешить пропуск кликом"),
+    ("settings.text.auto_advance", "Автопродолжение"),
+    (
+        "settings.text.auto_advance_delay",
+        "Задержка автопродолжения (с)",
+    ),
+    ("settings.text.wait_for_voice", "Ждать окончания озвучки"),
+    ("settings.text.voice_buffer", "Пауза после озвучки (с)"),
+    ("settings.text.speaker_name", "Показывать имя говорящего"),
+    ("settings.text.speaker_size", "Размер имени"),
+    ("settings.text.text_size", "Размер текста"),
+    ("settings.text.effect", "Оформление текста"),
+    (
+        "settings.text.effect_size",
+        "Толщина обводки / смещение тени",
+    ),
+    ("text_effect.none", "Нет"),
+    ("text_effect.outline", "Контур"),
+    ("text_effect.shadow", "Тень"),
+    ("settings.interface.heading", "Интерфейс"),
+    ("settings.interface.language", "Язык"),
+    ("settings.interface.ui_scale", "Масштаб UI"),
+    (
+        "settings.interface.compact_buttons",
+        "Компактные кнопки меню",
+    ),
+    (
+        "settings.interface.menu_title_size",
+        "Размер заголовка меню",
+    ),
+    (
+        "settings.interface.menu_button_size",
+        "Размер текста кнопок",
+    ),
+    (
+        "settings.interface.dialogue_opacity",
+        "Прозрачность диалогового окна",
+    ),
+    (
+        "settings.interface.dialogue_height",
+        "Высота диалогового окна",
+    ),
+    (
+        "settings.interface.dialogue_radius",
+        "Скругление диалогового окна",
+    ),
+    ("settings.interface.animation_speed", "Скорость анимаций"),
+    ("settings.interface.display_mode", "Режим экрана"),
+    ("display_mode.windowed", "В окне"),
+    ("display_mode.borderless", "Окно без рамки"),
+    ("display_mode.exclusive", "Полный экран"),
+    ("settings.interface.display", "Монитор"),
+    ("settings.interface.display_auto", "Текущий"),
+    ("settings.interface.resolution", "Разрешение"),
+    ("settings.interface.resolution_current", "Как сейчас"),
+    (
+        "settings.interface.present_mode",
+        "Вертикальная синхронизация",
+    ),
+    ("present_mode.fifo", "Включена (Fifo)"),
+    ("present_mode.mailbox", "Тройная буферизация (Mailbox)"),
+    ("present_mode.immediate", "Выключена (Immediate)"),
+    ("settings.interface.fps_limit_enabled", "Ограничить FPS"),
+    ("settings.interface.fps_limit", "Максимум кадров в секунду"),
+    ("settings.interface.theme", "Цветовая тема"),
+    ("settings.notifications.heading", "Уведомления и достижения"),
+    (
+        "settings.notifications.popup",
+        "Показывать всплывающее окно достижения",
+    ),
+    (
+        "settings.notifications.popup_duration",
+        "Длительность попапа (сек.)",
+    ),
+    (
+        "settings.notifications.descriptions",
+        "Показывать описание в списке достижений",
+    ),
+    (
+        "settings.notifications.high_contrast",
+        "Контрастные заблокированные карточки",
+    ),
+    (
+        "settings.notifications.spacing",
+        "Отступ между карточками достижений",
+    ),
+    (
+        "settings.notifications.reset_achievements",
+        "Сбросить достижения...",
+    ),
+    (
+        "settings.notifications.reset_warning",
+        "Все достижения и их прогресс будут заблокированы. Это нельзя отменить.",
+    ),
+    ("settings.notifications.reset_confirm", "Сбросить всё"),
+    ("settings.notifications.reset_cancel", "Отмена"),
+    ("settings.controls.heading", "Управление"),
+    ("settings.controls.press_key", "Нажмите клавишу..."),
+    (
+        "settings.controls.hint",
+        "Нажмите на клавишу, чтобы переназначить её, правой кнопкой — чтобы удалить. Esc отменяет ввод.",
+    ),
+    ("settings.controls.reset", "Сбросить клавиши"),
+    ("achievements.window_title", "Достижения"),
+    ("achievements.unlocked_count", "Открыто: {unlocked}/{total}"),
+    ("achievements.all_categories", "Все категории"),
+    (
+        "achievements.empty_filter",
+        "Нет достижений по выбранному фильтру.",
+    ),
+    ("achievements.status.unlocked", "Открыто"),
+    ("achievements.status.locked", "Заблокировано"),
+    ("achievements.rarity", "Есть у {percent}% игроков"),
+    ("achievements.rarity.common", "Обычное"),
+    ("achievements.rarity.uncommon", "Необычное"),
+    ("achievements.rarity.rare", "Редкое"),
+    ("achievements.rarity.legendary", "Легендарное"),
+    ("achievements.points", "Очки: {points}"),
+    ("achievements.score", "Счёт: {earned}/{total}"),
+    ("achievements.close", "Закрыть список достижений"),
+    ("achievements.hidden.name", "Скрытое достижение"),
+    (
+        "achievements.hidden.description",
+        "Подробности откроются после получения.",
+    ),
+    ("stats.window_title", "Статистика"),
+    ("stats.playtime", "Время в игре"),
+    ("stats.playtime_value", "{hours} ч {minutes} мин"),
+    ("stats.launches", "Запуски"),
+    ("stats.lines_read", "Прочитано реплик"),
+    ("stats.choices_made", "Сделано выборов"),
+    ("stats.scenes_completed", "Пройдено сцен"),
+    ("stats.close", "Закрыть статистику"),
+    ("achievements.tier.bronze", "Бронза"),
+    ("achievements.tier.silver", "Серебро"),
+    ("achievements.tier.gold", "Золото"),
+    ("achievements.progress", "{progress}/{goal}"),
+    ("achievements.tiers", "Уровни: {count}"),
+    ("popup.title", "Достижение получено!"),
+    ("toast.info", "Сообщение"),
+    ("toast.caption", "Звук"),
+    ("debug.objects", "Объекты: {visible}/{total}"),
+    (
+        "debug.draw_calls",
+        "Вызовы отрисовки: сцена {scene}, UI {ui}",
+    ),
+    ("profiler.title", "Профилировщик"),
+    ("profiler.waiting", "Ожидание первого кадра…"),
+    ("profiler.frame_time", "Последний кадр: {ms} мс"),
+    ("profiler.scope", "Участок"),
+    ("profiler.last", "Кадр, мс"),
+    ("profiler.average", "Среднее, мс"),
+    ("profiler.max", "Максимум, мс"),
+    ("profiler.export", "Экспорт для flamegraph"),
+    ("toast.profile_exported", "Профиль сохранён"),
+    (
+        "toast.profile_export_failed",
+        "Не удалось сохранить профиль",
+    ),
+    ("toast.success", "Готово"),
+    ("toast.error", "Ошибка"),
+    ("toast.continue_failed", "Не удалось загрузить сохранение"),
+    ("toast.quick_saved", "Игра сохранена"),
+    ("toast.achievements_reset", "Достижения сброшены"),
+    ("toast.save_tampered", "Файл сохранения был изменён вручную"),
+    ("toast.quick_loaded", "Сохранение загружено"),
+    ("toast.quick_save_failed", "Не удалось сохранить игру"),
+    ("toast.auto_on", "Автопродолжение включено"),
+    ("toast.auto_off", "Автопродолжение выключено"),
+    ("backlog.title", "История реплик"),
+    ("backlog.empty", "Реплик пока не было."),
+    ("loading.title", "Загрузка"),
+    ("pause.title", "Пауза"),
+    ("pause.hint", "Нажмите {key}, чтобы продолжить"),
+];
+
+const BUILTIN_EN: &[(&str, &str)] = &[
+    ("settings.tab.audio", "Audio"),
+    ("settings.tab.text", "Text"),
+    ("settings.tab.interface", "Interface"),
+    ("settings.tab.notifications", "Notifications"),
+    ("settings.tab.controls", "Controls"),
+    ("action.advance", "Continue / skip"),
+    ("action.exit", "Exit"),
+    ("action.auto", "Auto-advance"),
+    ("action.fast_forward", "Fast-forward (hold)"),
+    ("action.toggle_backlog", "Dialogue history"),
+    ("action.quick_save", "Quick save"),
+    ("action.quick_load", "Quick load"),
+    ("action.pause", "Pause"),
+    ("action.toggle_fullscreen", "Fullscreen"),
+    ("action.toggle_debug_overlay", "Debug overlay"),
+    ("action.toggle_profiler", "Profiler"),
+    ("action.hide_ui", "Hide interface"),
+    ("achievements.filter.all", "All"),
+    ("achievements.filter.unlocked", "Unlocked"),
+    ("achievements.filter.locked", "Locked"),
+    ("achievements.sort.catalog", "Catalog order"),
+    ("achievements.sort.alphabetical", "Alphabetical"),
+    ("achievements.sort.recent", "Recently unlocked"),
+    ("achievements.sort.rarity", "Rarity"),
+    ("theme.deep_sea", "Deep sea"),
+    ("theme.forest", "Forest"),
+    ("theme.ember", "Ember"),
+    ("theme.high_contrast", "High contrast"),
+    ("theme.colorblind_safe", "Colorblind safe"),
+    (
+        "settings.interface.enforce_contrast",
+        "Enforce minimum text contrast",
+    ),
+    ("settings.interface.min_contrast", "Contrast ratio"),
+    ("theme.custom", "Custom"),
+    ("theme.editor.create", "New theme from current"),
+    ("theme.editor.save", "Save themes"),
+    ("theme.editor.saved", "Themes saved."),
+    ("theme.editor.save_failed", "Failed to save themes."),
+    ("theme.editor.default_name", "My theme {index}"),
+    (
+        "theme.editor.hint",
+        "Create a custom theme to edit its colors.",
+    ),
+    ("theme.editor.name", "Name"),
+    ("theme.editor.delete", "Delete"),
+    ("theme.field.dialogue_fill", "Dialogue background"),
+    ("theme.field.menu_fill", "Menu background"),
+    ("theme.field.menu_stroke", "Menu border"),
+    ("theme.field.menu_title", "Menu title"),
+    ("theme.field.settings_fill", "Settings background"),
+    ("theme.field.settings_stroke", "Settings border"),
+    ("theme.field.settings_title", "Settings title"),
+    ("theme.field.dialogue_stroke", "Dialogue border"),
+    ("theme.field.dialogue_speaker", "Speaker name"),
+    ("theme.field.dialogue_text", "Dialogue text"),
+    ("theme.field.skip_ready", "Skip available"),
+    ("theme.field.skip_wait", "Skip unavailable"),
+    ("theme.field.popup_fill", "Popup background"),
+    ("theme.field.popup_stroke", "Popup border"),
+    ("theme.field.popup_title", "Popup title"),
+    ("theme.field.popup_name", "Achievement name"),
+    ("theme.field.popup_body", "Popup text"),
+    (
+        "controls.conflict",
+        "Key {key} is already bound to “{action}”.",
+    ),
+    ("dialogue.skip", "Skip"),
+    ("dialogue.typing", "Typing..."),
+    ("glossary.missing", "No description found."),
+    ("glossary.close", "Close"),
+    ("text_input.submit", "Confirm"),
+    ("menu.title", "Main menu"),
+    ("menu.continue", "Continue"),
+    ("menu.play", "Play"),
+    ("menu.settings", "Settings"),
+    ("menu.achievements", "Achievements"),
+    ("menu.stats", "Statistics"),
+    ("menu.gallery", "Gallery"),
+    ("menu.credits", "Credits"),
+    ("credits.pause", "Pause"),
+    ("credits.resume", "Resume"),
+    ("credits.speed", "Speed"),
+    ("credits.close", "Close"),
+    ("gallery.window_title", "Gallery"),
+    (
+        "gallery.unlocked_count",
+        "Illustrations unlocked: {unlocked}/{total}",
+    ),
+    ("gallery.empty", "The gallery has no illustrations yet."),
+    ("gallery.locked", "Locked"),
+    ("gallery.close", "Close"),
+    (
+        "gallery.viewer_hint",
+        "Click anywhere or press Esc to close",
+    ),
+    ("menu.exit", "Exit"),
+    ("settings.window_title", "Settings"),
+    ("settings.heading", "Interface settings"),
+    ("settings.subheading", "Changes are applied immediately."),
+    ("settings.reset", "Reset to defaults"),
+    ("settings.close", "Close settings"),
+    ("settings.audio.heading", "Audio"),
+    ("settings.audio.master_volume", "Master volume"),
+    ("settings.audio.typewriter_sound", "Typewriter sound"),
+    (
+        "settings.audio.typewriter_volume",
+        "Typewriter sound volume",
+    ),
+    ("settings.audio.ui_sounds", "Interface sounds"),
+    ("settings.audio.ui_volume", "Interface sound volume"),
+    ("settings.audio.voice_volume", "Voice volume"),
+    ("settings.audio.music_volume", "Music volume"),
+    ("settings.audio.ambience_volume", "Ambience volume"),
+    ("settings.audio.music_ducking", "Lower music during voice"),
+    ("settings.audio.mute", "Mute all sound"),
+    ("settings.audio.focus_loss", "Audio in background"),
+    ("settings.audio.captions", "Sound captions"),
+    ("focus_loss.keep_playing", "Keep playing"),
+    ("focus_loss.mute", "Mute"),
+    ("focus_loss.pause", "Pause"),
+    (
+        "settings.audio.hint",
+        "Tip: set 'Master volume' to 0.0 for silent mode.",
+    ),
+    ("settings.text.heading", "Text and dialogue"),
+    ("settings.text.typewriter", "Typewriter effect"),
+    ("settings.text.typing_speed", "Typing speed (chars/s)"),
+    ("settings.text.caret", "Show typing caret"),
+    ("settings.text.click_skip", "Allow click to skip"),
+    ("settings.text.auto_advance", "Auto-advance"),
+    ("settings.text.auto_advance_delay", "Auto-advance delay (s)"),
+    ("settings.text.wait_for_voice", "Wait for voice to finish"),
+    ("settings.text.voice_buffer", "Pause after voice (s)"),
+    ("settings.text.speaker_name", "Show speaker name"),
+    ("settings.text.speaker_size", "Speaker name size"),
+    ("settings.text.text_size", "Text size"),
+    ("settings.text.effect", "Text decoration"),
+    ("settings.text.effect_size", "Outline width / shadow offset"),
+    ("text_effect.none", "None"),
+    ("text_effect.outline", "Outline"),
+    ("text_effect.shadow", "Drop shadow"),
+    ("settings.interface.heading", "Interface"),
+    ("settings.interface.language", "Language"),
+    ("settings.interface.ui_scale", "UI scale"),
+    ("settings.interface.compact_buttons", "Compact menu buttons"),
+    ("settings.interface.menu_title_size", "Menu title size"),
+    ("settings.interface.menu_button_size", "Button text size"),
+    (
+        "settings.interface.dialogue_opacity",
+        "Dialogue box opacity",
+    ),
+    ("settings.interface.dialogue_height", "Dialogue box height"),
+    (
+        "settings.interface.dialogue_radius",
+        "Dialogue box corner radius",
+    ),
+    ("settings.interface.animation_speed", "Animation speed"),
+    ("settings.interface.display_mode", "Display mode"),
+    ("display_mode.windowed", "Windowed"),
+    ("display_mode.borderless", "Borderless"),
+    ("display_mode.exclusive", "Exclusive fullscreen"),
+    ("settings.interface.display", "Display"),
+    ("settings.interface.display_auto", "Current"),
+    ("settings.interface.resolution", "Resolution"),
+    ("settings.interface.resolution_current", "Keep current"),
+    ("settings.interface.present_mode", "Vertical sync"),
+    ("present_mode.fifo", "On (Fifo)"),
+    ("present_mode.mailbox", "Triple buffered (Mailbox)"),
+    ("present_mode.immediate", "Off (Immediate)"),
+    ("settings.interface.fps_limit_enabled", "Limit FPS"),
+    ("settings.interface.fps_limit", "Frames per second"),
+    ("settings.interface.theme", "Color theme"),
+    (
+        "settings.notifications.heading",
+        "Notifications and achievements",
+    ),
+    ("settings.notifications.popup", "Show achievement popup"),
+    (
+        "settings.notifications.popup_duration",
+        "Popup duration (s)",
+    ),
+    (
+        "settings.notifications.descriptions",
+        "Show descriptions in achievement list",
+    ),
+    (
+        "settings.notifications.high_contrast",
+        "High-contrast locked cards",
+    ),
+    ("settings.notifications.spacing", "Achievement card spacing"),
+    (
+        "settings.notifications.reset_achievements",
+        "Reset achievements...",
+    ),
+    (
+        "settings.notifications.reset_warning",
+        "Every achievement and its progress will be locked again. This cannot be undone.",
+    ),
+    ("settings.notifications.reset_confirm", "Reset everything"),
+    ("settings.notifications.reset_cancel", "Cancel"),
+    ("settings.controls.heading", "Controls"),
+    ("settings.controls.press_key", "Press a key..."),
+    (
+        "settings.controls.hint",
+        "Click a key to rebind it, right-click to remove it. Esc cancels.",
+    ),
+    ("settings.controls.reset", "Reset keys"),
+    ("achievements.window_title", "Achievements"),
+    (
+        "achievements.unlocked_count",
+        "Unlocked: {unlocked}/{total}",
+    ),
+    ("achievements.all_categories", "All categories"),
+    (
+        "achievements.empty_filter",
+        "No achievements match the filter.",
+    ),
+    ("achievements.status.unlocked", "Unlocked"),
+    ("achievements.status.locked", "Locked"),
+    ("achievements.rarity", "Owned by {percent}% of players"),
+    ("achievements.rarity.common", "Common"),
+    ("achievements.rarity.uncommon", "Uncommon"),
+    ("achievements.rarity.rare", "Rare"),
+    ("achievements.rarity.legendary", "Legendary"),
+    ("achievements.points", "{points} pts"),
+    ("achievements.score", "Score: {earned}/{total}"),
+    ("achievements.close", "Close achievements"),
+    ("achievements.hidden.name", "Hidden achievement"),
+    (
+        "achievements.hidden.description",
+        "Details are revealed once it is unlocked.",
+    ),
+    ("stats.window_title", "Statistics"),
+    ("stats.playtime", "Playtime"),
+    ("stats.playtime_value", "{hours} h {minutes} min"),
+    ("stats.launches", "Launches"),
+    ("stats.lines_read", "Lines read"),
+    ("stats.choices_made", "Choices made"),
+    ("stats.scenes_completed", "Scenes completed"),
+    ("stats.close", "Close statistics"),
+    ("achievements.tier.bronze", "Bronze"),
+    ("achievements.tier.silver", "Silver"),
+    ("achievements.tier.gold", "Gold"),
+    ("achievements.progress", "{progress}/{goal}"),
+    ("achievements.tiers", "Tiers: {count}"),
+    ("popup.title", "Achievement unlocked!"),
+    ("toast.info", "Notice"),
+    ("toast.caption", "Sound"),
+    ("debug.objects", "Objects: {visible}/{total}"),
+    ("debug.draw_calls", "Draw calls: scene {scene}, UI {ui}"),
+    ("profiler.title", "Profiler"),
+    ("profiler.waiting", "Waiting for the first frame…"),
+    ("profiler.frame_time", "Last frame: {ms} ms"),
+    ("profiler.scope", "Span"),
+    ("profiler.last", "Frame, ms"),
+    ("profiler.average", "Average, ms"),
+    ("profiler.max", "Max, ms"),
+    ("profiler.export", "Export for flamegraph"),
+    ("toast.profile_exported", "Profile saved"),
+    ("toast.profile_export_failed", "Failed to save the profile"),
+    ("toast.success", "Done"),
+    ("toast.error", "Error"),
+    ("toast.continue_failed", "Failed to load the save"),
+    ("toast.quick_saved", "Game saved"),
+    ("toast.achievements_reset", "Achievements reset"),
+    (
+        "toast.save_tampered",
+        "Save file was modified outside the game",
+    ),
+    ("toast.quick_loaded", "Save loaded"),
+    ("toast.quick_save_failed", "Failed to save the game"),
+    ("toast.auto_on", "Auto-advance on"),
+    ("toast.auto_off", "Auto-advance off"),
+    ("backlog.title", "Dialogue history"),
+    ("backlog.empty", "No lines yet."),
+    ("loading.title", "Loading"),
+    ("pause.title", "Paused"),
+    ("pause.hint", "Press {key} to resume"),
+];
+
+// One language: `{ "code": "de", "name": "Deutsch", "strings": { "menu.play": "Spielen" } }`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LocaleTable {
+    pub code: String,
+    pub name: String,
+    #[serde(default)]
+    pub strings: HashMap<String, String>,
+}
+
+impl LocaleTable {
+    fn builtin(code: &str, name: &str, strings: &[(&str, &str)]) -> Self {
+        Self {
+            code: code.to_owned(),
+            name: name.to_owned(),
+            strings: strings
+                .iter()
+                .map(|(key, value)| ((*key).to_owned(), (*value).to_owned()))
+                .collect(),
+        }
+    }
+}
+
+pub struct Localization {
+    tables: Vec<LocaleTable>,
+    active: usize,
+    fallback: usize,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self {
+            tables: vec![
+                LocaleTable::builtin("ru", "Русский", BUILTIN_RU),
+                LocaleTable::builtin("en", "English", BUILTIN_EN),
+            ],
+            active: 0,
+            fallback: 0,
+        }
+    }
+}
+
+impl Localization {
+    // Looks up `key` in the active language, then the fallback; returns the key itself if missing.
+    pub fn tr<'a>(&'a self, key: &'a str) -> &'a str {
+        [self.active, self.fallback]
+            .iter()
+            .find_map(|index| self.tables[*index].strings.get(key))
+            .map(String::as_str)
+            .unwrap_or(key)
+    }
+
+    // Like tr(), replacing `{name}` placeholders with the given values.
+    pub fn tr_args(&self, key: &str, args: &[(&str, String)]) -> String {
+        let mut text = self.tr(key).to_owned();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{name}}}"), value);
+        }
+        text
+    }
+
+    pub fn active_code(&self) -> &str {
+        &self.tables[self.active].code
+    }
+
+    pub fn languages(&self) -> Vec<(String, String)> {
+        self.tables
+            .iter()
+            .map(|table| (table.code.clone(), table.name.clone()))
+            .collect()
+    }
+
+    pub fn set_language(&mut self, code: &str) -> bool {
+        match self.tables.iter().position(|table| table.code == code) {
+            Some(index) => {
+                self.active = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Loads every `*.json` table in `dir`; a table with a known code overrides its strings.
+    pub fn load_directory(&mut self, dir: impl AsRef<Path>) -> Result<usize, String> {
+        let dir = dir.as_ref();
+        if !vfs::exists(dir) {
+            return Ok(0);
+        }
+
+        let entries = vfs::list_dir(dir)
+            .map_err(|err| format!("failed to read locales directory {}: {err}", dir.display()))?;
+
+        let mut loaded = 0;
+        for path in entries {
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            let raw = vfs::read_to_string(&path)
+                .map_err(|err| format!("failed to read locale file {}: {err}", path.display()))?;
+            let table: LocaleTable = serde_json::from_str(&raw)
+                .map_err(|err| format!("failed to parse locale file {}: {err}", path.display()))?;
+
+            match self
+                .tables
+                .iter_mut()
+                .find(|existing| existing.code == table.code)
+            {
+                Some(existing) => {
+                    existing.name = table.name;
+                    existing.strings.extend(table.strings);
+                }
+                None => self.tables.push(table),
+            }
+            loaded += 1;
+        }
+
+        Ok(loaded)
+    }
+}
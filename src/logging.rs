@@ -0,0 +1,198 @@
+use std::{
+    collections::VecDeque,
+    env,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::paths;
+
+// Filter spec such as `debug` or `warn,game_engine=debug,wgpu_core=error`.
+const LOG_FILTER_ENV: &str = "GAME_ENGINE_LOG";
+// Also append records to this file; relative paths live in the data directory.
+const LOG_FILE_ENV: &str = "GAME_ENGINE_LOG_FILE";
+// Our own messages at info, dependencies (wgpu is chatty) only when something is wrong.
+const DEFAULT_FILTER: &str = "warn,game_engine=info";
+// Used instead of `DEFAULT_FILTER` when started with `--debug`.
+const DEBUG_FILTER: &str = "warn,game_engine=debug";
+// Records kept for the in-game viewer.
+const RECENT_CAPACITY: usize = 200;
+
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+struct Logger {
+    default_level: LevelFilter,
+    // Longest matching target prefix wins.
+    targets: Vec<(String, LevelFilter)>,
+    // Set right after the logger is installed; see `init`.
+    file: OnceLock<Mutex<File>>,
+}
+
+static RECENT: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+// Installs the logger; call before anything logs. Prints to stderr (the browser console on
+// wasm32), optionally to a file, and keeps the latest records for `recent`.
+// `GAME_ENGINE_LOG` wins over `debug`.
+pub fn init(debug: bool) {
+    let spec = env::var(LOG_FILTER_ENV)
+        .unwrap_or_else(|_| if debug { DEBUG_FILTER } else { DEFAULT_FILTER }.to_owned());
+    let (default_level, targets) = parse_filter(&spec);
+
+    let max_level = targets
+        .iter()
+        .map(|(_, level)| *level)
+        .fold(default_level, Ord::max);
+    let logger: &'static Logger = Box::leak(Box::new(Logger {
+        default_level,
+        targets,
+        file: OnceLock::new(),
+    }));
+    if log::set_logger(logger).is_err() {
+        return;
+    }
+    log::set_max_level(max_level);
+
+    // Opened once records have somewhere to go: resolving a relative path can already log.
+    if let Some(path) = env::var_os(LOG_FILE_ENV).filter(|path| !path.is_empty()) {
+        match open_log_file(paths::user_path(path)) {
+            Ok(file) => {
+                let _ = logger.file.set(Mutex::new(file));
+            }
+            Err(err) => log::error!("{err}"),
+        }
+    }
+}
+
+// Newest last; at most `limit` records at `level` or more severe.
+pub fn recent(level: Level, limit: usize) -> Vec<LogEntry> {
+    let Ok(recent) = RECENT.lock() else {
+        return Vec::new();
+    };
+    let mut entries: Vec<LogEntry> = recent
+        .iter()
+        .rev()
+        .filter(|entry| entry.level <= level)
+        .take(limit)
+        .cloned()
+        .collect();
+    entries.reverse();
+    entries
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = record.args().to_string();
+        let line = format!("[{} {}] {message}", record.level(), record.target());
+        print_line(&line);
+        if let Some(file) = self.file.get()
+            && let Ok(mut file) = file.lock()
+        {
+            let seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs());
+            let _ = writeln!(file, "{seconds} {line}");
+        }
+
+        if let Ok(mut recent) = RECENT.lock() {
+            if recent.len() == RECENT_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(LogEntry {
+                level: record.level(),
+                target: record.target().to_owned(),
+                message,
+            });
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.file.get()
+            && let Ok(mut file) = file.lock()
+        {
+            let _ = file.flush();
+        }
+    }
+}
+
+impl Logger {
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(prefix, _)| {
+                target == prefix
+                    || target
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default_level, |(_, level)| *level)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn print_line(line: &str) {
+    eprintln!("{line}");
+}
+
+// stderr goes nowhere in the browser.
+#[cfg(target_arch = "wasm32")]
+fn print_line(line: &str) {
+    web_sys::console::log_1(&line.into());
+}
+
+fn parse_filter(spec: &str) -> (LevelFilter, Vec<(String, LevelFilter)>) {
+    let mut default_level = LevelFilter::Warn;
+    let mut targets = Vec::new();
+    for part in spec
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (target, level) = match part.split_once('=') {
+            Some((target, level)) => (Some(target.trim()), level.trim()),
+            None => (None, part),
+        };
+        let Ok(level) = level.parse::<LevelFilter>() else {
+            eprintln!("ignoring log filter {part:?}: unknown level");
+            continue;
+        };
+        match target {
+            Some(target) => targets.push((target.to_owned(), level)),
+            None => default_level = level,
+        }
+    }
+    (default_level, targets)
+}
+
+fn open_log_file(path: PathBuf) -> Result<File, String> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .map_err(|err| format!("failed to create log directory {}: {err}", parent.display()))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|err| format!("failed to open log file {}: {err}", path.display()))
+}
@@ -1,7 +1,9 @@
-use std::{sync::Arc, time::Instant};
+use std::{cell::Cell, rc::Rc, sync::Arc, time::Duration};
+use web_time::Instant;
 use winit::{
     application::ApplicationHandler,
-    event::WindowEvent,
+    dpi::PhysicalSize,
+    event::{DeviceEvent, DeviceId, ElementState, MouseButton, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     window::{Window, WindowAttributes},
 };
@@ -10,144 +12,433 @@ mod state;
 use state::State;
 mod achievements;
 mod audio;
+mod audio_source;
+mod cli;
+mod condition;
+mod crash;
+mod credits;
+mod dialogue_markup;
 mod dialogue_ui;
+mod display;
+mod flags;
+mod gallery;
+mod game_frame;
 mod game_object;
+mod glossary;
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+#[cfg(debug_assertions)]
+mod hot_reload;
+mod icon_cache;
 mod input;
+mod integrity;
+mod loading;
+mod locale;
+mod logging;
+#[cfg(not(target_arch = "wasm32"))]
+mod pack;
+mod paths;
+mod profiler;
+mod save;
 mod scene_objects;
 mod scene_script;
 mod scripts;
+mod stats;
+#[cfg(feature = "steam")]
+mod steam;
 mod tex;
+mod ui_theme;
+mod vfs;
+mod voice_bank;
 use achievements::AchievementManager;
-use audio::AudioEngine;
-use dialogue_ui::{DialogueUi, UiCommand};
+use audio::{AudioBus, AudioEngine, SoundDefaults};
+use audio_source::{Envelope, ToneSpec, Waveform};
+use cli::{CliExit, CliOptions};
+use credits::Credits;
+use dialogue_ui::{DialogueUi, ToastKind, UiCommand, UiSound};
+use display::{DisplayMode, DisplaySettings, GpuSettings, PowerPreference};
+use flags::FlagStore;
+use gallery::Gallery;
+use game_frame::{FrameUpdate, GameFrame};
+use glossary::Glossary;
 use input::{Action, ActionMap, InputState};
-use scene_script::{SceneRunner, ScriptContext, ScriptSignal};
+use loading::SceneLoader;
+use save::SaveGame;
+use scene_script::{SIMULATION_TICK, SceneRunner, ScriptContext, SignalRoutes};
+use stats::StatsManager;
 use tex::Tex;
+use voice_bank::VoiceBank;
+
+// How often a missing audio device is looked for again.
+const AUDIO_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const ACHIEVEMENT_STINGER_SOUND: &str = "achievement_unlocked";
+// A custom typewriter clip is mixed a little louder than the built-in blip.
+const TYPEWRITER_TONE_VOLUME: f32 = 0.16;
+const TYPEWRITER_CLIP_VOLUME: f32 = 0.20;
+// Audio fades out over this long when the game closes.
+const SHUTDOWN_AUDIO_FADE: Duration = Duration::from_millis(300);
+
+// Steps run by `App::shutdown`, in this order, however the game is closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShutdownStep {
+    // First, so whatever the scripts change still reaches the saves.
+    DestroyScripts,
+    Autosave,
+    SaveProgress,
+    SaveSettings,
+    // Last; blocks for the fade, with the window already hidden.
+    FadeOutAudio,
+}
+
+const SHUTDOWN_SEQUENCE: [ShutdownStep; 5] = [
+    ShutdownStep::DestroyScripts,
+    ShutdownStep::Autosave,
+    ShutdownStep::SaveProgress,
+    ShutdownStep::SaveSettings,
+    ShutdownStep::FadeOutAudio,
+];
+
+// Events posted to the loop from outside a handler.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+enum AppEvent {
+    // The GPU state for the window, created asynchronously in the browser.
+    StateReady(State),
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum AppMode {
     MainMenu,
+    // Scene assets are being prepared; the scene starts once they are resident.
+    Loading,
     InGame,
 }
 
 struct App {
     window: Option<Arc<Window>>,
+    #[cfg(target_arch = "wasm32")]
+    proxy: Option<winit::event_loop::EventLoopProxy<AppEvent>>,
     state: Option<State>,
     tex: Option<Tex>,
     dialogue_ui: Option<DialogueUi>,
     audio: Option<AudioEngine>,
+    // Next attempt to open an audio device while `audio` is None.
+    audio_retry_at: Option<Instant>,
     achievements: Option<AchievementManager>,
+    stats: StatsManager,
+    #[cfg(feature = "steam")]
+    steam: Option<steam::SteamAchievements>,
+    // Dev builds pick up catalog edits without a restart.
+    #[cfg(debug_assertions)]
+    achievements_watcher: Option<hot_reload::FileWatcher>,
+    // Set by the unlock subscription; the stinger plays on the next frame.
+    unlock_stinger_pending: Rc<Cell<bool>>,
     scene_runner: Option<SceneRunner>,
+    // Assets of the scene about to start, while in `AppMode::Loading`.
+    scene_loader: Option<SceneLoader>,
+    flags: FlagStore,
+    persistent_flags: FlagStore,
     input: InputState,
     action_map: ActionMap,
+    signal_routes: SignalRoutes,
     last_frame_time: Option<Instant>,
+    // Scaled frame time not yet consumed by a simulation tick.
+    tick_accumulator: f32,
+    // Whether the window currently accepts IME composition, which text entry needs.
+    ime_allowed: bool,
+    // Frame egui asked for after a delay, requested from `about_to_wait` once it is due.
+    redraw_at: Option<Instant>,
+    // Start of the last rendered frame, for the FPS cap.
+    last_redraw_at: Option<Instant>,
+    cli: CliOptions,
+    // `--skip-menu` start, issued on the first frame as if the menu button was pressed.
+    startup_command: Option<UiCommand>,
+    // `--scene` save restored by that start.
+    startup_save: Option<SaveGame>,
+    // Loaded before the window for the GPU choice, then handed to the UI.
+    startup_display: Option<DisplaySettings>,
+    // Adapter choice of this run, reused when the device has to be recreated.
+    gpu: GpuSettings,
+    // Display settings the window was last switched to; None until the first frame.
+    applied_display: Option<DisplaySettings>,
+    // Scale factor of the monitor the window was last on.
+    scale_factor: f64,
     mode: AppMode,
     scene_bootstrapped: bool,
+    // Set by `shutdown`; events still arriving before the loop ends are ignored.
+    shut_down: bool,
 }
 
 impl Default for App {
     fn default() -> Self {
         Self {
             window: None,
+            #[cfg(target_arch = "wasm32")]
+            proxy: None,
             state: None,
             tex: None,
             dialogue_ui: None,
             audio: None,
+            audio_retry_at: None,
             achievements: None,
+            stats: StatsManager::default(),
+            #[cfg(feature = "steam")]
+            steam: None,
+            #[cfg(debug_assertions)]
+            achievements_watcher: None,
+            unlock_stinger_pending: Rc::new(Cell::new(false)),
             scene_runner: None,
+            scene_loader: None,
+            flags: FlagStore::default(),
+            persistent_flags: FlagStore::default(),
             input: InputState::default(),
             action_map: ActionMap::default(),
+            signal_routes: SignalRoutes::default(),
             last_frame_time: None,
+            tick_accumulator: 0.0,
+            ime_allowed: false,
+            redraw_at: None,
+            last_redraw_at: None,
+            cli: CliOptions::default(),
+            startup_command: None,
+            startup_save: None,
+            startup_display: None,
+            gpu: GpuSettings::default(),
+            applied_display: None,
+            scale_factor: 1.0,
             mode: AppMode::MainMenu,
             scene_bootstrapped: false,
+            shut_down: false,
         }
     }
 }
 
-impl ApplicationHandler for App {
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        println!("ok");
-
-        let window = Some(Arc::new(
-            event_loop
-                .create_window(WindowAttributes::default())
-                .unwrap(),
-        ));
+impl App {
+    // Creates the GPU state for `window` and hands it to `state_ready`.
+    fn request_state(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window: Arc<Window>,
+        gpu: GpuSettings,
+    ) {
+        #[cfg(not(target_arch = "wasm32"))]
+        match pollster::block_on(State::new(window, &gpu)) {
+            Ok(state) => self.state_ready(event_loop, state),
+            Err(err) => {
+                log::error!("{err}");
+                event_loop.exit();
+            }
+        }
+        // The browser cannot block on adapter and device requests, so the state arrives
+        // later as `AppEvent::StateReady`.
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = event_loop;
+            let proxy = self.proxy.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match State::new(window, &gpu).await {
+                    Ok(state) => {
+                        if let Some(proxy) = proxy {
+                            let _ = proxy.send_event(AppEvent::StateReady(state));
+                        }
+                    }
+                    Err(err) => log::error!("{err}"),
+                }
+            });
+        }
+    }
 
-        self.window = window.clone();
+    fn state_ready(&mut self, event_loop: &ActiveEventLoop, state: State) {
+        if self.tex.is_some() {
+            self.restore_gpu_resources(state);
+        } else {
+            self.finish_setup(event_loop, state);
+        }
+    }
 
-        let state_ = pollster::block_on(State::new(window.unwrap()));
+    // Starts over on a new device after a driver reset or GPU removal; `Tex` keeps the
+    // scene definitions, so everything else carries on where it was.
+    fn recover_from_device_loss(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(window) = self.window.clone() else {
+            return;
+        };
+        log::warn!("recreating GPU resources after device loss");
+        let gpu = self.gpu.clone();
+        // The old surface has to go before the window gets a new one.
+        self.state = None;
+        self.request_state(event_loop, window, gpu);
+    }
 
-        self.state = Some(state_.unwrap());
+    fn restore_gpu_resources(&mut self, mut state: State) {
+        state.resumed();
+        if let Some(config) = state.config.as_ref() {
+            if let Some(tex) = self.tex.as_mut() {
+                tex.recreate(config, &state.adapter, &state.device, &state.queue);
+            }
+            if let (Some(dialogue_ui), Some(window)) = (self.dialogue_ui.as_mut(), &self.window) {
+                dialogue_ui.recreate_renderer(window, &state.device, config.format);
+            }
+        }
+        state.redraw();
+        self.state = Some(state);
+        log::info!("GPU resources restored");
+    }
 
-        State::resumed(&mut self.state.as_mut().unwrap());
+    // Everything that needs the GPU: renderers, UI, audio and the loaded game data.
+    fn finish_setup(&mut self, event_loop: &ActiveEventLoop, mut state: State) {
+        state.resumed();
 
-        if let Some(state) = &self.state {
-            let tex = Tex::init(
-                &state.config.as_ref().unwrap(),
-                &state.adapter,
-                &state.device,
-                &state.queue,
-            );
-            let mut dialogue_ui = DialogueUi::new(
-                self.window.as_ref().unwrap().as_ref(),
-                &state.device,
-                state.config.as_ref().unwrap().format,
-            );
-            let mut audio = match AudioEngine::new() {
-                Ok(audio) => Some(audio),
-                Err(err) => {
-                    eprintln!("audio disabled: {err}");
-                    None
-                }
-            };
-            if let Some(audio_engine) = audio.as_mut() {
-                // Built-in short blip used by dialogue typewriter.
-                audio_engine.register_tone("dialogue_typewriter", 1240, 18);
-                dialogue_ui.set_typewriter_sound("dialogue_typewriter", 0.16);
-
-                // Optional external override: place your own clip at assets/sfx/type_tick.wav.
-                if audio_engine
-                    .register_sound_file("dialogue_typewriter", "assets/sfx/type_tick.wav")
-                    .is_ok()
-                {
-                    dialogue_ui.set_typewriter_sound("dialogue_typewriter", 0.20);
-                }
+        let tex = Tex::init(
+            &state.config.as_ref().unwrap(),
+            &state.adapter,
+            &state.device,
+            &state.queue,
+        );
+        let mut dialogue_ui = DialogueUi::new(
+            self.window.as_ref().unwrap().as_ref(),
+            &state.device,
+            state.config.as_ref().unwrap().format,
+        );
+        let mut typewriter_volume = TYPEWRITER_TONE_VOLUME;
+        let audio = match AudioEngine::new() {
+            Ok(mut audio) => {
+                typewriter_volume = register_builtin_sounds(&mut audio);
+                Some(audio)
             }
-
-            let scene_runner =
-                SceneRunner::with_scripts(scene_objects::create_initial_scene_scripts());
-            let achievements_path = scripts::achievements_catalog::DEFAULT_ACHIEVEMENTS_PATH;
-            if let Err(err) =
-                scripts::achievements_catalog::ensure_achievements_json_exists(achievements_path)
-            {
-                eprintln!("failed to prepare achievements catalog: {err}");
+            Err(err) => {
+                log::warn!("audio disabled: {err}");
+                self.audio_retry_at = Some(Instant::now() + AUDIO_RETRY_INTERVAL);
+                None
             }
-            let achievements = AchievementManager::load_from_json_file(achievements_path)
-                .or_else(|err| {
-                    eprintln!("failed to load achievements json: {err}");
-                    AchievementManager::from_definitions(
-                        scripts::achievements_catalog::create_all_achievements(),
-                    )
-                })
+        };
+        dialogue_ui.set_typewriter_sound("dialogue_typewriter", typewriter_volume);
+        for kind in [UiSound::Hover, UiSound::Click, UiSound::Confirm] {
+            dialogue_ui.set_ui_sound(kind, kind.default_sound_id());
+        }
+
+        let scene_runner = SceneRunner::with_scripts(scene_objects::create_initial_scene_scripts());
+        let achievements_path = scripts::achievements_catalog::DEFAULT_ACHIEVEMENTS_PATH;
+        if let Err(err) =
+            scripts::achievements_catalog::ensure_achievements_json_exists(achievements_path)
+        {
+            log::warn!("failed to prepare achievements catalog: {err}");
+        }
+        let mut achievements = AchievementManager::load_from_json_file(achievements_path)
+            .or_else(|err| {
+                log::warn!("failed to load achievements json: {err}");
+                AchievementManager::from_definitions(
+                    scripts::achievements_catalog::create_all_achievements(),
+                )
+            })
+            .unwrap_or_else(|err| {
+                log::warn!("failed to create fallback achievements catalog: {err}");
+                AchievementManager::from_definitions(Vec::new())
+                    .expect("empty achievements catalog should be valid")
+            });
+        if let Err(err) = achievements.load_progress_from_json_file(paths::user_path(
+            scripts::achievements_catalog::DEFAULT_ACHIEVEMENT_PROGRESS_PATH,
+        )) {
+            log::warn!("failed to load achievement progress: {err}");
+        }
+
+        self.stats = StatsManager::load_from_json_file(paths::user_path(stats::DEFAULT_STATS_PATH))
+            .unwrap_or_else(|err| {
+                log::warn!("failed to load stats, starting over: {err}");
+                StatsManager::default()
+            });
+        self.stats.record_launch();
+        self.persistent_flags =
+            FlagStore::load_from_json_file(paths::user_path(flags::DEFAULT_PERSISTENT_FLAGS_PATH))
                 .unwrap_or_else(|err| {
-                    eprintln!("failed to create fallback achievements catalog: {err}");
-                    AchievementManager::from_definitions(Vec::new())
-                        .expect("empty achievements catalog should be valid")
+                    log::warn!("failed to load persistent flags: {err}");
+                    FlagStore::default()
                 });
 
-            dialogue_ui.set_achievements_snapshot(achievements.snapshot());
-            dialogue_ui.set_main_menu_enabled(true);
+        let glossary = Glossary::load_from_json_file(glossary::DEFAULT_GLOSSARY_PATH)
+            .unwrap_or_else(|err| {
+                log::warn!("glossary disabled: {err}");
+                Glossary::default()
+            });
+        let gallery =
+            Gallery::load_from_json_file(gallery::DEFAULT_GALLERY_PATH).unwrap_or_else(|err| {
+                log::warn!("gallery disabled: {err}");
+                Gallery::default()
+            });
+        let voice_bank = VoiceBank::load_from_json_file(voice_bank::DEFAULT_VOICE_MANIFEST_PATH)
+            .unwrap_or_else(|err| {
+                log::warn!("voice bank disabled: {err}");
+                VoiceBank::default()
+            });
+        let credits =
+            Credits::load_from_json_file(credits::DEFAULT_CREDITS_PATH).unwrap_or_else(|err| {
+                log::warn!("credits disabled: {err}");
+                Credits::default()
+            });
+
+        dialogue_ui.set_achievements_snapshot(achievements.snapshot());
+        dialogue_ui.set_glossary(glossary);
+        dialogue_ui.set_voice_bank(voice_bank);
+        dialogue_ui.set_gallery(gallery);
+        dialogue_ui.set_credits(credits);
+        if let Err(err) = dialogue_ui.load_locale_directory(locale::DEFAULT_LOCALES_DIR) {
+            log::warn!("failed to load locales: {err}");
+        }
+        if let Err(err) = dialogue_ui.load_custom_themes(ui_theme::DEFAULT_THEMES_PATH) {
+            log::warn!("custom themes disabled: {err}");
+        }
+        dialogue_ui.set_continue_available(
+            save::latest_slot(paths::user_path(save::DEFAULT_SAVE_DIR)).is_some(),
+        );
+        self.action_map =
+            ActionMap::load_from_json_file(paths::user_path(input::DEFAULT_KEYBINDINGS_PATH))
+                .unwrap_or_else(|err| {
+                    log::warn!("using default keybindings: {err}");
+                    ActionMap::default()
+                });
+        let display_settings = self
+            .startup_display
+            .take()
+            .unwrap_or_else(|| load_display_settings(&self.cli));
+        dialogue_ui.set_display_settings(display_settings);
+        dialogue_ui.set_available_displays(display::available_displays(event_loop));
+        dialogue_ui.set_main_menu_enabled(true);
+        if self.cli.debug {
+            dialogue_ui.toggle_debug_overlay();
+        }
+        if let Some(path) = self.cli.scene.as_ref() {
+            match save::read_file(path) {
+                Ok(save_game) => self.startup_save = Some(save_game),
+                Err(err) => log::error!("failed to load scene {}: {err}", path.display()),
+            }
+        }
+        if self.cli.skip_menu {
+            self.startup_command = Some(UiCommand::StartGame);
+        }
 
-            self.tex = Some(tex);
-            self.dialogue_ui = Some(dialogue_ui);
-            self.audio = audio;
-            self.achievements = Some(achievements);
-            self.scene_runner = Some(scene_runner);
-            self.last_frame_time = Some(Instant::now());
-            self.mode = AppMode::MainMenu;
-            self.scene_bootstrapped = false;
+        self.tex = Some(tex);
+        self.dialogue_ui = Some(dialogue_ui);
+        self.audio = audio;
+        let stinger_pending = self.unlock_stinger_pending.clone();
+        achievements.subscribe(move |unlock| {
+            if !unlock.silent {
+                stinger_pending.set(true);
+            }
+        });
+        #[cfg(feature = "steam")]
+        {
+            self.steam = steam::SteamAchievements::init(&mut achievements)
+                .inspect_err(|err| log::warn!("steam achievements disabled: {err}"))
+                .ok();
+        }
+        self.achievements = Some(achievements);
+        #[cfg(debug_assertions)]
+        {
+            self.achievements_watcher = Some(hot_reload::FileWatcher::new(achievements_path));
         }
+        self.scene_runner = Some(scene_runner);
+        self.last_frame_time = Some(Instant::now());
+        self.mode = AppMode::MainMenu;
+        self.scene_bootstrapped = false;
+        self.state = Some(state);
 
         // Request initial redraw
         if let Some(window) = &self.window {
@@ -155,29 +446,300 @@ impl ApplicationHandler for App {
         }
     }
 
+    // Runs `SHUTDOWN_SEQUENCE` once and ends the event loop.
+    fn shutdown(&mut self, event_loop: &ActiveEventLoop) {
+        if std::mem::replace(&mut self.shut_down, true) {
+            return;
+        }
+        log::info!("shutting down");
+        for step in SHUTDOWN_SEQUENCE {
+            self.run_shutdown_step(step);
+        }
+        event_loop.exit();
+    }
+
+    fn run_shutdown_step(&mut self, step: ShutdownStep) {
+        match step {
+            ShutdownStep::DestroyScripts => {
+                if !self.scene_bootstrapped {
+                    return;
+                }
+                if let (
+                    Some(scene_runner),
+                    Some(state),
+                    Some(tex),
+                    Some(dialogue_ui),
+                    Some(achievements),
+                ) = (
+                    self.scene_runner.as_mut(),
+                    self.state.as_ref(),
+                    self.tex.as_mut(),
+                    self.dialogue_ui.as_mut(),
+                    self.achievements.as_mut(),
+                ) {
+                    let mut script_context = ScriptContext {
+                        device: &state.device,
+                        queue: &state.queue,
+                        tex,
+                        dialogue_ui,
+                        achievements,
+                        stats: &mut self.stats,
+                        flags: &mut self.flags,
+                        persistent_flags: &mut self.persistent_flags,
+                        audio: self.audio.as_mut(),
+                        input: &self.input,
+                    };
+                    if let Err(err) = scene_runner.destroy(&mut script_context) {
+                        log::warn!("scene script on_destroy failed: {err}");
+                    }
+                }
+            }
+            ShutdownStep::Autosave => {
+                if self.scene_bootstrapped {
+                    write_autosave(self.scene_runner.as_ref(), &self.flags);
+                }
+            }
+            ShutdownStep::SaveProgress => {
+                write_stats(&mut self.stats);
+                if let Some(achievements) = self.achievements.as_mut()
+                    && let Err(err) = achievements.save_progress_to_json_file(paths::user_path(
+                        scripts::achievements_catalog::DEFAULT_ACHIEVEMENT_PROGRESS_PATH,
+                    ))
+                {
+                    log::error!("failed to save achievements progress: {err}");
+                }
+                if let Err(err) = self
+                    .persistent_flags
+                    .save_to_json_file(paths::user_path(flags::DEFAULT_PERSISTENT_FLAGS_PATH))
+                {
+                    log::error!("failed to save persistent flags: {err}");
+                }
+            }
+            // Display changes are saved once applied, so only those made on the last
+            // frame are left; anything applied already may hold command-line overrides.
+            ShutdownStep::SaveSettings => {
+                if let Some(dialogue_ui) = self.dialogue_ui.as_ref()
+                    && self.applied_display.is_some()
+                    && self.applied_display.as_ref() != Some(dialogue_ui.display_settings())
+                    && let Err(err) = dialogue_ui
+                        .display_settings()
+                        .save_to_json_file(paths::user_path(display::DEFAULT_DISPLAY_SETTINGS_PATH))
+                {
+                    log::error!("failed to save display settings: {err}");
+                }
+            }
+            ShutdownStep::FadeOutAudio => {
+                if let Some(audio) = self.audio.as_mut() {
+                    if let Some(window) = self.window.as_ref() {
+                        window.set_visible(false);
+                    }
+                    audio.fade_out_all(SHUTDOWN_AUDIO_FADE);
+                }
+            }
+        }
+    }
+
+    // Headphones plugged in after a failed startup bring sound back without a restart.
+    fn retry_audio_init(&mut self) {
+        if self.audio.is_some() {
+            return;
+        }
+        let now = Instant::now();
+        if self.audio_retry_at.is_none_or(|at| now < at) {
+            return;
+        }
+        self.audio_retry_at = Some(now + AUDIO_RETRY_INTERVAL);
+
+        if let Ok(mut audio) = AudioEngine::new() {
+            let typewriter_volume = register_builtin_sounds(&mut audio);
+            if let Some(dialogue_ui) = self.dialogue_ui.as_mut() {
+                dialogue_ui.set_typewriter_sound("dialogue_typewriter", typewriter_volume);
+            }
+            log::info!("audio device found, sound enabled");
+            self.audio = Some(audio);
+            self.audio_retry_at = None;
+        }
+    }
+}
+
+impl ApplicationHandler<AppEvent> for App {
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if self.state.as_ref().is_some_and(State::is_device_lost) {
+            self.recover_from_device_loss(event_loop);
+        }
+        self.retry_audio_init();
+
+        #[cfg(debug_assertions)]
+        if let (Some(watcher), Some(achievements)) = (
+            self.achievements_watcher.as_mut(),
+            self.achievements.as_mut(),
+        ) && watcher.poll()
+        {
+            match AchievementManager::load_from_json_file(watcher.path()) {
+                Ok(catalog) => {
+                    achievements.merge_catalog(catalog);
+                    log::info!("reloaded achievements catalog {}", watcher.path().display());
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                Err(err) => log::warn!("failed to reload achievements catalog: {err}"),
+            }
+        }
+
+        let now = Instant::now();
+        if self.redraw_at.is_some_and(|at| at <= now) {
+            self.redraw_at = None;
+            if let Some(window) = &self.window {
+                window.request_redraw();
+            }
+        }
+
+        // Sleep until the next event unless something is scheduled; frames that must keep
+        // coming are requested by the redraw handler itself.
+        let mut wake_at = self.redraw_at;
+        if self.audio.is_none() {
+            wake_at = earliest(wake_at, self.audio_retry_at);
+        }
+        #[cfg(debug_assertions)]
+        {
+            wake_at = earliest(
+                wake_at,
+                self.achievements_watcher
+                    .as_ref()
+                    .map(hot_reload::FileWatcher::next_poll),
+            );
+        }
+        event_loop.set_control_flow(match wake_at {
+            Some(at) => ControlFlow::WaitUntil(at),
+            None => ControlFlow::Wait,
+        });
+    }
+
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(state) = self.state.as_mut() {
+            log::debug!("resumed, recreating surface");
+            state.resume_surface();
+            let size = state.window.inner_size();
+            resize_surface(state, self.tex.as_mut(), size);
+            state.redraw();
+            if let Some(audio) = self.audio.as_mut() {
+                audio.on_suspended(false);
+            }
+            return;
+        }
+        // The browser is still creating the state for this window.
+        if self.window.is_some() {
+            return;
+        }
+        log::debug!("resumed, creating window");
+        let display_settings = load_display_settings(&self.cli);
+        self.gpu = gpu_settings(&display_settings.gpu, &self.cli);
+        self.startup_display = Some(display_settings);
+        let gpu = self.gpu.clone();
+
+        let window = Arc::new(event_loop.create_window(window_attributes()).unwrap());
+        self.scale_factor = window.scale_factor();
+        self.window = Some(window.clone());
+        self.request_state(event_loop, window, gpu);
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        log::debug!("suspended, releasing surface");
+        if let Some(state) = self.state.as_mut() {
+            state.suspend_surface();
+        }
+        if let Some(audio) = self.audio.as_mut() {
+            audio.on_suspended(true);
+        }
+        // Time spent in the background is neither play time nor a timeline step.
+        self.last_frame_time = None;
+        self.redraw_at = None;
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: AppEvent) {
+        match event {
+            AppEvent::StateReady(state) => self.state_ready(event_loop, state),
+        }
+    }
+
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        self.input.on_device_event(&event);
+    }
+
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
         _id: winit::window::WindowId,
         event: WindowEvent,
     ) {
-        if self.input.on_window_event(&event) {
-            if let Some(window) = &self.window {
-                window.request_redraw();
-            }
-        }
+        // Key presses go to the rebinding prompt instead of gameplay actions while it is open.
+        // Releases and mouse input still reach InputState so nothing held stays down.
+        let ui_captures_key = matches!(
+            &event,
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed
+        ) && self
+            .dialogue_ui
+            .as_ref()
+            .is_some_and(|dialogue_ui| dialogue_ui.is_capturing_key_binding());
 
+        // egui sees events first so input it consumes does not also trigger game actions.
+        let mut ui_consumed = false;
         if let (Some(dialogue_ui), Some(window)) = (self.dialogue_ui.as_mut(), self.window.as_ref())
         {
-            if dialogue_ui.on_window_event(window.as_ref(), &event) {
+            let response = dialogue_ui.on_window_event(window.as_ref(), &event);
+            if response.repaint {
                 window.request_redraw();
             }
+            // No widget uses the middle button, keep it available for HideUi.
+            let middle_click = matches!(
+                event,
+                WindowEvent::MouseInput {
+                    button: MouseButton::Middle,
+                    ..
+                }
+            );
+            ui_consumed = response.consumed && !middle_click;
+        }
+
+        if !ui_captures_key
+            && self.input.on_window_event(&event, ui_consumed)
+            && let Some(window) = &self.window
+        {
+            window.request_redraw();
         }
 
         match event {
-            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::CloseRequested => self.shutdown(event_loop),
 
             WindowEvent::RedrawRequested => {
+                // Nothing to present while suspended or minimized; resuming and restoring
+                // request a frame again.
+                if self.state.as_ref().is_some_and(State::is_hidden) {
+                    self.last_frame_time = None;
+                    return;
+                }
+                // Frames arriving sooner than the FPS cap allows are deferred to `about_to_wait`.
+                let frame_limit = self
+                    .dialogue_ui
+                    .as_ref()
+                    .and_then(|dialogue_ui| dialogue_ui.display_settings().frame_limit());
+                let now = Instant::now();
+                if let (Some(limit), Some(last)) = (frame_limit, self.last_redraw_at) {
+                    let due = last + Duration::from_secs_f64(1.0 / f64::from(limit.max(1)));
+                    if now < due {
+                        self.redraw_at = Some(due);
+                        return;
+                    }
+                }
+                self.last_redraw_at = Some(now);
+                let _frame = profiler::frame();
+
                 if let (
                     Some(state),
                     Some(tex),
@@ -185,28 +747,118 @@ impl ApplicationHandler for App {
                     Some(window),
                     Some(achievements),
                 ) = (
-                    self.state.as_ref(),
+                    self.state.as_mut(),
                     self.tex.as_mut(),
                     self.dialogue_ui.as_mut(),
                     self.window.as_ref(),
                     self.achievements.as_mut(),
                 ) {
-                    if self.action_map.just_pressed(Action::Exit, &self.input) {
-                        event_loop.exit();
+                    self.input.begin_frame();
+                    game_frame::set_input_contexts(
+                        &mut self.action_map,
+                        dialogue_ui,
+                        matches!(self.mode, AppMode::InGame),
+                    );
+
+                    // While the UI is hidden the next press only restores it (handled after render).
+                    let ui_hidden = dialogue_ui.is_ui_hidden();
+
+                    if !ui_hidden && self.action_map.just_pressed(Action::Exit, &self.input) {
+                        self.shutdown(event_loop);
                         return;
                     }
 
-                    if matches!(self.mode, AppMode::InGame)
-                        && self.action_map.just_pressed(Action::SkipWait, &self.input)
-                        && dialogue_ui.can_skip_wait()
+                    if !ui_hidden
+                        && self
+                            .action_map
+                            .just_pressed(Action::ToggleDebugOverlay, &self.input)
                     {
-                        if let Some(scene_runner) = self.scene_runner.as_mut() {
-                            // Broadcast to all scripts (used for dialogue skip/close behavior).
-                            scene_runner.send_signal(ScriptSignal::SkipWait);
+                        dialogue_ui.toggle_debug_overlay();
+                    }
+                    if !ui_hidden
+                        && self
+                            .action_map
+                            .just_pressed(Action::ToggleProfiler, &self.input)
+                    {
+                        dialogue_ui.toggle_profiler();
+                    }
+
+                    if !ui_hidden
+                        && self
+                            .action_map
+                            .just_pressed(Action::ToggleFullscreen, &self.input)
+                    {
+                        dialogue_ui.toggle_display_mode();
+                    }
+                    let display = dialogue_ui.display_settings();
+                    if self.applied_display.as_ref() != Some(display) {
+                        if self
+                            .applied_display
+                            .as_ref()
+                            .is_none_or(|applied| !applied.same_window(display))
+                        {
+                            window.set_fullscreen(display.fullscreen(window));
+                            let size = display
+                                .window_size()
+                                .and_then(|size| window.request_inner_size(size))
+                                .unwrap_or_else(|| window.inner_size());
+                            // Most platforms follow up with Resized; this covers those that do not.
+                            resize_surface(state, Some(&mut *tex), size);
                         }
+                        // The first frame only applies what was loaded.
+                        if self.applied_display.is_some()
+                            && let Err(err) = display.save_to_json_file(paths::user_path(
+                                display::DEFAULT_DISPLAY_SETTINGS_PATH,
+                            ))
+                        {
+                            log::error!("failed to save display settings: {err}");
+                        }
+                        self.applied_display = Some(display.clone());
                     }
 
-                    let dt = if matches!(self.mode, AppMode::InGame) {
+                    if matches!(self.mode, AppMode::Loading) {
+                        if let Some(scene_loader) = self.scene_loader.as_mut() {
+                            scene_loader.poll(
+                                &state.device,
+                                &state.queue,
+                                tex,
+                                self.audio.as_ref(),
+                            );
+                            dialogue_ui.set_loading_progress(Some(scene_loader.progress()));
+                        }
+                        // The scene starts on the frame its last asset became resident.
+                        if self
+                            .scene_loader
+                            .as_ref()
+                            .is_none_or(SceneLoader::is_finished)
+                        {
+                            self.scene_loader = None;
+                            dialogue_ui.set_loading_progress(None);
+                            if let Some(scene_runner) = self.scene_runner.as_mut() {
+                                let mut script_context = ScriptContext {
+                                    device: &state.device,
+                                    queue: &state.queue,
+                                    tex,
+                                    dialogue_ui,
+                                    achievements,
+                                    stats: &mut self.stats,
+                                    flags: &mut self.flags,
+                                    persistent_flags: &mut self.persistent_flags,
+                                    audio: self.audio.as_mut(),
+                                    input: &self.input,
+                                };
+                                scene_runner
+                                    .update(0.0, &mut script_context)
+                                    .expect("failed to initialize scene script");
+                            }
+                            self.scene_bootstrapped = true;
+                            self.mode = AppMode::InGame;
+                            self.tick_accumulator = 0.0;
+                            self.last_frame_time = Some(Instant::now());
+                        }
+                    }
+
+                    let raw_dt = if matches!(self.mode, AppMode::InGame) {
                         let now = Instant::now();
                         let dt = self
                             .last_frame_time
@@ -217,42 +869,130 @@ impl ApplicationHandler for App {
                     } else {
                         0.0
                     };
+                    let FrameUpdate {
+                        dt,
+                        paused,
+                        gameplay_keys,
+                    } = GameFrame {
+                        device: &state.device,
+                        queue: &state.queue,
+                        tex,
+                        dialogue_ui,
+                        scene_runner: self.scene_runner.as_mut(),
+                        achievements,
+                        stats: &mut self.stats,
+                        flags: &mut self.flags,
+                        persistent_flags: &mut self.persistent_flags,
+                        audio: self.audio.as_mut(),
+                        input: &mut self.input,
+                        action_map: &self.action_map,
+                        signal_routes: &self.signal_routes,
+                        in_game: matches!(self.mode, AppMode::InGame),
+                    }
+                    .update(&mut self.tick_accumulator, raw_dt)
+                    .expect("failed to update scene script");
 
-                    if matches!(self.mode, AppMode::InGame) {
-                        if let Some(scene_runner) = self.scene_runner.as_mut() {
-                            let mut script_context = ScriptContext {
-                                device: &state.device,
-                                queue: &state.queue,
-                                tex,
-                                dialogue_ui,
-                                achievements,
-                                audio: self.audio.as_mut(),
-                            };
-                            // Per-frame lifecycle update for all active scripts.
-                            scene_runner
-                                .update(dt, &mut script_context)
-                                .expect("failed to update scene script");
+                    let text_entry = self.input.is_text_entry_active();
+                    if text_entry != self.ime_allowed {
+                        window.set_ime_allowed(text_entry);
+                        self.ime_allowed = text_entry;
+                    }
+
+                    if gameplay_keys {
+                        let pressed = |action| self.action_map.just_pressed(action, &self.input);
+                        if pressed(Action::QuickSave)
+                            && let Some(scene_runner) = self.scene_runner.as_ref()
+                        {
+                            let (kind, key, body) =
+                                match write_save(save::QUICKSAVE_SLOT, scene_runner, &self.flags) {
+                                    Ok(()) => {
+                                        (ToastKind::Success, "toast.quick_saved", String::new())
+                                    }
+                                    Err(err) => {
+                                        log::error!("quick save failed: {err}");
+                                        (ToastKind::Error, "toast.quick_save_failed", err)
+                                    }
+                                };
+                            let title = dialogue_ui.tr(key).to_owned();
+                            dialogue_ui.push_toast(kind, title, body, 2.5);
+                        }
+
+                        if pressed(Action::QuickLoad) {
+                            let loaded = save::read_slot(
+                                paths::user_path(save::DEFAULT_SAVE_DIR),
+                                save::QUICKSAVE_SLOT,
+                            )
+                            .and_then(|save_game| {
+                                load_save_into_fresh_scene(
+                                    save_game,
+                                    tex,
+                                    dialogue_ui,
+                                    &mut self.flags,
+                                )
+                            });
+                            match loaded {
+                                // start() runs once the loading screen is done.
+                                Ok(scene_runner) => {
+                                    self.scene_loader = Some(SceneLoader::start(
+                                        scene_runner.required_assets(),
+                                        tex,
+                                        self.audio.as_mut(),
+                                    ));
+                                    self.scene_runner = Some(scene_runner);
+                                    self.mode = AppMode::Loading;
+                                    let title = dialogue_ui.tr("toast.quick_loaded").to_owned();
+                                    dialogue_ui.push_toast(
+                                        ToastKind::Success,
+                                        title,
+                                        String::new(),
+                                        2.5,
+                                    );
+                                }
+                                Err(err) => {
+                                    log::warn!("quick load failed: {err}");
+                                    let title = dialogue_ui.tr("toast.continue_failed").to_owned();
+                                    dialogue_ui.push_toast(ToastKind::Error, title, err, 6.0);
+                                }
+                            }
                         }
                     }
 
-                    dialogue_ui.set_achievements_snapshot(achievements.snapshot());
-                    dialogue_ui
-                        .enqueue_achievement_notifications(achievements.take_notifications());
+                    // Draw objects between the last two ticks.
+                    tex.interpolate(self.tick_accumulator / SIMULATION_TICK, &state.queue);
+
+                    let save_scope = profiler::scope("save_persistent_flags");
+                    if let Err(err) = self
+                        .persistent_flags
+                        .save_to_json_file(paths::user_path(flags::DEFAULT_PERSISTENT_FLAGS_PATH))
+                    {
+                        log::error!("failed to save persistent flags: {err}");
+                    }
+                    drop(save_scope);
 
                     // Acquire the current frame from the window surface.
-                    let frame = state
-                        .surface
-                        .get_current_texture()
-                        .expect("Failed to acquire next swap chain texture");
+                    state.set_present_mode(dialogue_ui.display_settings().present_mode);
+                    let acquire_scope = profiler::scope("acquire_frame");
+                    let Some(frame) = state.acquire_frame() else {
+                        return;
+                    };
+                    drop(acquire_scope);
 
                     let view = frame
                         .texture
                         .create_view(&wgpu::TextureViewDescriptor::default());
 
                     // Render the scene and dialogue UI into this frame.
+                    let render_scope = profiler::scope("scene_render");
                     tex.render(&view, &state.device, &state.queue);
+                    drop(render_scope);
+                    dialogue_ui.set_render_stats(tex.render_stats());
+                    if let Some(audio) = self.audio.as_mut() {
+                        // Sounds attached to scene objects follow them across the screen.
+                        audio.update_positions(|key| tex.object_screen_x(key));
+                    }
                     let audio = self.audio.as_mut();
-                    let ui_command = dialogue_ui.render(
+                    let ui_scope = profiler::scope("ui");
+                    let mut ui_command = dialogue_ui.render(
                         window.as_ref(),
                         &state.device,
                         &state.queue,
@@ -260,86 +1000,243 @@ impl ApplicationHandler for App {
                         dt,
                         audio,
                     );
+                    drop(ui_scope);
+                    if ui_command == UiCommand::None
+                        && let Some(command) = self.startup_command.take()
+                    {
+                        ui_command = command;
+                    }
 
                     // Present the frame on screen.
+                    let present_scope = profiler::scope("present");
                     frame.present();
+                    drop(present_scope);
+
+                    // Toggled after rendering so the restoring click does not also reach the dialogue box.
+                    if ui_hidden {
+                        if self.input.has_any_press() {
+                            dialogue_ui.set_ui_hidden(false);
+                            window.request_redraw();
+                        }
+                    } else if matches!(self.mode, AppMode::InGame)
+                        && !dialogue_ui.is_awaiting_text_input()
+                        && (self.action_map.just_pressed(Action::HideUi, &self.input)
+                            || self.input.was_mouse_just_pressed(MouseButton::Middle))
+                    {
+                        dialogue_ui.set_ui_hidden(true);
+                        window.request_redraw();
+                    }
+
+                    let binding_changes = dialogue_ui.take_binding_changes();
+                    if !binding_changes.is_empty() {
+                        for change in binding_changes {
+                            self.action_map.apply_change(change);
+                        }
+                        if let Err(err) = self
+                            .action_map
+                            .save_to_json_file(paths::user_path(input::DEFAULT_KEYBINDINGS_PATH))
+                        {
+                            log::error!("failed to save keybindings: {err}");
+                        }
+                    }
 
                     match ui_command {
                         UiCommand::None => {}
-                        UiCommand::StartGame => {
-                            if !self.scene_bootstrapped {
+                        UiCommand::StartGame | UiCommand::ContinueGame => {
+                            if self.scene_bootstrapped {
+                                self.mode = AppMode::InGame;
+                                self.last_frame_time = Some(Instant::now());
+                            } else {
                                 if let Some(scene_runner) = self.scene_runner.as_mut() {
-                                    let mut script_context = ScriptContext {
-                                        device: &state.device,
-                                        queue: &state.queue,
-                                        tex,
-                                        dialogue_ui,
-                                        achievements,
-                                        audio: self.audio.as_mut(),
+                                    let save_game = if ui_command == UiCommand::ContinueGame {
+                                        Some(save::read_latest(paths::user_path(
+                                            save::DEFAULT_SAVE_DIR,
+                                        )))
+                                    } else {
+                                        self.startup_save.take().map(Ok)
                                     };
-                                    scene_runner
-                                        .update(0.0, &mut script_context)
-                                        .expect("failed to initialize scene script");
+                                    if let Some(save_game) = save_game {
+                                        // Restore before the first update so start() sees saved progress.
+                                        let restored = save_game.and_then(|save_game| {
+                                            self.flags.restore(save_game.flags);
+                                            scene_runner.restore_states(&save_game.scripts)
+                                        });
+                                        if let Err(err) = restored {
+                                            log::warn!(
+                                                "failed to continue from latest save: {err}"
+                                            );
+                                            let title =
+                                                dialogue_ui.tr("toast.continue_failed").to_owned();
+                                            dialogue_ui.push_toast(
+                                                ToastKind::Error,
+                                                title,
+                                                err,
+                                                6.0,
+                                            );
+                                        }
+                                    }
+
+                                    self.scene_loader = Some(SceneLoader::start(
+                                        scene_runner.required_assets(),
+                                        tex,
+                                        self.audio.as_mut(),
+                                    ));
                                 }
-                                self.scene_bootstrapped = true;
+                                // start() runs once the loading screen is done.
+                                self.mode = AppMode::Loading;
                             }
-                            self.mode = AppMode::InGame;
                             dialogue_ui.set_main_menu_enabled(false);
-                            self.last_frame_time = Some(Instant::now());
                             window.request_redraw();
                         }
-                        UiCommand::SkipWait => {
-                            if matches!(self.mode, AppMode::InGame) && dialogue_ui.can_skip_wait() {
-                                if let Some(scene_runner) = self.scene_runner.as_mut() {
-                                    scene_runner.send_signal(ScriptSignal::SkipWait);
+                        // Reaches the scripts through `after_ui` below.
+                        UiCommand::SkipWait => window.request_redraw(),
+                        UiCommand::ExportProfile => {
+                            let path = paths::user_path(profiler::DEFAULT_PROFILE_EXPORT_PATH);
+                            let (kind, key, body) = match profiler::export_folded(&path) {
+                                Ok(_) => (
+                                    ToastKind::Success,
+                                    "toast.profile_exported",
+                                    path.display().to_string(),
+                                ),
+                                Err(err) => {
+                                    log::error!("{err}");
+                                    (ToastKind::Error, "toast.profile_export_failed", err)
                                 }
-                                window.request_redraw();
-                            }
+                            };
+                            let title = dialogue_ui.tr(key).to_owned();
+                            dialogue_ui.push_toast(kind, title, body, 4.0);
                         }
                         UiCommand::ExitApp => {
-                            event_loop.exit();
+                            self.shutdown(event_loop);
                             return;
                         }
                     }
 
-                    let achievements_path =
-                        scripts::achievements_catalog::DEFAULT_ACHIEVEMENTS_PATH;
-                    if let Err(err) = achievements.save_to_json_file(achievements_path) {
-                        eprintln!("failed to save achievements progress: {err}");
+                    if dialogue_ui.take_achievement_reset_request() {
+                        achievements.reset_all();
+                        #[cfg(feature = "steam")]
+                        if let Some(steam) = self.steam.as_mut() {
+                            steam.reset_all();
+                        }
+                        let title = dialogue_ui.tr("toast.achievements_reset").to_owned();
+                        dialogue_ui.push_toast(ToastKind::Info, title, String::new(), 2.5);
+                    }
+                    for path in integrity::take_flagged() {
+                        let title = dialogue_ui.tr("toast.save_tampered").to_owned();
+                        dialogue_ui.push_toast(
+                            ToastKind::Error,
+                            title,
+                            path.display().to_string(),
+                            4.0,
+                        );
+                    }
+                    GameFrame {
+                        device: &state.device,
+                        queue: &state.queue,
+                        tex,
+                        dialogue_ui,
+                        scene_runner: self.scene_runner.as_mut(),
+                        achievements,
+                        stats: &mut self.stats,
+                        flags: &mut self.flags,
+                        persistent_flags: &mut self.persistent_flags,
+                        audio: self.audio.as_mut(),
+                        input: &mut self.input,
+                        action_map: &self.action_map,
+                        signal_routes: &self.signal_routes,
+                        in_game: matches!(self.mode, AppMode::InGame),
+                    }
+                    .after_ui(ui_command, paused);
+                    if let Err(err) = self
+                        .stats
+                        .save_to_json_file(paths::user_path(stats::DEFAULT_STATS_PATH))
+                    {
+                        log::error!("failed to save stats: {err}");
+                    }
+                    #[cfg(feature = "steam")]
+                    if let Some(steam) = self.steam.as_mut() {
+                        steam.update(achievements);
+                    }
+                    if self.unlock_stinger_pending.replace(false)
+                        && let Some(audio) = self.audio.as_mut()
+                        && let Err(err) = audio.play(ACHIEVEMENT_STINGER_SOUND, AudioBus::Ui, 1.0)
+                    {
+                        log::warn!("achievement stinger playback failed: {err}");
+                    }
+                    if let Err(err) = achievements.save_progress_to_json_file(paths::user_path(
+                        scripts::achievements_catalog::DEFAULT_ACHIEVEMENT_PROGRESS_PATH,
+                    )) {
+                        log::error!("failed to save achievements progress: {err}");
                     }
 
-                    let has_achievement_popup = dialogue_ui.has_active_achievement_popup();
+                    // The debug overlay and profiler need continuous frames to measure.
+                    let ui_needs_redraw = dialogue_ui.has_active_toast()
+                        || dialogue_ui.is_debug_overlay_visible()
+                        || dialogue_ui.is_profiler_visible()
+                        || self
+                            .audio
+                            .as_ref()
+                            .is_some_and(AudioEngine::has_active_fades);
                     if matches!(self.mode, AppMode::InGame) {
                         let scripts_are_running = self
                             .scene_runner
                             .as_ref()
                             .is_some_and(|runner| !runner.is_finished());
-                        let dialogue_is_animating = dialogue_ui.has_active_typewriter_animation();
+                        let dialogue_is_animating = dialogue_ui.has_active_typewriter_animation()
+                            || dialogue_ui.has_active_dialogue_transition()
+                            || dialogue_ui.is_auto_advance_pending();
 
-                        if scripts_are_running || dialogue_is_animating || has_achievement_popup {
+                        if scripts_are_running || dialogue_is_animating || ui_needs_redraw {
                             window.request_redraw();
                         }
-                    } else if has_achievement_popup {
+                    } else if ui_needs_redraw || matches!(self.mode, AppMode::Loading) {
                         window.request_redraw();
                     }
+                    self.redraw_at = None;
+                    match dialogue_ui.repaint_delay() {
+                        Some(delay) if delay.is_zero() => window.request_redraw(),
+                        Some(delay) => self.redraw_at = Some(Instant::now() + delay),
+                        None => {}
+                    }
 
                     self.input.end_frame();
                 }
             }
 
+            WindowEvent::Focused(focused) => {
+                if let (Some(audio), Some(dialogue_ui)) = (self.audio.as_mut(), &self.dialogue_ui) {
+                    audio.on_focus_changed(focused, dialogue_ui.focus_loss_audio());
+                }
+            }
+
             WindowEvent::Resized(new_size) => {
                 if let Some(state) = &mut self.state {
-                    if let Some(config) = &mut state.config {
-                        config.width = new_size.width.max(1);
-                        config.height = new_size.height.max(1);
-                        state.surface.configure(&state.device, config);
+                    resize_surface(state, self.tex.as_mut(), new_size);
+                    state.redraw();
+                }
+            }
 
-                        if let Some(tex) = self.tex.as_mut() {
-                            tex.resize(config, &state.device, &state.queue);
-                        }
+            // Moved to a monitor with a different DPI. egui follows through `on_window_event`;
+            // the surface and projection are resized here, without waiting for the `Resized`
+            // that not every platform sends.
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                mut inner_size_writer,
+            } => {
+                let previous = std::mem::replace(&mut self.scale_factor, scale_factor);
+                let Some(state) = &mut self.state else {
+                    return;
+                };
+                let mut size = state.window.inner_size();
+                // A window keeps its logical size; fullscreen keeps covering the monitor.
+                if state.window.fullscreen().is_none() {
+                    size = size.to_logical::<f64>(previous).to_physical(scale_factor);
+                    if let Err(err) = inner_size_writer.request_inner_size(size) {
+                        log::warn!("failed to resize the window for the new scale factor: {err}");
                     }
-                    state.redraw();
                 }
+                resize_surface(state, self.tex.as_mut(), size);
+                state.redraw();
             }
 
             _ => {}
@@ -347,10 +1244,270 @@ impl ApplicationHandler for App {
     }
 }
 
+// Registers the typewriter and menu sounds; returns the typewriter volume that suits the
+// registered clip.
+fn register_builtin_sounds(audio_engine: &mut AudioEngine) -> f32 {
+    // Built-in short blip used by dialogue typewriter.
+    audio_engine.register_tone("dialogue_typewriter", 1240, 18);
+    let _ = audio_engine.set_sound_defaults(
+        "dialogue_typewriter",
+        SoundDefaults::default().with_max_instances(3),
+    );
+
+    // Optional external override: place your own clip at assets/sfx/type_tick.wav.
+    let custom_typewriter = audio_engine
+        .register_sound_file("dialogue_typewriter", "assets/sfx/type_tick.wav")
+        .is_ok();
+
+    // Menu feedback blips, overridable with assets/sfx/ui_<kind>.wav.
+    let pluck = Envelope::new(
+        Duration::from_millis(1),
+        Duration::from_millis(24),
+        0.3,
+        Duration::from_millis(6),
+    );
+    for (kind, tone) in [
+        (
+            UiSound::Hover,
+            ToneSpec::new(880.0, Duration::from_millis(14)),
+        ),
+        (
+            UiSound::Click,
+            ToneSpec::new(660.0, Duration::from_millis(32))
+                .with_waveform(Waveform::Square)
+                .with_envelope(pluck)
+                .with_volume(0.10),
+        ),
+        (
+            UiSound::Confirm,
+            ToneSpec::new(990.0, Duration::from_millis(64))
+                .with_sweep(1480.0)
+                .with_envelope(pluck),
+        ),
+    ] {
+        let sound_id = kind.default_sound_id();
+        audio_engine.register_synth(sound_id, tone);
+        let _ = audio_engine.set_sound_defaults(
+            sound_id,
+            SoundDefaults::default()
+                .with_bus(AudioBus::Ui)
+                .with_max_instances(2),
+        );
+        let _ = audio_engine.register_sound_file(sound_id, format!("assets/sfx/{sound_id}.wav"));
+    }
+
+    // Unlock stinger, overridable with assets/sfx/achievement_unlocked.wav.
+    audio_engine.register_synth(
+        ACHIEVEMENT_STINGER_SOUND,
+        ToneSpec::new(660.0, Duration::from_millis(220))
+            .with_sweep(1320.0)
+            .with_envelope(pluck)
+            .with_volume(0.25),
+    );
+    let _ = audio_engine.set_sound_defaults(
+        ACHIEVEMENT_STINGER_SOUND,
+        SoundDefaults::default()
+            .with_bus(AudioBus::Ui)
+            .with_max_instances(1),
+    );
+    let _ = audio_engine.register_sound_file(
+        ACHIEVEMENT_STINGER_SOUND,
+        format!("assets/sfx/{ACHIEVEMENT_STINGER_SOUND}.wav"),
+    );
+
+    // These play constantly, so decode them before the first line types out.
+    let frequent = [
+        "dialogue_typewriter",
+        UiSound::Hover.default_sound_id(),
+        UiSound::Click.default_sound_id(),
+        UiSound::Confirm.default_sound_id(),
+    ];
+    if let Err(err) = audio_engine.preload(frequent) {
+        log::warn!("sound preload failed: {err}");
+    }
+
+    // Project sounds live in data, not code.
+    if let Err(err) = audio_engine.load_manifest(audio::DEFAULT_AUDIO_MANIFEST_PATH) {
+        log::warn!("audio manifest: {err}");
+    }
+    if custom_typewriter {
+        TYPEWRITER_CLIP_VOLUME
+    } else {
+        TYPEWRITER_TONE_VOLUME
+    }
+}
+
+// Written on exit so "Continue" can resume the session.
+fn write_autosave(scene_runner: Option<&SceneRunner>, flags: &FlagStore) {
+    let Some(scene_runner) = scene_runner else {
+        return;
+    };
+    if let Err(err) = write_save(save::AUTOSAVE_SLOT, scene_runner, flags) {
+        log::error!("autosave failed: {err}");
+    }
+}
+
+// Also written on exit, with playtime not yet saved by the periodic write.
+fn write_stats(stats: &mut StatsManager) {
+    stats.flush_playtime();
+    if let Err(err) = stats.save_to_json_file(paths::user_path(stats::DEFAULT_STATS_PATH)) {
+        log::error!("failed to save stats: {err}");
+    }
+}
+
+fn write_save(slot: &str, scene_runner: &SceneRunner, flags: &FlagStore) -> Result<(), String> {
+    let save_game = SaveGame {
+        flags: flags.snapshot(),
+        scripts: scene_runner.save_states(),
+        ..SaveGame::default()
+    };
+    save::write_slot(paths::user_path(save::DEFAULT_SAVE_DIR), slot, save_game)
+}
+
+// Rebuilds the scene from its initial scripts and fast-forwards them to the saved progress.
+fn load_save_into_fresh_scene(
+    save_game: SaveGame,
+    tex: &mut Tex,
+    dialogue_ui: &mut DialogueUi,
+    flags: &mut FlagStore,
+) -> Result<SceneRunner, String> {
+    let mut scene_runner = SceneRunner::with_scripts(scene_objects::create_initial_scene_scripts());
+    scene_runner.restore_states(&save_game.scripts)?;
+    tex.clear_game_objects();
+    dialogue_ui.clear_dialogues();
+    flags.restore(save_game.flags);
+    Ok(scene_runner)
+}
+
+// Saved display settings with this run's command-line overrides applied.
+fn load_display_settings(cli: &CliOptions) -> DisplaySettings {
+    let mut settings = DisplaySettings::load_from_json_file(paths::user_path(
+        display::DEFAULT_DISPLAY_SETTINGS_PATH,
+    ))
+    .unwrap_or_else(|err| {
+        log::warn!("using default display settings: {err}");
+        DisplaySettings::default()
+    });
+    if cli.windowed {
+        settings.mode = DisplayMode::Windowed;
+    }
+    settings
+}
+
+// GPU choice for this run; the overrides are not written back to the settings file.
+fn gpu_settings(saved: &GpuSettings, cli: &CliOptions) -> GpuSettings {
+    let mut gpu = saved.clone();
+    if let Some(adapter) = cli.gpu.as_ref() {
+        gpu.adapter = Some(adapter.clone());
+    }
+    if cli.low_power {
+        gpu.power_preference = PowerPreference::LowPower;
+    }
+    if cli.allow_fallback_adapter {
+        gpu.allow_fallback_adapter = true;
+    }
+    gpu
+}
+
+fn resize_surface(state: &mut State, tex: Option<&mut Tex>, size: PhysicalSize<u32>) {
+    let Some(config) = state.resize(size).cloned() else {
+        return;
+    };
+    if let Some(tex) = tex {
+        tex.resize(&config, &state.device, &state.queue);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn window_attributes() -> WindowAttributes {
+    WindowAttributes::default()
+}
+
+// Adds the canvas to the page body; the page's CSS decides where it goes.
+#[cfg(target_arch = "wasm32")]
+fn window_attributes() -> WindowAttributes {
+    use winit::platform::web::WindowAttributesExtWebSys;
+
+    WindowAttributes::default().with_append(true)
+}
+
+fn earliest(left: Option<Instant>, right: Option<Instant>) -> Option<Instant> {
+    match (left, right) {
+        (Some(left), Some(right)) => Some(left.min(right)),
+        (at, None) | (None, at) => at,
+    }
+}
+
 fn main() {
-    let event_loop = EventLoop::new().unwrap();
-    event_loop.set_control_flow(ControlFlow::Poll);
+    let cli = match CliOptions::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(CliExit::Help) => {
+            println!("{}", CliOptions::usage());
+            return;
+        }
+        Err(CliExit::ListAdapters) => {
+            let adapters = state::adapter_descriptions(&wgpu::Instance::default());
+            if adapters.is_empty() {
+                println!("no GPU adapters found");
+            }
+            for adapter in adapters {
+                println!("{adapter}");
+            }
+            return;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        Err(CliExit::BuildPack(out)) => match vfs::build_pack(&out) {
+            Ok(files) => {
+                println!("packed {files} files into {}", out.display());
+                return;
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        },
+        #[cfg(target_arch = "wasm32")]
+        Err(CliExit::BuildPack(_)) => return,
+        Err(CliExit::Error(err)) => {
+            eprintln!("{err}\n\n{}", CliOptions::usage());
+            std::process::exit(2);
+        }
+    };
+    if let Some(profile) = cli.profile.as_deref() {
+        paths::set_profile(profile);
+    }
+    logging::init(cli.debug);
+    crash::install();
+    #[cfg(not(target_arch = "wasm32"))]
+    vfs::mount_shipped(cli.pack.as_deref());
+    #[cfg(not(target_arch = "wasm32"))]
+    for (point, dir) in &cli.mounts {
+        vfs::mount_dir(point, dir);
+    }
+    profiler::set_enabled(cli.debug);
+    integrity::configure_from_build_env();
+    let event_loop = EventLoop::<AppEvent>::with_user_event().build().unwrap();
+    event_loop.set_control_flow(ControlFlow::Wait);
 
-    let mut app = App::default();
+    #[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+    let mut app = App {
+        cli,
+        #[cfg(target_arch = "wasm32")]
+        proxy: Some(event_loop.create_proxy()),
+        ..App::default()
+    };
+    #[cfg(not(target_arch = "wasm32"))]
     event_loop.run_app(&mut app).unwrap();
+    // Shipped files have to be fetched before anything reads them; the browser keeps
+    // running the loop after `main` returns.
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(async move {
+        use winit::platform::web::EventLoopExtWebSys;
+
+        match vfs::preload().await {
+            Ok(count) => log::debug!("preloaded {count} asset files"),
+            Err(err) => log::error!("asset preload failed: {err}"),
+        }
+        event_loop.spawn_app(app);
+    });
 }
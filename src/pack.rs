@@ -0,0 +1,359 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::read::DeflateDecoder;
+
+// Read-only archives of shipped files, mounted through `vfs::mount_pack`. Two formats:
+//   .pak: "GEPAK" magic, a version byte, an index of (name, offset, size) and the files
+//         stored uncompressed, as written by `write_pak`.
+//   .zip: stored or deflated entries; zip64, encryption and multi-disk archives are not
+//         supported.
+// Entry names are '/'-separated and relative, e.g. "src/data/audio.json".
+const PAK_MAGIC: &[u8; 7] = b"GEPAK\0\0";
+const PAK_VERSION: u8 = 1;
+
+const ZIP_LOCAL_HEADER: u32 = 0x0403_4b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x0201_4b50;
+const ZIP_END_OF_DIRECTORY: u32 = 0x0605_4b50;
+// Fixed part of the end-of-central-directory record; a comment of up to 64 KiB follows.
+const ZIP_END_OF_DIRECTORY_LEN: u64 = 22;
+const ZIP_STORED: u16 = 0;
+const ZIP_DEFLATED: u16 = 8;
+
+#[derive(Clone, Copy, Debug)]
+struct PackEntry {
+    offset: u64,
+    stored_size: u64,
+    size: u64,
+    deflated: bool,
+}
+
+#[derive(Debug)]
+pub struct Pack {
+    path: PathBuf,
+    entries: BTreeMap<String, PackEntry>,
+}
+
+impl Pack {
+    // Reads the index; file contents stay on disk until `read`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let failed = |err: io::Error| format!("failed to read pack {}: {err}", path.display());
+        let mut file = BufReader::new(File::open(path).map_err(failed)?);
+
+        let mut magic = [0; 8];
+        let read = file.read(&mut magic).map_err(failed)?;
+        file.rewind().map_err(failed)?;
+        let entries = if read == magic.len() && magic.starts_with(PAK_MAGIC) {
+            if magic[7] != PAK_VERSION {
+                return Err(format!(
+                    "pack {} has unsupported version {}",
+                    path.display(),
+                    magic[7]
+                ));
+            }
+            read_pak_index(&mut file).map_err(failed)?
+        } else {
+            read_zip_index(&mut file, path)?
+        };
+
+        Ok(Self {
+            path: path.to_owned(),
+            entries,
+        })
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    // None when the pack has no such entry. Opens the archive per call so loader threads
+    // can read in parallel.
+    pub fn read(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        let entry = *self.entries.get(name)?;
+        Some(self.read_entry(entry))
+    }
+
+    fn read_entry(&self, entry: PackEntry) -> io::Result<Vec<u8>> {
+        let corrupt = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("corrupt entry in pack {}", self.path.display()),
+            )
+        };
+        let mut file = File::open(&self.path)?;
+        // Offsets and sizes come from the archive's own index; the stored span has to fit
+        // in the file before any of them sizes a buffer.
+        let len = file.metadata()?.len();
+        if entry
+            .offset
+            .checked_add(entry.stored_size)
+            .is_none_or(|end| end > len)
+        {
+            return Err(corrupt());
+        }
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let stored = file.take(entry.stored_size);
+        let mut contents = Vec::new();
+        if entry.deflated {
+            // One byte past the expected size is enough to tell a lying index apart.
+            DeflateDecoder::new(stored)
+                .take(entry.size.saturating_add(1))
+                .read_to_end(&mut contents)?;
+        } else {
+            contents.reserve_exact(entry.stored_size as usize);
+            BufReader::new(stored).read_to_end(&mut contents)?;
+        }
+        if contents.len() as u64 != entry.size {
+            return Err(corrupt());
+        }
+        Ok(contents)
+    }
+}
+
+// Writes `files` (entry name, source file) into a .pak at `out`, replacing it.
+pub fn write_pak(out: &Path, files: &[(String, PathBuf)]) -> io::Result<()> {
+    let index_len: u64 = files
+        .iter()
+        .map(|(name, _)| 2 + name.len() as u64 + 8 + 8)
+        .sum();
+    let mut offset = PAK_MAGIC.len() as u64 + 1 + 4 + index_len;
+    let mut sizes = Vec::with_capacity(files.len());
+    for (_, source) in files {
+        sizes.push(std::fs::metadata(source)?.len());
+    }
+
+    let mut writer = BufWriter::new(File::create(out)?);
+    writer.write_all(PAK_MAGIC)?;
+    writer.write_all(&[PAK_VERSION])?;
+    let count = u32::try_from(files.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many files"))?;
+    writer.write_all(&count.to_le_bytes())?;
+    for ((name, _), size) in files.iter().zip(&sizes) {
+        let name_len = u16::try_from(name.len()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("name too long: {name}"),
+            )
+        })?;
+        writer.write_all(&name_len.to_le_bytes())?;
+        writer.write_all(name.as_bytes())?;
+        writer.write_all(&offset.to_le_bytes())?;
+        writer.write_all(&size.to_le_bytes())?;
+        offset += size;
+    }
+    for ((_, source), size) in files.iter().zip(&sizes) {
+        let copied = io::copy(&mut File::open(source)?, &mut writer)?;
+        if copied != *size {
+            return Err(io::Error::other(format!(
+                "{} changed while packing",
+                source.display()
+            )));
+        }
+    }
+    writer
+        .into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()
+}
+
+fn read_pak_index(file: &mut BufReader<File>) -> io::Result<BTreeMap<String, PackEntry>> {
+    file.seek(SeekFrom::Start(PAK_MAGIC.len() as u64 + 1))?;
+    let count = read_u32(file)?;
+    let mut entries = BTreeMap::new();
+    for _ in 0..count {
+        let name_len = read_u16(file)?;
+        let name = read_name(file, name_len)?;
+        let offset = read_u64(file)?;
+        let size = read_u64(file)?;
+        entries.insert(
+            name,
+            PackEntry {
+                offset,
+                stored_size: size,
+                size,
+                deflated: false,
+            },
+        );
+    }
+    Ok(entries)
+}
+
+fn read_zip_index(
+    file: &mut BufReader<File>,
+    path: &Path,
+) -> Result<BTreeMap<String, PackEntry>, String> {
+    let failed = |err: io::Error| format!("failed to read pack {}: {err}", path.display());
+    let invalid =
+        |problem: &str| format!("pack {} is not a supported zip: {problem}", path.display());
+
+    // The end record sits at the very end, before an optional comment.
+    let len = file.seek(SeekFrom::End(0)).map_err(failed)?;
+    if len < ZIP_END_OF_DIRECTORY_LEN {
+        return Err(invalid("too short"));
+    }
+    let tail_start = len.saturating_sub(ZIP_END_OF_DIRECTORY_LEN + u16::MAX as u64);
+    file.seek(SeekFrom::Start(tail_start)).map_err(failed)?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).map_err(failed)?;
+    let end = (0..=tail.len() - ZIP_END_OF_DIRECTORY_LEN as usize)
+        .rev()
+        .find(|&at| tail[at..at + 4] == ZIP_END_OF_DIRECTORY.to_le_bytes())
+        .ok_or_else(|| invalid("no end of central directory"))?;
+    let record = &tail[end..];
+    let disk = u16::from_le_bytes([record[4], record[5]]);
+    let count = u16::from_le_bytes([record[10], record[11]]);
+    let directory_offset = u32::from_le_bytes([record[16], record[17], record[18], record[19]]);
+    if disk != 0 {
+        return Err(invalid("multi-disk archive"));
+    }
+    if count == u16::MAX || directory_offset == u32::MAX {
+        return Err(invalid("zip64 archive"));
+    }
+
+    file.seek(SeekFrom::Start(directory_offset as u64))
+        .map_err(failed)?;
+    let mut central = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let mut header = [0; 46];
+        file.read_exact(&mut header).map_err(failed)?;
+        let field_u16 = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]);
+        let field_u32 = |at: usize| {
+            u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+        };
+        if field_u32(0) != ZIP_CENTRAL_HEADER {
+            return Err(invalid("broken central directory"));
+        }
+        let flags = field_u16(8);
+        let method = field_u16(10);
+        let stored_size = field_u32(20);
+        let size = field_u32(24);
+        // Some archivers write Windows separators.
+        let name = read_name(file, field_u16(28))
+            .map_err(failed)?
+            .replace('\\', "/");
+        let skip = field_u16(30) as i64 + field_u16(32) as i64;
+        file.seek_relative(skip).map_err(failed)?;
+        let header_offset = field_u32(42);
+
+        if name.ends_with('/') {
+            continue;
+        }
+        if flags & 1 != 0 {
+            log::warn!("skipping encrypted {name} in pack {}", path.display());
+            continue;
+        }
+        if method != ZIP_STORED && method != ZIP_DEFLATED {
+            log::warn!(
+                "skipping {name} in pack {}: compression method {method} is not supported",
+                path.display()
+            );
+            continue;
+        }
+        if stored_size == u32::MAX || size == u32::MAX || header_offset == u32::MAX {
+            return Err(invalid("zip64 entry"));
+        }
+        central.push((name, method, stored_size, size, header_offset));
+    }
+
+    // Local headers repeat the name and carry their own extra field, so the data offset
+    // is only known from there.
+    let mut entries = BTreeMap::new();
+    for (name, method, stored_size, size, header_offset) in central {
+        let mut header = [0; 30];
+        file.seek(SeekFrom::Start(header_offset as u64))
+            .map_err(failed)?;
+        file.read_exact(&mut header).map_err(failed)?;
+        if header[..4] != ZIP_LOCAL_HEADER.to_le_bytes() {
+            return Err(invalid("broken local header"));
+        }
+        let name_len = u16::from_le_bytes([header[26], header[27]]) as u64;
+        let extra_len = u16::from_le_bytes([header[28], header[29]]) as u64;
+        entries.insert(
+            name,
+            PackEntry {
+                offset: header_offset as u64 + 30 + name_len + extra_len,
+                stored_size: stored_size as u64,
+                size: size as u64,
+                deflated: method == ZIP_DEFLATED,
+            },
+        );
+    }
+    Ok(entries)
+}
+
+fn read_name(reader: &mut impl Read, len: u16) -> io::Result<String> {
+    let mut name = vec![0; len as usize];
+    reader.read_exact(&mut name)?;
+    String::from_utf8(name).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn read_u16(reader: &mut impl Read) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{Pack, write_pak};
+
+    #[test]
+    fn forged_entry_size_is_rejected() {
+        let dir = std::env::temp_dir().join(format!("pack-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("hello.txt");
+        fs::write(&source, b"hello").unwrap();
+        let pak = dir.join("test.pak");
+        write_pak(&pak, &[("hello.txt".to_owned(), source)]).unwrap();
+        assert_eq!(
+            Pack::open(&pak)
+                .unwrap()
+                .read("hello.txt")
+                .unwrap()
+                .unwrap(),
+            b"hello"
+        );
+
+        // Size field of the only index entry: magic, version, count, name length, name, offset.
+        let mut bytes = fs::read(&pak).unwrap();
+        let size_at = 7 + 1 + 4 + 2 + "hello.txt".len() + 8;
+        bytes[size_at..size_at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        fs::write(&pak, bytes).unwrap();
+        assert!(
+            Pack::open(&pak)
+                .unwrap()
+                .read("hello.txt")
+                .unwrap()
+                .is_err()
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+// Folder created inside the platform data directory.
+const APP_DIR_NAME: &str = "game_engine";
+// Overrides the data directory, e.g. for portable installs.
+const DATA_DIR_ENV: &str = "GAME_ENGINE_DATA_DIR";
+// Named profiles keep their data in `<data dir>/profiles/<name>`.
+const PROFILES_DIR: &str = "profiles";
+
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+// Switches to a named profile; only takes effect before the first `data_dir` call.
+pub fn set_profile(name: &str) {
+    let _ = PROFILE.set(name.to_owned());
+}
+
+// Root for everything the game writes: saves, progress, stats and settings. Shipped data
+// is still read relative to the working directory.
+//   Windows: %APPDATA%\game_engine
+//   macOS:   ~/Library/Application Support/game_engine
+//   other:   $XDG_DATA_HOME/game_engine or ~/.local/share/game_engine
+//   browser: game_engine, as a localStorage key prefix
+// Falls back to the working directory when none of those can be resolved.
+pub fn data_dir() -> &'static Path {
+    static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+    DATA_DIR.get_or_init(|| {
+        let dir = env_dir(DATA_DIR_ENV).unwrap_or_else(|| {
+            platform_data_dir()
+                .map(|dir| dir.join(APP_DIR_NAME))
+                .unwrap_or_else(|| {
+                    log::warn!("failed to resolve user data directory, using working directory");
+                    PathBuf::from(".")
+                })
+        });
+        match PROFILE.get() {
+            Some(profile) => dir.join(PROFILES_DIR).join(profile),
+            None => dir,
+        }
+    })
+}
+
+// Resolves a path relative to the data directory; absolute paths are kept as-is.
+pub fn user_path(relative: impl AsRef<Path>) -> PathBuf {
+    data_dir().join(relative)
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(target_os = "windows")]
+fn platform_data_dir() -> Option<PathBuf> {
+    env_dir("APPDATA")
+}
+
+#[cfg(target_os = "macos")]
+fn platform_data_dir() -> Option<PathBuf> {
+    env_dir("HOME").map(|home| home.join("Library/Application Support"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn platform_data_dir() -> Option<PathBuf> {
+    Some(PathBuf::new())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_arch = "wasm32")))]
+fn platform_data_dir() -> Option<PathBuf> {
+    env_dir("XDG_DATA_HOME")
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env_dir("HOME").map(|home| home.join(".local/share")))
+}
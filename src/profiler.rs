@@ -0,0 +1,284 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    path::Path,
+    time::Duration,
+};
+use web_time::Instant;
+
+use crate::vfs;
+
+// Relative to `paths::data_dir()`.
+pub const DEFAULT_PROFILE_EXPORT_PATH: &str = "profile.folded";
+// Frames kept for the profiler window and the export.
+const HISTORY_FRAMES: usize = 240;
+
+// A timed span; spans at depth 0 are direct children of the frame.
+#[derive(Clone, Debug)]
+pub struct ScopeRecord {
+    pub name: &'static str,
+    pub depth: usize,
+    // Offset from the start of the frame.
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FrameProfile {
+    pub duration: Duration,
+    // In start order, so every span comes right after the spans enclosing it.
+    pub scopes: Vec<ScopeRecord>,
+}
+
+// Per-frame cost of one span over the kept history; frames without it count as zero.
+#[derive(Clone, Debug)]
+pub struct ScopeStats {
+    pub name: &'static str,
+    pub depth: usize,
+    pub last: Duration,
+    pub average: Duration,
+    pub max: Duration,
+}
+
+#[derive(Default)]
+struct Profiler {
+    enabled: bool,
+    frame_start: Option<Instant>,
+    scopes: Vec<ScopeRecord>,
+    // Indices into `scopes` of the spans still open, innermost last.
+    open: Vec<usize>,
+    history: VecDeque<FrameProfile>,
+}
+
+thread_local! {
+    // Everything measured runs on the event loop thread.
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::default());
+}
+
+// Off by default; while off, frames and spans cost a thread-local lookup and nothing is kept.
+pub fn set_enabled(enabled: bool) {
+    PROFILER.with_borrow_mut(|profiler| {
+        profiler.enabled = enabled;
+        if !enabled {
+            profiler.frame_start = None;
+            profiler.scopes.clear();
+            profiler.open.clear();
+            profiler.history.clear();
+        }
+    });
+}
+
+pub fn is_enabled() -> bool {
+    PROFILER.with_borrow(|profiler| profiler.enabled)
+}
+
+// Ends the frame when dropped.
+#[must_use = "the frame ends when this is dropped"]
+pub struct FrameGuard {
+    started: bool,
+}
+
+// Starts recording a frame; spans opened outside a frame are ignored.
+pub fn frame() -> FrameGuard {
+    let started = PROFILER.with_borrow_mut(|profiler| {
+        if !profiler.enabled {
+            return false;
+        }
+        profiler.frame_start = Some(Instant::now());
+        profiler.scopes.clear();
+        profiler.open.clear();
+        true
+    });
+    FrameGuard { started }
+}
+
+impl Drop for FrameGuard {
+    fn drop(&mut self) {
+        if !self.started {
+            return;
+        }
+        PROFILER.with_borrow_mut(|profiler| {
+            let Some(frame_start) = profiler.frame_start.take() else {
+                return;
+            };
+            profiler.open.clear();
+            if profiler.history.len() == HISTORY_FRAMES {
+                profiler.history.pop_front();
+            }
+            let frame = FrameProfile {
+                duration: frame_start.elapsed(),
+                scopes: std::mem::take(&mut profiler.scopes),
+            };
+            profiler.history.push_back(frame);
+        });
+    }
+}
+
+// Ends the span when dropped.
+#[must_use = "the span ends when this is dropped"]
+pub struct ScopeGuard {
+    index: Option<usize>,
+}
+
+// Times the enclosing block: `let _scope = profiler::scope("script_update");`.
+pub fn scope(name: &'static str) -> ScopeGuard {
+    let index = PROFILER.with_borrow_mut(|profiler| {
+        let frame_start = profiler.frame_start?;
+        let index = profiler.scopes.len();
+        profiler.scopes.push(ScopeRecord {
+            name,
+            depth: profiler.open.len(),
+            start: frame_start.elapsed(),
+            duration: Duration::ZERO,
+        });
+        profiler.open.push(index);
+        Some(index)
+    });
+    ScopeGuard { index }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let Some(index) = self.index else {
+            return;
+        };
+        PROFILER.with_borrow_mut(|profiler| {
+            // Spans left open when their frame ended are dropped with it.
+            if profiler.open.last() != Some(&index) {
+                return;
+            }
+            profiler.open.pop();
+            if let Some(frame_start) = profiler.frame_start
+                && let Some(record) = profiler.scopes.get_mut(index)
+            {
+                record.duration = frame_start.elapsed().saturating_sub(record.start);
+            }
+        });
+    }
+}
+
+// Most recently finished frame.
+pub fn latest() -> Option<FrameProfile> {
+    PROFILER.with_borrow(|profiler| profiler.history.back().cloned())
+}
+
+// Stats for the spans of the latest frame, in its order.
+pub fn scope_stats() -> Vec<ScopeStats> {
+    PROFILER.with_borrow(|profiler| {
+        let Some(latest) = profiler.history.back() else {
+            return Vec::new();
+        };
+
+        let mut totals: BTreeMap<String, (Duration, Duration)> = BTreeMap::new();
+        for frame in &profiler.history {
+            for (path, duration) in frame_totals(frame) {
+                let (sum, max) = totals.entry(path).or_default();
+                *sum += duration;
+                *max = (*max).max(duration);
+            }
+        }
+
+        let frames = profiler.history.len() as u32;
+        let latest_totals = frame_totals(latest);
+        let mut listed = BTreeSet::new();
+        let mut stats = Vec::new();
+        for (path, scope) in stack_paths(latest).into_iter().zip(&latest.scopes) {
+            let (sum, max) = totals.get(&path).copied().unwrap_or_default();
+            let last = latest_totals.get(&path).copied().unwrap_or_default();
+            // Spans entered several times in a frame, like ticks, are listed once.
+            if listed.insert(path) {
+                stats.push(ScopeStats {
+                    name: scope.name,
+                    depth: scope.depth,
+                    last,
+                    average: sum / frames,
+                    max,
+                });
+            }
+        }
+        stats
+    })
+}
+
+// Writes the kept history as folded stacks (`frame;ui;egui_run 1234`, self time in
+// microseconds), the input format of flamegraph.pl, inferno and speedscope.
+pub fn export_folded(path: impl AsRef<Path>) -> Result<usize, String> {
+    let path = path.as_ref();
+    let (stacks, frames) = PROFILER.with_borrow(|profiler| {
+        let mut stacks: BTreeMap<String, Duration> = BTreeMap::new();
+        for frame in &profiler.history {
+            add_folded_stacks(frame, &mut stacks);
+        }
+        (stacks, profiler.history.len())
+    });
+    if frames == 0 {
+        return Err("no profiled frames to export".to_owned());
+    }
+
+    let mut folded = String::new();
+    for (stack, duration) in stacks {
+        let micros = duration.as_micros();
+        if micros > 0 {
+            folded.push_str(&format!("{stack} {micros}\n"));
+        }
+    }
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        vfs::create_dir_all(parent).map_err(|err| {
+            format!(
+                "failed to create profile directory {}: {err}",
+                parent.display()
+            )
+        })?;
+    }
+    vfs::write(path, folded)
+        .map_err(|err| format!("failed to write profile {}: {err}", path.display()))?;
+    Ok(frames)
+}
+
+// `a;b;c` for each span, matching `frame.scopes` by index.
+fn stack_paths(frame: &FrameProfile) -> Vec<String> {
+    let mut names: Vec<&str> = Vec::new();
+    frame
+        .scopes
+        .iter()
+        .map(|scope| {
+            names.truncate(scope.depth);
+            names.push(scope.name);
+            names.join(";")
+        })
+        .collect()
+}
+
+// Total time per stack path within one frame.
+fn frame_totals(frame: &FrameProfile) -> BTreeMap<String, Duration> {
+    let mut totals: BTreeMap<String, Duration> = BTreeMap::new();
+    for (path, scope) in stack_paths(frame).into_iter().zip(&frame.scopes) {
+        *totals.entry(path).or_default() += scope.duration;
+    }
+    totals
+}
+
+fn add_folded_stacks(frame: &FrameProfile, stacks: &mut BTreeMap<String, Duration>) {
+    // Self time: each span's duration minus that of its direct children.
+    let mut self_times: Vec<Duration> = frame.scopes.iter().map(|scope| scope.duration).collect();
+    let mut root = frame.duration;
+    let mut parents: Vec<usize> = Vec::new();
+    for (index, scope) in frame.scopes.iter().enumerate() {
+        parents.truncate(scope.depth);
+        match parents.last() {
+            Some(&parent) => {
+                self_times[parent] = self_times[parent].saturating_sub(scope.duration);
+            }
+            None => root = root.saturating_sub(scope.duration),
+        }
+        parents.push(index);
+    }
+
+    *stacks.entry("frame".to_owned()).or_default() += root;
+    for (path, self_time) in stack_paths(frame).into_iter().zip(self_times) {
+        *stacks.entry(format!("frame;{path}")).or_default() += self_time;
+    }
+}
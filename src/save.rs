@@ -0,0 +1,276 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{integrity, vfs};
+
+// Relative to `paths::data_dir()`.
+pub const DEFAULT_SAVE_DIR: &str = "saves";
+pub const AUTOSAVE_SLOT: &str = "autosave";
+pub const QUICKSAVE_SLOT: &str = "quicksave";
+// Points at the most recently written slot; used by the main menu "Continue" button.
+const LATEST_SAVE_MARKER: &str = "latest.json";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SaveGame {
+    #[serde(default)]
+    pub saved_at: u64,
+    #[serde(default)]
+    pub flags: HashMap<String, String>,
+    // Per-script state in SceneRunner order; `null` for scripts without saved state.
+    #[serde(default)]
+    pub scripts: Vec<Option<serde_json::Value>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LatestSaveMarker {
+    slot: String,
+}
+
+// Remote storage for save slots, e.g. Steam Cloud. Slots always live on the local disk;
+// a backend mirrors them and may hold newer copies written on another machine.
+pub trait SaveBackend: Send {
+    // Called after a slot was written locally.
+    fn upload(&mut self, slot: &str, save: &SaveGame) -> Result<(), String>;
+
+    // Remote copy of a slot; None when the backend has none.
+    fn download(&mut self, slot: &str) -> Result<Option<SaveGame>, String>;
+
+    // Picks between the local and remote copy of a slot; the newer one wins, ties keep
+    // the local copy.
+    fn resolve_conflict(&mut self, local: SaveGame, remote: SaveGame) -> SaveGame {
+        if remote.saved_at > local.saved_at {
+            remote
+        } else {
+            local
+        }
+    }
+}
+
+// Default backend: nothing beyond the local disk.
+pub struct LocalDisk;
+
+impl SaveBackend for LocalDisk {
+    fn upload(&mut self, _slot: &str, _save: &SaveGame) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn download(&mut self, _slot: &str) -> Result<Option<SaveGame>, String> {
+        Ok(None)
+    }
+}
+
+static BACKEND: Mutex<Option<Box<dyn SaveBackend>>> = Mutex::new(None);
+
+// Installs the backend used by every slot read and write from now on.
+#[allow(dead_code)]
+pub fn set_backend(backend: impl SaveBackend + 'static) {
+    if let Ok(mut current) = BACKEND.lock() {
+        *current = Some(Box::new(backend));
+    }
+}
+
+fn with_backend<T>(action: impl FnOnce(&mut dyn SaveBackend) -> T) -> T {
+    match BACKEND.lock() {
+        Ok(mut backend) => match backend.as_mut() {
+            Some(backend) => action(backend.as_mut()),
+            None => action(&mut LocalDisk),
+        },
+        Err(_) => action(&mut LocalDisk),
+    }
+}
+
+// Writes through a synced temp file and a rename, so a crash leaves either the old or the
+// new file, never a torn one. The replaced version is kept as a rolling `.bak`.
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let temp_path = sibling_path(path, ".tmp");
+    vfs::write_synced(&temp_path, contents.as_bytes())?;
+
+    if vfs::is_file(path) {
+        vfs::rename(path, backup_path(path))?;
+    }
+    vfs::rename(&temp_path, path)
+}
+
+// `write_atomic` for player data, signed when save integrity is configured.
+pub fn write_signed(path: &Path, contents: &str) -> io::Result<()> {
+    write_atomic(path, &integrity::sign(contents))
+}
+
+pub fn backup_path(path: &Path) -> PathBuf {
+    sibling_path(path, ".bak")
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// Reads and parses `path`, falling back to its backup when the file is missing or corrupt.
+pub fn read_with_backup<T>(
+    path: &Path,
+    what: &str,
+    parse: impl Fn(&Path, &str) -> Result<T, String>,
+) -> Result<T, String> {
+    let read = |path: &Path| {
+        vfs::read_to_string(path)
+            .map_err(|err| format!("failed to read {what} {}: {err}", path.display()))
+            .and_then(|raw| parse(path, &raw))
+    };
+
+    read(path).or_else(|err| {
+        let backup = backup_path(path);
+        if !vfs::is_file(&backup) {
+            return Err(err);
+        }
+        let value = read(&backup).map_err(|_| err.clone())?;
+        log::warn!("{err}; recovered from backup {}", backup.display());
+        Ok(value)
+    })
+}
+
+// `read_with_backup` for files written by `write_signed`. Files failing the signature
+// check under `IntegrityMode::Reject` are treated as corrupt.
+pub fn read_signed_with_backup<T>(
+    path: &Path,
+    what: &str,
+    parse: impl Fn(&Path, &str) -> Result<T, String>,
+) -> Result<T, String> {
+    read_with_backup(path, what, |path, raw| {
+        parse(path, integrity::verify(path, raw)?)
+    })
+}
+
+pub fn write_slot(dir: impl AsRef<Path>, slot: &str, mut save: SaveGame) -> Result<(), String> {
+    let dir = dir.as_ref();
+    save.saved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    write_slot_file(dir, slot, &save)?;
+    // A failed upload keeps the local save; the next write tries again.
+    if let Err(err) = with_backend(|backend| backend.upload(slot, &save)) {
+        log::warn!("failed to upload save '{slot}': {err}");
+    }
+
+    let marker = LatestSaveMarker {
+        slot: slot.to_owned(),
+    };
+    let marker_path = dir.join(LATEST_SAVE_MARKER);
+    let json = serde_json::to_string_pretty(&marker)
+        .map_err(|err| format!("failed to serialize latest save marker: {err}"))?;
+    write_signed(&marker_path, &json).map_err(|err| {
+        format!(
+            "failed to write save marker {}: {err}",
+            marker_path.display()
+        )
+    })
+}
+
+fn write_slot_file(dir: &Path, slot: &str, save: &SaveGame) -> Result<(), String> {
+    vfs::create_dir_all(dir)
+        .map_err(|err| format!("failed to create save directory {}: {err}", dir.display()))?;
+    let slot_path = dir.join(format!("{slot}.json"));
+    let json = serde_json::to_string_pretty(save)
+        .map_err(|err| format!("failed to serialize save '{slot}': {err}"))?;
+    write_signed(&slot_path, &json)
+        .map_err(|err| format!("failed to write save {}: {err}", slot_path.display()))
+}
+
+// Reads a slot, preferring the backend's copy when it wins the conflict check. The winner
+// replaces the local file so the next read needs no download.
+pub fn read_slot(dir: impl AsRef<Path>, slot: &str) -> Result<SaveGame, String> {
+    let dir = dir.as_ref();
+    let local = read_file(dir.join(format!("{slot}.json")));
+    let remote = with_backend(|backend| backend.download(slot)).unwrap_or_else(|err| {
+        log::warn!("failed to download save '{slot}': {err}");
+        None
+    });
+    let Some(remote) = remote else {
+        return local;
+    };
+
+    let (save, from_remote) = match local {
+        Ok(local) => {
+            let local_saved_at = local.saved_at;
+            let save = with_backend(|backend| backend.resolve_conflict(local, remote));
+            let from_remote = save.saved_at != local_saved_at;
+            (save, from_remote)
+        }
+        Err(err) => {
+            log::warn!("{err}; using the remote copy");
+            (remote, true)
+        }
+    };
+    if from_remote && let Err(err) = write_slot_file(dir, slot, &save) {
+        log::warn!("failed to store downloaded save '{slot}': {err}");
+    }
+    Ok(save)
+}
+
+// Reads a save from an arbitrary path, e.g. one handed over on the command line.
+pub fn read_file(path: impl AsRef<Path>) -> Result<SaveGame, String> {
+    read_signed_with_backup(path.as_ref(), "save", |path, raw| {
+        serde_json::from_str(raw)
+            .map_err(|err| format!("failed to parse save {}: {err}", path.display()))
+    })
+}
+
+pub fn read_latest(dir: impl AsRef<Path>) -> Result<SaveGame, String> {
+    let dir = dir.as_ref();
+    let slot = latest_slot(dir).ok_or_else(|| format!("no latest save in {}", dir.display()))?;
+    read_slot(dir, &slot)
+}
+
+// Slot named by the latest-save marker, if both the marker and the slot file exist.
+pub fn latest_slot(dir: impl AsRef<Path>) -> Option<String> {
+    let dir = dir.as_ref();
+    let marker: LatestSaveMarker =
+        read_signed_with_backup(&dir.join(LATEST_SAVE_MARKER), "save marker", |path, raw| {
+            serde_json::from_str(raw)
+                .map_err(|err| format!("failed to parse save marker {}: {err}", path.display()))
+        })
+        .ok()?;
+
+    let slot_path = dir.join(format!("{}.json", marker.slot));
+    (vfs::is_file(&slot_path) || vfs::is_file(backup_path(&slot_path))).then_some(marker.slot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LocalDisk, SaveBackend, SaveGame};
+
+    fn save(saved_at: u64, copy: &str) -> SaveGame {
+        SaveGame {
+            saved_at,
+            flags: [("copy".to_owned(), copy.to_owned())].into(),
+            ..SaveGame::default()
+        }
+    }
+
+    fn winner(local_saved_at: u64, remote_saved_at: u64) -> String {
+        let local = save(local_saved_at, "local");
+        let remote = save(remote_saved_at, "remote");
+        LocalDisk.resolve_conflict(local, remote).flags["copy"].clone()
+    }
+
+    #[test]
+    fn resolve_conflict_keeps_the_newer_copy() {
+        assert_eq!(winner(100, 200), "remote");
+        assert_eq!(winner(200, 100), "local");
+    }
+
+    #[test]
+    fn resolve_conflict_keeps_the_local_copy_on_ties() {
+        assert_eq!(winner(100, 100), "local");
+    }
+}
@@ -1,14 +1,76 @@
-use std::collections::VecDeque;
+use std::{
+    collections::{BTreeSet, VecDeque},
+    time::Duration,
+};
 
 use crate::{
-    achievements::AchievementManager, audio::AudioEngine, dialogue_ui::DialogueUi,
-    game_object::SceneObject, tex::Tex,
+    achievements::AchievementManager,
+    audio::{AudioBus, AudioEngine, BeatEvent, FadeCurve, Playlist, SoundHandle},
+    dialogue_ui::DialogueUi,
+    flags::FlagStore,
+    game_object::{DialogueBoxObject, GameObject2D, SceneObject},
+    input::{Action, ActionMap, InputState},
+    stats::StatsManager,
+    tex::Tex,
 };
 
+// Scripts advance in steps of this length, independent of the frame rate.
+pub const SIMULATION_TICK: f32 = 1.0 / 60.0;
+// Catch-up limit after a stall; older time is dropped instead of fast-forwarding.
+const MAX_TICKS_PER_FRAME: u32 = 8;
+
 // Signals are broadcast by the app (input/system events) to all active scripts.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ScriptSignal {
     SkipWait,
+    // A beat of the current music track, for pulses and prompts synced to the soundtrack.
+    Beat(BeatEvent),
+    // Game-specific signal, matched by name in on_signal.
+    #[allow(dead_code)]
+    Custom(String),
+}
+
+// Actions that are forwarded to scripts as signals when pressed in gameplay.
+pub struct SignalRoutes {
+    routes: Vec<(Action, ScriptSignal)>,
+}
+
+impl Default for SignalRoutes {
+    fn default() -> Self {
+        Self {
+            routes: vec![(Action::Advance, ScriptSignal::SkipWait)],
+        }
+    }
+}
+
+impl SignalRoutes {
+    // An action may route to several signals; duplicates are ignored.
+    #[allow(dead_code)]
+    pub fn route(&mut self, action: Action, signal: ScriptSignal) -> &mut Self {
+        if !self
+            .routes
+            .iter()
+            .any(|(a, s)| *a == action && *s == signal)
+        {
+            self.routes.push((action, signal));
+        }
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn unroute(&mut self, action: Action) -> &mut Self {
+        self.routes.retain(|(a, _)| *a != action);
+        self
+    }
+
+    // Signals whose action was just pressed, in routing order.
+    pub fn pressed_signals(&self, action_map: &ActionMap, input: &InputState) -> Vec<ScriptSignal> {
+        self.routes
+            .iter()
+            .filter(|(action, _)| action_map.just_pressed(*action, input))
+            .map(|(_, signal)| signal.clone())
+            .collect()
+    }
 }
 
 // Per-frame services exposed to scripts.
@@ -18,11 +80,51 @@ pub struct ScriptContext<'a> {
     pub tex: &'a mut Tex,
     pub dialogue_ui: &'a mut DialogueUi,
     pub achievements: &'a mut AchievementManager,
+    pub stats: &'a mut StatsManager,
+    #[allow(dead_code)]
+    pub flags: &'a mut FlagStore,
+    // Survives new games: endings seen, bonus chapters, NG+ dialogue.
     #[allow(dead_code)]
+    pub persistent_flags: &'a mut FlagStore,
     pub audio: Option<&'a mut AudioEngine>,
+    // Read-only view of this frame's input, e.g. raw mouse_delta() for camera drag or parallax.
+    #[allow(dead_code)]
+    pub input: &'a InputState,
+}
+
+// What a scene needs resident before it starts, prepared by the loading screen.
+// Music and ambience stream from the bytes registered at startup and are not listed.
+#[derive(Clone, Debug, Default)]
+pub struct SceneAssets {
+    // Image paths, as used by `GameObject2D::texture_path`.
+    pub textures: BTreeSet<String>,
+    // Registered sound ids, decoded up front.
+    pub sounds: BTreeSet<String>,
 }
 
-// Unity-style lifecycle: start once, then update every frame.
+impl SceneAssets {
+    pub fn add_sprite(&mut self, sprite: &GameObject2D) {
+        if !sprite.texture_path.is_empty() {
+            self.textures.insert(sprite.texture_path.clone());
+        }
+    }
+
+    pub fn add_dialogue(&mut self, dialogue: &DialogueBoxObject) {
+        if let Some(voice) = &dialogue.voice {
+            self.sounds.insert(voice.clone());
+        }
+    }
+
+    pub fn add_object(&mut self, object: &SceneObject) {
+        match object {
+            SceneObject::Sprite(sprite) => self.add_sprite(sprite),
+            SceneObject::Dialogue(dialogue) => self.add_dialogue(dialogue),
+        }
+    }
+}
+
+// Unity-style lifecycle: start once, then update every simulation tick with a fixed dt;
+// object transforms set in between are interpolated when rendering.
 pub trait SceneScript {
     fn start(&mut self, _context: &mut ScriptContext<'_>) -> Result<(), String> {
         Ok(())
@@ -35,6 +137,25 @@ pub trait SceneScript {
     fn is_finished(&self) -> bool {
         false
     }
+
+    // Progress written into save games; scripts without meaningful progress keep `None`.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    // Called before start() when a save game is loaded.
+    fn restore_state(&mut self, _state: &serde_json::Value) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Assets to load before start(); anything left out is loaded when first used.
+    fn required_assets(&self, _assets: &mut SceneAssets) {}
+
+    // Called once when the game closes, on scripts that have started, before the
+    // autosave is written.
+    fn on_destroy(&mut self, _context: &mut ScriptContext<'_>) -> Result<(), String> {
+        Ok(())
+    }
 }
 
 struct ScriptEntry {
@@ -70,7 +191,7 @@ impl SceneRunner {
 
     pub fn send_signal(&mut self, signal: ScriptSignal) {
         for entry in &mut self.scripts {
-            entry.script.on_signal(signal);
+            entry.script.on_signal(signal.clone());
         }
     }
 
@@ -78,6 +199,70 @@ impl SceneRunner {
         self.scripts.iter().all(|entry| entry.script.is_finished())
     }
 
+    pub fn save_states(&self) -> Vec<Option<serde_json::Value>> {
+        self.scripts
+            .iter()
+            .map(|entry| entry.script.save_state())
+            .collect()
+    }
+
+    // Runs on_destroy() of every started script, also after one of them fails.
+    pub fn destroy(&mut self, context: &mut ScriptContext<'_>) -> Result<(), String> {
+        let errors: Vec<String> = self
+            .scripts
+            .iter_mut()
+            .filter(|entry| entry.started)
+            .filter_map(|entry| entry.script.on_destroy(context).err())
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    // Assets of the scripts that have not started yet.
+    pub fn required_assets(&self) -> SceneAssets {
+        let mut assets = SceneAssets::default();
+        for entry in self.scripts.iter().filter(|entry| !entry.started) {
+            entry.script.required_assets(&mut assets);
+        }
+        assets
+    }
+
+    // States are matched to scripts by position, as produced by save_states().
+    pub fn restore_states(&mut self, states: &[Option<serde_json::Value>]) -> Result<(), String> {
+        for (entry, state) in self.scripts.iter_mut().zip(states) {
+            if let Some(state) = state {
+                entry.script.restore_state(state)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Runs as many fixed ticks as `accumulator` plus `dt` covers; leftover time stays in
+    // `accumulator` for the next frame.
+    pub fn update_fixed(
+        &mut self,
+        accumulator: &mut f32,
+        dt: f32,
+        context: &mut ScriptContext<'_>,
+    ) -> Result<(), String> {
+        *accumulator += dt;
+        let mut ticks = 0;
+        while *accumulator >= SIMULATION_TICK {
+            if ticks == MAX_TICKS_PER_FRAME {
+                *accumulator %= SIMULATION_TICK;
+                break;
+            }
+            context.tex.begin_tick();
+            self.update(SIMULATION_TICK, context)?;
+            *accumulator -= SIMULATION_TICK;
+            ticks += 1;
+        }
+        Ok(())
+    }
+
     pub fn update(&mut self, dt: f32, context: &mut ScriptContext<'_>) -> Result<(), String> {
         for entry in &mut self.scripts {
             // Skip scripts that already reached terminal state.
@@ -108,6 +293,87 @@ pub enum SceneCommand {
     Spawn(SceneObject),
     Apply(SceneObject),
     Wait(f32),
+    // Shows a text-input box and pauses the timeline until the player submits a value,
+    // which is stored in the flag store under `variable`.
+    AskInput {
+        variable: String,
+        prompt: String,
+    },
+    Music(MusicCommand),
+    Effect(EffectCommand),
+    Ambience(AmbienceCommand),
+    // One-shot sound; with `wait` the timeline holds until it has finished playing.
+    Sound {
+        sound_id: String,
+        bus: AudioBus,
+        wait: bool,
+    },
+}
+
+// Background music control; failures are logged and never stop the timeline.
+#[derive(Clone, Debug)]
+#[allow(dead_code)]
+pub enum MusicCommand {
+    Play(String),
+    Crossfade {
+        sound_id: String,
+        seconds: f32,
+    },
+    Playlist {
+        tracks: Vec<String>,
+        shuffle: bool,
+    },
+    // Stems of one song, calmest first, blended by intensity (0..1).
+    Stems {
+        song_id: String,
+        stems: Vec<String>,
+        intensity: f32,
+    },
+    Intensity(f32),
+    SkipTrack,
+    Stop,
+}
+
+// Layered ambience control; each layer loops on its own and only its level changes.
+#[derive(Clone, Debug)]
+pub enum AmbienceCommand {
+    Layer {
+        sound_id: String,
+        volume: f32,
+        seconds: f32,
+    },
+    Stop {
+        seconds: f32,
+    },
+}
+
+impl AmbienceCommand {
+    // Same end state without the tween, for rebuilding a restored scene.
+    fn immediate(self) -> Self {
+        match self {
+            Self::Layer {
+                sound_id, volume, ..
+            } => Self::Layer {
+                sound_id,
+                volume,
+                seconds: 0.0,
+            },
+            Self::Stop { .. } => Self::Stop { seconds: 0.0 },
+        }
+    }
+}
+
+// Bus effects stay on until a later command changes them.
+#[derive(Clone, Debug)]
+pub enum EffectCommand {
+    LowPass {
+        bus: AudioBus,
+        cutoff_hz: Option<f32>,
+    },
+    Reverb {
+        bus: AudioBus,
+        mix: f32,
+    },
 }
 
 pub fn spawn(object: impl Into<SceneObject>) -> SceneCommand {
@@ -122,16 +388,139 @@ pub fn wait(seconds: f32) -> SceneCommand {
     SceneCommand::Wait(seconds.max(0.0))
 }
 
+#[allow(dead_code)]
+pub fn ask_input(variable: impl Into<String>, prompt: impl Into<String>) -> SceneCommand {
+    SceneCommand::AskInput {
+        variable: variable.into(),
+        prompt: prompt.into(),
+    }
+}
+
+#[allow(dead_code)]
+pub fn play_music(sound_id: impl Into<String>) -> SceneCommand {
+    SceneCommand::Music(MusicCommand::Play(sound_id.into()))
+}
+
+#[allow(dead_code)]
+pub fn crossfade_music(sound_id: impl Into<String>, seconds: f32) -> SceneCommand {
+    SceneCommand::Music(MusicCommand::Crossfade {
+        sound_id: sound_id.into(),
+        seconds: seconds.max(0.0),
+    })
+}
+
+#[allow(dead_code)]
+pub fn play_playlist(
+    tracks: impl IntoIterator<Item = impl Into<String>>,
+    shuffle: bool,
+) -> SceneCommand {
+    SceneCommand::Music(MusicCommand::Playlist {
+        tracks: tracks.into_iter().map(Into::into).collect(),
+        shuffle,
+    })
+}
+
+#[allow(dead_code)]
+pub fn play_stems(
+    song_id: impl Into<String>,
+    stems: impl IntoIterator<Item = impl Into<String>>,
+    intensity: f32,
+) -> SceneCommand {
+    SceneCommand::Music(MusicCommand::Stems {
+        song_id: song_id.into(),
+        stems: stems.into_iter().map(Into::into).collect(),
+        intensity,
+    })
+}
+
+// E.g. ramp toward the action stem as a chase begins.
+#[allow(dead_code)]
+pub fn music_intensity(intensity: f32) -> SceneCommand {
+    SceneCommand::Music(MusicCommand::Intensity(intensity))
+}
+
+#[allow(dead_code)]
+pub fn stop_music() -> SceneCommand {
+    SceneCommand::Music(MusicCommand::Stop)
+}
+
+// E.g. `muffle_bus(AudioBus::Voice, 800.0)` for a conversation behind a door.
+#[allow(dead_code)]
+pub fn muffle_bus(bus: AudioBus, cutoff_hz: f32) -> SceneCommand {
+    SceneCommand::Effect(EffectCommand::LowPass {
+        bus,
+        cutoff_hz: Some(cutoff_hz),
+    })
+}
+
+#[allow(dead_code)]
+pub fn unmuffle_bus(bus: AudioBus) -> SceneCommand {
+    SceneCommand::Effect(EffectCommand::LowPass {
+        bus,
+        cutoff_hz: None,
+    })
+}
+
+// `mix` 0.0 turns the reverb off.
+#[allow(dead_code)]
+pub fn bus_reverb(bus: AudioBus, mix: f32) -> SceneCommand {
+    SceneCommand::Effect(EffectCommand::Reverb { bus, mix })
+}
+
+// E.g. `ambient_layer("rain", 0.8, 4.0)` to let the rain swell in over four seconds.
+#[allow(dead_code)]
+pub fn ambient_layer(sound_id: impl Into<String>, volume: f32, seconds: f32) -> SceneCommand {
+    SceneCommand::Ambience(AmbienceCommand::Layer {
+        sound_id: sound_id.into(),
+        volume,
+        seconds: seconds.max(0.0),
+    })
+}
+
+#[allow(dead_code)]
+pub fn stop_ambience(seconds: f32) -> SceneCommand {
+    SceneCommand::Ambience(AmbienceCommand::Stop {
+        seconds: seconds.max(0.0),
+    })
+}
+
+#[allow(dead_code)]
+pub fn play_sound(sound_id: impl Into<String>, bus: AudioBus) -> SceneCommand {
+    SceneCommand::Sound {
+        sound_id: sound_id.into(),
+        bus,
+        wait: false,
+    }
+}
+
+#[allow(dead_code)]
+pub fn play_sound_and_wait(sound_id: impl Into<String>, bus: AudioBus) -> SceneCommand {
+    SceneCommand::Sound {
+        sound_id: sound_id.into(),
+        bus,
+        wait: true,
+    }
+}
+
 pub struct TimelineScript {
     pending: VecDeque<SceneCommand>,
+    total_commands: usize,
+    // Commands already consumed in a restored save; their objects are re-applied on start.
+    replay: Vec<SceneCommand>,
     wait_remaining: f32,
+    awaiting_input: bool,
+    awaiting_sound: Option<SoundHandle>,
 }
 
 impl TimelineScript {
     pub fn new(commands: Vec<SceneCommand>) -> Self {
         Self {
+            total_commands: commands.len(),
             pending: commands.into(),
+            replay: Vec::new(),
             wait_remaining: 0.0,
+            awaiting_input: false,
+            awaiting_sound: None,
         }
     }
 
@@ -141,6 +530,22 @@ impl TimelineScript {
         context: &mut ScriptContext<'_>,
     ) -> Result<(), String> {
         loop {
+            // Block until the requested text input has been submitted.
+            if self.awaiting_input {
+                if context.dialogue_ui.is_awaiting_text_input() {
+                    break;
+                }
+                self.awaiting_input = false;
+            }
+
+            // Block until a waited-on sound has played out.
+            if let Some(handle) = &self.awaiting_sound {
+                if !handle.is_finished() {
+                    break;
+                }
+                self.awaiting_sound = None;
+            }
+
             // Consume frame time against pending wait, if any.
             if self.wait_remaining > 0.0 {
                 if dt <= 0.0 {
@@ -168,12 +573,111 @@ impl TimelineScript {
                 SceneCommand::Spawn(object) | SceneCommand::Apply(object) => {
                     Self::apply_object(object, context)?;
                 }
+                SceneCommand::AskInput { variable, prompt } => {
+                    context.dialogue_ui.request_text_input(variable, prompt);
+                    self.awaiting_input = true;
+                }
+                SceneCommand::Music(command) => Self::apply_music(command, context),
+                SceneCommand::Effect(command) => Self::apply_effect(command, context),
+                SceneCommand::Ambience(command) => Self::apply_ambience(command, context),
+                SceneCommand::Sound {
+                    sound_id,
+                    bus,
+                    wait,
+                } => {
+                    let handle = Self::play_sound(&sound_id, bus, context);
+                    if wait {
+                        self.awaiting_sound = handle;
+                    }
+                }
             }
         }
 
         Ok(())
     }
 
+    fn apply_music(command: MusicCommand, context: &mut ScriptContext<'_>) {
+        let Some(audio) = context.audio.as_deref_mut() else {
+            return;
+        };
+        let result = match command {
+            MusicCommand::Play(sound_id) => audio.play_music(&sound_id),
+            MusicCommand::Crossfade { sound_id, seconds } => audio.crossfade_to(
+                &sound_id,
+                Duration::from_secs_f32(seconds),
+                FadeCurve::default(),
+            ),
+            MusicCommand::Playlist { tracks, shuffle } => {
+                audio.play_playlist(Playlist::new(tracks).with_shuffle(shuffle))
+            }
+            MusicCommand::Stems {
+                song_id,
+                stems,
+                intensity,
+            } => audio.play_stems(&song_id, &stems, intensity),
+            MusicCommand::Intensity(intensity) => {
+                audio.set_music_intensity(intensity);
+                Ok(())
+            }
+            MusicCommand::SkipTrack => audio.skip_track(),
+            MusicCommand::Stop => {
+                audio.stop_music();
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            log::warn!("music command failed: {err}");
+        }
+    }
+
+    fn apply_effect(command: EffectCommand, context: &mut ScriptContext<'_>) {
+        let Some(audio) = context.audio.as_deref_mut() else {
+            return;
+        };
+        match command {
+            EffectCommand::LowPass { bus, cutoff_hz } => audio.set_bus_lowpass(bus, cutoff_hz),
+            EffectCommand::Reverb { bus, mix } => audio.set_bus_reverb(bus, mix),
+        }
+    }
+
+    fn apply_ambience(command: AmbienceCommand, context: &mut ScriptContext<'_>) {
+        let Some(audio) = context.audio.as_deref_mut() else {
+            return;
+        };
+        match command {
+            AmbienceCommand::Layer {
+                sound_id,
+                volume,
+                seconds,
+            } => {
+                if let Err(err) =
+                    audio.set_ambient_layer(&sound_id, volume, Duration::from_secs_f32(seconds))
+                {
+                    log::warn!("ambience command failed: {err}");
+                }
+            }
+            AmbienceCommand::Stop { seconds } => {
+                audio.stop_ambience(Duration::from_secs_f32(seconds));
+            }
+        }
+    }
+
+    // A sound that fails to play is logged and not waited on.
+    fn play_sound(
+        sound_id: &str,
+        bus: AudioBus,
+        context: &mut ScriptContext<'_>,
+    ) -> Option<SoundHandle> {
+        let audio = context.audio.as_deref_mut()?;
+        match audio.play(sound_id, bus, 1.0) {
+            Ok(handle) => Some(handle),
+            Err(err) => {
+                log::warn!("timeline sound failed: {err}");
+                None
+            }
+        }
+    }
+
     fn apply_object(object: SceneObject, context: &mut ScriptContext<'_>) -> Result<(), String> {
         match object {
             // Sprite definitions are applied to the texture renderer.
@@ -193,20 +697,92 @@ impl TimelineScript {
 
 impl SceneScript for TimelineScript {
     fn start(&mut self, context: &mut ScriptContext<'_>) -> Result<(), String> {
+        // Waits and prompts of replayed commands are already done; only rebuild the scene,
+        // its bus effects and ambience, and the music that was playing.
+        let mut music = None;
+        for command in std::mem::take(&mut self.replay) {
+            match command {
+                SceneCommand::Spawn(object) | SceneCommand::Apply(object) => {
+                    Self::apply_object(object, context)?;
+                }
+                SceneCommand::Music(MusicCommand::SkipTrack) => {}
+                // Folded into the song so replay starts at the latest level.
+                SceneCommand::Music(MusicCommand::Intensity(level)) => {
+                    if let Some(MusicCommand::Stems { intensity, .. }) = music.as_mut() {
+                        *intensity = level;
+                    }
+                }
+                SceneCommand::Music(command) => music = Some(command),
+                SceneCommand::Effect(command) => Self::apply_effect(command, context),
+                SceneCommand::Ambience(command) => {
+                    Self::apply_ambience(command.immediate(), context);
+                }
+                SceneCommand::Wait(_)
+                | SceneCommand::AskInput { .. }
+                | SceneCommand::Sound { .. } => {}
+            }
+        }
+        if let Some(command) = music {
+            Self::apply_music(command, context);
+        }
         self.process_commands(0.0, context)
     }
 
     fn update(&mut self, dt: f32, context: &mut ScriptContext<'_>) -> Result<(), String> {
-        self.process_commands(dt, context)
+        self.process_commands(dt, context)?;
+        // The runner stops updating finished scripts, so this counts each timeline once.
+        if self.is_finished() {
+            context.stats.record_scene_completed();
+        }
+        Ok(())
     }
 
     fn on_signal(&mut self, signal: ScriptSignal) {
         if matches!(signal, ScriptSignal::SkipWait) {
+            // The skipped sound keeps playing; only the timeline moves on.
             self.wait_remaining = 0.0;
+            self.awaiting_sound = None;
         }
     }
 
     fn is_finished(&self) -> bool {
-        self.pending.is_empty() && self.wait_remaining <= 0.0
+        self.pending.is_empty()
+            && self.wait_remaining <= 0.0
+            && !self.awaiting_input
+            && self.awaiting_sound.is_none()
+    }
+
+    fn save_state(&self) -> Option<serde_json::Value> {
+        let mut consumed = self.total_commands - self.pending.len();
+        // An unanswered prompt is asked again after loading.
+        if self.awaiting_input {
+            consumed = consumed.saturating_sub(1);
+        }
+        Some(serde_json::json!({ "consumed": consumed }))
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        let consumed = state
+            .get("consumed")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or_else(|| format!("invalid timeline save state: {state}"))?
+            as usize;
+        let consumed = consumed.min(self.pending.len());
+        self.replay.extend(self.pending.drain(..consumed));
+        Ok(())
+    }
+
+    fn required_assets(&self, assets: &mut SceneAssets) {
+        for command in self.replay.iter().chain(&self.pending) {
+            match command {
+                SceneCommand::Spawn(object) | SceneCommand::Apply(object) => {
+                    assets.add_object(object);
+                }
+                SceneCommand::Sound { sound_id, .. } => {
+                    assets.sounds.insert(sound_id.clone());
+                }
+                _ => {}
+            }
+        }
     }
 }
@@ -4,11 +4,20 @@ pub fn trigger(manager: &mut AchievementManager, trigger_id: &str) -> Vec<String
     manager.trigger(trigger_id)
 }
 
+#[allow(dead_code)]
+pub fn trigger_with(
+    manager: &mut AchievementManager,
+    trigger_id: &str,
+    amount: u32,
+) -> Vec<String> {
+    manager.trigger_with(trigger_id, amount)
+}
+
 pub fn grant(manager: &mut AchievementManager, achievement_id: &str) -> bool {
     match manager.grant(achievement_id) {
         Ok(is_new) => is_new,
         Err(err) => {
-            eprintln!("achievement grant failed: {err}");
+            log::warn!("achievement grant failed: {err}");
             false
         }
     }
@@ -17,3 +26,11 @@ pub fn grant(manager: &mut AchievementManager, achievement_id: &str) -> bool {
 pub fn is_unlocked(manager: &AchievementManager, achievement_id: &str) -> bool {
     manager.is_unlocked(achievement_id)
 }
+
+#[allow(dead_code)]
+pub fn add_progress(manager: &mut AchievementManager, chain: &str, amount: u32) -> Vec<String> {
+    manager.add_progress(chain, amount).unwrap_or_else(|err| {
+        log::warn!("achievement progress failed: {err}");
+        Vec::new()
+    })
+}
@@ -1,8 +1,14 @@
-use std::{fs, path::Path};
+use std::path::Path;
 
-use crate::achievements::AchievementDefinition;
+use crate::{
+    achievements::{ACHIEVEMENTS_FILE_VERSION, AchievementDefinition, AchievementTier},
+    save, vfs,
+};
 
+// Read-only catalog shipped with the game.
 pub const DEFAULT_ACHIEVEMENTS_PATH: &str = "src/data/achievements.json";
+// Per-user unlocks, next to the save games; relative to `paths::data_dir()`.
+pub const DEFAULT_ACHIEVEMENT_PROGRESS_PATH: &str = "saves/achievements.json";
 
 pub fn create_all_achievements() -> Vec<AchievementDefinition> {
     vec![
@@ -11,32 +17,112 @@ pub fn create_all_achievements() -> Vec<AchievementDefinition> {
             name: "Первый запуск".to_owned(),
             description: "Запустить игру и перейти в игровой режим.".to_owned(),
             trigger: Some("game_started".to_owned()),
+            category: Some("Сюжет".to_owned()),
+            rarity: None,
+            chain: None,
+            tier: None,
+            goal: None,
+            threshold: None,
+            condition: None,
+            hidden: false,
+            points: Some(5),
+            icon: None,
+            locked_icon: None,
         },
         AchievementDefinition {
             id: "intro_closed".to_owned(),
             name: "Диалог завершён".to_owned(),
             description: "Закрыть стартовый диалог персонажа.".to_owned(),
             trigger: Some("intro_closed".to_owned()),
+            category: Some("Сюжет".to_owned()),
+            rarity: None,
+            chain: None,
+            tier: None,
+            goal: None,
+            threshold: None,
+            condition: None,
+            hidden: false,
+            points: Some(10),
+            icon: None,
+            locked_icon: None,
         },
         AchievementDefinition {
             id: "intro_skipped".to_owned(),
             name: "Быстрый читатель".to_owned(),
             description: "Закрыть стартовый диалог по сигналу SkipWait.".to_owned(),
             trigger: Some("intro_skipped".to_owned()),
+            category: Some("Сюжет".to_owned()),
+            rarity: None,
+            chain: None,
+            tier: None,
+            goal: None,
+            threshold: None,
+            condition: None,
+            hidden: true,
+            points: Some(15),
+            icon: None,
+            locked_icon: None,
         },
         AchievementDefinition {
             id: "script_reward".to_owned(),
             name: "Скриптовая награда".to_owned(),
             description: "Достижение выдано напрямую из скрипта.".to_owned(),
             trigger: None,
+            category: Some("Скрипты".to_owned()),
+            rarity: None,
+            chain: None,
+            tier: None,
+            goal: None,
+            threshold: None,
+            condition: None,
+            hidden: false,
+            points: Some(10),
+            icon: None,
+            locked_icon: None,
         },
+        returning_player("returning_bronze", AchievementTier::Bronze, "Гость", 3, 5),
+        returning_player(
+            "returning_silver",
+            AchievementTier::Silver,
+            "Завсегдатай",
+            10,
+            15,
+        ),
+        returning_player("returning_gold", AchievementTier::Gold, "Старожил", 25, 30),
     ]
 }
 
+// Tiers following the `launches` statistic.
+fn returning_player(
+    id: &str,
+    tier: AchievementTier,
+    name: &str,
+    goal: u32,
+    points: u32,
+) -> AchievementDefinition {
+    AchievementDefinition {
+        id: id.to_owned(),
+        name: name.to_owned(),
+        description: format!("Запустить игру {goal} раз."),
+        trigger: None,
+        category: Some("Сюжет".to_owned()),
+        rarity: None,
+        chain: Some("launches".to_owned()),
+        tier: Some(tier),
+        goal: Some(goal),
+        threshold: None,
+        condition: None,
+        hidden: false,
+        points: Some(points),
+        icon: None,
+        locked_icon: None,
+    }
+}
+
 pub fn write_achievements_json(path: impl AsRef<Path>) -> Result<(), String> {
     let path = path.as_ref();
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|err| {
+        vfs::create_dir_all(parent).map_err(|err| {
             format!(
                 "failed to create achievements directory {}: {err}",
                 parent.display()
@@ -50,7 +136,17 @@ pub fn write_achievements_json(path: impl AsRef<Path>) -> Result<(), String> {
         name: String,
         description: String,
         trigger: Option<String>,
-        unlocked: bool,
+        category: Option<String>,
+        rarity: Option<f32>,
+        chain: Option<String>,
+        tier: Option<AchievementTier>,
+        goal: Option<u32>,
+        threshold: Option<u32>,
+        condition: Option<String>,
+        hidden: bool,
+        points: Option<u32>,
+        icon: Option<String>,
+        locked_icon: Option<String>,
     }
 
     let entries: Vec<AchievementFileEntry> = create_all_achievements()
@@ -60,14 +156,28 @@ pub fn write_achievements_json(path: impl AsRef<Path>) -> Result<(), String> {
             name: definition.name,
             description: definition.description,
             trigger: definition.trigger,
-            unlocked: false,
+            category: definition.category,
+            rarity: definition.rarity,
+            chain: definition.chain,
+            tier: definition.tier,
+            goal: definition.goal,
+            threshold: definition.threshold,
+            condition: definition.condition,
+            hidden: definition.hidden,
+            points: definition.points,
+            icon: definition.icon,
+            locked_icon: definition.locked_icon,
         })
         .collect();
 
-    let json = serde_json::to_string_pretty(&entries)
+    let file = serde_json::json!({
+        "version": ACHIEVEMENTS_FILE_VERSION,
+        "achievements": entries,
+    });
+    let json = serde_json::to_string_pretty(&file)
         .map_err(|err| format!("failed to serialize achievements: {err}"))?;
 
-    fs::write(path, json).map_err(|err| {
+    vfs::write(path, json).map_err(|err| {
         format!(
             "failed to write achievements json {}: {err}",
             path.display()
@@ -77,7 +187,8 @@ pub fn write_achievements_json(path: impl AsRef<Path>) -> Result<(), String> {
 
 pub fn ensure_achievements_json_exists(path: impl AsRef<Path>) -> Result<(), String> {
     let path = path.as_ref();
-    if path.exists() {
+    // A backup left by an interrupted write is recovered on load instead.
+    if vfs::exists(path) || vfs::exists(save::backup_path(path)) {
         return Ok(());
     }
 
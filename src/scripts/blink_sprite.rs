@@ -1,6 +1,6 @@
 use crate::{
     game_object::GameObject2D,
-    scene_script::{SceneScript, ScriptContext},
+    scene_script::{SceneAssets, SceneScript, ScriptContext},
 };
 
 // Toggles sprite visibility at a fixed interval.
@@ -51,4 +51,8 @@ impl SceneScript for BlinkSpriteScript {
 
         Ok(())
     }
+
+    fn required_assets(&self, assets: &mut SceneAssets) {
+        assets.add_sprite(&self.sprite);
+    }
 }
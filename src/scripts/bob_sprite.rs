@@ -1,6 +1,6 @@
 use crate::{
     game_object::GameObject2D,
-    scene_script::{SceneScript, ScriptContext},
+    scene_script::{SceneAssets, SceneScript, ScriptContext},
 };
 
 // Applies a vertical sine-wave motion to a sprite.
@@ -44,4 +44,8 @@ impl SceneScript for BobSpriteScript {
             .tex
             .apply_game_object_from_definition(context.device, context.queue, object)
     }
+
+    fn required_assets(&self, assets: &mut SceneAssets) {
+        assets.add_sprite(&self.sprite);
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
     game_object::{DialogueBoxObject, GameObject2D},
-    scene_script::{SceneScript, ScriptContext, ScriptSignal},
+    scene_script::{SceneAssets, SceneScript, ScriptContext, ScriptSignal},
     scripts::achievements as achievement_scripts,
 };
 
@@ -17,8 +17,11 @@ pub struct Game {
 impl Game {
     pub fn new(image: GameObject2D) -> Self {
         Self {
-            dialogue: DialogueBoxObject::new("Hello my name Ajzakun.", "Ajzakun")
-                .with_id("intro_dialogue"),
+            dialogue: DialogueBoxObject::new(
+                "Hello my name {term=ajzakun}Ajzakun{/term}.",
+                "Ajzakun",
+            )
+            .with_id("intro_dialogue"),
             image,
             visible: true,
             close_requested: false,
@@ -74,4 +77,21 @@ impl SceneScript for Game {
     fn is_finished(&self) -> bool {
         self.finished
     }
+
+    fn save_state(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({ "finished": self.finished }))
+    }
+
+    fn restore_state(&mut self, state: &serde_json::Value) -> Result<(), String> {
+        if state.get("finished").and_then(serde_json::Value::as_bool) == Some(true) {
+            self.visible = false;
+            self.finished = true;
+        }
+        Ok(())
+    }
+
+    fn required_assets(&self, assets: &mut SceneAssets) {
+        assets.add_sprite(&self.image);
+        assets.add_dialogue(&self.dialogue);
+    }
 }
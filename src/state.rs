@@ -1,37 +1,44 @@
-use std::sync::Arc;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 use wgpu::{Adapter, Instance, Surface};
-use winit::window::Window;
+use winit::{dpi::PhysicalSize, window::Window};
+
+use crate::{crash, display::GpuSettings};
 
 #[allow(unused)]
 pub struct State {
     pub window: Arc<Window>,
     pub instance: Instance,
-    pub surface: Surface<'static>,
+    // None while suspended; some platforms destroy the native window in the background.
+    pub surface: Option<Surface<'static>>,
     pub adapter: Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub config: Option<wgpu::SurfaceConfiguration>,
     pub render_pipeline: Option<wgpu::RenderPipeline>,
+    // Set from wgpu's callback when the driver resets or the GPU goes away.
+    device_lost: Arc<AtomicBool>,
 }
 
 impl State {
-    pub async fn new(window: Arc<Window>) -> Result<Self, ()> {
+    pub async fn new(window: Arc<Window>, gpu: &GpuSettings) -> Result<Self, String> {
         let instance = Instance::default();
 
-        let surface = unsafe {
-            std::mem::transmute::<wgpu::Surface<'_>, wgpu::Surface<'static>>(
-                instance.create_surface(window.as_ref()).unwrap(),
-            )
-        };
+        let surface = instance
+            .create_surface(window.clone())
+            .map_err(|err| format!("failed to create a rendering surface for the window: {err}"))?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptionsBase {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .await
-            .unwrap();
+        let adapter = select_adapter(&instance, &surface, gpu).await?;
+        let info = adapter.get_info();
+        crash::set_gpu_info(&info);
+        log::info!(
+            "rendering with {} ({:?}, {:?})",
+            info.name,
+            info.backend,
+            info.device_type
+        );
 
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
@@ -45,36 +52,237 @@ impl State {
                 trace: wgpu::Trace::default(),
             })
             .await
-            .unwrap();
+            .map_err(|err| format!("failed to open GPU device {}: {err}", info.name))?;
+
+        let device_lost = Arc::new(AtomicBool::new(false));
+        let lost = device_lost.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            // Dropping the device on purpose reports `Destroyed`; that needs no recovery.
+            if reason != wgpu::DeviceLostReason::Destroyed {
+                log::error!("GPU device lost: {message}");
+                lost.store(true, Ordering::Relaxed);
+            }
+        });
+        // The default handler panics, which would also fire for every call on a lost device.
+        device.on_uncaptured_error(Arc::new(|err| log::error!("GPU error: {err}")));
 
         Ok(Self {
             window,
             instance,
-            surface,
+            surface: Some(surface),
             adapter,
             device,
             queue,
             config: None,
             render_pipeline: None,
+            device_lost,
         })
     }
 
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Relaxed)
+    }
+
     pub fn redraw(&self) {
         self.window.request_redraw();
     }
 
+    // Next swapchain texture, or None when this frame has to be skipped. Outdated and lost
+    // surfaces (alt-tab, display mode changes) are reconfigured and another redraw is
+    // requested; only running out of memory is fatal.
+    pub fn acquire_frame(&self) -> Option<wgpu::SurfaceTexture> {
+        let surface = self.surface.as_ref()?;
+        let err = match surface.get_current_texture() {
+            Ok(frame) => return Some(frame),
+            Err(err) => err,
+        };
+
+        match err {
+            wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost => {
+                if let Some(config) = self.config.as_ref() {
+                    surface.configure(&self.device, config);
+                }
+            }
+            wgpu::SurfaceError::OutOfMemory => {
+                panic!("failed to acquire next swap chain texture: {err}")
+            }
+            wgpu::SurfaceError::Timeout | wgpu::SurfaceError::Other => {
+                log::warn!("skipping frame: {err}");
+            }
+        }
+        self.redraw();
+        None
+    }
+
+    // Resizes the swapchain; returns the new configuration so dependents can follow. A zero
+    // size (minimized) keeps the old swapchain, which is not drawn to until restored.
+    pub fn resize(&mut self, size: PhysicalSize<u32>) -> Option<&wgpu::SurfaceConfiguration> {
+        if size.width == 0 || size.height == 0 {
+            return None;
+        }
+        let config = self.config.as_mut()?;
+        config.width = size.width;
+        config.height = size.height;
+        if let Some(surface) = self.surface.as_ref() {
+            surface.configure(&self.device, config);
+        }
+        Some(config)
+    }
+
+    // Whether there is nothing to draw to: suspended, or minimized to a zero-sized window.
+    pub fn is_hidden(&self) -> bool {
+        let size = self.window.inner_size();
+        self.surface.is_none()
+            || size.width == 0
+            || size.height == 0
+            || self.window.is_minimized() == Some(true)
+    }
+
+    // Releases the surface; the window may be destroyed before `resume_surface`.
+    pub fn suspend_surface(&mut self) {
+        self.surface = None;
+    }
+
+    // Recreates the surface released by `suspend_surface` with the previous configuration;
+    // follow with `resize` if the window size may have changed meanwhile.
+    pub fn resume_surface(&mut self) {
+        if self.surface.is_some() {
+            return;
+        }
+        let surface = match self.instance.create_surface(self.window.clone()) {
+            Ok(surface) => surface,
+            Err(err) => {
+                log::error!("failed to recreate surface: {err}");
+                return;
+            }
+        };
+        if let Some(config) = self.config.as_ref() {
+            surface.configure(&self.device, config);
+        }
+        self.surface = Some(surface);
+    }
+
+    // Reconfigures the surface when `mode` differs from the current one. Modes the surface
+    // does not support fall back to Fifo, which every backend provides.
+    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode) {
+        let Some(config) = self.config.as_mut() else {
+            return;
+        };
+        let Some(surface) = self.surface.as_ref() else {
+            return;
+        };
+        let supported = surface.get_capabilities(&self.adapter).present_modes;
+        let mode = if supported.contains(&mode) {
+            mode
+        } else {
+            wgpu::PresentMode::Fifo
+        };
+        if config.present_mode == mode {
+            return;
+        }
+        config.present_mode = mode;
+        surface.configure(&self.device, config);
+    }
+
     pub fn resumed(&mut self) {
-        let config = self
-            .surface
-            .get_default_config(
-                &self.adapter,
-                self.window.inner_size().width,
-                self.window.inner_size().height,
-            )
+        let Some(surface) = self.surface.as_ref() else {
+            return;
+        };
+        // A browser canvas can still be 0x0 before the page has been laid out.
+        let size = self.window.inner_size();
+        let config = surface
+            .get_default_config(&self.adapter, size.width.max(1), size.height.max(1))
             .unwrap();
+        surface.configure(&self.device, &config);
         self.config = Some(config);
+    }
+}
+
+// The adapter named in the settings if there is one that can draw to `surface`, otherwise
+// whatever wgpu prefers for the power preference; software only when allowed.
+async fn select_adapter(
+    instance: &Instance,
+    surface: &Surface<'_>,
+    gpu: &GpuSettings,
+) -> Result<Adapter, String> {
+    if let Some(name) = gpu.adapter.as_deref() {
+        match named_adapter(instance, surface, name) {
+            Some(adapter) => return Ok(adapter),
+            None => log::warn!(
+                "no usable GPU adapter matches {name:?}, choosing automatically; available: {}",
+                adapter_list(instance)
+            ),
+        }
+    }
+
+    let mut options = wgpu::RequestAdapterOptions {
+        power_preference: gpu.power_preference.to_wgpu(),
+        force_fallback_adapter: false,
+        compatible_surface: Some(surface),
+    };
+    let err = match instance.request_adapter(&options).await {
+        Ok(adapter) => return Ok(adapter),
+        Err(err) => err,
+    };
+    if gpu.allow_fallback_adapter {
+        log::warn!("no hardware GPU adapter available ({err}), trying a software renderer");
+        options.force_fallback_adapter = true;
+        if let Ok(adapter) = instance.request_adapter(&options).await {
+            return Ok(adapter);
+        }
+    }
+
+    let hint = if gpu.allow_fallback_adapter {
+        "no software renderer is available either; updating the graphics driver usually helps"
+    } else {
+        "update the graphics driver, or start with --allow-fallback-adapter to use a software \
+         renderer"
+    };
+    Err(format!(
+        "no compatible GPU adapter found ({err}); available: {}. {hint}",
+        adapter_list(instance)
+    ))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn named_adapter(instance: &Instance, surface: &Surface<'_>, name: &str) -> Option<Adapter> {
+    let name = name.to_lowercase();
+    instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .into_iter()
+        .filter(|adapter| adapter.get_info().name.to_lowercase().contains(&name))
+        .find(|adapter| adapter.is_surface_supported(surface))
+}
+
+// Browsers expose a single adapter and no names to match against.
+#[cfg(target_arch = "wasm32")]
+fn named_adapter(_instance: &Instance, _surface: &Surface<'_>, _name: &str) -> Option<Adapter> {
+    None
+}
+
+// "name (backend, type)" for every adapter wgpu can see, for `--list-adapters`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn adapter_descriptions(instance: &Instance) -> Vec<String> {
+    instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .iter()
+        .map(|adapter| {
+            let info = adapter.get_info();
+            format!("{} ({:?}, {:?})", info.name, info.backend, info.device_type)
+        })
+        .collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn adapter_descriptions(_instance: &Instance) -> Vec<String> {
+    Vec::new()
+}
 
-        self.surface
-            .configure(&self.device, &self.config.as_ref().unwrap());
+fn adapter_list(instance: &Instance) -> String {
+    let adapters = adapter_descriptions(instance);
+    if adapters.is_empty() {
+        "none".to_owned()
+    } else {
+        adapters.join(", ")
     }
 }
@@ -0,0 +1,181 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{save, vfs};
+
+// Per-user, next to the achievement progress; relative to `paths::data_dir()`.
+pub const DEFAULT_STATS_PATH: &str = "saves/stats.json";
+
+// Playtime is written at most this often; exits flush the remainder.
+const PLAYTIME_SAVE_INTERVAL_SECONDS: f64 = 30.0;
+
+// Counters usable as progress achievement sources: a chain named after `key()` follows
+// the stat's value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stat {
+    PlaytimeMinutes,
+    Launches,
+    LinesRead,
+    ChoicesMade,
+    ScenesCompleted,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 5] = [
+        Stat::PlaytimeMinutes,
+        Stat::Launches,
+        Stat::LinesRead,
+        Stat::ChoicesMade,
+        Stat::ScenesCompleted,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::PlaytimeMinutes => "playtime_minutes",
+            Self::Launches => "launches",
+            Self::LinesRead => "lines_read",
+            Self::ChoicesMade => "choices_made",
+            Self::ScenesCompleted => "scenes_completed",
+        }
+    }
+
+    pub fn title_key(self) -> &'static str {
+        match self {
+            Self::PlaytimeMinutes => "stats.playtime",
+            Self::Launches => "stats.launches",
+            Self::LinesRead => "stats.lines_read",
+            Self::ChoicesMade => "stats.choices_made",
+            Self::ScenesCompleted => "stats.scenes_completed",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct StatsSnapshot {
+    #[serde(default)]
+    pub playtime_seconds: f64,
+    #[serde(default)]
+    pub launches: u32,
+    #[serde(default)]
+    pub lines_read: u32,
+    #[serde(default)]
+    pub choices_made: u32,
+    #[serde(default)]
+    pub scenes_completed: u32,
+}
+
+impl StatsSnapshot {
+    pub fn value(&self, stat: Stat) -> u32 {
+        match stat {
+            Stat::PlaytimeMinutes => (self.playtime_seconds / 60.0) as u32,
+            Stat::Launches => self.launches,
+            Stat::LinesRead => self.lines_read,
+            Stat::ChoicesMade => self.choices_made,
+            Stat::ScenesCompleted => self.scenes_completed,
+        }
+    }
+}
+
+// Lifetime play statistics, kept across save slots.
+#[derive(Debug, Default)]
+pub struct StatsManager {
+    stats: StatsSnapshot,
+    unsaved_playtime: f64,
+    dirty: bool,
+}
+
+impl StatsManager {
+    // A missing file starts every counter at zero.
+    pub fn load_from_json_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        if !vfs::exists(path) && !vfs::exists(save::backup_path(path)) {
+            return Ok(Self::default());
+        }
+
+        let stats = save::read_signed_with_backup(path, "stats file", |path, raw| {
+            serde_json::from_str(raw)
+                .map_err(|err| format!("failed to parse stats json {}: {err}", path.display()))
+        })?;
+
+        Ok(Self {
+            stats,
+            ..Self::default()
+        })
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        self.stats
+    }
+
+    pub fn value(&self, stat: Stat) -> u32 {
+        self.stats.value(stat)
+    }
+
+    pub fn record_launch(&mut self) {
+        self.stats.launches = self.stats.launches.saturating_add(1);
+        self.dirty = true;
+    }
+
+    pub fn add_playtime(&mut self, seconds: f32) {
+        if seconds <= 0.0 {
+            return;
+        }
+        self.stats.playtime_seconds += f64::from(seconds);
+        self.unsaved_playtime += f64::from(seconds);
+        if self.unsaved_playtime >= PLAYTIME_SAVE_INTERVAL_SECONDS {
+            self.dirty = true;
+        }
+    }
+
+    pub fn add_lines_read(&mut self, count: u32) {
+        if count == 0 {
+            return;
+        }
+        self.stats.lines_read = self.stats.lines_read.saturating_add(count);
+        self.dirty = true;
+    }
+
+    pub fn record_choice(&mut self) {
+        self.stats.choices_made = self.stats.choices_made.saturating_add(1);
+        self.dirty = true;
+    }
+
+    pub fn record_scene_completed(&mut self) {
+        self.stats.scenes_completed = self.stats.scenes_completed.saturating_add(1);
+        self.dirty = true;
+    }
+
+    // Makes the next save include playtime not yet written, e.g. before exiting.
+    pub fn flush_playtime(&mut self) {
+        if self.unsaved_playtime > 0.0 {
+            self.dirty = true;
+        }
+    }
+
+    pub fn save_to_json_file(&mut self, path: impl AsRef<Path>) -> Result<bool, String> {
+        if !self.dirty {
+            return Ok(false);
+        }
+
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            vfs::create_dir_all(parent).map_err(|err| {
+                format!(
+                    "failed to create stats directory {}: {err}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        let json = serde_json::to_string_pretty(&self.stats)
+            .map_err(|err| format!("failed to serialize stats: {err}"))?;
+
+        save::write_signed(path, &json)
+            .map_err(|err| format!("failed to write stats json {}: {err}", path.display()))?;
+
+        self.dirty = false;
+        self.unsaved_playtime = 0.0;
+        Ok(true)
+    }
+}
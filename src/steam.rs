@@ -0,0 +1,121 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use steamworks::{CallbackHandle, Client, ClientManager, SingleClient, UserStatsReceived};
+
+use crate::achievements::AchievementManager;
+
+// Mirrors local achievement unlocks to Steam. Local ids are used as the Steam API names,
+// so both catalogs must match.
+pub struct SteamAchievements {
+    client: Client<ClientManager>,
+    single: SingleClient<ClientManager>,
+    stats_received: Arc<AtomicBool>,
+    _stats_callback: CallbackHandle<ClientManager>,
+    reconciled: bool,
+    // Ids already unlocked on Steam, or missing there and never sent.
+    synced: HashSet<String>,
+    // Filled by the unlock subscription, sent once stats are received.
+    pending: Rc<RefCell<Vec<String>>>,
+}
+
+impl SteamAchievements {
+    // Fails when Steam is not running; during development the app id comes from
+    // steam_appid.txt next to the executable.
+    pub fn init(achievements: &mut AchievementManager) -> Result<Self, String> {
+        let (client, single) =
+            Client::init().map_err(|err| format!("failed to initialize steam: {err}"))?;
+
+        let stats_received = Arc::new(AtomicBool::new(false));
+        let received = stats_received.clone();
+        let stats_callback =
+            client.register_callback(move |stats: UserStatsReceived| match stats.result {
+                Ok(()) => received.store(true, Ordering::Relaxed),
+                Err(err) => log::warn!("failed to receive steam stats: {err}"),
+            });
+        client.user_stats().request_current_stats();
+
+        // Unlocks made before the subscription are picked up by the reconcile pass.
+        let pending = Rc::new(RefCell::new(Vec::new()));
+        let queue = pending.clone();
+        achievements.subscribe(move |unlock| queue.borrow_mut().push(unlock.id.clone()));
+
+        Ok(Self {
+            client,
+            single,
+            stats_received,
+            _stats_callback: stats_callback,
+            reconciled: false,
+            synced: HashSet::new(),
+            pending,
+        })
+    }
+
+    // Called every frame: runs Steam callbacks and pushes unlocks made since the last call.
+    pub fn update(&mut self, achievements: &mut AchievementManager) {
+        self.single.run_callbacks();
+        if !self.reconciled {
+            if !self.stats_received.load(Ordering::Relaxed) {
+                return;
+            }
+            self.reconcile(achievements);
+            self.reconciled = true;
+        }
+
+        let user_stats = self.client.user_stats();
+        let mut changed = false;
+        let pending = std::mem::take(&mut *self.pending.borrow_mut());
+        for id in pending {
+            if self.synced.contains(&id) {
+                continue;
+            }
+            if user_stats.achievement(&id).set().is_err() {
+                log::warn!("steam achievement {id} could not be set");
+            }
+            self.synced.insert(id);
+            changed = true;
+        }
+
+        if changed && user_stats.store_stats().is_err() {
+            log::error!("failed to store steam stats");
+        }
+    }
+
+    // Clears Steam achievements too, otherwise the next start would restore them.
+    pub fn reset_all(&mut self) {
+        if self.client.user_stats().reset_all_stats(true).is_err() {
+            log::error!("failed to reset steam achievements");
+        }
+        self.synced.clear();
+    }
+
+    // Steam unlocks from other machines are restored locally without a toast; local
+    // unlocks Steam does not know yet are queued for the regular update.
+    fn reconcile(&mut self, achievements: &mut AchievementManager) {
+        let user_stats = self.client.user_stats();
+        let ids: Vec<String> = achievements.ids().map(str::to_owned).collect();
+        for id in ids {
+            match user_stats.achievement(&id).get() {
+                Ok(true) => {
+                    achievements.restore_unlocked(&id);
+                    self.synced.insert(id);
+                }
+                Ok(false) if achievements.is_unlocked(&id) => {
+                    self.pending.borrow_mut().push(id);
+                }
+                Ok(false) => {}
+                Err(()) => {
+                    log::warn!("achievement {id} is not configured on steam");
+                    self.synced.insert(id);
+                }
+            }
+        }
+    }
+}
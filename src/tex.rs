@@ -1,6 +1,9 @@
-use std::{collections::HashMap, mem::size_of, path::Path};
+use std::{collections::HashMap, mem::size_of};
 
-use crate::game_object::{GameObject2D, RenderLayer};
+use crate::{
+    game_object::{GameObject2D, RenderLayer},
+    profiler, vfs,
+};
 use image::{DynamicImage, GenericImageView};
 use wgpu::util::DeviceExt;
 
@@ -35,12 +38,23 @@ fn create_vertices() -> ([Vertex; 4], [u16; 6]) {
 
 struct RenderObject {
     game_object: GameObject2D,
+    // Transform at the start of the current simulation tick, blended towards by `interpolate`.
+    previous_position: glam::Vec2,
+    previous_scale: glam::Vec2,
     order: u64,
     diffuse_bind_group: wgpu::BindGroup,
     uniform_bind_group: wgpu::BindGroup,
     uniform_buf: wgpu::Buffer,
 }
 
+// Counters from the most recent render() call, shown by the debug overlay.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderStats {
+    pub object_count: usize,
+    pub visible_objects: usize,
+    pub draw_calls: usize,
+}
+
 pub struct Tex {
     vertex_buf: wgpu::Buffer,
     index_buf: wgpu::Buffer,
@@ -52,7 +66,10 @@ pub struct Tex {
     view_proj: glam::Mat4,
     objects: Vec<RenderObject>,
     object_lookup: HashMap<String, usize>,
+    // Uploaded textures by path, shared by every object that uses the same file.
+    textures: HashMap<String, wgpu::BindGroup>,
     next_object_order: u64,
+    last_stats: RenderStats,
 }
 
 impl Tex {
@@ -74,12 +91,16 @@ impl Tex {
     }
 
     fn build_model_view_projection(view_proj: glam::Mat4, object: &GameObject2D) -> glam::Mat4 {
-        let model =
-            glam::Mat4::from_translation(glam::Vec3::new(
-                object.position.x,
-                object.position.y,
-                0.0,
-            )) * glam::Mat4::from_scale(glam::Vec3::new(object.scale.x, object.scale.y, 1.0));
+        Self::build_transform(view_proj, object.position, object.scale)
+    }
+
+    fn build_transform(
+        view_proj: glam::Mat4,
+        position: glam::Vec2,
+        scale: glam::Vec2,
+    ) -> glam::Mat4 {
+        let model = glam::Mat4::from_translation(position.extend(0.0))
+            * glam::Mat4::from_scale(scale.extend(1.0));
         view_proj * model
     }
 
@@ -112,6 +133,7 @@ impl Tex {
         diffuse_image: DynamicImage,
         label: &str,
     ) -> wgpu::BindGroup {
+        let _scope = profiler::scope("texture_upload");
         let diffuse_rgba = diffuse_image.to_rgba8();
         let dimensions = diffuse_image.dimensions();
 
@@ -194,32 +216,61 @@ impl Tex {
         }
     }
 
-    fn push_game_object_from_image(
+    // Uploads a decoded image under `path`, replacing an earlier upload of the same path.
+    pub fn upload_texture(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        game_object: GameObject2D,
-        diffuse_image: DynamicImage,
+        path: &str,
+        image: DynamicImage,
     ) {
-        let texture_label = if game_object.texture_path.is_empty() {
-            "scene_object".to_string()
+        let label = if path.is_empty() {
+            "scene_object"
         } else {
-            game_object.texture_path.clone()
+            path
         };
-
-        let diffuse_bind_group = Self::create_diffuse_bind_group_from_image(
+        let bind_group = Self::create_diffuse_bind_group_from_image(
             device,
             queue,
             &self.texture_bind_group_layout,
-            diffuse_image,
-            texture_label.as_str(),
+            image,
+            label,
         );
+        self.textures.insert(path.to_owned(), bind_group);
+    }
+
+    pub fn has_texture(&self, path: &str) -> bool {
+        self.textures.contains_key(path)
+    }
 
+    // The uploaded texture for `path`, reading and uploading the file first if needed.
+    fn texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: &str,
+    ) -> Result<wgpu::BindGroup, String> {
+        if !self.textures.contains_key(path) {
+            let image = vfs::read_image(path)
+                .map_err(|err| format!("failed to load texture '{path}': {err}"))?;
+            self.upload_texture(device, queue, path, image);
+        }
+        Ok(self.textures[path].clone())
+    }
+
+    fn push_game_object(
+        &mut self,
+        device: &wgpu::Device,
+        game_object: GameObject2D,
+        diffuse_bind_group: wgpu::BindGroup,
+    ) {
         let transform = Self::build_model_view_projection(self.view_proj, &game_object);
         let (uniform_buf, uniform_bind_group) =
             Self::create_uniform_resources(device, &self.uniform_bind_group_layout, transform);
 
         let object = RenderObject {
+            previous_position: game_object.position,
+            previous_scale: game_object.scale,
             game_object,
             order: self.next_object_order,
             diffuse_bind_group,
@@ -397,10 +448,12 @@ impl Tex {
             view_proj: Self::build_view_projection(config.width as f32 / config.height as f32),
             objects: Vec::new(),
             object_lookup: HashMap::new(),
+            textures: HashMap::new(),
             next_object_order: 0,
+            last_stats: RenderStats::default(),
         };
 
-        println!("done!");
+        log::debug!("scene renderer ready");
         tex
     }
 
@@ -447,13 +500,8 @@ impl Tex {
         queue: &wgpu::Queue,
         object: GameObject2D,
     ) -> Result<(), String> {
-        let diffuse_image = image::open(Path::new(&object.texture_path)).map_err(|err| {
-            format!(
-                "failed to load texture '{}': {err}",
-                object.texture_path.as_str()
-            )
-        })?;
-        self.push_game_object_from_image(device, queue, object, diffuse_image);
+        let diffuse_bind_group = self.texture(device, queue, &object.texture_path)?;
+        self.push_game_object(device, object, diffuse_bind_group);
         Ok(())
     }
 
@@ -503,15 +551,7 @@ impl Tex {
 
         if texture_changed {
             let texture_path = texture_path_for_reload.expect("texture_changed checked above");
-            let diffuse_image = image::open(Path::new(&texture_path))
-                .map_err(|err| format!("failed to load texture '{texture_path}': {err}"))?;
-            let new_bind_group = Self::create_diffuse_bind_group_from_image(
-                device,
-                queue,
-                &self.texture_bind_group_layout,
-                diffuse_image,
-                texture_path.as_str(),
-            );
+            let new_bind_group = self.texture(device, queue, &texture_path)?;
             if let Some(existing) = self.objects.get_mut(index) {
                 existing.diffuse_bind_group = new_bind_group;
             }
@@ -526,6 +566,70 @@ impl Tex {
         Ok(())
     }
 
+    // Horizontal screen position of an object's center: -1.0 at the left edge, 1.0 at the right.
+    pub fn object_screen_x(&self, scene_key: &str) -> Option<f32> {
+        let object = &self
+            .objects
+            .get(*self.object_lookup.get(scene_key)?)?
+            .game_object;
+        let clip = self.view_proj * glam::Vec4::new(object.position.x, object.position.y, 0.0, 1.0);
+        Some(clip.x / clip.w)
+    }
+
+    // Starts a simulation tick: objects moved from here on are blended from where they are now.
+    pub fn begin_tick(&mut self) {
+        for object in &mut self.objects {
+            object.previous_position = object.game_object.position;
+            object.previous_scale = object.game_object.scale;
+        }
+    }
+
+    // Writes each visible object's transform `alpha` (0..1) of the way from the start of the
+    // last tick to its end, so motion stays smooth when frames and ticks do not line up.
+    pub fn interpolate(&mut self, alpha: f32, queue: &wgpu::Queue) {
+        let alpha = alpha.clamp(0.0, 1.0);
+        for object in self
+            .objects
+            .iter()
+            .filter(|object| !object.game_object.hidden)
+        {
+            let position = object
+                .previous_position
+                .lerp(object.game_object.position, alpha);
+            let scale = object.previous_scale.lerp(object.game_object.scale, alpha);
+            let matrix = Self::build_transform(self.view_proj, position, scale).to_cols_array();
+            queue.write_buffer(&object.uniform_buf, 0, bytemuck::bytes_of(&matrix));
+        }
+    }
+
+    // Moves the scene to a new device after the old one was lost: every resource is created
+    // again and each object is reloaded from its definition.
+    pub fn recreate(
+        &mut self,
+        config: &wgpu::SurfaceConfiguration,
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        let definitions: Vec<GameObject2D> = self
+            .objects
+            .drain(..)
+            .map(|object| object.game_object)
+            .collect();
+        *self = Self::init(config, adapter, device, queue);
+        for definition in definitions {
+            let key = definition.scene_key();
+            if let Err(err) = self.create_game_object_from_definition(device, queue, definition) {
+                log::warn!("failed to restore scene object {key}: {err}");
+            }
+        }
+    }
+
+    pub fn clear_game_objects(&mut self) {
+        self.objects.clear();
+        self.object_lookup.clear();
+    }
+
     pub fn resize(
         &mut self,
         config: &wgpu::SurfaceConfiguration,
@@ -541,6 +645,10 @@ impl Tex {
         }
     }
 
+    pub fn render_stats(&self) -> RenderStats {
+        self.last_stats
+    }
+
     pub fn render(&mut self, view: &wgpu::TextureView, device: &wgpu::Device, queue: &wgpu::Queue) {
         let mut encoder =
             device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
@@ -569,23 +677,31 @@ impl Tex {
             rpass.set_index_buffer(self.index_buf.slice(..), wgpu::IndexFormat::Uint16);
             rpass.set_vertex_buffer(0, self.vertex_buf.slice(..));
 
+            let mut stats = RenderStats {
+                object_count: self.objects.len(),
+                ..RenderStats::default()
+            };
             for object in &self.objects {
                 if object.game_object.hidden {
                     continue;
                 }
+                stats.visible_objects += 1;
 
                 rpass.set_pipeline(&self.pipeline);
                 rpass.set_bind_group(0, &object.diffuse_bind_group, &[]);
                 rpass.set_bind_group(1, &object.uniform_bind_group, &[]);
                 rpass.draw_indexed(0..self.index_count, 0, 0..1);
+                stats.draw_calls += 1;
 
                 if let Some(ref pipe) = self.pipeline_wire {
                     rpass.set_pipeline(pipe);
                     rpass.set_bind_group(0, &object.diffuse_bind_group, &[]);
                     rpass.set_bind_group(1, &object.uniform_bind_group, &[]);
                     rpass.draw_indexed(0..self.index_count, 0, 0..1);
+                    stats.draw_calls += 1;
                 }
             }
+            self.last_stats = stats;
         }
 
         queue.submit(Some(encoder.finish()));
@@ -0,0 +1,218 @@
+use std::path::Path;
+
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::vfs;
+
+pub const DEFAULT_THEMES_PATH: &str = "src/data/themes.json";
+
+// Colors are stored as "#rrggbb" or "#rrggbbaa" strings in theme files.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct UiThemePalette {
+    #[serde(with = "hex_color")]
+    pub menu_fill: Color32,
+    #[serde(with = "hex_color")]
+    pub menu_stroke: Color32,
+    #[serde(with = "hex_color")]
+    pub menu_title: Color32,
+    #[serde(with = "hex_color")]
+    pub settings_fill: Color32,
+    #[serde(with = "hex_color")]
+    pub settings_stroke: Color32,
+    #[serde(with = "hex_color")]
+    pub settings_title: Color32,
+    pub dialogue_fill_rgb: [u8; 3],
+    #[serde(with = "hex_color")]
+    pub dialogue_stroke: Color32,
+    #[serde(with = "hex_color")]
+    pub dialogue_speaker: Color32,
+    #[serde(with = "hex_color")]
+    pub dialogue_text: Color32,
+    #[serde(with = "hex_color")]
+    pub skip_ready: Color32,
+    #[serde(with = "hex_color")]
+    pub skip_wait: Color32,
+    #[serde(with = "hex_color")]
+    pub popup_fill: Color32,
+    #[serde(with = "hex_color")]
+    pub popup_stroke: Color32,
+    #[serde(with = "hex_color")]
+    pub popup_title: Color32,
+    #[serde(with = "hex_color")]
+    pub popup_name: Color32,
+    #[serde(with = "hex_color")]
+    pub popup_body: Color32,
+}
+
+impl UiThemePalette {
+    // Editable colors keyed by their locale label; dialogue_fill_rgb is edited separately.
+    pub fn colors_mut(&mut self) -> [(&'static str, &mut Color32); 16] {
+        [
+            ("theme.field.menu_fill", &mut self.menu_fill),
+            ("theme.field.menu_stroke", &mut self.menu_stroke),
+            ("theme.field.menu_title", &mut self.menu_title),
+            ("theme.field.settings_fill", &mut self.settings_fill),
+            ("theme.field.settings_stroke", &mut self.settings_stroke),
+            ("theme.field.settings_title", &mut self.settings_title),
+            ("theme.field.dialogue_stroke", &mut self.dialogue_stroke),
+            ("theme.field.dialogue_speaker", &mut self.dialogue_speaker),
+            ("theme.field.dialogue_text", &mut self.dialogue_text),
+            ("theme.field.skip_ready", &mut self.skip_ready),
+            ("theme.field.skip_wait", &mut self.skip_wait),
+            ("theme.field.popup_fill", &mut self.popup_fill),
+            ("theme.field.popup_stroke", &mut self.popup_stroke),
+            ("theme.field.popup_title", &mut self.popup_title),
+            ("theme.field.popup_name", &mut self.popup_name),
+            ("theme.field.popup_body", &mut self.popup_body),
+        ]
+    }
+}
+
+impl UiThemePalette {
+    // Pushes every text color away from its background until it reaches `min_ratio`.
+    pub fn with_min_contrast(mut self, min_ratio: f32) -> Self {
+        let [r, g, b] = self.dialogue_fill_rgb;
+        let dialogue_fill = Color32::from_rgb(r, g, b);
+
+        self.menu_title = ensure_contrast(self.menu_title, self.menu_fill, min_ratio);
+        self.settings_title = ensure_contrast(self.settings_title, self.settings_fill, min_ratio);
+        self.dialogue_speaker = ensure_contrast(self.dialogue_speaker, dialogue_fill, min_ratio);
+        self.dialogue_text = ensure_contrast(self.dialogue_text, dialogue_fill, min_ratio);
+        self.skip_ready = ensure_contrast(self.skip_ready, dialogue_fill, min_ratio);
+        self.skip_wait = ensure_contrast(self.skip_wait, dialogue_fill, min_ratio);
+        self.popup_title = ensure_contrast(self.popup_title, self.popup_fill, min_ratio);
+        self.popup_name = ensure_contrast(self.popup_name, self.popup_fill, min_ratio);
+        self.popup_body = ensure_contrast(self.popup_body, self.popup_fill, min_ratio);
+        self
+    }
+}
+
+// WCAG relative luminance; alpha is ignored since UI fills are nearly opaque.
+fn relative_luminance(color: Color32) -> f32 {
+    let channel = |value: u8| {
+        let value = value as f32 / 255.0;
+        if value <= 0.03928 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(color.r()) + 0.7152 * channel(color.g()) + 0.0722 * channel(color.b())
+}
+
+pub fn contrast_ratio(a: Color32, b: Color32) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+// Blends `foreground` toward white or black (whichever contrasts more with
+// `background`) just far enough to reach `min_ratio`.
+pub fn ensure_contrast(foreground: Color32, background: Color32, min_ratio: f32) -> Color32 {
+    if contrast_ratio(foreground, background) >= min_ratio {
+        return foreground;
+    }
+
+    let target = if contrast_ratio(Color32::WHITE, background)
+        >= contrast_ratio(Color32::BLACK, background)
+    {
+        Color32::WHITE
+    } else {
+        Color32::BLACK
+    };
+
+    const STEPS: u8 = 20;
+    for step in 1..=STEPS {
+        let candidate = foreground.lerp_to_gamma(target, step as f32 / STEPS as f32);
+        if contrast_ratio(candidate, background) >= min_ratio {
+            return candidate;
+        }
+    }
+    target
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CustomTheme {
+    pub name: String,
+    pub palette: UiThemePalette,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ThemeFile {
+    #[serde(default)]
+    themes: Vec<CustomTheme>,
+}
+
+pub fn load_custom_themes(path: impl AsRef<Path>) -> Result<Vec<CustomTheme>, String> {
+    let path = path.as_ref();
+    if !vfs::exists(path) {
+        return Ok(Vec::new());
+    }
+
+    let raw = vfs::read_to_string(path)
+        .map_err(|err| format!("failed to read themes file {}: {err}", path.display()))?;
+    let file: ThemeFile = serde_json::from_str(&raw)
+        .map_err(|err| format!("failed to parse themes json {}: {err}", path.display()))?;
+
+    Ok(file.themes)
+}
+
+pub fn save_custom_themes(path: impl AsRef<Path>, themes: &[CustomTheme]) -> Result<(), String> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        vfs::create_dir_all(parent).map_err(|err| {
+            format!(
+                "failed to create themes directory {}: {err}",
+                parent.display()
+            )
+        })?;
+    }
+
+    let file = ThemeFile {
+        themes: themes.to_vec(),
+    };
+    let json = serde_json::to_string_pretty(&file)
+        .map_err(|err| format!("failed to serialize themes: {err}"))?;
+
+    vfs::write(path, json)
+        .map_err(|err| format!("failed to write themes json {}: {err}", path.display()))
+}
+
+mod hex_color {
+    use egui::Color32;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(color: &Color32, serializer: S) -> Result<S::Ok, S::Error> {
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+        let text = if a == u8::MAX {
+            format!("#{r:02x}{g:02x}{b:02x}")
+        } else {
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+        };
+        serializer.serialize_str(&text)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color32, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let hex = text.trim().trim_start_matches('#');
+        let channel = |index: usize| {
+            hex.get(index * 2..index * 2 + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+        };
+
+        let parsed = match hex.len() {
+            6 => channel(0)
+                .zip(channel(1))
+                .zip(channel(2))
+                .map(|((r, g), b)| Color32::from_rgb(r, g, b)),
+            8 => channel(0)
+                .zip(channel(1))
+                .zip(channel(2))
+                .zip(channel(3))
+                .map(|(((r, g), b), a)| Color32::from_rgba_unmultiplied(r, g, b, a)),
+            _ => None,
+        };
+
+        parsed.ok_or_else(|| D::Error::custom(format!("invalid color '{text}'")))
+    }
+}
@@ -0,0 +1,422 @@
+// File access that also works in the browser. On wasm32 the shipped files listed in
+// `ASSET_MANIFEST` are fetched once by `preload` and served from memory, while writes go to
+// localStorage, which also shadows shipped files of the same path.
+//
+// Native reads of relative paths go through the mount table first: loose directories and
+// packs (see `pack`) mounted at a virtual prefix, newest mount first. Paths no mount has,
+// absolute paths and every write use the file system as-is, so with nothing mounted this
+// is plain `std::fs`.
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    collections::BTreeSet,
+    io,
+    path::{Component, Path, PathBuf},
+    sync::RwLock,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::pack::{self, Pack};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::fs::{create_dir_all, rename};
+
+// Pack that release builds read shipped files from when it sits in the working directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub const DEFAULT_PACK_PATH: &str = "game.pak";
+// What `build_pack` collects; Rust sources under `src` are left out.
+#[cfg(not(target_arch = "wasm32"))]
+const SHIPPED_ROOTS: [&str; 2] = ["assets", "src"];
+
+#[cfg(not(target_arch = "wasm32"))]
+enum MountSource {
+    Dir(PathBuf),
+    Pack(Pack),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Mount {
+    // Virtual prefix without a trailing '/'; empty mounts at the root.
+    point: String,
+    source: MountSource,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+static MOUNTS: RwLock<Vec<Mount>> = RwLock::new(Vec::new());
+
+// Serves `dir` under the virtual prefix `point`, e.g. `mount_dir("src/data", "mods/data")`
+// makes "src/data/audio.json" read "mods/data/audio.json".
+#[cfg(not(target_arch = "wasm32"))]
+pub fn mount_dir(point: impl AsRef<Path>, dir: impl Into<PathBuf>) {
+    push_mount(point.as_ref(), MountSource::Dir(dir.into()));
+}
+
+// Serves the entries of a .pak or .zip under `point`; returns how many files it has.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn mount_pack(point: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<usize, String> {
+    let pack = Pack::open(path)?;
+    let files = pack.len();
+    push_mount(point.as_ref(), MountSource::Pack(pack));
+    Ok(files)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn push_mount(point: &Path, source: MountSource) {
+    let point = virtual_path(point).unwrap_or_default();
+    MOUNTS
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .push(Mount { point, source });
+}
+
+// Startup mounts: the pack given on the command line, else `DEFAULT_PACK_PATH` in release
+// builds when present. Debug builds read loose files so edits show up without repacking.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn mount_shipped(pack: Option<&Path>) {
+    let pack = pack.or_else(|| {
+        let default = Path::new(DEFAULT_PACK_PATH);
+        (!cfg!(debug_assertions) && default.is_file()).then_some(default)
+    });
+    let Some(pack) = pack else {
+        return;
+    };
+    match mount_pack("", pack) {
+        Ok(files) => log::info!("reading {files} shipped files from {}", pack.display()),
+        Err(err) => log::error!("{err}; reading loose files instead"),
+    }
+}
+
+// Writes every shipped file under `SHIPPED_ROOTS` into a .pak at `out`, with the same
+// relative paths the game reads them by; returns how many files went in.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn build_pack(out: &Path) -> Result<usize, String> {
+    let mut files = Vec::new();
+    for root in SHIPPED_ROOTS {
+        collect_shipped(Path::new(root), &mut files)
+            .map_err(|err| format!("failed to list {root}: {err}"))?;
+    }
+    files.sort();
+    pack::write_pak(out, &files)
+        .map_err(|err| format!("failed to write pack {}: {err}", out.display()))?;
+    Ok(files.len())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn collect_shipped(dir: &Path, files: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_shipped(&path, files)?;
+        } else if path.extension().is_none_or(|extension| extension != "rs")
+            && let Some(name) = virtual_path(&path)
+        {
+            files.push((name, path));
+        }
+    }
+    Ok(())
+}
+
+// "./src/data/x.json" and "src\\data\\x.json" name the same file. None for paths that
+// leave the working directory, which mounts never serve.
+#[cfg(not(target_arch = "wasm32"))]
+fn virtual_path(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?.replace('\\', "/")),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+// Path of `path` inside a mount at `point`: "" for the mount point itself.
+#[cfg(not(target_arch = "wasm32"))]
+fn strip_point<'a>(path: &'a str, point: &str) -> Option<&'a str> {
+    if point.is_empty() {
+        return Some(path);
+    }
+    let rest = path.strip_prefix(point)?;
+    if rest.is_empty() {
+        return Some(rest);
+    }
+    rest.strip_prefix('/')
+}
+
+// First answer from the newest mount that has `path`.
+#[cfg(not(target_arch = "wasm32"))]
+fn find_mounted<T>(
+    path: &Path,
+    mut lookup: impl FnMut(&MountSource, &str) -> Option<T>,
+) -> Option<T> {
+    let path = virtual_path(path)?;
+    let mounts = MOUNTS.read().unwrap_or_else(|err| err.into_inner());
+    mounts.iter().rev().find_map(|mount| {
+        let inner = strip_point(&path, &mount.point)?;
+        lookup(&mount.source, inner)
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let path = path.as_ref();
+    let mounted = find_mounted(path, |source, inner| match source {
+        MountSource::Dir(dir) => {
+            let file = dir.join(inner);
+            file.is_file().then(|| std::fs::read(file))
+        }
+        MountSource::Pack(pack) => pack.read(inner),
+    });
+    mounted.unwrap_or_else(|| std::fs::read(path))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    String::from_utf8(read(path)?).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    std::fs::write(path, contents)
+}
+
+// `write` followed by an fsync, for files that are renamed into place afterwards.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn exists(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    is_file(path) || path.exists() || !list_dir(path).unwrap_or_default().is_empty()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn is_file(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    let mounted = find_mounted(path, |source, inner| match source {
+        MountSource::Dir(dir) => dir.join(inner).is_file().then_some(()),
+        MountSource::Pack(pack) => pack.contains(inner).then_some(()),
+    });
+    mounted.is_some() || path.is_file()
+}
+
+// Paths of the files directly inside `dir`, from the file system and every mount.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn list_dir(dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    let mut names = BTreeSet::new();
+    if let Some(path) = virtual_path(dir) {
+        let mounts = MOUNTS.read().unwrap_or_else(|err| err.into_inner());
+        for mount in mounts.iter() {
+            let Some(inner) = strip_point(&path, &mount.point) else {
+                continue;
+            };
+            match &mount.source {
+                MountSource::Dir(root) => {
+                    if let Ok(entries) = std::fs::read_dir(root.join(inner)) {
+                        names.extend(entries.flatten().map(|entry| entry.file_name()));
+                    }
+                }
+                MountSource::Pack(pack) => {
+                    let prefix = if inner.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{inner}/")
+                    };
+                    names.extend(
+                        pack.names()
+                            .filter_map(|name| name.strip_prefix(&prefix))
+                            .filter(|name| !name.contains('/'))
+                            .map(Into::into),
+                    );
+                }
+            }
+        }
+    }
+
+    match std::fs::read_dir(dir) {
+        Ok(entries) => {
+            for entry in entries {
+                names.insert(entry?.file_name());
+            }
+        }
+        Err(err) if names.is_empty() => return Err(err),
+        Err(_) => {}
+    }
+    Ok(names.into_iter().map(|name| dir.join(name)).collect())
+}
+
+// Decodes an image file, guessing the format from its contents like `image::open` does.
+pub fn read_image(path: impl AsRef<std::path::Path>) -> Result<image::DynamicImage, String> {
+    let bytes = read(path).map_err(|err| err.to_string())?;
+    image::load_from_memory(&bytes).map_err(|err| err.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use web::{
+    create_dir_all, exists, is_file, list_dir, preload, read, read_to_string, rename, write,
+    write_synced,
+};
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use std::{
+        cell::RefCell,
+        collections::BTreeMap,
+        io,
+        path::{Path, PathBuf},
+        rc::Rc,
+    };
+
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    // JSON array of the shipped files to fetch, relative to the page.
+    const ASSET_MANIFEST: &str = "assets.json";
+    // Keeps our keys apart from anything else on the same origin.
+    const STORAGE_PREFIX: &str = "game_engine:";
+
+    thread_local! {
+        static ASSETS: RefCell<BTreeMap<String, Rc<[u8]>>> = RefCell::new(BTreeMap::new());
+    }
+
+    // Fetches every file in the asset manifest; call before anything reads assets.
+    pub async fn preload() -> Result<usize, String> {
+        let manifest = fetch(ASSET_MANIFEST).await?;
+        let paths: Vec<String> = serde_json::from_slice(&manifest)
+            .map_err(|err| format!("failed to parse {ASSET_MANIFEST}: {err}"))?;
+
+        let mut loaded = 0;
+        for path in paths {
+            match fetch(&path).await {
+                Ok(bytes) => {
+                    ASSETS.with_borrow_mut(|assets| {
+                        assets.insert(key(Path::new(&path)), bytes.into())
+                    });
+                    loaded += 1;
+                }
+                Err(err) => log::warn!("{err}"),
+            }
+        }
+        Ok(loaded)
+    }
+
+    async fn fetch(url: &str) -> Result<Vec<u8>, String> {
+        let failed = |err: wasm_bindgen::JsValue| format!("failed to fetch {url}: {err:?}");
+        let window =
+            web_sys::window().ok_or_else(|| format!("failed to fetch {url}: no window"))?;
+        let response: web_sys::Response = JsFuture::from(window.fetch_with_str(url))
+            .await
+            .map_err(failed)?
+            .dyn_into()
+            .map_err(failed)?;
+        if !response.ok() {
+            return Err(format!("failed to fetch {url}: HTTP {}", response.status()));
+        }
+        let buffer = JsFuture::from(response.array_buffer().map_err(failed)?)
+            .await
+            .map_err(failed)?;
+        Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+    }
+
+    // "./src/data/x.json" and "src\\data\\x.json" name the same file.
+    fn key(path: &Path) -> String {
+        let path = path.to_string_lossy().replace('\\', "/");
+        path.trim_start_matches("./").to_owned()
+    }
+
+    fn storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "localStorage unavailable"))
+    }
+
+    fn storage_error(err: wasm_bindgen::JsValue) -> io::Error {
+        io::Error::other(format!("localStorage: {err:?}"))
+    }
+
+    fn stored(path: &Path) -> Option<String> {
+        let key = format!("{STORAGE_PREFIX}{}", key(path));
+        storage().ok()?.get_item(&key).ok().flatten()
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} not found", path.display()),
+        )
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        let path = path.as_ref();
+        if let Some(contents) = stored(path) {
+            return Ok(contents.into_bytes());
+        }
+        ASSETS
+            .with_borrow(|assets| assets.get(&key(path)).map(|bytes| bytes.to_vec()))
+            .ok_or_else(|| not_found(path))
+    }
+
+    pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+        String::from_utf8(read(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    // localStorage only holds text, which is all the game writes.
+    pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        let contents = std::str::from_utf8(contents.as_ref())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let key = format!("{STORAGE_PREFIX}{}", key(path.as_ref()));
+        storage()?.set_item(&key, contents).map_err(storage_error)
+    }
+
+    // localStorage writes are atomic already.
+    pub fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+        write(path, contents)
+    }
+
+    pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        let from = from.as_ref();
+        let contents = stored(from).ok_or_else(|| not_found(from))?;
+        write(to, contents)?;
+        let key = format!("{STORAGE_PREFIX}{}", key(from));
+        storage()?.remove_item(&key).map_err(storage_error)
+    }
+
+    // Directories are implied by the keys.
+    pub fn create_dir_all(_path: impl AsRef<Path>) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn is_file(path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        stored(path).is_some() || ASSETS.with_borrow(|assets| assets.contains_key(&key(path)))
+    }
+
+    pub fn exists(path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        is_file(path) || !list_dir(path).unwrap_or_default().is_empty()
+    }
+
+    // Shipped files only; nothing the game writes is listed.
+    pub fn list_dir(dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+        let prefix = format!("{}/", key(dir.as_ref()).trim_end_matches('/'));
+        Ok(ASSETS.with_borrow(|assets| {
+            assets
+                .keys()
+                .filter_map(|key| key.strip_prefix(&prefix))
+                .filter(|name| !name.contains('/'))
+                .map(|name| dir.as_ref().join(name))
+                .collect()
+        }))
+    }
+}
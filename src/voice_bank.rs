@@ -0,0 +1,87 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::audio::AudioEngine;
+
+use crate::vfs;
+
+pub const DEFAULT_VOICE_MANIFEST_PATH: &str = "src/data/voice.json";
+
+// {"root": "assets", "lines": {"intro_01": "voice/ajzakun/intro_01.ogg"}}
+#[derive(Deserialize)]
+struct VoiceManifest {
+    #[serde(default = "default_voice_root")]
+    root: PathBuf,
+    lines: HashMap<String, String>,
+}
+
+fn default_voice_root() -> PathBuf {
+    PathBuf::from("assets")
+}
+
+// Voice clips keyed by dialogue line id, registered with the AudioEngine on first use.
+#[derive(Clone, Debug, Default)]
+pub struct VoiceBank {
+    root: PathBuf,
+    lines: HashMap<String, String>,
+    // Line ids whose clip is registered under voice_sound_id().
+    loaded: HashSet<String>,
+    // Line ids whose clip failed to load; reported once, then treated as unvoiced.
+    missing: HashSet<String>,
+}
+
+impl VoiceBank {
+    pub fn load_from_json_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let raw = vfs::read_to_string(path)
+            .map_err(|err| format!("failed to read voice manifest {}: {err}", path.display()))?;
+
+        let manifest: VoiceManifest = serde_json::from_str(&raw)
+            .map_err(|err| format!("failed to parse voice manifest {}: {err}", path.display()))?;
+
+        Ok(Self {
+            root: manifest.root,
+            lines: manifest.lines,
+            loaded: HashSet::new(),
+            missing: HashSet::new(),
+        })
+    }
+
+    // True when the line has a clip that has not failed to load.
+    pub fn has_voice(&self, line_id: &str) -> bool {
+        self.lines.contains_key(line_id) && !self.missing.contains(line_id)
+    }
+
+    // Sound id to play for `line_id`, loading its clip on first call. Unmapped and missing
+    // lines return None; a missing file is logged only the first time.
+    pub fn sound_for_line(&mut self, line_id: &str, audio: &mut AudioEngine) -> Option<String> {
+        let file = self.lines.get(line_id)?;
+        let sound_id = voice_sound_id(line_id);
+        if self.loaded.contains(line_id) {
+            return Some(sound_id);
+        }
+        if self.missing.contains(line_id) {
+            return None;
+        }
+
+        match audio.register_sound_file(sound_id.clone(), self.root.join(file)) {
+            Ok(()) => {
+                self.loaded.insert(line_id.to_owned());
+                Some(sound_id)
+            }
+            Err(err) => {
+                log::warn!("voice for line '{line_id}' unavailable: {err}");
+                self.missing.insert(line_id.to_owned());
+                None
+            }
+        }
+    }
+}
+
+fn voice_sound_id(line_id: &str) -> String {
+    format!("voice:{line_id}")
+}